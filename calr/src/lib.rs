@@ -0,0 +1,688 @@
+//! Rust version of `cal`, the `run` function is called by the `calr` binary and by `ruty`
+
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+#[command(about, version)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `cal`
+pub struct Cli {
+    #[arg(value_name("YEAR"), value_parser = clap::value_parser!(i32).range(1..=9999))]
+    year: Option<i32>,
+
+    #[arg(short)]
+    /// Month name or number (1-12)
+    month: Option<String>,
+
+    #[arg(short('y'), long("year"), conflicts_with_all(["month", "year"]))]
+    /// Show whole current year
+    show_current_year: bool,
+
+    #[arg(short('3'), conflicts_with_all(["show_current_year", "months"]))]
+    /// Show the previous, current and next month
+    three: bool,
+
+    #[arg(
+        short('n'),
+        long,
+        value_name("N"),
+        value_parser = clap::value_parser!(u32).range(1..=12 * 9999),
+        conflicts_with("show_current_year")
+    )]
+    /// Show N months starting from the month, at most the months of 9999 years
+    months: Option<u32>,
+
+    #[arg(long, value_name("WHEN"), default_value_t)]
+    /// Highlight today: auto, always or never
+    color: ruty_core::ColorChoice,
+
+    #[arg(long, conflicts_with("week_start"))]
+    /// Weeks start on Monday
+    monday: bool,
+
+    #[arg(long, value_name("DAY"), value_parser = helpers::parse_weekday)]
+    /// Day the weeks start on, like sunday or mon
+    week_start: Option<chrono::Weekday>,
+
+    #[arg(short, long)]
+    /// Show the days of the year (Julian days) instead of the days of the month
+    julian: bool,
+
+    #[arg(short('w'), long)]
+    /// Show the ISO week numbers before the weeks
+    week_numbers: bool,
+}
+
+mod helpers {
+    use chrono::{Datelike, Local, NaiveDate, Weekday};
+    use ruty_core::color::{Painter, Style};
+
+    /// Weekday header names, from Sunday
+    const DAY_NAMES: [&str; 7] = ["Su", "Mo", "Tu", "We", "Th", "Fr", "Sa"];
+
+    /// How the months are drawn: the days of the year take cells of 3 characters instead of 2, and
+    /// the week numbers a column on the left
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub struct Layout {
+        pub julian: bool,
+        pub week_numbers: bool,
+    }
+
+    impl Layout {
+        fn cell_width(self) -> usize {
+            if self.julian { 3 } else { 2 }
+        }
+
+        /// Width of the week numbers column, with the space after it
+        fn margin(self) -> usize {
+            if self.week_numbers { 3 } else { 0 }
+        }
+
+        /// Width of the days of a week
+        fn week_width(self) -> usize {
+            7 * self.cell_width() + 6
+        }
+
+        /// Width of the lines of a month, with the two spaces that separate it from the next one
+        pub fn line_width(self) -> usize {
+            self.margin() + self.week_width() + 2
+        }
+    }
+
+    const MONTH_NAMES: [&str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ];
+
+    pub fn run(args: crate::Cli) -> anyhow::Result<()> {
+        let today = Local::now().date_naive();
+        let painter = Painter::stdout(args.color);
+        let week_start = if args.monday {
+            Weekday::Mon
+        } else {
+            args.week_start.unwrap_or(Weekday::Sun)
+        };
+        let layout = Layout {
+            julian: args.julian,
+            week_numbers: args.week_numbers,
+        };
+        let mut month = args.month.map(parse_month).transpose()?;
+        let mut year = args.year;
+
+        if args.show_current_year {
+            month = None;
+            year = Some(today.year_ce().1 as i32);
+        } else if month.is_none() && year.is_none() {
+            month = Some(today.month0() + 1);
+            year = Some(today.year_ce().1 as i32);
+        }
+
+        let year = year.unwrap_or(today.year_ce().1 as i32);
+
+        // A span of months starts on January when only the year is given
+        let span = if args.three {
+            Some((-1, 3))
+        } else {
+            args.months.map(|n| (0, n))
+        };
+        if let Some((offset, len)) = span {
+            let (year, month) = shift_month(year, month.unwrap_or(1), offset);
+            let months: Vec<(i32, u32)> = (0..len as i32)
+                .map(|i| shift_month(year, month, i))
+                .collect();
+            print_months(&months, true, today, week_start, layout, painter);
+            return Ok(());
+        }
+
+        match month {
+            None => {
+                print_whole_year(year, today, week_start, layout, painter);
+            }
+            Some(m) => {
+                print_month(year, m, today, week_start, layout, painter);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn print_whole_year(
+        year: i32,
+        today: NaiveDate,
+        week_start: Weekday,
+        layout: Layout,
+        painter: Painter,
+    ) {
+        println!("{}{}", " ".repeat((3 * layout.line_width() - 10) / 2), year);
+        let months: Vec<(i32, u32)> = (1..=12).map(|m| (year, m)).collect();
+        print_months(&months, false, today, week_start, layout, painter);
+    }
+
+    /// Prints the months side by side, three per row, with an empty line between the rows
+    pub fn print_months(
+        months: &[(i32, u32)],
+        print_year: bool,
+        today: NaiveDate,
+        week_start: Weekday,
+        layout: Layout,
+        painter: Painter,
+    ) {
+        for (i, chunk) in months.chunks(3).enumerate() {
+            if i != 0 {
+                println!();
+            }
+
+            let cols: Vec<Vec<String>> = chunk
+                .iter()
+                .map(|&(y, m)| format_month(y, m, print_year, today, week_start, layout, painter))
+                .collect();
+
+            for row in 0..8 {
+                let output: Vec<&str> = cols.iter().map(|col| col[row].as_str()).collect();
+                println!("{}", output.join(""));
+            }
+        }
+    }
+
+    /// Year and month `offset` months away from the month
+    pub fn shift_month(year: i32, month: u32, offset: i32) -> (i32, u32) {
+        let index = year * 12 + month as i32 - 1 + offset;
+        (index.div_euclid(12), index.rem_euclid(12) as u32 + 1)
+    }
+
+    pub fn print_month(
+        year: i32,
+        month: u32,
+        today: NaiveDate,
+        week_start: Weekday,
+        layout: Layout,
+        painter: Painter,
+    ) {
+        let output = format_month(year, month, true, today, week_start, layout, painter);
+        output.iter().for_each(|r| println!("{}", r));
+    }
+
+    pub fn parse_weekday(day: &str) -> Result<Weekday, String> {
+        day.parse().map_err(|_| {
+            format!(
+                r#"Invalid day "{}", expected a day of the week like monday or mon"#,
+                day
+            )
+        })
+    }
+
+    pub fn parse_month(month: String) -> anyhow::Result<u32> {
+        match month.parse::<u32>() {
+            Err(_) => {
+                let num_candidates = MONTH_NAMES
+                    .iter()
+                    .filter(|m| m.to_lowercase().starts_with(&month.to_lowercase()))
+                    .count();
+
+                if num_candidates > 1 {
+                    anyhow::bail!(r#"Invalid month "{}""#, month);
+                }
+
+                match MONTH_NAMES
+                    .iter()
+                    .enumerate()
+                    .find(|(_, m)| m.to_lowercase().starts_with(&month.to_lowercase()))
+                {
+                    None => {
+                        anyhow::bail!(r#"Invalid month "{}""#, month);
+                    }
+                    Some((i, _)) => Ok((i + 1) as u32),
+                }
+            }
+            Ok(val) => {
+                if (1..=12).contains(&val) {
+                    Ok(val)
+                } else {
+                    anyhow::bail!(r#"month "{}" not in the range 1 through 12"#, val);
+                }
+            }
+        }
+    }
+
+    pub fn format_month(
+        year: i32,
+        month: u32,
+        print_year: bool,
+        today: NaiveDate,
+        week_start: Weekday,
+        layout: Layout,
+        painter: Painter,
+    ) -> Vec<String> {
+        let mut output: Vec<String> = Vec::new();
+        let width = layout.cell_width();
+
+        // Store Header row
+        output.push(generate_header_row(year, month, print_year, layout));
+
+        // Store weekday_row
+        output.push(generate_weekday_row(week_start, layout));
+
+        // Format row of days
+        let mut week_row: Vec<String> = Vec::new();
+        let first_day_in_month = NaiveDate::from_ymd_opt(year, month, 1).expect("Valid data");
+        let days_in_month = first_day_in_month.num_days_in_month() as usize;
+
+        // Week number of the row, in the margin
+        let margin = |d: NaiveDate| {
+            if layout.week_numbers {
+                format!("{:>2} ", week_number(d, week_start))
+            } else {
+                String::new()
+            }
+        };
+
+        first_day_in_month
+            .iter_days()
+            .take(days_in_month)
+            .for_each(|d| {
+                let number = if layout.julian { d.ordinal() } else { d.day() };
+                let day = format!("{:>width$}", number);
+
+                if today == d {
+                    week_row.push(painter.paint(Style::new().reverse(), &day));
+                } else {
+                    week_row.push(day);
+                }
+
+                if d.weekday() == week_start.pred() {
+                    let num_d = 7 - week_row.len();
+
+                    // Pad days in week
+                    for _ in 0..num_d {
+                        week_row.insert(0, " ".repeat(width));
+                    }
+
+                    week_row.push(String::from(" "));
+
+                    // Push to output
+                    output.push(margin(d) + &week_row.join(" "));
+
+                    // Clean vector
+                    week_row.clear();
+                } else if d.day() == days_in_month as u32 {
+                    let num_d = 7 - week_row.len();
+
+                    // Pad days in week
+                    for _ in 0..num_d {
+                        week_row.push(" ".repeat(width));
+                    }
+
+                    week_row.push(String::from(" "));
+
+                    // Push to output
+                    output.push(margin(d) + &week_row.join(" "));
+
+                    // Clean vector
+                    week_row.clear();
+                }
+            });
+
+        while output.len() < 8 {
+            output.push(" ".repeat(layout.line_width()));
+        }
+
+        output
+    }
+
+    /// ISO week number of the row of `day`, the one of its Thursday, which is the one of all its
+    /// days when the weeks start on Monday
+    pub fn week_number(day: NaiveDate, week_start: Weekday) -> u32 {
+        let from_start = day.weekday().days_since(week_start);
+        let thursday = Weekday::Thu.days_since(week_start);
+        let thursday =
+            day + chrono::Days::new(thursday.into()) - chrono::Days::new(from_start.into());
+
+        thursday.iso_week().week()
+    }
+
+    /// Names of the days of the week, starting on `week_start`
+    pub fn generate_weekday_row(week_start: Weekday, layout: Layout) -> String {
+        let start = week_start.num_days_from_sunday() as usize;
+        let width = layout.cell_width();
+        let names: Vec<String> = (0..7)
+            .map(|i| format!("{:>width$}", DAY_NAMES[(start + i) % 7]))
+            .collect();
+
+        format!("{}{}  ", " ".repeat(layout.margin()), names.join(" "))
+    }
+
+    #[allow(dead_code)]
+    fn generate_header_row(year: i32, month: u32, print_year: bool, layout: Layout) -> String {
+        let mut header_row: String = " ".repeat(layout.line_width() - 3);
+
+        // Format header row
+        let header = if print_year {
+            format!(
+                "{} {}",
+                MONTH_NAMES
+                    .get((month - 1) as usize)
+                    .expect("Month is previously checked"),
+                year
+            )
+        } else {
+            MONTH_NAMES
+                .get((month - 1) as usize)
+                .expect("Month is previously checked")
+                .to_string()
+        };
+
+        let n = layout.margin() + (layout.week_width() - header.len()) / 2;
+        header_row.insert_str(n, &header);
+        header_row.truncate(layout.line_width());
+
+        header_row
+    }
+
+    #[allow(dead_code)]
+    pub fn last_day_in_month(year: i32, month: u32) -> Option<NaiveDate> {
+        // The first day of the next month ...
+        let (y, m): (i32, i32) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month as i32 + 1)
+        };
+
+        match NaiveDate::from_ymd_opt(y, m as u32, 1) {
+            None => None,
+            Some(d) => {
+                // ...is preceded by the last day of the original month
+                d.pred_opt()
+            }
+        }
+    }
+}
+
+pub use helpers::run;
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{
+        Layout, format_month, generate_weekday_row, last_day_in_month, parse_month, shift_month,
+        week_number,
+    };
+    use chrono::prelude::*;
+    use ruty_core::Painter;
+
+    #[test]
+    fn test_parse_month() {
+        let res = parse_month("1".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1u32);
+
+        let res = parse_month("12".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 12u32);
+
+        let res = parse_month("jan".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1u32);
+
+        let res = parse_month("0".to_string());
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            r#"month "0" not in the range 1 through 12"#
+        );
+
+        let res = parse_month("13".to_string());
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            r#"month "13" not in the range 1 through 12"#
+        );
+
+        let res = parse_month("foo".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"Invalid month "foo""#);
+    }
+
+    #[test]
+    fn test_format_month() {
+        let today = NaiveDate::from_ymd_opt(0, 1, 1).unwrap();
+        let leap_february = vec![
+            "   February 2020      ",
+            "Su Mo Tu We Th Fr Sa  ",
+            "                   1  ",
+            " 2  3  4  5  6  7  8  ",
+            " 9 10 11 12 13 14 15  ",
+            "16 17 18 19 20 21 22  ",
+            "23 24 25 26 27 28 29  ",
+            "                      ",
+        ];
+        assert_eq!(
+            format_month(
+                2020,
+                2,
+                true,
+                today,
+                Weekday::Sun,
+                Layout::default(),
+                Painter::new(true)
+            ),
+            leap_february
+        );
+
+        let may = vec![
+            "        May           ",
+            "Su Mo Tu We Th Fr Sa  ",
+            "                1  2  ",
+            " 3  4  5  6  7  8  9  ",
+            "10 11 12 13 14 15 16  ",
+            "17 18 19 20 21 22 23  ",
+            "24 25 26 27 28 29 30  ",
+            "31                    ",
+        ];
+        assert_eq!(
+            format_month(
+                2020,
+                5,
+                false,
+                today,
+                Weekday::Sun,
+                Layout::default(),
+                Painter::new(true)
+            ),
+            may
+        );
+
+        let april_hl = vec![
+            "     April 2021       ",
+            "Su Mo Tu We Th Fr Sa  ",
+            "             1  2  3  ",
+            " 4  5  6 \u{1b}[7m 7\u{1b}[0m  8  9 10  ",
+            "11 12 13 14 15 16 17  ",
+            "18 19 20 21 22 23 24  ",
+            "25 26 27 28 29 30     ",
+            "                      ",
+        ];
+        let today = NaiveDate::from_ymd_opt(2021, 4, 7).unwrap();
+        assert_eq!(
+            format_month(
+                2021,
+                4,
+                true,
+                today,
+                Weekday::Sun,
+                Layout::default(),
+                Painter::new(true)
+            ),
+            april_hl
+        );
+
+        let april = format_month(
+            2021,
+            4,
+            true,
+            today,
+            Weekday::Sun,
+            Layout::default(),
+            Painter::new(false),
+        );
+        assert_eq!(april[3], " 4  5  6  7  8  9 10  ");
+
+        // Weeks starting on Monday
+        let may_monday = vec![
+            "        May           ",
+            "Mo Tu We Th Fr Sa Su  ",
+            "             1  2  3  ",
+            " 4  5  6  7  8  9 10  ",
+            "11 12 13 14 15 16 17  ",
+            "18 19 20 21 22 23 24  ",
+            "25 26 27 28 29 30 31  ",
+            "                      ",
+        ];
+        assert_eq!(
+            format_month(
+                2020,
+                5,
+                false,
+                today,
+                Weekday::Mon,
+                Layout::default(),
+                Painter::new(true)
+            ),
+            may_monday
+        );
+
+        // A month that starts on the first day of the week has no padding
+        let june_monday = format_month(
+            2020,
+            6,
+            true,
+            today,
+            Weekday::Mon,
+            Layout::default(),
+            Painter::new(true),
+        );
+        assert_eq!(june_monday[2], " 1  2  3  4  5  6  7  ");
+        assert_eq!(june_monday[6], "29 30                 ");
+    }
+
+    #[test]
+    fn test_format_month_layouts() {
+        let today = NaiveDate::from_ymd_opt(0, 1, 1).unwrap();
+        let julian = Layout {
+            julian: true,
+            week_numbers: false,
+        };
+        let leap_february = vec![
+            "       February 2020         ",
+            " Su  Mo  Tu  We  Th  Fr  Sa  ",
+            "                         32  ",
+            " 33  34  35  36  37  38  39  ",
+            " 40  41  42  43  44  45  46  ",
+            " 47  48  49  50  51  52  53  ",
+            " 54  55  56  57  58  59  60  ",
+            "                             ",
+        ];
+        assert_eq!(
+            format_month(
+                2020,
+                2,
+                true,
+                today,
+                Weekday::Sun,
+                julian,
+                Painter::new(true)
+            ),
+            leap_february
+        );
+
+        let weeks = Layout {
+            julian: false,
+            week_numbers: true,
+        };
+        let january_monday = vec![
+            "       January 2021      ",
+            "   Mo Tu We Th Fr Sa Su  ",
+            "53              1  2  3  ",
+            " 1  4  5  6  7  8  9 10  ",
+            " 2 11 12 13 14 15 16 17  ",
+            " 3 18 19 20 21 22 23 24  ",
+            " 4 25 26 27 28 29 30 31  ",
+            "                         ",
+        ];
+        assert_eq!(
+            format_month(
+                2021,
+                1,
+                true,
+                today,
+                Weekday::Mon,
+                weeks,
+                Painter::new(true)
+            ),
+            january_monday
+        );
+        assert_eq!(julian.line_width(), 29);
+        assert_eq!(weeks.line_width(), 25);
+    }
+
+    #[test]
+    fn test_week_number() {
+        // The week of the Thursday, whatever the day the weeks start on
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(week_number(day(2021, 1, 1), Weekday::Mon), 53);
+        assert_eq!(week_number(day(2021, 1, 4), Weekday::Mon), 1);
+        assert_eq!(week_number(day(2020, 2, 1), Weekday::Sun), 5);
+        assert_eq!(week_number(day(2020, 2, 2), Weekday::Sun), 6);
+        assert_eq!(week_number(day(2021, 1, 2), Weekday::Sun), 53);
+        assert_eq!(week_number(day(2021, 1, 3), Weekday::Sun), 1);
+    }
+
+    #[test]
+    fn test_generate_weekday_row() {
+        assert_eq!(
+            generate_weekday_row(Weekday::Sun, Layout::default()),
+            "Su Mo Tu We Th Fr Sa  "
+        );
+        assert_eq!(
+            generate_weekday_row(Weekday::Mon, Layout::default()),
+            "Mo Tu We Th Fr Sa Su  "
+        );
+        assert_eq!(
+            generate_weekday_row(Weekday::Sat, Layout::default()),
+            "Sa Su Mo Tu We Th Fr  "
+        );
+    }
+
+    #[test]
+    fn test_shift_month() {
+        assert_eq!(shift_month(2020, 5, 0), (2020, 5));
+        assert_eq!(shift_month(2020, 12, 1), (2021, 1));
+        assert_eq!(shift_month(2020, 1, -1), (2019, 12));
+        assert_eq!(shift_month(2020, 3, 25), (2022, 4));
+        assert_eq!(shift_month(2020, 3, -27), (2017, 12));
+    }
+
+    #[test]
+    fn test_last_day_in_month() {
+        assert_eq!(
+            last_day_in_month(2020, 1),
+            NaiveDate::from_ymd_opt(2020, 1, 31)
+        );
+        assert_eq!(
+            last_day_in_month(2020, 2),
+            NaiveDate::from_ymd_opt(2020, 2, 29)
+        );
+        assert_eq!(
+            last_day_in_month(2020, 4),
+            NaiveDate::from_ymd_opt(2020, 4, 30)
+        );
+    }
+}
@@ -195,6 +195,43 @@ fn default_one_month() -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn default_redirected_is_not_colored() -> Result<()> {
+    let cmd = Command::cargo_bin(PRG)?.assert().success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(!stdout.contains('\u{1b}'));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn color_always_highlights_today() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .arg("--color=always")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{1b}[7m"));
+
+    Command::cargo_bin(PRG)?
+        .arg("--color=never")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{1b}").not());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_invalid_color() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .arg("--color=yes")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected auto, always or never"));
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn test_2_2020_leap_year() -> Result<()> {
@@ -219,6 +256,36 @@ fn test_2020() -> Result<()> {
     run(&["2020"], "tests/expected/2020.txt")
 }
 
+// --------------------------------------------------
+#[test]
+fn test_2_2020_monday() -> Result<()> {
+    run(
+        &["--monday", "-m", "2", "2020"],
+        "tests/expected/2-2020-monday.txt",
+    )?;
+    run(
+        &["--week-start", "Mon", "-m", "2", "2020"],
+        "tests/expected/2-2020-monday.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_2020_monday() -> Result<()> {
+    run(&["--monday", "2020"], "tests/expected/2020-monday.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_invalid_week_start() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--week-start", "foo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"Invalid day "foo""#));
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn year() -> Result<()> {
@@ -228,3 +295,102 @@ fn year() -> Result<()> {
     assert_eq!(lines.len(), 37);
     Ok(())
 }
+
+// --------------------------------------------------
+#[test]
+fn test_1_2020_three() -> Result<()> {
+    run(
+        &["-3", "-m", "1", "2020"],
+        "tests/expected/1-2020-three.txt",
+    )?;
+    run(
+        &["-n", "3", "-m", "12", "2019"],
+        "tests/expected/1-2020-three.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_11_2020_four_months() -> Result<()> {
+    run(
+        &["--months", "4", "-m", "11", "2020"],
+        "tests/expected/11-2020-n4.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn default_three() -> Result<()> {
+    let cmd = Command::cargo_bin(PRG)?.arg("-3").assert().success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let lines: Vec<&str> = stdout.split('\n').collect();
+    assert_eq!(lines.len(), 9);
+    assert_eq!(lines[0].len(), 66);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_three_and_months() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-3", "-n", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_months_0() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--months <N>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_too_many_months() -> Result<()> {
+    for months in ["120000", "3000000000"] {
+        Command::cargo_bin(PRG)?
+            .args(["-n", months])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "invalid value '{months}' for '--months <N>'"
+            )));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_2_2020_julian() -> Result<()> {
+    run(
+        &["-j", "-m", "2", "2020"],
+        "tests/expected/2-2020-julian.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_12_2020_julian_week_numbers() -> Result<()> {
+    run(
+        &["--julian", "--week-numbers", "-m", "12", "2020"],
+        "tests/expected/12-2020-julian-weeks.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_1_2021_week_numbers_monday() -> Result<()> {
+    run(
+        &["-w", "--monday", "-m", "1", "2021"],
+        "tests/expected/1-2021-weeks-monday.txt",
+    )
+}
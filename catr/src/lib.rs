@@ -0,0 +1,219 @@
+//! Rust version of `cat`, the `run` function is called by the `catr` binary and by `ruty`
+
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+#[command(version, about)]
+/// Rust version of `cat`
+pub struct Args {
+    /// Input file(s), - or none for stdin
+    #[arg(default_value = "-")]
+    files: Vec<String>,
+    /// Read the names of the input files from FILE, or from stdin when FILE is -, separated by NULs
+    /// like the output of find -print0 or else by newlines
+    #[arg(long("files-from"), value_name = "FILE", conflicts_with = "files")]
+    files_from: Option<String>,
+    /// Number lines
+    #[arg(short('n'), long("number"))]
+    number_lines: bool,
+    /// Number non-blanck lines
+    #[arg(short('b'), long("number-nonblank"), conflicts_with = "number_lines")]
+    number_nonblank_lines: bool,
+    /// Equivalent to -vET
+    #[arg(short('A'), long("show-all"))]
+    show_all: bool,
+    /// Display $ at the end of each line
+    #[arg(short('E'), long("show-ends"))]
+    show_ends: bool,
+    /// Display tabs as ^I
+    #[arg(short('T'), long("show-tabs"))]
+    show_tabs: bool,
+    /// Use ^ and M- notation, except for line ends and tabs
+    #[arg(short('v'), long("show-nonprinting"))]
+    show_nonprinting: bool,
+    /// Suppress repeated empty lines
+    #[arg(short('s'), long("squeeze-blank"))]
+    squeeze_blank: bool,
+}
+
+mod helpers {
+    use ruty_core::open_maybe_compressed;
+    use std::io::{BufRead, Cursor, Read};
+
+    /// How the lines are displayed, as they are unless one of the --show options is given.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct Show {
+        pub ends: bool,
+        pub tabs: bool,
+        pub nonprinting: bool,
+    }
+
+    impl Show {
+        pub fn from_args(args: &crate::Args) -> Self {
+            Show {
+                ends: args.show_all || args.show_ends,
+                tabs: args.show_all || args.show_tabs,
+                nonprinting: args.show_all || args.show_nonprinting,
+            }
+        }
+
+        /// Escapes the line like GNU cat: control characters as ^X, DEL as ^? and the bytes
+        /// above 127 as M- followed by the escaped byte without its high bit.
+        pub fn line(&self, line: &[u8]) -> String {
+            let mut out: Vec<u8> = Vec::with_capacity(line.len());
+
+            // The carriage return of a Windows line end is shown with the end, as ^M$
+            let (line, carriage_return) = match line.strip_suffix(b"\r") {
+                Some(line) if self.ends => (line, true),
+                _ => (line, false),
+            };
+
+            for &byte in line {
+                match byte {
+                    b'\t' if self.tabs => out.extend_from_slice(b"^I"),
+                    b'\t' => out.push(byte),
+                    _ if !self.nonprinting => out.push(byte),
+                    128.. => {
+                        out.extend_from_slice(b"M-");
+                        caret(byte - 128, &mut out);
+                    }
+                    _ => caret(byte, &mut out),
+                }
+            }
+
+            if carriage_return {
+                out.extend_from_slice(b"^M");
+            }
+
+            if self.ends {
+                out.push(b'$');
+            }
+
+            String::from_utf8_lossy(&out).into_owned()
+        }
+    }
+
+    /// Which lines are numbered
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Numbering {
+        None,
+        All,
+        NonBlank,
+    }
+
+    /// Turns the lines of a file into the lines printed, it keeps the line number and whether the
+    /// previous line was empty for --squeeze-blank.
+    #[derive(Debug)]
+    pub struct LinePrinter {
+        show: Show,
+        numbering: Numbering,
+        squeeze_blank: bool,
+        number: usize,
+        previous_blank: bool,
+    }
+
+    impl LinePrinter {
+        pub fn from_args(args: &crate::Args) -> Self {
+            let numbering = if args.number_lines {
+                Numbering::All
+            } else if args.number_nonblank_lines {
+                Numbering::NonBlank
+            } else {
+                Numbering::None
+            };
+
+            LinePrinter {
+                show: Show::from_args(args),
+                numbering,
+                squeeze_blank: args.squeeze_blank,
+                number: 0,
+                previous_blank: false,
+            }
+        }
+
+        /// The line to print, `None` for an empty line that follows another one with --squeeze-blank
+        pub fn line(&mut self, line: &[u8]) -> Option<String> {
+            let blank = line.is_empty();
+            if self.squeeze_blank && blank && self.previous_blank {
+                return None;
+            }
+            self.previous_blank = blank;
+
+            let numbered = match self.numbering {
+                Numbering::None => false,
+                Numbering::All => true,
+                Numbering::NonBlank => !blank,
+            };
+
+            if numbered {
+                self.number += 1;
+                Some(format!("{:>6}\t{}", self.number, self.show.line(line)))
+            } else {
+                Some(self.show.line(line))
+            }
+        }
+    }
+
+    /// Pushes an ASCII byte, in ^ notation when it is a control character
+    fn caret(byte: u8, out: &mut Vec<u8>) {
+        match byte {
+            ..32 => out.extend_from_slice(&[b'^', byte + 64]),
+            127 => out.extend_from_slice(b"^?"),
+            _ => out.push(byte),
+        }
+    }
+
+    /// Method for performing the main logic of the command-line.
+    pub fn run(args: &crate::Args) -> anyhow::Result<()> {
+        let files = match &args.files_from {
+            Some(from) => read_files_from(from)?,
+            None => args.files.clone(),
+        };
+
+        files
+            .iter()
+            .for_each(|file| match open_maybe_compressed(file) {
+                Err(err) => eprintln!("Failed to open {}: {}", file, err),
+                Ok(handler) => read(handler, LinePrinter::from_args(args)),
+            });
+        Ok(())
+    }
+
+    /// Reads the file names given to --files-from, separated by NULs when there is any and else by
+    /// newlines. Empty names are skipped, and so is `-` when the names come from stdin.
+    pub fn read_files_from(from: &str) -> anyhow::Result<Vec<String>> {
+        let mut list: Vec<u8> = Vec::new();
+        ruty_core::open_input(from)
+            .and_then(|mut reader| reader.read_to_end(&mut list))
+            .map_err(|e| anyhow::anyhow!("{}: {}", from, e))?;
+
+        let delimiter = if list.contains(&0) { 0 } else { b'\n' };
+        let mut files = Vec::new();
+
+        for name in ruty_core::Records::new(Cursor::new(list), delimiter) {
+            let name = String::from_utf8_lossy(&name?).into_owned();
+
+            if name == "-" && from == "-" {
+                eprintln!("when reading file names from stdin, no file name of '-' allowed");
+            } else if !name.is_empty() {
+                files.push(name);
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Private function for printing the text inside a file, the lines numbered from 1 in each file.
+    fn read(handler: Box<dyn BufRead>, mut printer: LinePrinter) {
+        // For failing lines read we opt for passing an empty line,
+        // the error is rare and the alternative is too much aggresive.
+        for line in ruty_core::lines(handler).map(|l| l.unwrap_or_default()) {
+            if let Some(out) = printer.line(&line) {
+                println!("{}", out);
+            }
+        }
+    }
+}
+
+pub use helpers::run;
@@ -2,7 +2,7 @@ use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
-use rand::{distr::Alphanumeric, Rng};
+use rand::{Rng, distr::Alphanumeric};
 use std::fs;
 
 const PRG: &str = "catr";
@@ -10,6 +10,8 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const NONPRINTING: &str = "tests/inputs/nonprinting.txt";
+const BLANKS: &str = "tests/inputs/blanks.txt";
 
 // --------------------------------------------------
 #[test]
@@ -197,3 +199,139 @@ fn all_n() -> Result<()> {
 fn all_b() -> Result<()> {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+#[cfg(feature = "compressed")]
+fn fox_gzip() -> Result<()> {
+    run(&["tests/inputs/fox.txt.gz"], "tests/expected/fox.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_all() -> Result<()> {
+    run(&[NONPRINTING, "-A"], "tests/expected/nonprinting.txt.A.out")?;
+    run(
+        &[NONPRINTING, "-vET"],
+        "tests/expected/nonprinting.txt.A.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_ends() -> Result<()> {
+    run(
+        &[NONPRINTING, "--show-ends"],
+        "tests/expected/nonprinting.txt.E.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_tabs() -> Result<()> {
+    run(
+        &[NONPRINTING, "--show-tabs"],
+        "tests/expected/nonprinting.txt.T.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_nonprinting() -> Result<()> {
+    run(&[NONPRINTING, "-v"], "tests/expected/nonprinting.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_b_show_all() -> Result<()> {
+    run(
+        &[NONPRINTING, "-bA"],
+        "tests/expected/nonprinting.txt.bA.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_squeeze_blank() -> Result<()> {
+    run(&[BLANKS, "-s"], "tests/expected/blanks.txt.s.out")?;
+    run(
+        &[BLANKS, "--squeeze-blank"],
+        "tests/expected/blanks.txt.s.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_squeeze_blank_n() -> Result<()> {
+    run(&[BLANKS, "-sn"], "tests/expected/blanks.txt.sn.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_squeeze_blank_b() -> Result<()> {
+    run_stdin(BLANKS, &["-sb", "-"], "tests/expected/blanks.txt.sb.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_stdin_no_args() -> Result<()> {
+    run_stdin(BUSTLE, &[], "tests/expected/the-bustle.txt.stdin.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_among_files() -> Result<()> {
+    run_stdin(
+        BUSTLE,
+        &[FOX, "-", SPIDERS],
+        "tests/expected/fox-stdin-spiders.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn files_from_lines() -> Result<()> {
+    run(
+        &["--files-from", "tests/inputs/files.txt"],
+        "tests/expected/all.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn files_from_stdin_nul() -> Result<()> {
+    // Like the output of find -print0
+    let expected = fs::read_to_string("tests/expected/all.n.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["-n", "--files-from", "-"])
+        .write_stdin(format!("{}\0{}\0\0-\0{}\0", FOX, SPIDERS, BUSTLE))
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr(predicate::str::contains("no file name of '-' allowed"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files_from_with_files() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--files-from", "tests/inputs/files.txt", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_files_from() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = format!("{bad}: .* [(]os error 2[)]");
+    Command::cargo_bin(PRG)?
+        .args(["--files-from", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
@@ -0,0 +1,307 @@
+//! Rust version of `comm`, the `run` function is called by the `commr` binary and by `ruty`
+
+use clap::{ArgAction, Parser};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `comm`
+pub struct Cli {
+    #[arg(value_name = "FILE1")]
+    /// Input file 1
+    file1: String,
+
+    #[arg(value_name = "FILE2")]
+    /// Input file 2
+    file2: String,
+
+    #[arg(short('1'), action=ArgAction::SetFalse)]
+    /// Suppress printing of column 1 (lines unique to FILE1)
+    show_col1: bool,
+
+    #[arg(short('2'), action=ArgAction::SetFalse)]
+    /// Suppress printing of column 2 (lines unique to FILE2)
+    show_col2: bool,
+
+    #[arg(short('3'), action=ArgAction::SetFalse)]
+    /// Suppress printing of column 3 (lines that appear in both files)
+    show_col3: bool,
+
+    #[arg(short('i'))]
+    /// Case-insensitive comparison of lines
+    insensitive: bool,
+
+    #[arg(short('d'), long("output-delimiter"), default_value_t = String::from("\t"))]
+    /// Output delimiter
+    delimiter: String,
+
+    #[arg(long)]
+    /// Print a summary with the number of lines of each column
+    total: bool,
+
+    #[arg(long, overrides_with = "nocheck_order")]
+    /// Fail when an input is not sorted, instead of warning about it
+    check_order: bool,
+
+    #[arg(long, overrides_with = "check_order")]
+    /// Do not check that the inputs are sorted
+    nocheck_order: bool,
+
+    #[arg(short('z'), long)]
+    /// Line delimiter is NUL, not newline
+    zero_terminated: bool,
+}
+
+mod helper {
+    use ruty_core::{Records, open_input};
+    use std::cmp::Ordering;
+    use std::io::{self, BufRead};
+
+    pub fn run(args: super::Cli) -> anyhow::Result<()> {
+        if args.file1 == "-" && args.file2 == "-" {
+            anyhow::bail!(r#"Both input files cannot be STDIN ("-")"#);
+        }
+
+        let fh1 = open(&args.file1)?;
+        let fh2 = open(&args.file2)?;
+
+        let check = if args.check_order {
+            CheckOrder::Fail
+        } else if args.nocheck_order {
+            CheckOrder::Skip
+        } else {
+            CheckOrder::Warn
+        };
+
+        let delimiter = if args.zero_terminated { b'\0' } else { b'\n' };
+        let mut comm = Comm::new(fh1, fh2, delimiter, args.insensitive, check);
+        let mut totals = [0; 3];
+
+        for column in comm.by_ref() {
+            let column = column?;
+            totals[column.index()] += 1;
+
+            print_format(
+                &column,
+                args.show_col1,
+                args.show_col2,
+                args.show_col3,
+                &args.delimiter,
+                char::from(delimiter),
+            );
+        }
+
+        if args.total {
+            let totals: Vec<String> = totals.iter().map(u64::to_string).collect();
+            let totals = totals.join(&args.delimiter);
+            print!("{}{}total{}", totals, args.delimiter, char::from(delimiter));
+        }
+
+        if comm.unsorted.contains(&true) {
+            anyhow::bail!("input is not in sorted order");
+        }
+
+        Ok(())
+    }
+
+    fn open(filename: &str) -> anyhow::Result<Box<dyn BufRead>> {
+        open_input(filename).map_err(|e| anyhow::anyhow!("{}: {}", filename, e))
+    }
+
+    /// Line of the output, in the column of the file or files it was found in
+    enum Column {
+        Col1(String),
+        Col2(String),
+        Col3(String),
+    }
+
+    impl Column {
+        fn index(&self) -> usize {
+            match self {
+                Column::Col1(_) => 0,
+                Column::Col2(_) => 1,
+                Column::Col3(_) => 2,
+            }
+        }
+    }
+
+    /// What to do with a line that goes before the previous line of its file
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum CheckOrder {
+        /// Warn once for each file and fail at the end, like GNU comm
+        Warn,
+        Fail,
+        Skip,
+    }
+
+    /// Merges two sorted inputs line by line, so only the current line of each one is kept in memory
+    struct Comm {
+        lines1: Records<Box<dyn BufRead>>,
+        lines2: Records<Box<dyn BufRead>>,
+        line1: Option<String>,
+        line2: Option<String>,
+        /// Byte the lines end with, newline or NUL
+        delimiter: u8,
+        insensitive: bool,
+        check: CheckOrder,
+        /// Last line read of each input, to check the order of the next one
+        previous: [Option<String>; 2],
+        /// Inputs found not to be sorted
+        unsorted: [bool; 2],
+    }
+
+    impl Comm {
+        fn new(
+            file1: Box<dyn BufRead>,
+            file2: Box<dyn BufRead>,
+            delimiter: u8,
+            insensitive: bool,
+            check: CheckOrder,
+        ) -> Self {
+            Comm {
+                lines1: Records::new(file1, delimiter),
+                lines2: Records::new(file2, delimiter),
+                line1: None,
+                line2: None,
+                delimiter,
+                insensitive,
+                check,
+                previous: [None, None],
+                unsorted: [false, false],
+            }
+        }
+
+        /// Reads the next line of the inputs whose current line was already merged
+        fn fill(&mut self) -> io::Result<()> {
+            if self.line1.is_none() {
+                let line = self
+                    .lines1
+                    .next()
+                    .map(|line| self.decode(line))
+                    .transpose()?;
+                self.check_order(0, line.as_deref())?;
+                self.line1 = line;
+            }
+            if self.line2.is_none() {
+                let line = self
+                    .lines2
+                    .next()
+                    .map(|line| self.decode(line))
+                    .transpose()?;
+                self.check_order(1, line.as_deref())?;
+                self.line2 = line;
+            }
+            Ok(())
+        }
+
+        /// Lines must be valid UTF-8, and like `BufRead::lines` a '\r' before the newline is dropped
+        fn decode(&self, line: io::Result<Vec<u8>>) -> io::Result<String> {
+            let mut line = line?;
+            if self.delimiter == b'\n' && line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            String::from_utf8(line).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "stream did not contain valid UTF-8",
+                )
+            })
+        }
+
+        /// Checks that the line doesn't go before the previous line of its input
+        fn check_order(&mut self, input: usize, line: Option<&str>) -> io::Result<()> {
+            let Some(line) = line else {
+                return Ok(());
+            };
+            if self.check == CheckOrder::Skip {
+                return Ok(());
+            }
+
+            let previous = self.previous[input].replace(line.to_string());
+            if previous.is_some_and(|p| compare(&p, line, self.insensitive) == Ordering::Greater) {
+                let message = format!("file {} is not in sorted order", input + 1);
+
+                match self.check {
+                    CheckOrder::Fail => return Err(io::Error::other(message)),
+                    _ if !self.unsorted[input] => eprintln!("{}", message),
+                    _ => {}
+                }
+                self.unsorted[input] = true;
+            }
+
+            Ok(())
+        }
+    }
+
+    impl Iterator for Comm {
+        type Item = io::Result<Column>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if let Err(e) = self.fill() {
+                return Some(Err(e));
+            }
+
+            let column = match (self.line1.take(), self.line2.take()) {
+                (None, None) => return None,
+                (Some(line1), None) => Column::Col1(line1),
+                (None, Some(line2)) => Column::Col2(line2),
+                (Some(line1), Some(line2)) => match compare(&line1, &line2, self.insensitive) {
+                    Ordering::Less => {
+                        self.line2 = Some(line2);
+                        Column::Col1(line1)
+                    }
+                    Ordering::Greater => {
+                        self.line1 = Some(line1);
+                        Column::Col2(line2)
+                    }
+                    Ordering::Equal => Column::Col3(line1),
+                },
+            };
+
+            Some(Ok(column))
+        }
+    }
+
+    fn print_format(
+        column: &Column,
+        show_col1: bool,
+        show_col2: bool,
+        show_col3: bool,
+        delimiter: &str,
+        terminator: char,
+    ) {
+        let mut columns: Vec<&str> = Vec::new();
+
+        match column {
+            Column::Col1(line) if show_col1 => columns.push(line),
+            Column::Col2(line) if show_col2 => {
+                if show_col1 {
+                    columns.push("");
+                }
+                columns.push(line);
+            }
+            Column::Col3(line) if show_col3 => {
+                if show_col1 {
+                    columns.push("");
+                }
+                if show_col2 {
+                    columns.push("");
+                }
+                columns.push(line);
+            }
+            _ => return,
+        }
+
+        print!("{}{}", columns.join(delimiter), terminator);
+    }
+
+    fn compare(a: &str, b: &str, insensitive: bool) -> Ordering {
+        if insensitive {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        } else {
+            a.cmp(b)
+        }
+    }
+}
+
+pub use helper::run;
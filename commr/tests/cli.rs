@@ -2,14 +2,18 @@ use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
-use rand::{distr::Alphanumeric, Rng};
+use rand::{Rng, distr::Alphanumeric};
 use std::fs;
 
 const PRG: &str = "commr";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FILE1: &str = "tests/inputs/file1.txt";
 const FILE2: &str = "tests/inputs/file2.txt";
+const FILE3: &str = "tests/inputs/file3.txt";
 const BLANK: &str = "tests/inputs/blank.txt";
+const UNSORTED: &str = "tests/inputs/unsorted.txt";
+const NAMES1: &str = "tests/inputs/names1.txt";
+const NAMES2: &str = "tests/inputs/names2.txt";
 
 // --------------------------------------------------
 #[test]
@@ -338,3 +342,89 @@ fn file1_file2_123_delim() -> Result<()> {
 fn blank_file1() -> Result<()> {
     run(&[BLANK, FILE1], "tests/expected/blank_file1.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn file3_file1() -> Result<()> {
+    // A repeated line only matches as many times as it is in the other file
+    run(&[FILE3, FILE1], "tests/expected/file3_file1.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn file1_file2_total() -> Result<()> {
+    run(
+        &[FILE1, FILE2, "--total"],
+        "tests/expected/file1_file2.total.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn file1_file2_12_total_delim() -> Result<()> {
+    run(
+        &[FILE1, FILE2, "-12", "--total", "-d", ":"],
+        "tests/expected/file1_file2.12.total.delim.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn unsorted_file1() -> Result<()> {
+    // All the lines are printed, with a warning for each unsorted file
+    let expected = fs::read_to_string("tests/expected/unsorted_file1.out")?;
+    Command::cargo_bin(PRG)?
+        .args([UNSORTED, FILE1])
+        .assert()
+        .failure()
+        .stdout(expected)
+        .stderr("file 1 is not in sorted order\ninput is not in sorted order\n");
+
+    Command::cargo_bin(PRG)?
+        .args([FILE1, UNSORTED])
+        .assert()
+        .failure()
+        .stderr("file 2 is not in sorted order\ninput is not in sorted order\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unsorted_file1_check_order() -> Result<()> {
+    // Stops at the first unsorted line
+    let expected = fs::read_to_string("tests/expected/unsorted_file1.check_order.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--check-order", UNSORTED, FILE1])
+        .assert()
+        .failure()
+        .stdout(expected)
+        .stderr("file 1 is not in sorted order\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unsorted_file1_nocheck_order() -> Result<()> {
+    run(
+        &["--nocheck-order", UNSORTED, FILE1],
+        "tests/expected/unsorted_file1.out",
+    )?;
+    run(
+        &["--check-order", "--nocheck-order", UNSORTED, FILE1],
+        "tests/expected/unsorted_file1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn names1_names2_zero_terminated() -> Result<()> {
+    // The names are separated by NUL, one has a newline in it
+    run(
+        &["-z", NAMES1, NAMES2],
+        "tests/expected/names1_names2.z.out",
+    )?;
+    run(
+        &["--zero-terminated", "-3", "--total", NAMES1, NAMES2],
+        "tests/expected/names1_names2.z.3.total.out",
+    )
+}
@@ -0,0 +1,485 @@
+//! Rust version of `cut`, the `run` function is called by the `cutr` binary and by `ruty`
+
+use clap::{Args, Parser};
+
+#[derive(Debug, Parser)]
+#[command(about, version)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `cut`
+pub struct Cli {
+    #[arg(value_name = "FILES", default_values = ["-"], num_args=0..)]
+    /// Input file(s)
+    files: Vec<String>,
+
+    #[arg(short, long, value_name = "DELIMITER", default_value = "\t")]
+    /// Field delimeter
+    delimiter: String,
+
+    #[arg(short('w'), long, conflicts_with = "delimiter")]
+    /// Split the fields on runs of spaces and tabs, like BSD cut
+    whitespace: bool,
+
+    #[arg(long, value_name = "STRING")]
+    /// Separator of the printed fields, the delimiter by default and a tab with --whitespace
+    output_delimiter: Option<String>,
+
+    #[command(flatten)]
+    extract: ArgsExtract,
+
+    #[arg(long)]
+    /// Print the fields, bytes or chars that are not selected
+    complement: bool,
+
+    #[arg(short('s'), long)]
+    /// Do not print the lines without delimiter
+    only_delimited: bool,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct ArgsExtract {
+    #[arg(short, long, allow_hyphen_values = true)]
+    /// Selected fields, like 1,3-5, -2 (up to the second) or 4- (from the fourth)
+    fields: Option<String>,
+
+    #[arg(short, long, allow_hyphen_values = true)]
+    /// Selected bytes
+    bytes: Option<String>,
+
+    #[arg(short, long, allow_hyphen_values = true)]
+    /// Selected chars
+    chars: Option<String>,
+}
+
+mod helpers {
+    use std::{borrow::Cow, io::BufRead, ops::Range};
+
+    use csv::{ReaderBuilder, StringRecord};
+    use ruty_core::open_maybe_compressed;
+
+    type PositionList = Vec<Range<usize>>;
+
+    /// End of the open ranges like `3-`, up to the end of the line whatever its length
+    pub const END: usize = usize::MAX;
+
+    #[derive(Debug)]
+    enum Extract {
+        Fields(PositionList),
+        Bytes(PositionList),
+        Chars(PositionList),
+    }
+
+    pub fn run(args: crate::Cli) -> anyhow::Result<()> {
+        if args.delimiter.len() != 1 {
+            anyhow::bail!("--delim \"{}\" must be a single byte", args.delimiter);
+        }
+        if args.only_delimited && args.extract.fields.is_none() {
+            anyhow::bail!("--only-delimited makes sense only when operating on fields");
+        }
+        if args.whitespace && args.extract.fields.is_none() {
+            anyhow::bail!("--whitespace makes sense only when operating on fields");
+        }
+        if args.output_delimiter.is_some() && args.extract.fields.is_none() {
+            anyhow::bail!("--output-delimiter makes sense only when operating on fields");
+        }
+
+        let output_delimiter = match (args.output_delimiter, args.whitespace) {
+            (Some(delimiter), _) => delimiter,
+            (None, true) => "\t".to_string(),
+            (None, false) => args.delimiter.clone(),
+        };
+        let print = |record: &StringRecord, field_pos: &[Range<usize>]| {
+            // A line without delimiter is read as a single field
+            if args.only_delimited && record.len() < 2 {
+                return;
+            }
+
+            let field_pos = positions(field_pos, args.complement, record.len());
+            println!(
+                "{}",
+                extract_fields(record, &field_pos).join(&output_delimiter)
+            );
+        };
+
+        let extract = if let Some(fields) = args.extract.fields.map(parse_pos).transpose()? {
+            Extract::Fields(fields)
+        } else if let Some(bytes) = args.extract.bytes.map(parse_pos).transpose()? {
+            Extract::Bytes(bytes)
+        } else if let Some(chars) = args.extract.chars.map(parse_pos).transpose()? {
+            Extract::Chars(chars)
+        } else {
+            anyhow::bail!("The extract should have at least one field");
+        };
+
+        for filename in &args.files {
+            match open_maybe_compressed(filename) {
+                Err(err) => eprintln!("{}: {}", filename, err),
+                Ok(handler) => match extract {
+                    Extract::Fields(ref field_pos) if args.whitespace => {
+                        handler.lines().for_each(|l| {
+                            let line = l.unwrap_or("".to_string());
+                            print(&split_blanks(&line), field_pos);
+                        })
+                    }
+                    Extract::Fields(ref field_pos) => {
+                        let mut reader = ReaderBuilder::new()
+                            .delimiter(*args.delimiter.as_bytes().first().unwrap_or(&b'\t'))
+                            .has_headers(false)
+                            // Lines can have any number of fields, or no delimiter at all
+                            .flexible(true)
+                            .from_reader(handler);
+
+                        for record in reader.records() {
+                            print(&record.unwrap(), field_pos);
+                        }
+                    }
+                    Extract::Bytes(ref byte_pos) => handler.lines().for_each(|l| {
+                        let line = l.unwrap_or("".to_string());
+                        let byte_pos = positions(byte_pos, args.complement, line.len());
+                        println!("{}", extract_bytes(&line, &byte_pos))
+                    }),
+                    Extract::Chars(ref chars_pos) => handler.lines().for_each(|l| {
+                        let line = l.unwrap_or("".to_string());
+                        let chars_pos = positions(chars_pos, args.complement, line.chars().count());
+                        println!("{}", extract_chars(&line, &chars_pos))
+                    }),
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn parse_pos(range: String) -> anyhow::Result<PositionList> {
+        let mut out: PositionList = Vec::new();
+
+        if range.is_empty() || range.contains('+') {
+            anyhow::bail!("illegal list value: \"{}\"", range);
+        }
+
+        let ranges = range.split(',');
+
+        for r in ranges {
+            let i: Vec<&str> = r.split("-").collect();
+
+            if i.contains(&"0") {
+                anyhow::bail!("illegal list value: \"0\"")
+            }
+
+            match i.len() {
+                1 => {
+                    let up = match i[0].parse::<usize>() {
+                        Ok(v) => v,
+                        Err(_) => anyhow::bail!("illegal list value: \"{}\"", i[0]),
+                    };
+
+                    if up == 0 {
+                        anyhow::bail!("illegal list value: \"{}\"", up);
+                    }
+
+                    out.push(up - 1..up);
+                }
+                2 => {
+                    let parse = |value: &str| match value.parse::<usize>() {
+                        Ok(v) if v > 0 => Ok(v),
+                        _ => Err(anyhow::anyhow!("illegal list value: \"{}\"", range)),
+                    };
+
+                    // `-N` goes from the start of the line and `N-` to its end
+                    let (down, up) = match (i[0], i[1]) {
+                        ("", "") => anyhow::bail!("illegal list value: \"{}\"", range),
+                        ("", up) => {
+                            out.push(0..parse(up)?);
+                            continue;
+                        }
+                        (down, "") => {
+                            out.push(parse(down)? - 1..END);
+                            continue;
+                        }
+                        (down, up) => (parse(down)?, parse(up)?),
+                    };
+
+                    if up <= down {
+                        anyhow::bail!(
+                            "First number in range ({}) must be lower than second number ({})",
+                            down,
+                            up
+                        );
+                    }
+
+                    out.push(down - 1..up);
+                }
+                _ => anyhow::bail!("illegal list value: \"{}\"", range),
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Positions printed from a line of `len` items, the ones not selected with --complement
+    fn positions(pos: &[Range<usize>], complement: bool, len: usize) -> Cow<'_, [Range<usize>]> {
+        if complement {
+            Cow::Owned(invert(pos, len))
+        } else {
+            Cow::Borrowed(pos)
+        }
+    }
+
+    /// Ranges of the items of a line of `len` items that are in none of `pos`, in order
+    pub fn invert(pos: &[Range<usize>], len: usize) -> PositionList {
+        let mut selected: PositionList = pos
+            .iter()
+            .map(|range| range.start.min(len)..range.end.min(len))
+            .collect();
+        selected.sort_by_key(|range| range.start);
+
+        let mut out: PositionList = Vec::new();
+        let mut next = 0;
+
+        for range in selected {
+            if range.start > next {
+                out.push(next..range.start);
+            }
+            next = next.max(range.end);
+        }
+
+        if next < len {
+            out.push(next..len);
+        }
+
+        out
+    }
+
+    /// Items of the ranges in order, skipping to the start of each range and stopping at the end of
+    /// the items, so open ranges cost no more than the line
+    fn select<T, I: Iterator<Item = T> + Clone>(
+        items: I,
+        pos: &[Range<usize>],
+    ) -> impl Iterator<Item = T> {
+        pos.iter()
+            .flat_map(move |range| items.clone().skip(range.start).take(range.len()))
+    }
+
+    pub fn extract_chars(line: &str, char_pos: &[Range<usize>]) -> String {
+        select(line.chars(), char_pos).collect()
+    }
+
+    pub fn extract_bytes(line: &str, byte_pos: &[Range<usize>]) -> String {
+        let bytes: Vec<u8> = select(line.bytes(), byte_pos).collect();
+
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Fields of a line split on runs of spaces and tabs, the leading and trailing ones ignored
+    pub fn split_blanks(line: &str) -> StringRecord {
+        line.split([' ', '\t'])
+            .filter(|field| !field.is_empty())
+            .collect()
+    }
+
+    pub fn extract_fields(record: &StringRecord, field_pos: &[Range<usize>]) -> Vec<String> {
+        select(record.iter(), field_pos)
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+pub use helpers::run;
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod unit_tests {
+    use crate::helpers::*;
+    use csv::StringRecord;
+
+    #[test]
+    fn test_parse_pos() {
+        // The empty string is an error
+        assert!(parse_pos("".to_string()).is_err());
+
+        // Zero is an error
+        let res = parse_pos("0".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"illegal list value: "0""#);
+
+        let res = parse_pos("0-1".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"illegal list value: "0""#);
+
+        // A leading "+" is an error
+        let res = parse_pos("+1".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"illegal list value: "+1""#,);
+
+        let res = parse_pos("+1-2".to_string());
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            r#"illegal list value: "+1-2""#,
+        );
+
+        let res = parse_pos("1-+2".to_string());
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            r#"illegal list value: "1-+2""#,
+        );
+
+        // Any non-number is an error
+        let res = parse_pos("a".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"illegal list value: "a""#);
+
+        let res = parse_pos("1,a".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"illegal list value: "a""#);
+
+        let res = parse_pos("1-a".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"illegal list value: "1-a""#,);
+
+        let res = parse_pos("a-1".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"illegal list value: "a-1""#,);
+
+        // Wonky ranges
+        let res = parse_pos("-".to_string());
+        assert!(res.is_err());
+
+        let res = parse_pos(",".to_string());
+        assert!(res.is_err());
+
+        let res = parse_pos("1,".to_string());
+        assert!(res.is_err());
+
+        let res = parse_pos("1-1-1".to_string());
+        assert!(res.is_err());
+
+        let res = parse_pos("1-1-a".to_string());
+        assert!(res.is_err());
+
+        // First number must be less than second
+        let res = parse_pos("1-1".to_string());
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "First number in range (1) must be lower than second number (1)"
+        );
+
+        let res = parse_pos("2-1".to_string());
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "First number in range (2) must be lower than second number (1)"
+        );
+
+        // All the following are acceptable
+        let res = parse_pos("1".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1]);
+
+        let res = parse_pos("01".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1]);
+
+        let res = parse_pos("1,3".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1, 2..3]);
+
+        let res = parse_pos("001,0003".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1, 2..3]);
+
+        let res = parse_pos("1-3".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..3]);
+
+        let res = parse_pos("0001-03".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..3]);
+
+        let res = parse_pos("1,7,3-5".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1, 6..7, 2..5]);
+
+        let res = parse_pos("15,19-20".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![14..15, 18..20]);
+
+        // Open ranges go to the end or from the start of the line
+        let res = parse_pos("3-".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![2..END]);
+
+        let res = parse_pos("-5".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..5]);
+
+        let res = parse_pos("-2,4-".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..2, 3..END]);
+
+        let res = parse_pos("-00".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"illegal list value: "-00""#);
+    }
+
+    #[test]
+    fn test_invert() {
+        assert_eq!(invert(&[0..1], 3), vec![1..3]);
+        assert_eq!(invert(&[1..2], 3), vec![0..1, 2..3]);
+        assert_eq!(invert(&[0..3], 3), vec![]);
+        assert_eq!(invert(&[2..END], 5), vec![0..2]);
+        assert_eq!(invert(&[0..2], 0), vec![]);
+
+        // Out of order and overlapping ranges
+        assert_eq!(invert(&[6..7, 0..2, 1..3], 8), vec![3..6, 7..8]);
+
+        // Ranges past the end of the line
+        assert_eq!(invert(&[1..2, 5..9], 3), vec![0..1, 2..3]);
+    }
+
+    #[test]
+    fn test_extract_chars() {
+        assert_eq!(extract_chars("", &[0..1]), "".to_string());
+        assert_eq!(extract_chars("ábc", &[0..1]), "á".to_string());
+        assert_eq!(extract_chars("ábc", &[0..1, 2..3]), "ác".to_string());
+        assert_eq!(extract_chars("ábc", &[0..3]), "ábc".to_string());
+        assert_eq!(extract_chars("ábc", &[2..3, 1..2]), "cb".to_string());
+        assert_eq!(extract_chars("ábc", &[0..1, 1..2, 4..5]), "áb".to_string());
+        assert_eq!(extract_chars("ábc", &[1..END]), "bc".to_string());
+    }
+
+    #[test]
+    fn test_extract_bytes() {
+        assert_eq!(extract_bytes("ábc", &[0..1]), "�".to_string());
+        assert_eq!(extract_bytes("ábc", &[0..2]), "á".to_string());
+        assert_eq!(extract_bytes("ábc", &[0..3]), "áb".to_string());
+        assert_eq!(extract_bytes("ábc", &[0..4]), "ábc".to_string());
+        assert_eq!(extract_bytes("ábc", &[3..4, 2..3]), "cb".to_string());
+        assert_eq!(extract_bytes("ábc", &[0..2, 5..6]), "á".to_string());
+        assert_eq!(extract_bytes("ábc", &[2..END]), "bc".to_string());
+    }
+
+    #[test]
+    fn test_extract_fields() {
+        let rec = StringRecord::from(vec!["Captain", "Sham", "12345"]);
+        assert_eq!(extract_fields(&rec, &[0..1]), &["Captain"]);
+        assert_eq!(extract_fields(&rec, &[1..2]), &["Sham"]);
+        assert_eq!(extract_fields(&rec, &[0..1, 2..3]), &["Captain", "12345"]);
+        assert_eq!(extract_fields(&rec, &[0..1, 3..4]), &["Captain"]);
+        assert_eq!(extract_fields(&rec, &[1..2, 0..1]), &["Sham", "Captain"]);
+        assert_eq!(extract_fields(&rec, &[1..END]), &["Sham", "12345"]);
+        assert_eq!(extract_fields(&rec, &[0..2]), &["Captain", "Sham"]);
+    }
+
+    #[test]
+    fn test_split_blanks() {
+        assert_eq!(split_blanks(""), StringRecord::new());
+        assert_eq!(split_blanks("one"), StringRecord::from(vec!["one"]));
+        assert_eq!(
+            split_blanks("  drwxr-xr-x \t 2  root\troot "),
+            StringRecord::from(vec!["drwxr-xr-x", "2", "root", "root"])
+        );
+    }
+}
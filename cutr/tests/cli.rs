@@ -2,7 +2,7 @@ use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
-use rand::{distr::Alphanumeric, Rng};
+use rand::{Rng, distr::Alphanumeric};
 use std::fs;
 
 const PRG: &str = "cutr";
@@ -236,6 +236,27 @@ fn csv_f3() -> Result<()> {
     )
 }
 
+// --------------------------------------------------
+#[test]
+fn csv_f2_to_end() -> Result<()> {
+    run(
+        &[CSV, "-f", "2-", "-d", ","],
+        "tests/expected/movies1.csv.f2-.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c_start_to_3() -> Result<()> {
+    run(&[TSV, "-c", "-3"], "tests/expected/movies1.tsv.c-3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b8_to_end() -> Result<()> {
+    run(&[TSV, "-b", "8-"], "tests/expected/movies1.tsv.b8-.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn csv_f1_2() -> Result<()> {
@@ -340,3 +361,135 @@ fn tsv_c1_8() -> Result<()> {
 fn repeated_value() -> Result<()> {
     run(&[BOOKS, "-c", "1,1"], "tests/expected/books.c1,1.out")
 }
+
+// --------------------------------------------------
+#[test]
+#[cfg(feature = "compressed")]
+fn books_xz() -> Result<()> {
+    run(
+        &["tests/inputs/books.tsv.xz", "-c", "1,1"],
+        "tests/expected/books.c1,1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_only_delimited_chars() -> Result<()> {
+    dies(
+        &[TSV, "-c", "1", "-s"],
+        "--only-delimited makes sense only when operating on fields",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn books_f2_complement() -> Result<()> {
+    run(
+        &[BOOKS, "-f", "2", "--complement"],
+        "tests/expected/books.f2.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b1_5_8_to_end_complement() -> Result<()> {
+    run(
+        &[TSV, "-b", "1-5,8-", "--complement"],
+        "tests/expected/movies1.tsv.b1-5,8-.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c2_3_complement() -> Result<()> {
+    run(
+        &[TSV, "-c", "2-3", "--complement"],
+        "tests/expected/movies1.tsv.c2-3.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn partial_f2_only_delimited() -> Result<()> {
+    run(
+        &["tests/inputs/partial.tsv", "-f", "2", "-s"],
+        "tests/expected/partial.f2.s.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn partial_f1_only_delimited_complement() -> Result<()> {
+    run(
+        &["tests/inputs/partial.tsv", "-f", "1", "-s", "--complement"],
+        "tests/expected/partial.f1.s.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn books_f1_3_output_delimiter() -> Result<()> {
+    run(
+        &[BOOKS, "-f", "1,3", "--output-delimiter", ","],
+        "tests/expected/books.f1,3.ocomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn books_f2_to_end_output_delimiter_string() -> Result<()> {
+    run(
+        &[BOOKS, "-f", "2-", "--output-delimiter", " | "],
+        "tests/expected/books.f2-.obar.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_f3_5_whitespace() -> Result<()> {
+    run(
+        &["tests/inputs/blanks.txt", "-w", "-f", "3,5"],
+        "tests/expected/blanks.f3,5.w.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_f9_to_end_whitespace_only_delimited() -> Result<()> {
+    run(
+        &[
+            "tests/inputs/blanks.txt",
+            "-w",
+            "-s",
+            "-f",
+            "9-",
+            "--output-delimiter",
+            ",",
+        ],
+        "tests/expected/blanks.f9-.w.s.ocomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_whitespace_and_delimiter() -> Result<()> {
+    dies(&[TSV, "-w", "-d", ",", "-f", "1"], "cannot be used with")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_whitespace_chars() -> Result<()> {
+    dies(
+        &[TSV, "-w", "-c", "1"],
+        "--whitespace makes sense only when operating on fields",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_output_delimiter_bytes() -> Result<()> {
+    dies(
+        &[TSV, "--output-delimiter", ",", "-b", "1"],
+        "--output-delimiter makes sense only when operating on fields",
+    )
+}
@@ -1,4 +1,8 @@
-use clap::{Arg, ArgAction, Command, Parser};
+use clap::Parser;
+use std::io::{self, Write};
+use std::iter::Peekable;
+use std::process;
+use std::str::Chars;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
@@ -11,19 +15,112 @@ struct Args {
     /// Do not print newline
     #[arg(short('n'))]
     omit_newline: bool,
+
+    /// Interpret backslash escapes (\n, \t, \\, \xHH, \0NNN, \c...)
+    #[arg(short('e'), overrides_with("no_escapes"))]
+    escapes: bool,
+
+    /// Do not interpret backslash escapes (default)
+    #[arg(short('E'), overrides_with("escapes"))]
+    no_escapes: bool,
+}
+
+/// Value of the `max` digits at most of radix `radix` at the start of `chars`, consuming them
+fn take_digits(chars: &mut Peekable<Chars>, radix: u32, max: usize) -> Option<u32> {
+    let mut value = None;
+
+    for _ in 0..max {
+        match chars.peek().and_then(|c| c.to_digit(radix)) {
+            Some(digit) => {
+                value = Some(value.unwrap_or(0) * radix + digit);
+                chars.next();
+            }
+            None => break,
+        }
+    }
+
+    value
+}
+
+/// Interprets the backslash escapes of `text` like bash's `echo -e`. Returns the bytes to print
+/// and whether a `\c` was found, which suppresses the rest of the output and the newline
+fn unescape(text: &str) -> (Vec<u8>, bool) {
+    let mut output = vec![];
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            output.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        let byte = match chars.next() {
+            Some('a') => 0x07,
+            Some('b') => 0x08,
+            Some('c') => return (output, true),
+            Some('e') | Some('E') => 0x1b,
+            Some('f') => 0x0c,
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('v') => 0x0b,
+            Some('\\') => b'\\',
+            // Octal values above 0o377 wrap around like in bash
+            Some('0') => take_digits(&mut chars, 8, 3).unwrap_or(0) as u8,
+            Some('x') => match take_digits(&mut chars, 16, 2) {
+                Some(value) => value as u8,
+                None => {
+                    output.extend_from_slice(b"\\x");
+                    continue;
+                }
+            },
+            // Unknown escapes and a trailing backslash are printed as they are
+            Some(other) => {
+                output.push(b'\\');
+                let mut buf = [0; 4];
+                output.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+                continue;
+            }
+            None => b'\\',
+        };
+        output.push(byte);
+    }
+
+    (output, false)
 }
 
 fn main() {
     let args = Args::parse();
 
-    dbg!(&args);
+    let text = args.text.join(" ");
+    let (mut output, stop) = match args.escapes {
+        true => unescape(&text),
+        false => (text.into_bytes(), false),
+    };
 
-    match !args.omit_newline {
-        true => {
-            println!("{}", args.text.join(" "));
-        }
-        false => {
-            print!("{}", args.text.join(" "));
-        }
+    if !args.omit_newline && !stop {
+        output.push(b'\n');
+    }
+
+    if let Err(e) = io::stdout().write_all(&output) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unescape;
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"a\tb\nc\\d"), (b"a\tb\nc\\d".to_vec(), false));
+        assert_eq!(unescape(r"\x41\x4a\xzz\x"), (b"AJ\\xzz\\x".to_vec(), false));
+        assert_eq!(unescape(r"\0101\0\01234"), (b"A\0S4".to_vec(), false));
+        assert_eq!(unescape(r"\xff"), (vec![0xff], false));
+        assert_eq!(unescape(r"\q\"), (b"\\q\\".to_vec(), false));
+        assert_eq!(unescape(r"stop\c here"), (b"stop".to_vec(), true));
+        assert_eq!(unescape("héllo"), ("héllo".as_bytes().to_vec(), false));
     }
 }
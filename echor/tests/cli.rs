@@ -1,6 +1,5 @@
 use anyhow::Result;
 use assert_cmd::Command;
-use predicates::prelude::*;
 use pretty_assertions::assert_eq;
 use std::fs;
 
@@ -64,3 +63,29 @@ fn hello1_no_newline() -> Result<()> {
 fn hello2_no_newline() -> Result<()> {
     run(&["-n", "Hello", "there"], "tests/expected/hello2.n.txt")
 }
+
+#[test]
+fn escapes() -> Result<()> {
+    run(
+        &["-e", r"tab\there\nnew \\ \x41\0102\q"],
+        "tests/expected/escapes.e.txt",
+    )
+}
+
+#[test]
+fn escapes_disabled() -> Result<()> {
+    run(&["-E", r"tab\there\n"], "tests/expected/escapes.E.txt")
+}
+
+#[test]
+fn escapes_last_flag_wins() -> Result<()> {
+    run(
+        &["-e", "-E", r"tab\there\n"],
+        "tests/expected/escapes.e.E.txt",
+    )
+}
+
+#[test]
+fn escapes_stop_output() -> Result<()> {
+    run(&["-e", r"stop\c here"], "tests/expected/escapes.c.txt")
+}
@@ -7,3 +7,7 @@ echo    "Hello there"    > $OUTDIR/hello1.txt
 echo    "Hello"  "there" > $OUTDIR/hello2.txt
 echo -n "Hello  there"   > $OUTDIR/hello1.n.txt
 echo -n "Hello"  "there" > $OUTDIR/hello2.n.txt
+echo -e 'tab\there\nnew \\ \x41\0102\q' > $OUTDIR/escapes.e.txt
+echo -E 'tab\there\n'                   > $OUTDIR/escapes.E.txt
+echo -e -E 'tab\there\n'                > $OUTDIR/escapes.e.E.txt
+echo -e 'stop\c here'                   > $OUTDIR/escapes.c.txt
@@ -0,0 +1,393 @@
+//! Rust version of `find`, the `run` function is called by the `findr` binary and by `ruty`
+
+use clap::{Parser, ValueEnum};
+
+#[derive(Debug, Parser)]
+#[command(about, version)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+///Rust verion of `find`
+pub struct Args {
+    /// Search paths
+    #[arg(default_values_t = vec![".".to_string()], value_name= "PATH")]
+    paths: Vec<String>,
+
+    /// Name
+    #[arg(short, long("name"), value_name = "NAME", num_args=0..)]
+    names: Vec<regex::Regex>,
+
+    /// Entry type
+    #[arg(short('t'), long("type"), value_name = "TYPE", num_args=0..)]
+    entry_types: Vec<EntryType>,
+
+    /// Size, like find's -size: +N for more than N, -N for less than N, N for exactly N units of
+    /// c (bytes), w (2 bytes), b (512 bytes, the default), k, M or G, the size being rounded up to the unit
+    #[arg(long("size"), value_name = "SIZE", allow_hyphen_values = true)]
+    sizes: Vec<Size>,
+
+    /// Modification time, like find's -mtime: +N for more than N days ago, -N for less than N days
+    /// ago, N for exactly N days ago, the days being rounded down
+    #[arg(long("mtime"), value_name = "DAYS", allow_hyphen_values = true)]
+    mtimes: Vec<Mtime>,
+
+    /// Modified more recently than FILE
+    #[arg(long, value_name = "FILE")]
+    newer: Option<String>,
+
+    /// Command to run for each match instead of printing it, ended by a `;`: every `{}` is replaced by the
+    /// path, which is added at the end when there is no `{}`
+    #[arg(
+        short('x'),
+        long,
+        value_name = "COMMAND",
+        num_args = 1..,
+        value_terminator = ";",
+        allow_hyphen_values = true,
+        conflicts_with = "exec_batch"
+    )]
+    exec: Vec<String>,
+
+    /// Like --exec, but passing as many matches as possible to each run of the command, in place of a `{}`
+    /// argument or at the end
+    #[arg(
+        short('X'),
+        long,
+        value_name = "COMMAND",
+        num_args = 1..,
+        value_terminator = ";",
+        allow_hyphen_values = true
+    )]
+    exec_batch: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EntryType {
+    Dir,
+    File,
+    Link,
+}
+
+impl ValueEnum for EntryType {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Dir, Self::File, Self::Link]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(match self {
+            Self::Dir => clap::builder::PossibleValue::new("d"),
+            Self::File => clap::builder::PossibleValue::new("f"),
+            Self::Link => clap::builder::PossibleValue::new("l"),
+        })
+    }
+}
+
+impl EntryType {
+    fn type_of_path(entry: &std::path::Path) -> Option<Self> {
+        match entry {
+            p if p.is_symlink() => Some(EntryType::Link),
+            p if p.is_dir() => Some(EntryType::Dir),
+            p if p.is_file() => Some(EntryType::File),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Size {
+    comparison: std::cmp::Ordering,
+    units: u64,
+    unit: u64,
+}
+
+impl std::str::FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid size \"{}\", expected [+-]N[cwbkMG]", s);
+
+        let (comparison, rest) = match s.as_bytes().first() {
+            Some(b'+') => (std::cmp::Ordering::Greater, &s[1..]),
+            Some(b'-') => (std::cmp::Ordering::Less, &s[1..]),
+            _ => (std::cmp::Ordering::Equal, s),
+        };
+
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (units, suffix) = rest.split_at(split);
+
+        let unit = match suffix {
+            "c" => 1,
+            "w" => 2,
+            "" | "b" => 512,
+            "k" => 1 << 10,
+            "M" => 1 << 20,
+            "G" => 1 << 30,
+            _ => return Err(error()),
+        };
+
+        Ok(Size {
+            comparison,
+            units: units.parse().map_err(|_| error())?,
+            unit,
+        })
+    }
+}
+
+impl Size {
+    /// Compares the length in units, rounded up like find does, so -1k only matches empty files
+    fn matches(&self, len: u64) -> bool {
+        len.div_ceil(self.unit).cmp(&self.units) == self.comparison
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Mtime {
+    comparison: std::cmp::Ordering,
+    days: i64,
+}
+
+impl std::str::FromStr for Mtime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid mtime \"{}\", expected [+-]N", s);
+
+        let (comparison, rest) = match s.as_bytes().first() {
+            Some(b'+') => (std::cmp::Ordering::Greater, &s[1..]),
+            Some(b'-') => (std::cmp::Ordering::Less, &s[1..]),
+            _ => (std::cmp::Ordering::Equal, s),
+        };
+
+        if rest.is_empty() || !rest.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(error());
+        }
+
+        Ok(Mtime {
+            comparison,
+            days: rest.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+impl Mtime {
+    /// Compares the whole days since the modification, the ones in the future being negative
+    fn matches(&self, now: std::time::SystemTime, modified: std::time::SystemTime) -> bool {
+        let seconds = match now.duration_since(modified) {
+            Ok(age) => age.as_secs() as i64,
+            Err(e) => -(e.duration().as_secs() as i64),
+        };
+
+        seconds.div_euclid(24 * 60 * 60).cmp(&self.days) == self.comparison
+    }
+}
+
+mod helpers {
+    use std::{fs::Metadata, process::Command, time::SystemTime};
+
+    use walkdir::WalkDir;
+
+    /// Most bytes of paths passed to a run of the --exec-batch command, far below the usual limits
+    const MAX_BATCH_BYTES: usize = 128 * 1024;
+
+    pub fn run(args: crate::Args) -> anyhow::Result<()> {
+        let mut batch: Vec<String> = Vec::new();
+        let (mut runs, mut failed) = (0, 0);
+        let now = SystemTime::now();
+        let newer = match &args.newer {
+            Some(file) => Some(
+                std::fs::metadata(file)
+                    .and_then(|metadata| metadata.modified())
+                    .map_err(|e| anyhow::anyhow!("{file}: {e}"))?,
+            ),
+            None => None,
+        };
+
+        for path in args.paths {
+            for entry in WalkDir::new(path) {
+                match entry {
+                    Err(e) => eprintln!("{e}"),
+                    Ok(entry) => {
+                        let path = entry.path().display().to_string();
+                        let file = entry.file_name().to_string_lossy().into_owned();
+                        let entry_type = match crate::EntryType::type_of_path(entry.path()) {
+                            Some(t) => t,
+                            None => break,
+                        };
+
+                        if check_type(&args.entry_types, &entry_type)
+                            && check_match(&args.names, &file)
+                            && check_metadata(&args.sizes, &args.mtimes, newer, now, &entry)
+                        {
+                            if !args.exec.is_empty() {
+                                runs += 1;
+                                failed += usize::from(!exec(&args.exec, &path));
+                            } else if !args.exec_batch.is_empty() {
+                                batch.push(path);
+                            } else {
+                                println!("{}", &path);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for paths in batches(&batch) {
+            runs += 1;
+            failed += usize::from(!exec_batch(&args.exec_batch, paths));
+        }
+
+        if failed > 0 {
+            let command = args.exec.first().or(args.exec_batch.first());
+            anyhow::bail!(
+                "{}: {} of {} runs failed",
+                command.map_or("", |c| c.as_str()),
+                failed,
+                runs
+            );
+        }
+        Ok(())
+    }
+
+    /// Runs the command for a path, false when it can't be started or doesn't exit successfully
+    fn exec(command: &[String], path: &str) -> bool {
+        let args: Vec<String> = if command[1..].iter().any(|arg| arg.contains("{}")) {
+            command[1..]
+                .iter()
+                .map(|arg| arg.replace("{}", path))
+                .collect()
+        } else {
+            command[1..]
+                .iter()
+                .cloned()
+                .chain([path.to_string()])
+                .collect()
+        };
+
+        status(Command::new(&command[0]).args(args))
+    }
+
+    /// Runs the command once for all the paths, in place of the `{}` arguments or at the end
+    fn exec_batch(command: &[String], paths: &[String]) -> bool {
+        let mut cmd = Command::new(&command[0]);
+
+        if command[1..].iter().any(|arg| arg == "{}") {
+            for arg in &command[1..] {
+                if arg == "{}" {
+                    cmd.args(paths);
+                } else {
+                    cmd.arg(arg);
+                }
+            }
+        } else {
+            cmd.args(&command[1..]).args(paths);
+        }
+
+        status(&mut cmd)
+    }
+
+    fn status(cmd: &mut Command) -> bool {
+        match cmd.status() {
+            Err(e) => {
+                eprintln!("{}: {e}", cmd.get_program().to_string_lossy());
+                false
+            }
+            Ok(status) => status.success(),
+        }
+    }
+
+    /// Splits the paths in batches of at most `MAX_BATCH_BYTES`, but of at least a path
+    fn batches(paths: &[String]) -> Vec<&[String]> {
+        let mut output: Vec<&[String]> = Vec::new();
+        let (mut start, mut bytes) = (0, 0);
+
+        for (i, path) in paths.iter().enumerate() {
+            if i > start && bytes + path.len() + 1 > MAX_BATCH_BYTES {
+                output.push(&paths[start..i]);
+                (start, bytes) = (i, 0);
+            }
+            bytes += path.len() + 1;
+        }
+
+        if start < paths.len() {
+            output.push(&paths[start..]);
+        }
+        output
+    }
+
+    fn check_match(set: &[regex::Regex], hay: &str) -> bool {
+        if set.is_empty() {
+            return true;
+        } else {
+            if set.iter().any(|re| re.is_match(hay)) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Runs the tests on the metadata of the entry, not of the file a link points to, which is
+    /// only fetched when there is any test and once for all of them
+    fn check_metadata(
+        sizes: &[crate::Size],
+        mtimes: &[crate::Mtime],
+        newer: Option<SystemTime>,
+        now: SystemTime,
+        entry: &walkdir::DirEntry,
+    ) -> bool {
+        if sizes.is_empty() && mtimes.is_empty() && newer.is_none() {
+            return true;
+        }
+
+        match entry.metadata() {
+            Err(e) => {
+                eprintln!("{e}");
+                false
+            }
+            Ok(metadata) => {
+                check_size(sizes, &metadata) && check_mtime(mtimes, newer, now, &metadata)
+            }
+        }
+    }
+
+    /// The length of the entry must match all the sizes
+    fn check_size(sizes: &[crate::Size], metadata: &Metadata) -> bool {
+        sizes.iter().all(|size| size.matches(metadata.len()))
+    }
+
+    /// The modification time of the entry must match all the mtimes and be later than `newer`
+    fn check_mtime(
+        mtimes: &[crate::Mtime],
+        newer: Option<SystemTime>,
+        now: SystemTime,
+        metadata: &Metadata,
+    ) -> bool {
+        if mtimes.is_empty() && newer.is_none() {
+            return true;
+        }
+
+        match metadata.modified() {
+            Err(e) => {
+                eprintln!("{e}");
+                false
+            }
+            Ok(modified) => {
+                mtimes.iter().all(|mtime| mtime.matches(now, modified))
+                    && newer.is_none_or(|newer| modified > newer)
+            }
+        }
+    }
+
+    fn check_type(file_types: &[crate::EntryType], entry_type: &crate::EntryType) -> bool {
+        if file_types.is_empty() {
+            return true;
+        } else {
+            if file_types.iter().any(|t| t == entry_type) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+pub use helpers::run;
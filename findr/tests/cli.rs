@@ -2,7 +2,7 @@ use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
-use rand::{distr::Alphanumeric, Rng};
+use rand::{Rng, distr::Alphanumeric};
 use std::{borrow::Cow, fs, path::Path};
 
 const PRG: &str = "findr";
@@ -58,6 +58,42 @@ fn dies_bad_type() -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_bad_size() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--size", "10X"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"invalid size "10X""#));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_mtime() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--mtime", "+1d"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"invalid mtime "+1d""#));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_newer() -> Result<()> {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--newer", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(format!(
+            "{bad}: .* [(]os error 2[)]"
+        ))?);
+    Ok(())
+}
+
 // --------------------------------------------------
 #[cfg(windows)]
 fn format_file_name(expected_file: &str) -> Cow<str> {
@@ -67,7 +103,7 @@ fn format_file_name(expected_file: &str) -> Cow<str> {
 
 // --------------------------------------------------
 #[cfg(not(windows))]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Borrowed(expected_file)
     expected_file.into()
 }
@@ -129,6 +165,32 @@ fn type_f() -> Result<()> {
     run(&["tests/inputs", "-t", "f"], "tests/expected/type_f.txt")
 }
 
+// --------------------------------------------------
+#[test]
+fn type_f_size() -> Result<()> {
+    // The input files are 2 bytes long
+    run(
+        &["tests/inputs", "-t", "f", "--size", "2c"],
+        "tests/expected/type_f.txt",
+    )?;
+    run(
+        &["tests/inputs", "-t", "f", "--size", "+1c", "--size", "-3c"],
+        "tests/expected/type_f.txt",
+    )?;
+    run(
+        &["tests/inputs", "-t", "f", "--size", "1k"],
+        "tests/expected/type_f.txt",
+    )?;
+    run(
+        &["tests/inputs", "-t", "f", "--size", "-1k"],
+        "tests/expected/size_none.txt",
+    )?;
+    run(
+        &["tests/inputs", "-t", "f", "--size", "+2c"],
+        "tests/expected/size_none.txt",
+    )
+}
+
 // --------------------------------------------------
 #[test]
 fn type_f_path_a() -> Result<()> {
@@ -309,3 +371,158 @@ fn unreadable_dir() -> Result<()> {
     assert!(stderr.contains("cant-touch-this: Permission denied"));
     Ok(())
 }
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn exec_type_f_path_d() -> Result<()> {
+    let cmd = Command::cargo_bin(PRG)?
+        .args([
+            "tests/inputs/d",
+            "-t",
+            "f",
+            "--exec",
+            "echo",
+            "found {}",
+            ";",
+        ])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    lines.sort();
+
+    assert_eq!(
+        lines,
+        [
+            "found tests/inputs/d/d.tsv",
+            "found tests/inputs/d/d.txt",
+            "found tests/inputs/d/e/e.mp3",
+        ]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn exec_batch_type_f_path_d() -> Result<()> {
+    let cmd = Command::cargo_bin(PRG)?
+        .args([
+            "tests/inputs/d",
+            "-t",
+            "f",
+            "-X",
+            "echo",
+            "start",
+            "{}",
+            "end",
+            ";",
+        ])
+        .assert()
+        .success();
+
+    // A single run with all the paths in place of the {}
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let words: Vec<&str> = stdout.trim_end().split(' ').collect();
+    assert_eq!(stdout.lines().count(), 1);
+    assert_eq!(words.len(), 5);
+    assert_eq!((words[0], words[4]), ("start", "end"));
+
+    let mut paths = words[1..4].to_vec();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "tests/inputs/d/d.tsv",
+            "tests/inputs/d/d.txt",
+            "tests/inputs/d/e/e.mp3",
+        ]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn exec_fails() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a", "-t", "f", "--exec", "test", "-d", ";"])
+        .assert()
+        .failure()
+        .stderr("test: 3 of 3 runs failed\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_exec_and_exec_batch() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--exec", "echo", ";", "--exec-batch", "echo", ";"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+fn find_in(dir: &Path, args: &[&str]) -> Result<Vec<String>> {
+    let output = Command::cargo_bin(PRG)?.arg(dir).args(args).output()?;
+    assert!(output.status.success());
+
+    let mut files: Vec<String> = String::from_utf8(output.stdout)?
+        .lines()
+        .map(|line| {
+            Path::new(line)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// --------------------------------------------------
+#[test]
+fn mtime_newer() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let day = std::time::Duration::from_secs(24 * 60 * 60);
+    let now = std::time::SystemTime::now();
+
+    for (name, days) in [("old.txt", 10), ("week.txt", 7), ("new.txt", 0)] {
+        let file = fs::File::create(dir.path().join(name))?;
+        file.set_modified(now - day * days - std::time::Duration::from_secs(60))?;
+    }
+
+    assert_eq!(
+        find_in(dir.path(), &["-t", "f", "--mtime", "+7"])?,
+        ["old.txt"]
+    );
+    assert_eq!(
+        find_in(dir.path(), &["-t", "f", "--mtime", "7"])?,
+        ["week.txt"]
+    );
+    assert_eq!(
+        find_in(dir.path(), &["-t", "f", "--mtime", "-7"])?,
+        ["new.txt"]
+    );
+    assert_eq!(
+        find_in(dir.path(), &["-t", "f", "--mtime", "+1", "--mtime", "-10"])?,
+        ["week.txt"]
+    );
+
+    let old = dir.path().join("old.txt");
+    let old = old.to_str().unwrap();
+    assert_eq!(
+        find_in(dir.path(), &["-t", "f", "--newer", old])?,
+        ["new.txt", "week.txt"]
+    );
+    assert_eq!(
+        find_in(dir.path(), &["-t", "f", "--newer", old, "--mtime", "+1"])?,
+        ["week.txt"]
+    );
+    Ok(())
+}
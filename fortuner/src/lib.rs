@@ -0,0 +1,887 @@
+//! Rust version of `fortune`, the `run` function is called by the `fortuner` binary and by `ruty`
+
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `fortune`
+pub struct Cli {
+    #[arg(value_name = "FILE", required = true)]
+    /// Input files or directories, each one can be preceded by the percentage of the fortunes to pick from it
+    sources: Vec<String>,
+
+    #[arg(short, long)]
+    /// Make all the files equally likely, instead of weighing them by their number of fortunes
+    equal: bool,
+
+    #[arg(short('m'), long, value_name = "PATTERN")]
+    /// Pattern
+    pattern: Option<String>,
+
+    #[arg(short, long)]
+    /// Case-insensitive pattern matching
+    insensitive: bool,
+
+    #[arg(long, value_name = "SEED", value_parser = clap::value_parser!(u64))]
+    /// Random seed
+    seed: Option<u64>,
+
+    #[arg(short, long, conflicts_with = "long")]
+    /// Only pick short fortunes, of at most --length characters
+    short: bool,
+
+    #[arg(short, long)]
+    /// Only pick long fortunes, of more than --length characters
+    long: bool,
+
+    #[arg(short('n'), long, value_name = "CHARS", default_value_t = 160)]
+    /// Longest length of the short fortunes
+    length: usize,
+
+    #[arg(long, conflicts_with_all = ["pattern", "seed", "short", "long"])]
+    /// Write a strfile .dat index next to each file, used to pick a fortune without reading the whole file
+    compile: bool,
+}
+
+mod helpers {
+    use std::collections::{HashMap, HashSet};
+    use std::ffi::OsStr;
+    use std::fs::{self, File};
+    use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+    use std::path::{self, Path, PathBuf};
+
+    use rand::distr::{Distribution, weighted::WeightedIndex};
+    use rand::{SeedableRng, seq::IndexedRandom};
+
+    #[derive(Debug)]
+    pub struct Fortune {
+        pub source: String,
+        pub path: PathBuf,
+        pub text: String,
+    }
+
+    /// Fortunes to pick by their length in characters, like fortune's -s, -l and -n
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Length {
+        Any,
+        Short(usize),
+        Long(usize),
+    }
+
+    impl Length {
+        pub fn matches(self, text: &str) -> bool {
+            match self {
+                Length::Any => true,
+                Length::Short(max) => text.chars().count() <= max,
+                Length::Long(max) => text.chars().count() > max,
+            }
+        }
+    }
+
+    /// File or directory of the command line, with the percentage of the fortunes to pick from it
+    #[derive(Debug, PartialEq)]
+    pub struct Source {
+        pub path: String,
+        pub percent: Option<u32>,
+    }
+
+    /// Chance of each file of being picked
+    pub type Weights = HashMap<PathBuf, f64>;
+
+    /// Version of the strfile format of the .dat indexes, the one of fortune-mod
+    const STRFILE_VERSION: u32 = 2;
+
+    /// Line that separates the fortunes of a file
+    const DELIMITER: u8 = b'%';
+
+    /// Strfile index of a fortune file: a header of big-endian u32, followed by the offset where each
+    /// fortune starts and the offset of the end of the last one
+    #[derive(Debug, PartialEq)]
+    pub struct Index {
+        pub offsets: Vec<u32>,
+        pub longest: u32,
+        pub shortest: u32,
+    }
+
+    impl Index {
+        /// Number of fortunes
+        pub fn len(&self) -> usize {
+            self.offsets.len().saturating_sub(1)
+        }
+
+        pub fn write<W: Write>(&self, mut out: W) -> anyhow::Result<()> {
+            let header = [
+                STRFILE_VERSION,
+                self.len() as u32,
+                self.longest,
+                self.shortest,
+                0,
+            ];
+            for value in header {
+                out.write_all(&value.to_be_bytes())?;
+            }
+            out.write_all(&[DELIMITER, 0, 0, 0])?;
+
+            for offset in &self.offsets {
+                out.write_all(&offset.to_be_bytes())?;
+            }
+
+            out.flush()?;
+            Ok(())
+        }
+
+        pub fn read<R: Read>(mut input: R) -> anyhow::Result<Index> {
+            let mut read_u32 = || -> anyhow::Result<u32> {
+                let mut bytes = [0; 4];
+                input.read_exact(&mut bytes)?;
+                Ok(u32::from_be_bytes(bytes))
+            };
+
+            let version = read_u32()?;
+            if version != 1 && version != STRFILE_VERSION {
+                anyhow::bail!("unknown strfile version {}", version);
+            }
+
+            let num_strings = read_u32()?;
+            let longest = read_u32()?;
+            let shortest = read_u32()?;
+            let _flags = read_u32()?;
+            let _delimiter = read_u32()?;
+
+            let offsets = (0..=num_strings)
+                .map(|_| read_u32())
+                .collect::<anyhow::Result<Vec<u32>>>()?;
+
+            Ok(Index {
+                offsets,
+                longest,
+                shortest,
+            })
+        }
+    }
+
+    /// Where a fortune starts, read only when it is picked
+    #[derive(Debug, PartialEq)]
+    pub struct FortuneOffset {
+        pub path: PathBuf,
+        pub offset: u64,
+    }
+
+    pub fn run(args: crate::Cli) -> anyhow::Result<()> {
+        let pattern = args
+            .pattern
+            .map(|val: String| {
+                regex::RegexBuilder::new(&val)
+                    .case_insensitive(args.insensitive)
+                    .build()
+                    .map_err(|_| anyhow::anyhow!(r#"Invalid --pattern "{}""#, val))
+            })
+            .transpose()?;
+
+        let length = if args.short {
+            Length::Short(args.length)
+        } else if args.long {
+            Length::Long(args.length)
+        } else {
+            Length::Any
+        };
+
+        let sources = parse_sources(&args.sources)?;
+        let paths: Vec<String> = sources.iter().map(|s| s.path.clone()).collect();
+        let files = find_files(&paths)?;
+
+        if args.compile {
+            for file in &files {
+                let index = compile(file)?;
+                let dat = dat_path(file);
+                index.write(BufWriter::new(File::create(&dat)?))?;
+
+                println!(r#""{}" created"#, dat.display());
+                println!("There were {} strings", index.len());
+                println!("Longest string: {} bytes", index.longest);
+                println!("Shortest string: {} bytes", index.shortest);
+            }
+            return Ok(());
+        }
+
+        match pattern {
+            Some(re) => {
+                let fortunes = read_fortunes(&files, length)?;
+
+                if fortunes.is_empty() {
+                    println!("No fortunes found");
+                    return Ok(());
+                }
+
+                let mut sources: Vec<String> = Vec::new();
+
+                for fortune in fortunes {
+                    // Print all the fortunes matching the pattern
+                    if re.is_match(&fortune.text) {
+                        if !sources.contains(&fortune.source) {
+                            sources.push(fortune.source);
+                        }
+                        println!("{}", fortune.text);
+                        println!("%");
+                    }
+                }
+
+                for source in sources {
+                    eprintln!("({})", source);
+                    eprintln!("%");
+                }
+            }
+            None if length != Length::Any => {
+                // The length of the fortunes is only known reading them all
+                let fortunes = read_fortunes(&files, length)?;
+                let counts = count_fortunes(&fortunes, |f| &f.path);
+                let weights = weigh(&sources, &counts, args.equal)?;
+
+                match pick_fortune(&fortunes, weights.as_ref(), args.seed) {
+                    None => println!("No fortunes found"),
+                    Some(f) => println!("{f}"),
+                }
+            }
+            None => {
+                // Only the offsets of the fortunes are read, then the text of the picked one
+                let offsets = read_offsets(&files)?;
+                let counts = count_fortunes(&offsets, |f| &f.path);
+                let weights = weigh(&sources, &counts, args.equal)?;
+
+                match pick_indexed_fortune(&offsets, weights.as_ref(), args.seed)? {
+                    None => println!("No fortunes found"),
+                    Some(f) => println!("{f}"),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Splits the `N%` arguments from the files and directories they precede
+    pub fn parse_sources(args: &[String]) -> anyhow::Result<Vec<Source>> {
+        let mut sources: Vec<Source> = Vec::new();
+        let mut percent: Option<(&str, u32)> = None;
+
+        for arg in args {
+            let value = arg
+                .strip_suffix('%')
+                .filter(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+                .map(|n| {
+                    n.parse::<u32>()
+                        .map_err(|_| anyhow::anyhow!("{arg}: invalid percentage"))
+                })
+                .transpose()?;
+
+            match (value, percent) {
+                (Some(_), Some((prev, _))) => {
+                    anyhow::bail!("{prev}: percentage not followed by a file or directory")
+                }
+                (Some(value), None) => percent = Some((arg, value)),
+                (None, _) => sources.push(Source {
+                    path: arg.clone(),
+                    percent: percent.take().map(|(_, value)| value),
+                }),
+            }
+        }
+
+        if let Some((prev, _)) = percent {
+            anyhow::bail!("{prev}: percentage not followed by a file or directory");
+        }
+
+        let total: u32 = sources.iter().filter_map(|s| s.percent).sum();
+        if total > 100 {
+            anyhow::bail!("probabilities sum to {total}% > 100%");
+        }
+
+        Ok(sources)
+    }
+
+    pub fn find_files(paths: &[String]) -> anyhow::Result<Vec<path::PathBuf>> {
+        let mut files: Vec<path::PathBuf> = Vec::new();
+
+        for path in paths {
+            match fs::metadata(path) {
+                Err(e) => anyhow::bail!("{path}: {e}"),
+                Ok(_) => files.extend(
+                    walkdir::WalkDir::new(path)
+                        .into_iter()
+                        .filter_map(Result::ok)
+                        .filter(|e| {
+                            if let Ok(metadata) = e.metadata() {
+                                e.file_type().is_file()
+                                    && e.path().extension() != Some(OsStr::new("dat"))
+                                    && metadata.len() > 0
+                            } else {
+                                false
+                            }
+                        })
+                        .map(|e| e.path().into()),
+                ),
+            }
+        }
+
+        files.sort();
+        files.dedup();
+
+        Ok(files)
+    }
+
+    /// Fortunes of the files of the given length
+    pub fn read_fortunes(paths: &[PathBuf], length: Length) -> anyhow::Result<Vec<Fortune>> {
+        let mut output: Vec<Fortune> = Vec::new();
+
+        for path in paths {
+            let mut buf: Vec<u8> = Vec::new();
+            let mut line: Vec<u8> = Vec::new();
+            let mut reader = BufReader::new(File::open(path)?);
+
+            // Fortunes end on the lines that are only a `%`, like in the .dat index
+            loop {
+                line.clear();
+                let n = reader.read_until(b'\n', &mut line)?;
+
+                if n > 0 && !is_delimiter(&line) {
+                    buf.append(&mut line);
+                    continue;
+                }
+
+                let source = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+                let text = String::from_utf8_lossy(&buf).trim().to_string();
+
+                if !text.is_empty() && length.matches(&text) {
+                    output.push(Fortune {
+                        source,
+                        path: path.clone(),
+                        text,
+                    });
+                }
+
+                buf.clear();
+                if n == 0 {
+                    break;
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Index file of a fortune file, `jokes.dat` for `jokes`
+    pub fn dat_path(path: &Path) -> PathBuf {
+        let mut dat = path.as_os_str().to_owned();
+        dat.push(".dat");
+        dat.into()
+    }
+
+    /// Builds the index of a fortune file, the fortunes are the text between the lines with only a `%`,
+    /// blank ones are left out like `read_fortunes` does
+    pub fn compile(path: &Path) -> anyhow::Result<Index> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut line: Vec<u8> = Vec::new();
+        let mut offsets: Vec<u32> = Vec::new();
+        let (mut longest, mut shortest) = (0, u32::MAX);
+        let (mut start, mut position, mut blank) = (0, 0, true);
+
+        let too_large = || anyhow::anyhow!("{}: too large to be indexed", path.display());
+
+        loop {
+            line.clear();
+            let n = reader.read_until(b'\n', &mut line)?;
+            let end = n == 0;
+
+            if end || is_delimiter(&line) {
+                if !blank {
+                    let length = u32::try_from(position - start).map_err(|_| too_large())?;
+                    offsets.push(u32::try_from(start).map_err(|_| too_large())?);
+                    longest = longest.max(length);
+                    shortest = shortest.min(length);
+                }
+
+                position += n as u64;
+                start = position;
+                blank = true;
+            } else {
+                position += n as u64;
+                blank &= line.trim_ascii().is_empty();
+            }
+
+            if end {
+                break;
+            }
+        }
+
+        offsets.push(u32::try_from(position).map_err(|_| too_large())?);
+
+        Ok(Index {
+            offsets,
+            longest,
+            shortest: if longest == 0 { 0 } else { shortest },
+        })
+    }
+
+    fn is_delimiter(line: &[u8]) -> bool {
+        line.strip_suffix(b"\n").unwrap_or(line) == [DELIMITER]
+    }
+
+    /// Offsets of the fortunes of the files, from their .dat index when it is not older than the file,
+    /// otherwise from the file itself
+    pub fn read_offsets(paths: &[PathBuf]) -> anyhow::Result<Vec<FortuneOffset>> {
+        let mut output: Vec<FortuneOffset> = Vec::new();
+
+        for path in paths {
+            let dat = dat_path(path);
+            let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified());
+
+            let index = match (modified(&dat), modified(path)) {
+                (Ok(dat_time), Ok(path_time)) if dat_time >= path_time => {
+                    Index::read(BufReader::new(File::open(&dat)?))
+                        .map_err(|e| anyhow::anyhow!("{}: {}", dat.display(), e))?
+                }
+                _ => compile(path)?,
+            };
+
+            let fortunes = index.len();
+            output.extend(
+                index
+                    .offsets
+                    .into_iter()
+                    .take(fortunes)
+                    .map(|offset| FortuneOffset {
+                        path: path.clone(),
+                        offset: offset.into(),
+                    }),
+            );
+        }
+
+        Ok(output)
+    }
+
+    /// Reads the fortune starting at the offset, up to the next delimiter
+    pub fn read_fortune_at(fortune: &FortuneOffset) -> anyhow::Result<String> {
+        let mut reader = BufReader::new(File::open(&fortune.path)?);
+        reader.seek(SeekFrom::Start(fortune.offset))?;
+
+        let mut text: Vec<u8> = Vec::new();
+        let mut line: Vec<u8> = Vec::new();
+
+        while reader.read_until(b'\n', &mut line)? > 0 && !is_delimiter(&line) {
+            text.append(&mut line);
+        }
+
+        Ok(String::from_utf8_lossy(&text).trim().to_string())
+    }
+
+    fn rng(seed: Option<u64>) -> rand::rngs::StdRng {
+        match seed {
+            None => rand::rngs::StdRng::from_rng(&mut rand::rng()),
+            Some(state) => rand::rngs::StdRng::seed_from_u64(state),
+        }
+    }
+
+    /// Number of fortunes of each file
+    pub fn count_fortunes<T>(
+        fortunes: &[T],
+        path: impl Fn(&T) -> &Path,
+    ) -> HashMap<PathBuf, usize> {
+        let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+        for fortune in fortunes {
+            *counts.entry(path(fortune).to_path_buf()).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Chance of each file of being picked, `None` when every fortune is equally likely. The percentage of
+    /// a source is split among its files, what is left among the files of the sources without one, by their
+    /// number of fortunes or equally with `equal`
+    pub fn weigh(
+        sources: &[Source],
+        counts: &HashMap<PathBuf, usize>,
+        equal: bool,
+    ) -> anyhow::Result<Option<Weights>> {
+        if !equal && sources.iter().all(|s| s.percent.is_none()) {
+            return Ok(None);
+        }
+
+        // A file found through several sources belongs to the first one
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        let mut groups: Vec<(Option<u32>, Vec<PathBuf>)> = Vec::new();
+        for source in sources {
+            let files = find_files(std::slice::from_ref(&source.path))?
+                .into_iter()
+                .filter(|f| counts.get(f).is_some_and(|&n| n > 0) && seen.insert(f.clone()))
+                .collect();
+            groups.push((source.percent, files));
+        }
+
+        let total: u32 = sources.iter().filter_map(|s| s.percent).sum();
+        let rest: Vec<PathBuf> = groups
+            .iter()
+            .filter(|(percent, _)| percent.is_none())
+            .flat_map(|(_, files)| files.clone())
+            .collect();
+
+        if rest.is_empty() && total != 100 {
+            anyhow::bail!("probabilities sum to {total}%");
+        }
+        if !rest.is_empty() && total == 100 {
+            anyhow::bail!(
+                "probabilities sum to 100%, nothing left for the files without a percentage"
+            );
+        }
+
+        let mut weights = Weights::new();
+        let mut share = |percent: f64, files: &[PathBuf]| {
+            let size = |f: &PathBuf| if equal { 1.0 } else { counts[f] as f64 };
+            let sum: f64 = files.iter().map(size).sum();
+            for file in files {
+                weights.insert(file.clone(), percent * size(file) / sum);
+            }
+        };
+
+        for (percent, files) in &groups {
+            if let Some(percent) = percent {
+                share(f64::from(*percent), files);
+            }
+        }
+        share(f64::from(100 - total), &rest);
+
+        Ok(Some(weights))
+    }
+
+    /// Picks a file according to the weights, then one of its fortunes. Without weights it picks among all
+    /// the fortunes, which is picking a file by its number of fortunes
+    fn pick<'a, T>(
+        fortunes: &'a [T],
+        path: impl Fn(&T) -> &Path,
+        weights: Option<&Weights>,
+        seed: Option<u64>,
+    ) -> Option<&'a T> {
+        let mut rng = rng(seed);
+
+        let Some(weights) = weights else {
+            return fortunes.choose(&mut rng);
+        };
+
+        let mut files: Vec<&Path> = fortunes.iter().map(&path).collect();
+        files.dedup();
+
+        let dist = WeightedIndex::new(
+            files
+                .iter()
+                .map(|f| weights.get(*f).copied().unwrap_or(0.0)),
+        )
+        .ok()?;
+        let file = files[dist.sample(&mut rng)];
+
+        let within: Vec<&T> = fortunes.iter().filter(|f| path(f) == file).collect();
+        within.choose(&mut rng).copied()
+    }
+
+    /// Picks a fortune among the ones already read, when their length has to be known first
+    pub fn pick_fortune(
+        fortunes: &[Fortune],
+        weights: Option<&Weights>,
+        seed: Option<u64>,
+    ) -> Option<String> {
+        pick(fortunes, |f| &f.path, weights, seed).map(|f| f.text.clone())
+    }
+
+    /// Picks a fortune like `pick_fortune`, reading only the picked one
+    pub fn pick_indexed_fortune(
+        fortunes: &[FortuneOffset],
+        weights: Option<&Weights>,
+        seed: Option<u64>,
+    ) -> anyhow::Result<Option<String>> {
+        pick(fortunes, |f| &f.path, weights, seed)
+            .map(read_fortune_at)
+            .transpose()
+    }
+}
+
+pub use helpers::run;
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{
+        Fortune, Index, Length, Source, compile, count_fortunes, find_files, parse_sources,
+        pick_fortune, pick_indexed_fortune, read_fortunes, read_offsets, weigh,
+    };
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_find_files() {
+        // Verify that the function finds a file known to exist
+        let res = find_files(&["./tests/inputs/jokes".to_string()]);
+        assert!(res.is_ok());
+
+        let files = res.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files.first().unwrap().to_string_lossy(),
+            "./tests/inputs/jokes"
+        );
+
+        // Fails to find a bad file
+        let res = find_files(&["/path/does/not/exist".to_string()]);
+        assert!(res.is_err());
+
+        // Finds all the input files, excludes ".dat"
+        let res = find_files(&["./tests/inputs".to_string()]);
+        assert!(res.is_ok());
+
+        // Check number and order of files
+        let files = res.unwrap();
+        assert_eq!(files.len(), 4);
+        let first = files.first().unwrap().display().to_string();
+        assert!(first.contains("ascii-art"));
+        let last = files.last().unwrap().display().to_string();
+        assert!(last.contains("quotes"));
+
+        // Test for multiple sources, path must be unique and sorted
+        let res = find_files(&[
+            "./tests/inputs/jokes".to_string(),
+            "./tests/inputs/ascii-art".to_string(),
+            "./tests/inputs/jokes".to_string(),
+        ]);
+        assert!(res.is_ok());
+        let files = res.unwrap();
+        assert_eq!(files.len(), 2);
+        if let Some(filename) = files.first().unwrap().file_name() {
+            assert_eq!(filename.to_string_lossy(), "ascii-art".to_string())
+        }
+        if let Some(filename) = files.last().unwrap().file_name() {
+            assert_eq!(filename.to_string_lossy(), "jokes".to_string())
+        }
+    }
+
+    #[test]
+    fn test_read_fortunes() {
+        // Parses all the fortunes without a filter
+        let res = read_fortunes(&[PathBuf::from("./tests/inputs/jokes")], Length::Any);
+        assert!(res.is_ok());
+
+        if let Ok(fortunes) = res {
+            // Correct number and sorting
+            assert_eq!(fortunes.len(), 6);
+            assert_eq!(
+                fortunes.first().unwrap().text,
+                "Q. What do you call a head of lettuce in a shirt and tie?\n\
+                A. Collared greens."
+            );
+            assert_eq!(
+                fortunes.last().unwrap().text,
+                "Q: What do you call a deer wearing an eye patch?\n\
+                A: A bad idea (bad-eye deer)."
+            );
+        }
+
+        // Filters for matching text
+        let files = [
+            PathBuf::from("./tests/inputs/jokes"),
+            PathBuf::from("./tests/inputs/quotes"),
+        ];
+        let res = read_fortunes(&files, Length::Any);
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap().len(), 11);
+
+        // Filters by length, of at most 160 characters by default for the short ones
+        assert_eq!(read_fortunes(&files, Length::Short(160)).unwrap().len(), 9);
+        assert_eq!(read_fortunes(&files, Length::Long(160)).unwrap().len(), 2);
+
+        let fortunes = read_fortunes(&files, Length::Short(47)).unwrap();
+        assert_eq!(fortunes.len(), 1);
+        assert_eq!(
+            fortunes[0].text,
+            "It's like deja vu all over again.\n-- Yogi Berra"
+        );
+    }
+
+    #[test]
+    fn test_read_fortunes_percent_inside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sales");
+        std::fs::write(&path, "Save 50% today\n%\nSecond\n%\n").unwrap();
+
+        // Only the lines that are a `%` end the fortunes, like in the index
+        let fortunes = read_fortunes(std::slice::from_ref(&path), Length::Any).unwrap();
+        let texts: Vec<&str> = fortunes.iter().map(|f| f.text.as_str()).collect();
+        assert_eq!(texts, ["Save 50% today", "Second"]);
+        assert_eq!(compile(&path).unwrap().len(), fortunes.len());
+
+        // Measured whole by the length filters
+        let short = read_fortunes(std::slice::from_ref(&path), Length::Short(10)).unwrap();
+        assert_eq!(short.len(), 1);
+        assert_eq!(short[0].text, "Second");
+    }
+
+    #[test]
+    fn test_length() {
+        assert!(Length::Any.matches(""));
+        assert!(Length::Short(3).matches("abc"));
+        assert!(!Length::Short(3).matches("abcd"));
+        assert!(Length::Long(3).matches("abcd"));
+        assert!(!Length::Long(3).matches("abc"));
+
+        // Characters, not bytes
+        assert!(Length::Short(3).matches("àèì"));
+    }
+
+    #[test]
+    fn test_pick_fortune() {
+        // Create a slice of fortunes
+        let fortunes = &[
+            Fortune {
+                source: "fortunes".to_string(),
+                path: PathBuf::from("fortunes"),
+                text: "You cannot achieve the impossible without \
+                      attempting the absurd."
+                    .to_string(),
+            },
+            Fortune {
+                source: "fortunes".to_string(),
+                path: PathBuf::from("fortunes"),
+                text: "Assumption is the mother of all screw-ups.".to_string(),
+            },
+            Fortune {
+                source: "fortunes".to_string(),
+                path: PathBuf::from("fortunes"),
+                text: "Neckties strangle clear thinking.".to_string(),
+            },
+        ];
+
+        // Pick a fortune with a seed
+        assert_eq!(
+            pick_fortune(fortunes, None, Some(1)).unwrap(),
+            "Neckties strangle clear thinking.".to_string()
+        );
+    }
+
+    #[test]
+    fn test_compile() {
+        let index = compile(Path::new("./tests/inputs/jokes")).unwrap();
+        assert_eq!(index.len(), 6);
+        assert_eq!(index.offsets.first(), Some(&0));
+
+        // The last offset is the end of the file, after the empty fortune at the end
+        let size = std::fs::metadata("./tests/inputs/jokes").unwrap().len();
+        assert_eq!(index.offsets.last(), Some(&(size as u32)));
+        assert!(index.shortest > 0 && index.shortest <= index.longest);
+
+        // Header of big-endian u32: version, number of strings, longest, shortest, flags and delimiter
+        let mut dat: Vec<u8> = Vec::new();
+        index.write(&mut dat).unwrap();
+        assert_eq!(dat.len(), 24 + 4 * 7);
+        assert_eq!(dat[..8], [0, 0, 0, 2, 0, 0, 0, 6]);
+        assert_eq!(dat[20..24], [b'%', 0, 0, 0]);
+
+        assert_eq!(Index::read(dat.as_slice()).unwrap(), index);
+        assert!(Index::read(&dat[..30]).is_err());
+    }
+
+    #[test]
+    fn test_pick_indexed_fortune() {
+        // Reading only the picked fortune gives the same fortune as reading them all
+        let files = find_files(&["./tests/inputs".to_string()]).unwrap();
+        let fortunes = read_fortunes(&files, Length::Any).unwrap();
+        let offsets = read_offsets(&files).unwrap();
+        assert_eq!(offsets.len(), fortunes.len());
+
+        let sources = [Source {
+            path: "./tests/inputs".to_string(),
+            percent: None,
+        }];
+        let weights = weigh(&sources, &count_fortunes(&offsets, |f| &f.path), true).unwrap();
+
+        for seed in 0..20 {
+            assert_eq!(
+                pick_indexed_fortune(&offsets, None, Some(seed)).unwrap(),
+                pick_fortune(&fortunes, None, Some(seed))
+            );
+            assert_eq!(
+                pick_indexed_fortune(&offsets, weights.as_ref(), Some(seed)).unwrap(),
+                pick_fortune(&fortunes, weights.as_ref(), Some(seed))
+            );
+        }
+
+        assert_eq!(pick_indexed_fortune(&[], None, Some(1)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_sources() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            parse_sources(&args(&["30%", "jokes", "quotes"])).unwrap(),
+            [
+                Source {
+                    path: "jokes".to_string(),
+                    percent: Some(30),
+                },
+                Source {
+                    path: "quotes".to_string(),
+                    percent: None,
+                },
+            ]
+        );
+
+        // Only a number followed by a % is a percentage
+        assert_eq!(
+            parse_sources(&args(&["%", "a%b"])).unwrap(),
+            [
+                Source {
+                    path: "%".to_string(),
+                    percent: None,
+                },
+                Source {
+                    path: "a%b".to_string(),
+                    percent: None,
+                },
+            ]
+        );
+
+        assert!(parse_sources(&args(&["jokes", "30%"])).is_err());
+        assert!(parse_sources(&args(&["30%", "40%", "jokes"])).is_err());
+        assert!(parse_sources(&args(&["60%", "jokes", "50%", "quotes"])).is_err());
+    }
+
+    #[test]
+    fn test_weigh() {
+        let files = find_files(&["./tests/inputs".to_string()]).unwrap();
+        let counts = count_fortunes(&read_offsets(&files).unwrap(), |f| &f.path);
+        let jokes = PathBuf::from("./tests/inputs/jokes");
+        let quotes = PathBuf::from("./tests/inputs/quotes");
+        let source = |path: &Path, percent| Source {
+            path: path.display().to_string(),
+            percent,
+        };
+
+        // Every fortune is equally likely without percentages
+        let sources = [source(&jokes, None), source(&quotes, None)];
+        assert!(weigh(&sources, &counts, false).unwrap().is_none());
+
+        // The files are equally likely with -e
+        let weights = weigh(&sources, &counts, true).unwrap().unwrap();
+        assert_eq!(weights[&jokes], 50.0);
+        assert_eq!(weights[&quotes], 50.0);
+
+        // The rest goes to the files without a percentage
+        let sources = [
+            source(&jokes, Some(30)),
+            source(Path::new("./tests/inputs"), None),
+        ];
+        let weights = weigh(&sources, &counts, false).unwrap().unwrap();
+        assert_eq!(weights[&jokes], 30.0);
+        assert_eq!(weights.values().sum::<f64>(), 100.0);
+        assert_eq!(weights.len(), 4);
+
+        // The percentages must sum to 100 when every source has one
+        assert!(weigh(&[source(&jokes, Some(30))], &counts, false).is_err());
+        let sources = [source(&jokes, Some(100)), source(&quotes, None)];
+        assert!(weigh(&sources, &counts, false).is_err());
+    }
+}
@@ -91,7 +91,7 @@ fn no_fortunes_found() -> Result<()> {
 #[test]
 fn quotes_seed_1() -> Result<()> {
     run(
-        &[QUOTES, "-s", "1"],
+        &[QUOTES, "--seed", "1"],
         "You can observe a lot just by watching.\n-- Yogi Berra\n",
     )
 }
@@ -100,7 +100,7 @@ fn quotes_seed_1() -> Result<()> {
 #[test]
 fn jokes_seed_1() -> Result<()> {
     run(
-        &[JOKES, "-s", "1"],
+        &[JOKES, "--seed", "1"],
         "Q: What happens when frogs park illegally?\nA: They get toad.\n",
     )
 }
@@ -109,12 +109,47 @@ fn jokes_seed_1() -> Result<()> {
 #[test]
 fn dir_seed_10() -> Result<()> {
     run(
-        &[FORTUNE_DIR, "-s", "10"],
+        &[FORTUNE_DIR, "--seed", "10"],
         "Q: Why did the fungus and the alga marry?\n\
         A: Because they took a lichen to each other!\n",
     )
 }
 
+// --------------------------------------------------
+#[test]
+fn compile_and_use_index() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let jokes = dir.path().join("jokes");
+    fs::copy(JOKES, &jokes)?;
+    let jokes = jokes.display().to_string();
+
+    Command::cargo_bin(PRG)?
+        .args(["--compile", &jokes])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "\"{jokes}.dat\" created\nThere were 6 strings\n"
+        )));
+    assert!(dir.path().join("jokes.dat").is_file());
+
+    // The index is not read as a fortune file and gives the same fortunes
+    run(
+        &[&jokes, "--seed", "1"],
+        "Q: What happens when frogs park illegally?\nA: They get toad.\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_compile_with_pattern() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--compile", "-m", "Yogi", QUOTES])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run(args: &[&str], expected: &'static str) -> Result<()> {
     let output = Command::cargo_bin(PRG)?.args(args).output().expect("fail");
@@ -201,3 +236,97 @@ fn mark_twain_lower_i() -> Result<()> {
         "tests/expected/twain_lower_i.err",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn dies_percentage_without_file() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([JOKES, "30%"])
+        .assert()
+        .failure()
+        .stderr("30%: percentage not followed by a file or directory\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_percentages_over_100() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["60%", JOKES, "50%", QUOTES])
+        .assert()
+        .failure()
+        .stderr("probabilities sum to 110% > 100%\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn weighted_jokes_seed_1() -> Result<()> {
+    // The quotes are never picked
+    run(
+        &["--seed", "1", "0%", QUOTES, JOKES],
+        "Q: What happens when frogs park illegally?\nA: They get toad.\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn equal_dir_seed_1() -> Result<()> {
+    run(
+        &["-e", FORTUNE_DIR, "--seed", "1"],
+        "It's like deja vu all over again.\n-- Yogi Berra\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn long_quotes_length_200() -> Result<()> {
+    run(
+        &[QUOTES, "-l", "-n", "200", "--seed", "1"],
+        "Twenty years from now you will be more disappointed by the things that you didn't do \
+        than by the ones you did do. So throw off the bowlines. Sail away from the safe harbor. \
+        Catch the trade winds in your sails. Explore. Dream. Discover.\n-- Mark Twain\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn short_quotes_length_50() -> Result<()> {
+    run(
+        &[QUOTES, "-s", "-n", "50"],
+        "It's like deja vu all over again.\n-- Yogi Berra\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn short_pattern() -> Result<()> {
+    let output = Command::cargo_bin(PRG)?
+        .args(["-s", "-m", "Mark Twain", QUOTES])
+        .output()?;
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(
+        stdout,
+        "I can live for two months on a good compliment.\n-- Mark Twain\n%\n"
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_long_jokes() -> Result<()> {
+    run(&[JOKES, "-l"], "No fortunes found\n")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_short_and_long() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-s", "-l", JOKES])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
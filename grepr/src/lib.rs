@@ -0,0 +1,723 @@
+//! Rust version of `grep`, the `run` function is called by the `grepr` binary and by `ruty`
+
+use clap::{Parser, ValueEnum};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `grep`
+pub struct Cli {
+    #[arg(required_unless_present = "regexp")]
+    /// Search pattern
+    pattern: Option<String>,
+
+    #[arg(num_args=0.., value_name="FILE")]
+    /// Input file(s), stdin by default
+    files: Vec<String>,
+
+    #[arg(short('e'), long, value_name("PATTERN"))]
+    /// Search pattern, can be repeated, the first positional argument is then a file
+    regexp: Vec<String>,
+
+    #[arg(short('F'), long("fixed-strings"))]
+    /// Search the patterns as literal strings instead of regular expressions
+    fixed_strings: bool,
+
+    #[arg(short('w'), long("word-regexp"))]
+    /// Match only whole words
+    word_regexp: bool,
+
+    #[arg(short, long)]
+    /// Case-insensitive
+    insensitive: bool,
+
+    #[arg(short, long)]
+    /// Recursive search
+    recursive: bool,
+
+    #[arg(short, long)]
+    /// Count occurences
+    count: bool,
+
+    #[arg(short('v'), long("invert-match"))]
+    /// Invert match
+    invert: bool,
+
+    #[arg(short('n'), long("line-number"))]
+    /// Prefix each line with its line number
+    line_number: bool,
+
+    #[arg(short('b'), long("byte-offset"))]
+    /// Prefix each line with the byte offset of its start, of the match itself with -o
+    byte_offset: bool,
+
+    #[arg(short('o'), long("only-matching"))]
+    /// Print only the matched parts of the lines, each on its own line
+    only_matching: bool,
+
+    #[arg(long, value_enum, value_name("TYPE"), default_value_t = BinaryFiles::Binary)]
+    /// How to search the files with a NUL byte in their first chunk
+    binary_files: BinaryFiles,
+
+    #[arg(short('a'), long, conflicts_with = "binary_files")]
+    /// Search binary files as text, like --binary-files=text
+    text: bool,
+
+    #[arg(short('A'), long("after-context"), value_name("NUM"))]
+    /// Print NUM lines of trailing context
+    after_context: Option<usize>,
+
+    #[arg(short('B'), long("before-context"), value_name("NUM"))]
+    /// Print NUM lines of leading context
+    before_context: Option<usize>,
+
+    #[arg(short('C'), long("context"), value_name("NUM"))]
+    /// Print NUM lines of leading and trailing context
+    context: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum BinaryFiles {
+    /// Print only whether the file matches
+    Binary,
+    /// Print the matching lines like in any other file
+    Text,
+    /// Skip the file
+    WithoutMatch,
+}
+
+mod helper {
+    use aho_corasick::{AhoCorasick, MatchKind};
+    use regex::Regex;
+    use ruty_core::open_maybe_compressed;
+    use std::cmp::Reverse;
+    use std::collections::VecDeque;
+    use std::io::{self, BufRead};
+    use std::ops::Range;
+    use walkdir::WalkDir;
+
+    /// Lines printed around each match, -A and -B take precedence over -C
+    #[derive(Debug, Default, Clone, Copy, PartialEq)]
+    pub struct Context {
+        pub before: usize,
+        pub after: usize,
+    }
+
+    impl Context {
+        pub fn from_args(args: &crate::Cli) -> Context {
+            Context {
+                before: args.before_context.or(args.context).unwrap_or(0),
+                after: args.after_context.or(args.context).unwrap_or(0),
+            }
+        }
+
+        /// Non-contiguous groups of lines are separated by `--` only when there is context
+        pub fn is_empty(&self) -> bool {
+            self.before == 0 && self.after == 0
+        }
+    }
+
+    /// Line of an input with where it was found and what matched in it
+    #[derive(Debug, PartialEq)]
+    pub struct Record {
+        /// 1-based line number
+        pub number: usize,
+        /// Byte offset of the start of the line in the input
+        pub offset: usize,
+        /// Line with its line ending
+        pub text: String,
+        /// Byte ranges of the non empty matches in `text`, none for context and inverted matches
+        pub spans: Vec<Range<usize>>,
+    }
+
+    /// What `find_lines` looks for in each line
+    pub trait Matcher {
+        /// Byte ranges of the matches in `hay` from left to right, empty matches included
+        fn find_spans(&self, hay: &str) -> Vec<Range<usize>>;
+
+        /// Whether `hay` has a match, even an empty one
+        fn is_match(&self, hay: &str) -> bool;
+    }
+
+    impl Matcher for Regex {
+        fn find_spans(&self, hay: &str) -> Vec<Range<usize>> {
+            self.find_iter(hay).map(|m| m.range()).collect()
+        }
+
+        fn is_match(&self, hay: &str) -> bool {
+            Regex::is_match(self, hay)
+        }
+    }
+
+    /// Literal strings searched all at once with Aho-Corasick, the case is ignored only for ASCII
+    pub struct FixedStrings {
+        automaton: AhoCorasick,
+        word: bool,
+    }
+
+    impl FixedStrings {
+        pub fn new(patterns: &[String], insensitive: bool, word: bool) -> anyhow::Result<Self> {
+            // Whole words are picked among all the overlapping matches
+            let kind = if word {
+                MatchKind::Standard
+            } else {
+                MatchKind::LeftmostLongest
+            };
+            let automaton = AhoCorasick::builder()
+                .ascii_case_insensitive(insensitive)
+                .match_kind(kind)
+                .build(patterns)?;
+
+            Ok(FixedStrings { automaton, word })
+        }
+    }
+
+    impl Matcher for FixedStrings {
+        fn find_spans(&self, hay: &str) -> Vec<Range<usize>> {
+            if !self.word {
+                return self.automaton.find_iter(hay).map(|m| m.range()).collect();
+            }
+
+            let mut words: Vec<Range<usize>> = self
+                .automaton
+                .find_overlapping_iter(hay)
+                .map(|m| m.range())
+                .filter(|span| is_whole_word(hay, span))
+                .collect();
+            words.sort_by_key(|span| (span.start, Reverse(span.end)));
+
+            // The leftmost and then longest words, without overlaps
+            let mut spans: Vec<Range<usize>> = Vec::new();
+            for span in words {
+                if spans.last().is_none_or(|last| span.start >= last.end) {
+                    spans.push(span);
+                }
+            }
+            spans
+        }
+
+        fn is_match(&self, hay: &str) -> bool {
+            if self.word {
+                !self.find_spans(hay).is_empty()
+            } else {
+                self.automaton.is_match(hay)
+            }
+        }
+    }
+
+    /// Letters, digits and the underscore make words, like in grep
+    fn is_word_char(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+
+    /// A match is a whole word when it is not preceded nor followed by a word character
+    fn is_whole_word(hay: &str, span: &Range<usize>) -> bool {
+        let before = hay[..span.start].chars().next_back();
+        let after = hay[span.end..].chars().next();
+
+        !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+    }
+
+    /// Builds the matcher of the patterns, literal strings with -F and a regex otherwise
+    fn build_matcher(args: &crate::Cli, patterns: &[String]) -> anyhow::Result<Box<dyn Matcher>> {
+        if args.fixed_strings {
+            let matcher = FixedStrings::new(patterns, args.insensitive, args.word_regexp)?;
+            return Ok(Box::new(matcher));
+        }
+
+        for pattern in patterns {
+            Regex::new(pattern).map_err(|_| anyhow::anyhow!(r#"Invalid pattern "{}""#, pattern))?;
+        }
+
+        let mut pattern = patterns
+            .iter()
+            .map(|pattern| format!("(?:{})", pattern))
+            .collect::<Vec<_>>()
+            .join("|");
+        if args.word_regexp {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        let re = regex::RegexBuilder::new(&pattern)
+            .case_insensitive(args.insensitive)
+            .build()?;
+        Ok(Box::new(re))
+    }
+
+    /// Line found by `find_lines`
+    #[derive(Debug, PartialEq)]
+    pub enum Line {
+        Match(Record),
+        Context(Record),
+        /// Gap between two groups of matches and their context
+        Separator,
+    }
+
+    pub fn run(mut args: crate::Cli) -> anyhow::Result<()> {
+        // With -e the first positional argument is a file
+        let patterns: Vec<String> = if args.regexp.is_empty() {
+            args.pattern.take().into_iter().collect()
+        } else {
+            args.files.splice(0..0, args.pattern.take());
+            args.regexp.clone()
+        };
+        if args.files.is_empty() {
+            args.files.push("-".to_string());
+        }
+
+        let matcher = build_matcher(&args, &patterns)?;
+
+        let entries = find_files(&args.files, args.recursive);
+        let context = Context::from_args(&args);
+        let binary_files = if args.text {
+            crate::BinaryFiles::Text
+        } else {
+            args.binary_files
+        };
+        let mut printed = false;
+
+        for entry in entries {
+            match entry {
+                Err(e) => eprintln!("{}", e),
+                Ok(filename) => match open_maybe_compressed(&filename) {
+                    Err(e) => eprintln!("{}: {}", filename, e),
+                    Ok(mut file) => {
+                        let binary =
+                            binary_files != crate::BinaryFiles::Text && is_binary(&mut file)?;
+                        if binary && binary_files == crate::BinaryFiles::WithoutMatch {
+                            continue;
+                        }
+
+                        let lines = find_lines(file, matcher.as_ref(), args.invert, context)?;
+
+                        if args.count {
+                            let count = lines
+                                .iter()
+                                .filter(|line| matches!(line, Line::Match(_)))
+                                .count();
+                            print_output(&args, &filename, ':', &format!("{}\n", count));
+                        } else if binary {
+                            if lines.iter().any(|line| matches!(line, Line::Match(_))) {
+                                let name = if filename == "-" {
+                                    "(standard input)"
+                                } else {
+                                    &filename
+                                };
+                                println!("Binary file {} matches", name);
+                            }
+                        } else {
+                            // Groups of different files are separated too
+                            if printed && !context.is_empty() && !lines.is_empty() {
+                                println!("--");
+                            }
+                            printed |= !lines.is_empty();
+
+                            lines.iter().for_each(|line| match line {
+                                Line::Match(record) if args.only_matching => {
+                                    for span in &record.spans {
+                                        print_record(
+                                            &args,
+                                            &filename,
+                                            ':',
+                                            record.number,
+                                            record.offset + span.start,
+                                            &format!("{}\n", &record.text[span.clone()]),
+                                        );
+                                    }
+                                }
+                                Line::Match(record) => print_record(
+                                    &args,
+                                    &filename,
+                                    ':',
+                                    record.number,
+                                    record.offset,
+                                    &record.text,
+                                ),
+                                // Like grep, only the separators of the context are left with -o
+                                Line::Context(_) if args.only_matching => {}
+                                Line::Context(record) => print_record(
+                                    &args,
+                                    &filename,
+                                    '-',
+                                    record.number,
+                                    record.offset,
+                                    &record.text,
+                                ),
+                                Line::Separator => println!("--"),
+                            });
+                        }
+                    }
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints a line like `print_output`, after its line number with -n and its byte offset with -b
+    fn print_record(
+        args: &crate::Cli,
+        filename: &str,
+        separator: char,
+        number: usize,
+        offset: usize,
+        out: &str,
+    ) {
+        let mut prefix = String::new();
+        if args.line_number {
+            prefix.push_str(&format!("{}{}", number, separator));
+        }
+        if args.byte_offset {
+            prefix.push_str(&format!("{}{}", offset, separator));
+        }
+
+        print_output(args, filename, separator, &format!("{}{}", prefix, out));
+    }
+
+    /// Prints a line, after the filename when there are several files, followed by `:` for matches and `-` for context
+    fn print_output(args: &crate::Cli, filename: &str, separator: char, out: &str) {
+        if args.files.len() > 1 || args.recursive {
+            print!("{}{}{}", filename, separator, out);
+        } else {
+            print!("{}", out);
+        }
+    }
+
+    /// A file is binary when its first chunk has a NUL byte, the chunk is left to be read
+    pub fn is_binary<T: BufRead>(file: &mut T) -> io::Result<bool> {
+        Ok(file.fill_buf()?.contains(&0))
+    }
+
+    /// Returns the matching lines with the lines of context around them, and separators between
+    /// the groups of lines that are not contiguous
+    pub fn find_lines<T: BufRead>(
+        mut file: T,
+        matcher: &dyn Matcher,
+        invert: bool,
+        context: Context,
+    ) -> anyhow::Result<Vec<Line>> {
+        let mut out: Vec<Line> = Vec::new();
+        let mut buf: Vec<u8> = Vec::new();
+
+        // Lines not printed yet that precede the next match
+        let mut before: VecDeque<Record> = VecDeque::with_capacity(context.before);
+        let mut after_left = 0;
+        let mut last_printed: Option<usize> = None;
+        let mut index = 0;
+        let mut offset = 0;
+
+        loop {
+            match file.read_until(b'\n', &mut buf) {
+                Err(e) => {
+                    return Err(anyhow::anyhow!("{}", e));
+                }
+                Ok(n) => {
+                    if n == 0 {
+                        break;
+                    }
+
+                    // Invalid UTF-8, like in binary files searched as text, is replaced
+                    let hay = String::from_utf8_lossy(&buf);
+
+                    let spans: Vec<Range<usize>> = if invert {
+                        Vec::new()
+                    } else {
+                        matcher
+                            .find_spans(&hay)
+                            .into_iter()
+                            .filter(|span| !span.is_empty())
+                            .collect()
+                    };
+                    let record = Record {
+                        number: index + 1,
+                        offset,
+                        text: hay.to_string(),
+                        spans,
+                    };
+
+                    if matcher.is_match(&hay) ^ invert {
+                        let first = index - before.len();
+                        if !context.is_empty() && last_printed.is_some_and(|last| first > last + 1)
+                        {
+                            out.push(Line::Separator);
+                        }
+
+                        out.extend(before.drain(..).map(Line::Context));
+                        out.push(Line::Match(record));
+                        last_printed = Some(index);
+                        after_left = context.after;
+                    } else if after_left > 0 {
+                        out.push(Line::Context(record));
+                        last_printed = Some(index);
+                        after_left -= 1;
+                    } else if context.before > 0 {
+                        if before.len() == context.before {
+                            before.pop_front();
+                        }
+                        before.push_back(record);
+                    }
+
+                    buf.clear();
+                    index += 1;
+                    offset += n;
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    pub fn find_files(paths: &[String], recursive: bool) -> Vec<anyhow::Result<String>> {
+        let mut out: Vec<anyhow::Result<String>> = Vec::new();
+
+        for path in paths {
+            if path == "-" {
+                out.push(Ok("-".to_string()));
+            } else {
+                for (i, entry) in WalkDir::new(path).into_iter().enumerate() {
+                    match entry {
+                        Err(e) => {
+                            out.push(Err(anyhow::anyhow!("{}: {}", path, e)));
+                        }
+                        Ok(e) => {
+                            if i == 0 && !recursive && e.file_type().is_dir() {
+                                out.push(Err(anyhow::anyhow!(
+                                    "{} is a directory",
+                                    e.path().display()
+                                )));
+                                break;
+                            }
+
+                            if e.file_type().is_file() {
+                                out.push(Ok(e.path().display().to_string()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+pub use helper::run;
+
+#[cfg(test)]
+mod test {
+    use crate::helper::*;
+    use pretty_assertions::assert_eq;
+    use rand::{Rng, distr::Alphanumeric};
+    use regex::{Regex, RegexBuilder};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_find_lines() {
+        let text = b"Lorem\nIpsum\r\nDOLOR";
+
+        println!("First test");
+        // The pattern _or_ should match the one line, "Lorem"
+        let re1 = Regex::new("or").unwrap();
+        let matches = find_lines(Cursor::new(&text), &re1, false, Context::default());
+        assert!(matches.is_ok());
+        assert_eq!(matches.unwrap().len(), 1);
+
+        println!("Second test");
+        // When inverted, the function should match the other two lines
+        let matches = find_lines(Cursor::new(&text), &re1, true, Context::default());
+        assert!(matches.is_ok());
+        assert_eq!(matches.unwrap().len(), 2);
+
+        println!("Third test");
+        // This regex will be case-insensitive
+        let re2 = RegexBuilder::new("or")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+
+        println!("Fourth test");
+        // The two lines "Lorem" and "DOLOR" should match
+        let matches = find_lines(Cursor::new(&text), &re2, false, Context::default());
+        assert!(matches.is_ok());
+        assert_eq!(matches.unwrap().len(), 2);
+
+        println!("Fifth test");
+        // When inverted, the one remaining line should match
+        let matches = find_lines(Cursor::new(&text), &re2, true, Context::default());
+        assert!(matches.is_ok());
+        assert_eq!(matches.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_find_lines_context() {
+        let text = b"one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+        let re = Regex::new("^(two|six)").unwrap();
+        let context = Context {
+            before: 1,
+            after: 1,
+        };
+
+        let lines = find_lines(Cursor::new(&text), &re, false, context).unwrap();
+        let texts: Vec<_> = lines
+            .iter()
+            .map(|line| match line {
+                Line::Match(record) => format!(":{}", record.text),
+                Line::Context(record) => format!("-{}", record.text),
+                Line::Separator => "--".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "-one\n", ":two\n", "-three\n", "--", "-five\n", ":six\n", "-seven\n"
+            ]
+        );
+
+        // Groups that touch are merged, without a separator
+        let context = Context {
+            before: 3,
+            after: 0,
+        };
+        let lines = find_lines(Cursor::new(&text), &re, false, context).unwrap();
+        assert_eq!(lines.len(), 6);
+        assert!(!lines.contains(&Line::Separator));
+
+        // Without context only the matches are returned
+        let lines = find_lines(Cursor::new(&text), &re, false, Context::default()).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| matches!(line, Line::Match(_))));
+    }
+
+    #[test]
+    fn test_find_lines_records() {
+        let text = b"cat cat\nbird\ncat dog cat\n";
+        let re = Regex::new("cat|x*").unwrap();
+
+        let lines = find_lines(Cursor::new(&text), &re, false, Context::default()).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                Line::Match(Record {
+                    number: 1,
+                    offset: 0,
+                    text: "cat cat\n".to_string(),
+                    spans: vec![0..3, 4..7],
+                }),
+                // Empty matches select the line but are not spans
+                Line::Match(Record {
+                    number: 2,
+                    offset: 8,
+                    text: "bird\n".to_string(),
+                    spans: vec![],
+                }),
+                Line::Match(Record {
+                    number: 3,
+                    offset: 13,
+                    text: "cat dog cat\n".to_string(),
+                    spans: vec![0..3, 8..11],
+                }),
+            ]
+        );
+
+        // Inverted matches have no spans
+        let re = Regex::new("dog").unwrap();
+        let lines = find_lines(Cursor::new(&text), &re, true, Context::default()).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines
+                .iter()
+                .all(|line| matches!(line, Line::Match(r) if r.spans.is_empty()))
+        );
+    }
+
+    #[test]
+    fn test_fixed_strings() {
+        let text = b"a.b cat\nconcat a.b\nA.B\naxb\n";
+        let spans = |matcher: &dyn Matcher| -> Vec<Vec<std::ops::Range<usize>>> {
+            find_lines(Cursor::new(&text), matcher, false, Context::default())
+                .unwrap()
+                .into_iter()
+                .map(|line| match line {
+                    Line::Match(record) => record.spans,
+                    _ => vec![],
+                })
+                .collect()
+        };
+        let patterns = ["a.b".to_string(), "cat".to_string()];
+
+        // The dot is not a wildcard
+        let fixed = FixedStrings::new(&patterns, false, false).unwrap();
+        assert_eq!(spans(&fixed), vec![vec![0..3, 4..7], vec![3..6, 7..10]]);
+
+        let fixed = FixedStrings::new(&patterns, true, false).unwrap();
+        assert_eq!(spans(&fixed).len(), 3);
+
+        // Only whole words, "cat" inside "concat" is left out
+        let words = FixedStrings::new(&patterns, false, true).unwrap();
+        assert_eq!(spans(&words), vec![vec![0..3, 4..7], vec![7..10]]);
+
+        let patterns = ["cat".to_string(), "concat".to_string()];
+        let words = FixedStrings::new(&patterns, false, true).unwrap();
+        assert_eq!(spans(&words), vec![vec![4..7], vec![0..6]]);
+    }
+
+    #[test]
+    fn test_is_binary() {
+        let mut text = Cursor::new(b"fox\njumps\n");
+        assert!(!is_binary(&mut text).unwrap());
+
+        // The chunk checked is still read by find_lines
+        let mut binary = Cursor::new(b"fox\0\xff jumps\nfox\n");
+        assert!(is_binary(&mut binary).unwrap());
+
+        let re = Regex::new("fox").unwrap();
+        let lines = find_lines(binary, &re, false, Context::default()).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(matches!(&lines[0], Line::Match(r) if r.text == "fox\0\u{FFFD} jumps\n"));
+    }
+
+    #[test]
+    fn test_find_files() {
+        // Verify that the function finds a file known to exist
+        let files = find_files(&["./tests/inputs/fox.txt".to_string()], false);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].as_ref().unwrap(), "./tests/inputs/fox.txt");
+
+        // The function should reject a directory without the recursive option
+        let files = find_files(&["./tests/inputs".to_string()], false);
+        assert_eq!(files.len(), 1);
+        if let Err(e) = &files[0] {
+            assert_eq!(e.to_string(), "./tests/inputs is a directory");
+        }
+
+        // Verify the function recurses to find four files in the directory
+        let res = find_files(&["./tests/inputs".to_string()], true);
+        let mut files: Vec<String> = res
+            .iter()
+            .map(|r| r.as_ref().unwrap().replace("\\", "/"))
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 4);
+        assert_eq!(
+            files,
+            vec![
+                "./tests/inputs/bustle.txt",
+                "./tests/inputs/empty.txt",
+                "./tests/inputs/fox.txt",
+                "./tests/inputs/nobody.txt",
+            ]
+        );
+
+        // Generate a random string to represent a nonexistent file
+        let bad: String = rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+
+        // Verify that the function returns the bad file as an error
+        let files = find_files(&[bad], false);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].is_err());
+    }
+}
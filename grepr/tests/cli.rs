@@ -2,7 +2,7 @@ use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
-use rand::{distr::Alphanumeric, Rng};
+use rand::{Rng, distr::Alphanumeric};
 use std::{fs, path::Path};
 use sys_info::os_type;
 
@@ -100,6 +100,87 @@ fn bustle_capitalized() -> Result<()> {
     )
 }
 
+// --------------------------------------------------
+#[test]
+fn bustle_capitalized_after_context() -> Result<()> {
+    run(
+        &["-A", "1", "The", BUSTLE],
+        "tests/expected/bustle.txt.the.capitalized.A1",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_nobody_context() -> Result<()> {
+    run(
+        &["-C", "1", "us", BUSTLE, NOBODY],
+        "tests/expected/bustle.nobody.us.C1",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_insensitive_line_number() -> Result<()> {
+    run(
+        &["-n", "-i", "the", BUSTLE],
+        "tests/expected/bustle.txt.the.insensitive.n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_fox_line_number_before_context() -> Result<()> {
+    run(
+        &["--line-number", "-B", "1", "The", BUSTLE, FOX],
+        "tests/expected/bustle.fox.The.n.B1",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_nobody_only_matching() -> Result<()> {
+    run(
+        &["-o", "-i", "the", BUSTLE, NOBODY],
+        "tests/expected/bustle.nobody.the.insensitive.o",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_line_number_byte_offset_context() -> Result<()> {
+    run(
+        &["-n", "--byte-offset", "-C", "1", "The", BUSTLE],
+        "tests/expected/bustle.txt.The.n.b.C1",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_nobody_only_matching_byte_offset() -> Result<()> {
+    run(
+        &["-o", "-b", "-i", "the", BUSTLE, NOBODY],
+        "tests/expected/bustle.nobody.the.insensitive.o.b",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_only_matching_line_number_context() -> Result<()> {
+    run(
+        &["--only-matching", "-n", "-C", "1", "The", BUSTLE],
+        "tests/expected/bustle.txt.The.on.C1",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn all_insensitive_before_context() -> Result<()> {
+    run(
+        &["-i", "-B", "2", "nobody", BUSTLE, EMPTY, FOX, NOBODY],
+        "tests/expected/all.nobody.insensitive.B2",
+    )
+}
+
 // --------------------------------------------------
 #[test]
 fn bustle_lowercase() -> Result<()> {
@@ -281,3 +362,179 @@ fn stdin_insensitive_count() -> Result<()> {
     assert_eq!(stdout, expected);
     Ok(())
 }
+
+// --------------------------------------------------
+#[test]
+#[cfg(feature = "compressed")]
+fn gzip() -> Result<()> {
+    let fox_gz = "tests/compressed/fox.txt.gz";
+    run(&["fox", fox_gz], "tests/expected/empty_regex.fox.txt")?;
+
+    let expected = fs::read_to_string("tests/expected/empty_regex.fox.txt")?;
+    Command::cargo_bin(PRG)?
+        .arg("fox")
+        .write_stdin(fs::read(fox_gz)?)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+fn binary_dir() -> Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("binary.dat"),
+        b"fox\0\xff\xfe jumps\nanother fox\n",
+    )?;
+    fs::copy(FOX, dir.path().join("fox.txt"))?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_file_matches() -> Result<()> {
+    let dir = binary_dir()?;
+    let binary = dir.path().join("binary.dat").display().to_string();
+
+    Command::cargo_bin(PRG)?
+        .args(["fox", &binary])
+        .assert()
+        .success()
+        .stdout(format!("Binary file {binary} matches\n"));
+
+    // Nothing is printed when there is no match, and matches are counted as usual
+    Command::cargo_bin(PRG)?
+        .args(["dog", &binary])
+        .assert()
+        .success()
+        .stdout("");
+    Command::cargo_bin(PRG)?
+        .args(["-c", "fox", &binary])
+        .assert()
+        .success()
+        .stdout("2\n");
+
+    Command::cargo_bin(PRG)?
+        .arg("fox")
+        .write_stdin(fs::read(&binary)?)
+        .assert()
+        .success()
+        .stdout("Binary file (standard input) matches\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_files_recursive() -> Result<()> {
+    let dir = binary_dir()?;
+    let path = dir.path().display().to_string();
+    let fox = fs::read_to_string(FOX)?;
+
+    let output = Command::cargo_bin(PRG)?
+        .args(["-r", "fox", &path])
+        .output()?;
+    assert!(output.status.success());
+    let mut lines: Vec<String> = String::from_utf8(output.stdout)?
+        .lines()
+        .map(String::from)
+        .collect();
+    lines.sort();
+    let mut expected = vec![
+        format!("Binary file {path}/binary.dat matches"),
+        format!("{path}/fox.txt:{}", fox.trim_end()),
+    ];
+    expected.sort();
+    assert_eq!(lines, expected);
+
+    Command::cargo_bin(PRG)?
+        .args(["-r", "--binary-files", "without-match", "fox", &path])
+        .assert()
+        .success()
+        .stdout(format!("{path}/fox.txt:{fox}"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_files_text() -> Result<()> {
+    let dir = binary_dir()?;
+    let binary = dir.path().join("binary.dat").display().to_string();
+    let expected = "fox\0\u{FFFD}\u{FFFD} jumps\nanother fox\n";
+
+    for args in [
+        ["-a", "fox", &binary],
+        ["--binary-files=text", "fox", &binary],
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_text_with_binary_files() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-a", "--binary-files", "binary", "fox", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fixed_strings() -> Result<()> {
+    run(&["-F", "you?", NOBODY], "tests/expected/nobody.txt.you.F")
+}
+
+// --------------------------------------------------
+#[test]
+fn fixed_strings_words_only_matching() -> Result<()> {
+    run(
+        &["-o", "-F", "-w", "-e", "us", "-e", "you", NOBODY],
+        "tests/expected/nobody.txt.us.you.F.w.o",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn word_regexp_insensitive() -> Result<()> {
+    run(
+        &["-w", "-i", "the", BUSTLE],
+        "tests/expected/bustle.txt.the.w.insensitive",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn word_regexp_only_matching() -> Result<()> {
+    run(
+        &["-w", "-o", "a[a-z]*", NOBODY],
+        "tests/expected/nobody.txt.w.o.a",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn several_patterns() -> Result<()> {
+    run(
+        &["-e", "The", "-e", "Nobody", BUSTLE, NOBODY],
+        "tests/expected/bustle.nobody.e.The.Nobody",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_regexp() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-e", "fox", "-e", "*foo", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"Invalid pattern "*foo""#));
+    Ok(())
+}
@@ -0,0 +1,8 @@
+tests/inputs/bustle.txt:0:The
+tests/inputs/bustle.txt:22:The
+tests/inputs/bustle.txt:97:The
+tests/inputs/bustle.txt:113:the
+tests/inputs/nobody.txt:51:The
+tests/inputs/nobody.txt:56:the
+tests/inputs/nobody.txt:91:the
+tests/inputs/nobody.txt:205:the
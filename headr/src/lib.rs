@@ -0,0 +1,344 @@
+//! Rust version of `head`, the `run` function is called by the `headr` binary and by `ruty`
+
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `head`
+pub struct Args {
+    /// Input file(s)
+    #[arg(
+        default_value = "-",
+        num_args=0..,
+        value_name = "FILE"
+    )]
+    files: Vec<String>,
+
+    /// Number of lines, with a leading '-' all but the last ones
+    #[arg(
+        short('n'),
+        long("lines"),
+        default_value = "10",
+        conflicts_with = "bytes",
+        value_name = "LINES",
+        allow_hyphen_values = true,
+        value_parser(helper::parse_count)
+    )]
+    lines: helper::Count,
+
+    /// Number of bytes, with a suffix like K, M, G or KiB, KB..., with a leading '-' all but the last ones
+    #[arg(
+        short('c'),
+        long("bytes"),
+        value_name = "BYTES",
+        allow_hyphen_values = true,
+        value_parser(helper::parse_count)
+    )]
+    bytes: Option<helper::Count>,
+
+    /// Never print headers with the file names
+    #[arg(short('q'), long, visible_alias("silent"), overrides_with = "verbose")]
+    quiet: bool,
+
+    /// Always print headers with the file names
+    #[arg(short('v'), long, overrides_with = "quiet")]
+    verbose: bool,
+
+    /// Line delimiter is NUL, not newline
+    #[arg(short('z'), long)]
+    zero_terminated: bool,
+}
+
+mod helper {
+    use ruty_core::{header, open_maybe_compressed, parse_size};
+    use std::collections::VecDeque;
+    use std::io::{self, BufRead, Read, Write};
+    use std::num::NonZeroUsize;
+    use std::sync::{Mutex, mpsc};
+    use std::thread;
+
+    /// Files read ahead of the one being printed when printing the first lines or bytes of many files
+    const PREFETCHED: usize = 64;
+
+    /// Largest count of bytes read ahead from each file
+    const PREFETCHED_BYTES: u64 = 1 << 20;
+
+    /// Lines or bytes printed from the start of each file
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Count {
+        First(u64),
+        AllButLast(u64),
+    }
+
+    /// Parses a count like GNU head: a size like `ruty_core::parse_size` does, `-N` being all but the last N
+    pub fn parse_count(count: &str) -> Result<Count, String> {
+        let error = |_| format!("invalid size: \"{}\"", count);
+
+        match count.strip_prefix('-') {
+            Some(last) => parse_size(last).map(Count::AllButLast).map_err(error),
+            None => parse_size(count).map(Count::First).map_err(error),
+        }
+    }
+
+    /// Command line main logic
+    pub fn run(args: crate::Args) -> anyhow::Result<()> {
+        let head = Head {
+            args: &args,
+            headers: !args.quiet && (args.verbose || args.files.len() > 1),
+            delimiter: if args.zero_terminated { b'\0' } else { b'\n' },
+        };
+        let mut stdout = io::stdout().lock();
+
+        // With a leading '-' the whole file is read, reading many of them ahead could take a lot of memory
+        let prefetch = match (args.bytes, args.lines) {
+            (Some(Count::First(n)), _) => n <= PREFETCHED_BYTES,
+            (None, Count::First(_)) => true,
+            _ => false,
+        };
+        if args.files.len() > 1 && prefetch {
+            return head.print_prefetched(&mut stdout);
+        }
+
+        for (i, filename) in args.files.iter().enumerate() {
+            head.print_file(&mut stdout, i, filename)?;
+        }
+        Ok(())
+    }
+
+    /// A file read by a thread: the error opening it, or what is printed and how reading ended
+    enum Prefetched {
+        Unopened(io::Error),
+        Read(Vec<u8>, anyhow::Result<()>),
+    }
+
+    /// How the start of every file is printed
+    struct Head<'a> {
+        args: &'a crate::Args,
+        headers: bool,
+        delimiter: u8,
+    }
+
+    impl Head<'_> {
+        /// Prints the start of a file as it is read
+        fn print_file(
+            &self,
+            output: &mut impl Write,
+            i: usize,
+            filename: &str,
+        ) -> anyhow::Result<()> {
+            match open_maybe_compressed(filename) {
+                Err(err) => eprintln!("{}: {}", filename, err),
+                Ok(mut handler) => {
+                    if self.headers {
+                        write!(output, "{}", header(filename, i == 0))?;
+                    }
+                    self.print_start(&mut handler, output)?;
+                }
+            }
+            Ok(())
+        }
+
+        fn print_start(
+            &self,
+            handler: &mut Box<dyn BufRead>,
+            output: &mut impl Write,
+        ) -> anyhow::Result<()> {
+            match (self.args.bytes, self.args.lines) {
+                (None, Count::First(n)) => print_lines(handler, output, n, self.delimiter),
+                (None, Count::AllButLast(n)) => {
+                    print_all_but_last_lines(handler, output, n, self.delimiter)
+                }
+                (Some(Count::First(n)), _) => print_bytes(handler, output, n),
+                (Some(Count::AllButLast(n)), _) => print_all_but_last_bytes(handler, output, n),
+            }
+        }
+
+        /// Reads the start of a file into memory, to be printed later by `print_prefetched`
+        fn prefetch(&self, filename: &str) -> Prefetched {
+            match open_maybe_compressed(filename) {
+                Err(err) => Prefetched::Unopened(err),
+                Ok(mut handler) => {
+                    let mut output: Vec<u8> = Vec::new();
+                    let result = self.print_start(&mut handler, &mut output);
+                    Prefetched::Read(output, result)
+                }
+            }
+        }
+
+        /// Prints the files in order while threads open and read up to `PREFETCHED` of the next ones,
+        /// so the time spent waiting for each open and read overlaps. Stdin is read when its turn comes.
+        fn print_prefetched(&self, output: &mut impl Write) -> anyhow::Result<()> {
+            let threads = thread::available_parallelism()
+                .map_or(4, NonZeroUsize::get)
+                .clamp(4, 16);
+            let (jobs, queue) = mpsc::channel::<(&str, mpsc::SyncSender<Prefetched>)>();
+            let queue = Mutex::new(queue);
+
+            thread::scope(|scope| {
+                for _ in 0..threads {
+                    scope.spawn(|| {
+                        loop {
+                            let job = queue.lock().map(|queue| queue.recv());
+                            let Ok(Ok((filename, sender))) = job else {
+                                break;
+                            };
+                            // The receiver is gone if printing failed, so nobody waits for the file
+                            let _ = sender.send(self.prefetch(filename));
+                        }
+                    });
+                }
+
+                let mut pending = VecDeque::new();
+                let mut files = self.args.files.iter().enumerate();
+                let result: anyhow::Result<()> = files.try_for_each(|(i, filename)| {
+                    let receiver = (filename != "-").then(|| {
+                        let (sender, receiver) = mpsc::sync_channel(1);
+                        // Only fails if the threads are gone, then `print_pending` reads the file
+                        let _ = jobs.send((filename.as_str(), sender));
+                        receiver
+                    });
+                    pending.push_back((i, filename.as_str(), receiver));
+
+                    if pending.len() > PREFETCHED {
+                        self.print_pending(output, &mut pending)?;
+                    }
+                    Ok(())
+                });
+                // The threads stop once the files sent to them are read
+                drop(jobs);
+
+                result?;
+                while !pending.is_empty() {
+                    self.print_pending(output, &mut pending)?;
+                }
+                Ok(())
+            })
+        }
+
+        /// Prints the oldest of the pending files
+        fn print_pending(
+            &self,
+            output: &mut impl Write,
+            pending: &mut VecDeque<(usize, &str, Option<mpsc::Receiver<Prefetched>>)>,
+        ) -> anyhow::Result<()> {
+            let Some((i, filename, receiver)) = pending.pop_front() else {
+                return Ok(());
+            };
+            let Some(receiver) = receiver else {
+                return self.print_file(output, i, filename);
+            };
+
+            match receiver.recv().unwrap_or_else(|_| self.prefetch(filename)) {
+                Prefetched::Unopened(err) => eprintln!("{}: {}", filename, err),
+                Prefetched::Read(start, result) => {
+                    if self.headers {
+                        write!(output, "{}", header(filename, i == 0))?;
+                    }
+                    output.write_all(&start)?;
+                    output.flush()?;
+                    result?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Prints the first lines, ended by `delimiter`, as they are
+    fn print_lines(
+        handler: &mut Box<dyn BufRead>,
+        output: &mut impl Write,
+        num_lines: u64,
+        delimiter: u8,
+    ) -> anyhow::Result<()> {
+        let mut buff: Vec<u8> = Vec::new();
+
+        for _ in 0..num_lines {
+            let bytes = handler.read_until(delimiter, &mut buff)?;
+
+            if bytes == 0 {
+                break;
+            }
+
+            output.write_all(&buff)?;
+            buff.clear();
+        }
+
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Prints the lines once more than `num_lines` lines follow them, so only the last ones are kept in memory
+    fn print_all_but_last_lines(
+        handler: &mut Box<dyn BufRead>,
+        output: &mut impl Write,
+        num_lines: u64,
+        delimiter: u8,
+    ) -> anyhow::Result<()> {
+        let mut last: VecDeque<Vec<u8>> = VecDeque::new();
+
+        loop {
+            let mut line: Vec<u8> = Vec::new();
+            if handler.read_until(delimiter, &mut line)? == 0 {
+                break;
+            }
+            last.push_back(line);
+
+            if last.len() as u64 > num_lines
+                && let Some(line) = last.pop_front()
+            {
+                output.write_all(&line)?;
+            }
+        }
+
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Prints the bytes once more than `num_bytes` bytes follow them
+    fn print_all_but_last_bytes(
+        handler: &mut Box<dyn BufRead>,
+        output: &mut impl Write,
+        num_bytes: u64,
+    ) -> anyhow::Result<()> {
+        let mut pending: Vec<u8> = Vec::new();
+
+        loop {
+            let buffer = handler.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+
+            pending.extend_from_slice(buffer);
+            let length = buffer.len();
+            handler.consume(length);
+
+            let ready = pending
+                .len()
+                .saturating_sub(num_bytes.try_into().unwrap_or(usize::MAX));
+            output.write_all(&pending[..ready])?;
+            pending.drain(..ready);
+        }
+
+        output.flush()?;
+        Ok(())
+    }
+
+    fn print_bytes(
+        handler: &mut Box<dyn BufRead>,
+        output: &mut impl Write,
+        num_bytes: u64,
+    ) -> anyhow::Result<()> {
+        let bytes: Vec<u8> = handler
+            .bytes()
+            .take(num_bytes as usize)
+            .map(|c| c.unwrap_or(b' '))
+            .collect();
+
+        write!(output, "{}", String::from_utf8_lossy(&bytes))?;
+        output.flush()?;
+        Ok(())
+    }
+}
+
+pub use helper::run;
@@ -2,7 +2,7 @@ use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
-use rand::{distr::Alphanumeric, Rng};
+use rand::{Rng, distr::Alphanumeric};
 use std::fs::{self, File};
 use std::io::prelude::*;
 
@@ -12,6 +12,7 @@ const ONE: &str = "./tests/inputs/one.txt";
 const TWO: &str = "./tests/inputs/two.txt";
 const THREE: &str = "./tests/inputs/three.txt";
 const TWELVE: &str = "./tests/inputs/twelve.txt";
+const NUL: &str = "./tests/inputs/nul.txt";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -38,7 +39,7 @@ fn dies_bad_bytes() -> Result<()> {
     let bad = random_string();
     let expected = format!(
         "invalid value '{bad}' for \
-        '--bytes <BYTES>': invalid digit found in string"
+        '--bytes <BYTES>': invalid size: \"{bad}\""
     );
 
     Command::cargo_bin(PRG)?
@@ -56,7 +57,7 @@ fn dies_bad_lines() -> Result<()> {
     let bad = random_string();
     let expected = format!(
         "error: invalid value '{bad}' for \
-        '--lines <LINES>': invalid digit found in string"
+        '--lines <LINES>': invalid size: \"{bad}\""
     );
     Command::cargo_bin(PRG)?
         .args(["-n", &bad, EMPTY])
@@ -419,3 +420,81 @@ fn multiple_files_c4() -> Result<()> {
         "tests/expected/all.c4.out",
     )
 }
+
+#[test]
+#[cfg(feature = "compressed")]
+fn twelve_zstd() -> Result<()> {
+    run(
+        &["./tests/inputs/twelve.txt.zst", "-n", "4"],
+        "tests/expected/twelve.txt.n4.out",
+    )
+}
+
+#[test]
+fn twelve_all_but_last_lines() -> Result<()> {
+    run(&[TWELVE, "-n", "-3"], "tests/expected/twelve.txt.n-3.out")
+}
+
+#[test]
+fn twelve_all_but_last_bytes() -> Result<()> {
+    run(&[TWELVE, "-c", "-5"], "tests/expected/twelve.txt.c-5.out")
+}
+
+#[test]
+fn twelve_suffixes() -> Result<()> {
+    run(&[TWELVE, "-c", "1K"], "tests/expected/twelve.txt.c1K.out")?;
+    run(&[TWELVE, "-n", "2M"], "tests/expected/twelve.txt.n2M.out")
+}
+
+#[test]
+fn twelve_zero_lines() -> Result<()> {
+    run(&[TWELVE, "-n", "0"], EMPTY)
+}
+
+// --------------------------------------------------
+#[test]
+fn one_verbose() -> Result<()> {
+    run(&[ONE, "-v"], "tests/expected/one.txt.v.out")?;
+    run(
+        &[ONE, "--quiet", "--verbose"],
+        "tests/expected/one.txt.v.out",
+    )
+}
+
+#[test]
+fn all_quiet_n2() -> Result<()> {
+    run(
+        &[ONE, TWO, THREE, "-q", "-n", "2"],
+        "tests/expected/all.q.n2.out",
+    )?;
+    run(
+        &[ONE, TWO, THREE, "-v", "--silent", "-n", "2"],
+        "tests/expected/all.q.n2.out",
+    )
+}
+
+#[test]
+fn nul_zero_terminated() -> Result<()> {
+    run(&[NUL, "-z", "-n", "2"], "tests/expected/nul.txt.z.n2.out")?;
+    run_stdin(
+        &["--zero-terminated", "-n", "2"],
+        NUL,
+        "tests/expected/nul.txt.z.n2.out",
+    )
+}
+
+#[test]
+fn nul_zero_terminated_all_but_last() -> Result<()> {
+    run(&[NUL, "-z", "-n", "-1"], "tests/expected/nul.txt.z.n-1.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn many_files_in_order() -> Result<()> {
+    let mut args = vec!["-n", "2"];
+    for _ in 0..50 {
+        args.extend([ONE, TWO, THREE, TWELVE, EMPTY]);
+    }
+    args.extend(["./tests/inputs/missing.txt", "-", TWELVE]);
+    run_stdin(&args, THREE, "tests/expected/many.n2.out")
+}
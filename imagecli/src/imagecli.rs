@@ -1,89 +1,425 @@
-mod imagix;
+use std::{io, num::NonZeroUsize, path::PathBuf};
 
-use std::path::PathBuf;
-
-use imagix::{error::ImagixError, resize::{self, process_resize_request}, stats::get_stats};
-
-use structopt::StructOpt;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueHint};
+use clap_complete::Shell;
+use imagix::{
+    batch::{self, BatchPlan, BatchReport, Mode},
+    error::ImagixError,
+    fit::Fit,
+    format::{DEFAULT_QUALITY, OutputFormat, register_decoders},
+    metadata::MetadataMode,
+    operation::{Flip, Geometry, Gravity, Operation, Rotation},
+    optimize::Reduction,
+    output::{OutputOptions, Overwrite},
+    resize::Size,
+    stats::{StatsFormat, get_stats},
+    watch::watch,
+};
 
 // Define commandline arguments in a struct
-#[derive(StructOpt, Debug)]
-#[structopt(
-    name = "resize",
-    about = "This is a tool for image resizing and stats",
-    help = "Specify subcommand resize or stats. For help,
-     type imagecli resize --help or imagecli stats --help"
-)]
+#[derive(Debug, Parser)]
+#[command(name = "imagecli", version)]
+/// Tool for resizing, cropping, transforming, converting and optimizing images, and for their stats
+struct Cli {
+    #[command(subcommand)]
+    command: CommandLine,
+}
+
+#[derive(Debug, Subcommand)]
 enum CommandLine {
-    #[structopt(help = "
-        Specify size(small/medium/large),
-        mode(single/all) and srcfolder")]
+    /// Resize images to a size preset, or to a width, height or maximum dimension
     Resize {
-        #[structopt(long)]
-        size: resize::SizeOption,
-        #[structopt(long)]
-        mode: resize::Mode,
-        #[structopt(long)]
-        srcfolder: PathBuf,
+        /// Size preset: small (200px), medium (400px) or large (800px)
+        #[arg(long)]
+        size: Option<Size>,
+        /// Maximum width of the resized images in pixels
+        #[arg(long)]
+        width: Option<u32>,
+        /// Maximum height of the resized images in pixels
+        #[arg(long)]
+        height: Option<u32>,
+        /// Maximum width and height of the resized images in pixels
+        #[arg(long = "max-dim")]
+        max_dim: Option<u32>,
+        /// How the images are shaped into the size: fit, fill, crop, stretch or exact
+        #[arg(long, default_value = "fit")]
+        fit: Fit,
+        #[command(flatten)]
+        batch: BatchArgs,
+    },
+    /// Keep an area of the images
+    Crop {
+        /// Area kept of each image, WxH+X+Y or WxH
+        #[arg(long)]
+        geometry: Geometry,
+        /// Point the area is placed from: northwest, north, northeast, west, center, east, southwest, south or southeast
+        #[arg(long, default_value = "northwest")]
+        gravity: Gravity,
+        #[command(flatten)]
+        batch: BatchArgs,
+    },
+    /// Rotate and/or flip images
+    Transform {
+        /// Clockwise rotation in degrees: 90, 180 or 270
+        #[arg(long)]
+        rotate: Option<Rotation>,
+        /// Mirror the images horizontally (h) or vertically (v), after the rotation
+        #[arg(long)]
+        flip: Option<Flip>,
+        #[command(flatten)]
+        batch: BatchArgs,
+    },
+    /// Re-encode images in another format
+    Convert {
+        /// Format the images are re-encoded in: png, jpg, webp, bmp, tiff or gif
+        #[arg(long)]
+        to: OutputFormat,
+        /// Quality of the jpeg images, from 1 to 100
+        #[arg(long, default_value_t = DEFAULT_QUALITY)]
+        quality: u8,
+        /// Delete each original image once it has been converted
+        #[arg(long = "delete-originals")]
+        delete_originals: bool,
+        #[command(flatten)]
+        batch: BatchArgs,
     },
-    #[structopt(help = "Specify srcfolder")]
+    /// Recompress png and jpeg images to make them smaller
+    Optimize {
+        /// Percentage of their size the jpeg images should lose, their quality is lowered down to 40 to reach it.
+        /// The png images are always reduced without loss
+        #[arg(long = "target-reduction")]
+        target_reduction: Option<Reduction>,
+        #[command(flatten)]
+        batch: BatchArgs,
+    },
+    /// Print the dimensions, format and size of the images of a folder
     Stats {
-        #[structopt(long, parse(from_os_str))]
+        #[arg(long, value_hint = ValueHint::DirPath)]
         srcfolder: PathBuf,
+        /// Output of the stats: text, or json for scripts
+        #[arg(long, default_value = "text")]
+        format: StatsFormat,
+    },
+    /// Print the completion script of a shell, e.g. imagecli completions bash > /etc/bash_completion.d/imagecli
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
     },
 }
 
+/// Arguments shared by the subcommands processing images: which images and where the results go
+#[derive(Debug, Args)]
+struct BatchArgs {
+    /// single or all, by default all when srcfolder is a folder and single otherwise
+    #[arg(long)]
+    mode: Option<Mode>,
+    /// Image or folder of images to process
+    #[arg(long, value_hint = ValueHint::AnyPath)]
+    srcfolder: PathBuf,
+    /// Folder the processed images are written to, by default a tmp folder next to the images
+    #[arg(long, value_hint = ValueHint::DirPath)]
+    out: Option<PathBuf>,
+    /// Name of the processed images with {stem}, {ext}, {width} and {height} placeholders, e.g. "{stem}_{width}.{ext}"
+    #[arg(long = "name-template")]
+    name_template: Option<String>,
+    /// Text added after the name of each processed image, a shorthand for `--name-template "{stem}<suffix>.{ext}"`
+    #[arg(long)]
+    suffix: Option<String>,
+    /// Process the images of the subfolders too, keeping the same folders under the output folder
+    #[arg(short, long)]
+    recursive: bool,
+    /// Copy the EXIF and ICC data of the images into the processed images
+    #[arg(long = "keep-metadata")]
+    keep_metadata: bool,
+    /// Leave the EXIF and ICC data out of the processed images, the default
+    #[arg(long = "strip-metadata")]
+    strip_metadata: bool,
+    /// What is done when a processed image already exists: replace, skip or rename
+    #[arg(long, default_value = "replace")]
+    overwrite: Overwrite,
+    /// Print the planned source -> destination of each image, with its predicted size, without writing anything
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+    /// Keep running and process the new or changed images of srcfolder as they appear, until interrupted
+    #[arg(long)]
+    watch: bool,
+    /// Number of images processed at the same time, by default one per CPU
+    #[arg(short, long)]
+    jobs: Option<NonZeroUsize>,
+}
+
+impl BatchArgs {
+    /// Output options of the batch, with the images encoded in the given format and quality
+    fn output_options(
+        &self,
+        format: OutputFormat,
+        quality: u8,
+    ) -> Result<OutputOptions, ImagixError> {
+        let metadata = MetadataMode::from_flags(self.keep_metadata, self.strip_metadata)?;
+
+        Ok(OutputOptions::new(
+            self.out.clone(),
+            self.name_template.clone(),
+            self.suffix.clone(),
+        )?
+        .with_format(format, quality)?
+        .with_metadata(metadata)
+        .with_overwrite(self.overwrite))
+    }
+
+    /// Scope of the batch, by default all the images when srcfolder is a folder
+    fn mode(&self) -> Mode {
+        self.mode.unwrap_or_else(|| Mode::for_path(&self.srcfolder))
+    }
+}
+
+/// Prints the message of an error of the library
+fn report(e: ImagixError) {
+    eprintln!("{}", e);
+}
+
+/// Prints the images that failed and the summary of a request
+fn print_summary(result: Result<BatchReport, ImagixError>) {
+    match result {
+        Ok(summary) => {
+            // The errors name the file they are about
+            for (_, e) in summary.failed.iter() {
+                eprintln!("Unable to process {}", e);
+            }
+            println!("{}", summary);
+        }
+        Err(e) => report(e),
+    }
+}
+
+/// Prints the planned images and the summary of a dry run
+fn print_plan(result: Result<BatchPlan, ImagixError>) {
+    match result {
+        Ok(plan) => {
+            for planned in plan.planned.iter() {
+                println!("{}", planned);
+            }
+            for (_, e) in plan.failed.iter() {
+                eprintln!("Unable to process {}", e);
+            }
+            println!("{}", plan);
+        }
+        Err(e) => report(e),
+    }
+}
+
+/// Applies an operation to the images given by the batch arguments, encoded with the given format and quality
+fn run_batch(
+    operation: Result<Operation, ImagixError>,
+    batch: BatchArgs,
+    format: OutputFormat,
+    quality: u8,
+) {
+    let operation = match operation {
+        Ok(operation) => operation,
+        Err(e) => return report(e),
+    };
+    let output = match batch.output_options(format, quality) {
+        Ok(output) => output,
+        Err(e) => return report(e),
+    };
+
+    if batch.dry_run && batch.watch {
+        return report(ImagixError::InvalidInput(
+            "--dry-run cannot be combined with --watch".to_string(),
+        ));
+    }
+    if let Some(jobs) = batch.jobs
+        && let Err(e) = batch::set_jobs(jobs.get())
+    {
+        return report(e);
+    }
+    if batch.dry_run {
+        return print_plan(batch::plan_request(
+            operation,
+            batch.mode(),
+            &batch.srcfolder,
+            &output,
+            batch.recursive,
+        ));
+    }
+    if batch.watch {
+        println!(
+            "Watching {:?} for new images, press Ctrl+C to stop",
+            batch.srcfolder
+        );
+        let watched = watch(
+            operation,
+            batch.mode(),
+            &batch.srcfolder,
+            &output,
+            batch.recursive,
+            |result| {
+                print_summary(result);
+                true
+            },
+        );
+        if let Err(e) = watched {
+            report(e);
+        }
+        return;
+    }
+    print_summary(batch::process_request(
+        operation,
+        batch.mode(),
+        &batch.srcfolder,
+        &output,
+        batch.recursive,
+    ));
+}
+
 fn main() {
-    let args: CommandLine = CommandLine::from_args();
+    let args = Cli::parse();
+    register_decoders();
 
-    match args {
+    match args.command {
         CommandLine::Resize {
             size,
-            mode,
-            mut srcfolder 
+            width,
+            height,
+            max_dim,
+            fit,
+            batch,
+        } => {
+            let operation =
+                Size::from_flags(size, width, height, max_dim).map(|size| Operation::Resize {
+                    size: size.dimensions(),
+                    fit,
+                });
+            run_batch(operation, batch, OutputFormat::default(), DEFAULT_QUALITY);
+        }
+        CommandLine::Crop {
+            geometry,
+            gravity,
+            batch,
+        } => {
+            run_batch(
+                Ok(Operation::Crop { geometry, gravity }),
+                batch,
+                OutputFormat::default(),
+                DEFAULT_QUALITY,
+            );
+        }
+        CommandLine::Transform {
+            rotate,
+            flip,
+            batch,
+        } => {
+            run_batch(
+                Operation::transform(rotate, flip),
+                batch,
+                OutputFormat::default(),
+                DEFAULT_QUALITY,
+            );
+        }
+        CommandLine::Convert {
+            to,
+            quality,
+            delete_originals,
+            batch,
         } => {
-            match process_resize_request(size, mode,  &mut srcfolder) {
-                Ok(_) => println!("Image resized succesfully"),
-                Err(e) => {
-                    match e {
-                        ImagixError::FileIOError(e) => {
-                            eprintln!("{}", e);
-                        },
-                        ImagixError::FormatError(e) => {
-                            eprintln!("{}", e);
-                        },
-                        ImagixError::ImageResizingError(e) => {
-                            eprintln!("{}", e);
-                        },
-                        ImagixError::UserInputError(e) => {
-                            eprintln!("{}", e);
-                        },
+            run_batch(
+                Ok(Operation::Convert { delete_originals }),
+                batch,
+                to,
+                quality,
+            );
+        }
+        CommandLine::Optimize {
+            target_reduction,
+            batch,
+        } => {
+            run_batch(
+                Ok(Operation::Optimize {
+                    target: target_reduction,
+                }),
+                batch,
+                OutputFormat::default(),
+                DEFAULT_QUALITY,
+            );
+        }
+        CommandLine::Stats { srcfolder, format } => {
+            match get_stats(srcfolder).and_then(|stats| Ok((stats.render(format)?, stats))) {
+                Ok((rendered, stats)) => {
+                    for file in stats.unreadable.iter() {
+                        eprintln!("Unable to read {:?}", file);
                     }
+                    println!("{}", rendered);
                 }
+                Err(e) => report(e),
             }
         }
-        CommandLine::Stats { srcfolder } => {
-            match get_stats(srcfolder) {
-                Ok((count, size )) => {
-                    println!("Found {:?} image files with aggreate size of {:?} KB", count, size);
-                }
-                Err (e) => {
-                    match e {
-                        ImagixError::FileIOError(e) => {
-                            eprintln!("{}", e);
-                        },
-                        ImagixError::FormatError(e) => {
-                            eprintln!("{}", e);
-                        },
-                        ImagixError::ImageResizingError(e) => {
-                            eprintln!("{}", e);
-                        },
-                        ImagixError::UserInputError(e) => {
-                            eprintln!("{}", e);
-                        },
-                    }
-                }
+        CommandLine::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "imagecli", &mut io::stdout());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_resize() {
+        let args = Cli::try_parse_from([
+            "imagecli",
+            "resize",
+            "--size",
+            "small",
+            "--fit",
+            "fill",
+            "-r",
+            "-j",
+            "4",
+            "--srcfolder",
+            "photos",
+        ])
+        .unwrap();
+
+        match args.command {
+            CommandLine::Resize {
+                size, fit, batch, ..
+            } => {
+                assert_eq!(size, Some(Size::Small));
+                assert_eq!(fit, Fit::Fill);
+                assert!(batch.recursive);
+                assert_eq!(batch.jobs, NonZeroUsize::new(4));
             }
+            command => panic!("Unexpected command {:?}", command),
         }
+        assert!(
+            Cli::try_parse_from([
+                "imagecli",
+                "resize",
+                "--size",
+                "huge",
+                "--srcfolder",
+                "photos"
+            ])
+            .is_err()
+        );
+        assert!(
+            Cli::try_parse_from([
+                "imagecli",
+                "resize",
+                "--size",
+                "small",
+                "--jobs",
+                "0",
+                "--srcfolder",
+                "photos"
+            ])
+            .is_err()
+        );
     }
 }
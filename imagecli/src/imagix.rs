@@ -1,3 +0,0 @@
-pub mod resize;
-pub mod stats;
-pub mod error;
\ No newline at end of file
@@ -0,0 +1,763 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use walkdir::WalkDir;
+
+use super::{
+    error::{ImagixError, WithPath},
+    format,
+    metadata::{self, ImageMetadata, MetadataMode},
+    operation::Operation,
+    optimize,
+    output::{self, OutputOptions, Overwrite},
+    stats::Elapsed,
+};
+
+/// Data structure that specifies the scope of the process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Single,
+    All,
+}
+
+impl FromStr for Mode {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" | "Single" => Ok(Mode::Single),
+            "all" | "All" => Ok(Mode::All),
+            _ => Err(ImagixError::InvalidInput(format!(
+                "Invalid mode, expected single or all: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl Mode {
+    /// Scope used when none is given: all the images of a folder, or the single image of a file
+    pub fn for_path(path: &Path) -> Mode {
+        if path.is_dir() {
+            Mode::All
+        } else {
+            Mode::Single
+        }
+    }
+}
+
+/// Data structure that describes an image written by a request
+#[derive(Debug)]
+pub struct Processed {
+    /// What was done to the image, like "Thumbnailed"
+    pub verb: &'static str,
+    pub src: PathBuf,
+    pub dest: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub elapsed: Elapsed,
+    /// Size in bytes of the source file and of the written one, given by the optimizations
+    pub bytes: Option<(u64, u64)>,
+}
+
+impl Processed {
+    /// Bytes the image lost, 0 when it did not shrink
+    pub fn saved(&self) -> u64 {
+        self.bytes
+            .map_or(0, |(before, after)| before.saturating_sub(after))
+    }
+}
+
+impl fmt::Display for Processed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} file: {:?} to size {}x{} in {}. Output file in {:?}",
+            self.verb, self.src, self.width, self.height, self.elapsed, self.dest
+        )?;
+        if let Some((before, _)) = self.bytes {
+            write!(
+                f,
+                ". Saved {} bytes ({}%)",
+                self.saved(),
+                self.saved() * 100 / before.max(1)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Data structure that describes what a request will do to an image, before anything is written
+#[derive(Debug)]
+pub struct Planned {
+    pub src: PathBuf,
+    pub dest: PathBuf,
+    /// Predicted size of the processed image
+    pub width: u32,
+    pub height: u32,
+    /// The destination exists and the overwrite policy is to skip it
+    pub skip: bool,
+}
+
+impl fmt::Display for Planned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.skip {
+            write!(
+                f,
+                "{:?} -> {:?} skipped, the file exists",
+                self.src, self.dest
+            )
+        } else {
+            write!(
+                f,
+                "{:?} -> {:?} ({}x{})",
+                self.src, self.dest, self.width, self.height
+            )
+        }
+    }
+}
+
+/// Images a request will process, and the ones that cannot be read
+#[derive(Debug)]
+pub struct BatchPlan {
+    pub planned: Vec<Planned>,
+    pub failed: Vec<(PathBuf, ImagixError)>,
+}
+
+impl fmt::Display for BatchPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let skipped = self.planned.iter().filter(|planned| planned.skip).count();
+        write!(f, "Would process {} images", self.planned.len() - skipped)?;
+        if skipped > 0 {
+            write!(f, ", {} skipped", skipped)?;
+        }
+        if !self.failed.is_empty() {
+            write!(f, ", {} failed", self.failed.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Summary of a request: the images processed and the ones that failed, a bad image does not stop the others
+#[derive(Debug)]
+pub struct BatchReport {
+    pub processed: Vec<Processed>,
+    pub failed: Vec<(PathBuf, ImagixError)>,
+    /// Images left alone because their destination exists
+    pub skipped: Vec<PathBuf>,
+    pub elapsed: Elapsed,
+    /// Number of workers the images were processed on
+    pub jobs: usize,
+}
+
+impl BatchReport {
+    /// Average time spent on each processed image, None when none was processed
+    pub fn average(&self) -> Option<Duration> {
+        let total: Duration = self
+            .processed
+            .iter()
+            .map(|processed| processed.elapsed.duration())
+            .sum();
+        total.checked_div(u32::try_from(self.processed.len()).ok()?)
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Processed {} images in {} on {} workers",
+            self.processed.len(),
+            self.elapsed,
+            self.jobs
+        )?;
+        if let Some(average) = self.average() {
+            write!(f, ", {:.2?} per image", average)?;
+        }
+        if self
+            .processed
+            .iter()
+            .any(|processed| processed.bytes.is_some())
+        {
+            write!(
+                f,
+                ", {} bytes saved",
+                self.processed.iter().map(Processed::saved).sum::<u64>()
+            )?;
+        }
+        if !self.skipped.is_empty() {
+            write!(f, ", {} skipped", self.skipped.len())?;
+        }
+        if !self.failed.is_empty() {
+            write!(f, ", {} failed", self.failed.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies the operation to every image of the request.
+/// With `recursive` the images of the subfolders are processed too, in the same subfolders of the output folder
+pub fn process_request(
+    operation: Operation,
+    mode: Mode,
+    src_folder: &Path,
+    output: &OutputOptions,
+    recursive: bool,
+) -> Result<BatchReport, ImagixError> {
+    let timer = Instant::now();
+    let mut plan = plan_request(operation, mode, src_folder, output, recursive)?;
+
+    match mode {
+        // A single image reports its error instead of a summary
+        Mode::Single => {
+            if let Some((_, e)) = plan.failed.pop() {
+                return Err(e);
+            }
+            let mut report = execute(operation, plan, output, ProgressBar::hidden(), &timer);
+            match report.failed.pop() {
+                Some((_, e)) => Err(e),
+                None => Ok(report),
+            }
+        }
+        Mode::All => {
+            let progress = ProgressBar::new(plan.planned.len() as u64);
+            progress.set_style(
+                ProgressStyle::with_template("[{elapsed_precise}] {bar:40} {pos}/{len} images")
+                    .unwrap_or_else(|_| ProgressStyle::default_bar()),
+            );
+            Ok(execute(operation, plan, output, progress, &timer))
+        }
+    }
+}
+
+/// Sets the number of images processed at the same time, by default one per CPU.
+/// The workers are shared by every batch, so they can only be set once and before the first batch
+pub fn set_jobs(jobs: usize) -> Result<(), ImagixError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build_global()?;
+    Ok(())
+}
+
+/// Works out the destination and size of every image of the request without writing anything,
+/// the destinations that exist are replaced, skipped or renamed following the overwrite policy
+pub fn plan_request(
+    operation: Operation,
+    mode: Mode,
+    src_folder: &Path,
+    output: &OutputOptions,
+    recursive: bool,
+) -> Result<BatchPlan, ImagixError> {
+    let (entries, root) = match mode {
+        Mode::Single => (
+            vec![src_folder.to_path_buf()],
+            src_folder.parent().unwrap_or(Path::new("")).to_path_buf(),
+        ),
+        Mode::All => (
+            find_images(src_folder, output, recursive)?,
+            src_folder.to_path_buf(),
+        ),
+    };
+
+    Ok(plan_entries(operation, entries, &root, output))
+}
+
+/// Applies the operation to the given images, `root` is the folder the output tree mirrors.
+/// It is used by the watch mode on the images that changed
+pub fn process_files(
+    operation: Operation,
+    files: Vec<PathBuf>,
+    root: &Path,
+    output: &OutputOptions,
+) -> BatchReport {
+    let timer = Instant::now();
+    let plan = plan_entries(operation, files, root, output);
+    execute(operation, plan, output, ProgressBar::hidden(), &timer)
+}
+
+/// Output folder of the images of `src_folder` when it is inside of it, the images in it are not processed again.
+/// None when the images are written in the source folder itself
+pub fn excluded_dir(src_folder: &Path, output: &OutputOptions) -> Option<PathBuf> {
+    let out_dir = fs::canonicalize(output::output_dir(src_folder, output)).ok()?;
+    match fs::canonicalize(src_folder) {
+        Ok(src_folder) if src_folder == out_dir => None,
+        _ => Some(out_dir),
+    }
+}
+
+fn plan_entries(
+    operation: Operation,
+    entries: Vec<PathBuf>,
+    root: &Path,
+    output: &OutputOptions,
+) -> BatchPlan {
+    let mut plan = BatchPlan {
+        planned: vec![],
+        failed: vec![],
+    };
+    // Destinations claimed by the images planned before, so two images are not renamed to the same file
+    let mut claimed = HashSet::new();
+    for entry in entries {
+        match plan_image(operation, &entry, root, output, &mut claimed) {
+            Ok(planned) => plan.planned.push(planned),
+            Err(e) => plan.failed.push((entry, e)),
+        }
+    }
+
+    plan
+}
+
+/// Images of a folder to process, leaving out the ones already written into an output folder inside it
+fn find_images(
+    path: &Path,
+    output: &OutputOptions,
+    recursive: bool,
+) -> Result<Vec<PathBuf>, ImagixError> {
+    let mut entries = get_images_files(path.to_path_buf(), recursive)?;
+
+    // The images already written into an output folder inside the source folder are not resized again
+    if let Some(out_dir) = excluded_dir(path, output) {
+        entries.retain(|entry| {
+            fs::canonicalize(entry).map_or(true, |entry| !entry.starts_with(&out_dir))
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Plans one image from the size in its header, `root` is the folder the output tree mirrors
+fn plan_image(
+    operation: Operation,
+    src: &Path,
+    root: &Path,
+    output: &OutputOptions,
+    claimed: &mut HashSet<PathBuf>,
+) -> Result<Planned, ImagixError> {
+    // The optimized images keep their format, and so their extension
+    let ext = match operation {
+        Operation::Optimize { .. } if !optimize::is_optimizable(src) => {
+            return Err(ImagixError::NotOptimizable {
+                path: src.to_path_buf(),
+            });
+        }
+        Operation::Optimize { .. } => src
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default(),
+        _ => output.format.extension(),
+    };
+
+    let (width, height) = operation
+        .output_size(metadata::oriented_dimensions(src)?)
+        .with_path(src)?;
+    let dest = output::plan_destination(src, root, output, (width, height), ext)?;
+
+    let taken = |dest: &Path| dest.exists() || claimed.contains(dest);
+    let (dest, skip) = match output.overwrite {
+        _ if !taken(&dest) => (dest, false),
+        Overwrite::Replace => (dest, false),
+        Overwrite::Skip => (dest, true),
+        Overwrite::Rename => {
+            let renamed = (1..)
+                .map(|n| output::numbered(&dest, n))
+                .find(|dest| !taken(dest))
+                .unwrap_or(dest);
+            (renamed, false)
+        }
+    };
+    if !skip {
+        claimed.insert(dest.clone());
+    }
+
+    Ok(Planned {
+        src: src.to_path_buf(),
+        dest,
+        width,
+        height,
+        skip,
+    })
+}
+
+/// Runs a plan, the images are processed in parallel while the progress bar follows them.
+/// The errors are collected so one bad image does not abort the batch
+fn execute(
+    operation: Operation,
+    plan: BatchPlan,
+    output: &OutputOptions,
+    progress: ProgressBar,
+    timer: &Instant,
+) -> BatchReport {
+    let (skipped, planned): (Vec<Planned>, Vec<Planned>) =
+        plan.planned.into_iter().partition(|planned| planned.skip);
+
+    let results: Vec<(PathBuf, Result<Processed, ImagixError>)> = planned
+        .par_iter()
+        .map(|planned| {
+            let result = process_image(operation, planned, output);
+            if let Ok(processed) = &result {
+                progress.suspend(|| println!("{}", processed));
+            }
+            progress.inc(1);
+            (planned.src.clone(), result)
+        })
+        .collect();
+    progress.finish_and_clear();
+
+    let mut report = BatchReport {
+        processed: vec![],
+        failed: plan.failed,
+        skipped: skipped.into_iter().map(|planned| planned.src).collect(),
+        elapsed: Elapsed::from(timer),
+        jobs: rayon::current_num_threads(),
+    };
+    for (entry, result) in results {
+        match result {
+            Ok(processed) => report.processed.push(processed),
+            Err(e) => report.failed.push((entry, e)),
+        }
+    }
+
+    report
+}
+
+/// This functions generetes the processed image at its planned destination and the necesary folder
+fn process_image(
+    operation: Operation,
+    planned: &Planned,
+    output: &OutputOptions,
+) -> Result<Processed, ImagixError> {
+    // Process image and take some measuraments
+    let timer = Instant::now();
+    // The image is turned upright before the operation, so crops and sizes apply to what is seen
+    let (img, source_metadata) = metadata::open_oriented(&planned.src)?;
+    let scaled = operation.apply(&img).with_path(&planned.src)?;
+
+    // The image is encoded before its destination is opened, as the destination can be the source itself
+    let metadata = match output.metadata {
+        MetadataMode::Keep => source_metadata,
+        MetadataMode::Strip => ImageMetadata::default(),
+    };
+    let (encoded, bytes) = match operation {
+        Operation::Optimize { target } => {
+            let original = fs::metadata(&planned.src).with_path(&planned.src)?.len();
+            let encoded = optimize::recompress(&planned.src, &scaled, target, &metadata)?;
+            let written = encoded.len() as u64;
+            (encoded, Some((original, written)))
+        }
+        _ => {
+            let mut encoded = Cursor::new(vec![]);
+            output
+                .format
+                .encode(&scaled, output.quality, &metadata, &mut encoded)
+                .with_path(&planned.dest)?;
+            (encoded.into_inner(), None)
+        }
+    };
+
+    // Create the folders of the destination if they do not exist
+    let dest_folder = &planned.dest;
+    if let Some(parent) = dest_folder.parent() {
+        fs::create_dir_all(parent).with_path(parent)?;
+    }
+    fs::write(dest_folder, encoded).with_path(dest_folder)?;
+
+    // The original is only removed once the new image is written, and never when it was just overwritten by it
+    if let Operation::Convert {
+        delete_originals: true,
+    } = operation
+        && fs::canonicalize(&planned.src).with_path(&planned.src)?
+            != fs::canonicalize(dest_folder).with_path(dest_folder)?
+    {
+        fs::remove_file(&planned.src).with_path(&planned.src)?;
+    }
+
+    Ok(Processed {
+        verb: operation.verb(),
+        src: planned.src.clone(),
+        dest: dest_folder.clone(),
+        width: scaled.width(),
+        height: scaled.height(),
+        elapsed: Elapsed::from(&timer),
+        bytes,
+    })
+}
+
+/// This function retrieves the list of images files contained in a source folder, and in its subfolders when `recursive` is set
+pub fn get_images_files(src_folder: PathBuf, recursive: bool) -> Result<Vec<PathBuf>, ImagixError> {
+    // 1. retrieve the directory entries in the source folder (or the whole tree below it) and collect tem in a vector
+    let paths: Vec<PathBuf> = if recursive {
+        WalkDir::new(&src_folder)
+            .sort_by_file_name()
+            .into_iter()
+            .map(|res| {
+                res.map(|e| e.into_path()).map_err(|e| {
+                    let path = e.path().unwrap_or(&src_folder).to_path_buf();
+                    ImagixError::Io {
+                        path,
+                        source: io::Error::from(e),
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, ImagixError>>()?
+    } else {
+        fs::read_dir(&src_folder)
+            .with_path(&src_folder)?
+            .map(|res| res.map(|e| e.path()))
+            .collect::<Result<Vec<_>, io::Error>>()
+            .with_path(&src_folder)?
+    };
+
+    // 2. keep the image files
+    let entries: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|r| format::is_supported_input(r))
+        .collect();
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fit::Fit, fixtures, format::OutputFormat, resize::Size};
+
+    fn small() -> Operation {
+        Operation::Resize {
+            size: Size::Small.dimensions(),
+            fit: Fit::Contain,
+        }
+    }
+
+    #[test]
+    fn test_recursive_resize_mirrors_tree() {
+        let (dir, out_dir) = (fixtures::empty_folder(), fixtures::empty_folder());
+        let (folder, out) = (dir.path(), out_dir.path().to_path_buf());
+        fs::create_dir_all(folder.join("a/b")).unwrap();
+        fixtures::write_jpeg(&folder.join("image1.jpg"), 1200, 800);
+        fixtures::write_jpeg(&folder.join("a/b/image2.jpg"), 600, 900);
+
+        let output = OutputOptions::new(Some(out.clone()), None, None).unwrap();
+        let report = process_request(small(), Mode::All, folder, &output, true).unwrap();
+
+        assert_eq!(report.processed.len(), 2);
+        assert!(report.jobs >= 1);
+        assert!(report.average().is_some());
+        assert!(out.join("image1.png").exists());
+        assert!(out.join("a/b/image2.png").exists());
+    }
+
+    #[test]
+    fn test_batch_continues_after_bad_image() {
+        let dir = fixtures::empty_folder();
+        let folder = dir.path();
+        fixtures::write_jpeg(&folder.join("image1.jpg"), 1200, 800);
+        fs::write(folder.join("broken.jpg"), b"not an image").unwrap();
+
+        let report =
+            process_request(small(), Mode::All, folder, &OutputOptions::default(), false).unwrap();
+
+        assert_eq!(report.processed.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, folder.join("broken.jpg"));
+        // The error names the image that failed
+        assert!(
+            matches!(&report.failed[0].1, ImagixError::Image { path, .. } if path == &folder.join("broken.jpg"))
+        );
+        assert!(
+            report.failed[0]
+                .1
+                .to_string()
+                .starts_with(&folder.join("broken.jpg").display().to_string())
+        );
+        assert!(folder.join("tmp/image1.png").exists());
+    }
+
+    #[test]
+    fn test_convert_and_delete_originals() {
+        let dir = fixtures::image_folder();
+        let folder = dir.path().to_path_buf();
+
+        let output = OutputOptions::new(Some(folder.clone()), None, None)
+            .unwrap()
+            .with_format(OutputFormat::WebP, 80)
+            .unwrap();
+        let report = process_request(
+            Operation::Convert {
+                delete_originals: true,
+            },
+            Mode::All,
+            &folder,
+            &output,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(report.processed.len(), 2);
+        let converted = report
+            .processed
+            .iter()
+            .find(|processed| processed.src == folder.join("image1.jpg"))
+            .unwrap();
+        assert_eq!((converted.width, converted.height), (1200, 800));
+        assert_eq!(
+            image::ImageFormat::from_path(&converted.dest).unwrap(),
+            image::ImageFormat::WebP
+        );
+        assert!(folder.join("image1.webp").exists());
+        assert!(!folder.join("image1.jpg").exists());
+        assert!(!folder.join("image2.jpg").exists());
+    }
+
+    #[test]
+    fn test_keep_and_strip_metadata() {
+        let dir = fixtures::empty_folder();
+        let folder = dir.path();
+        crate::metadata::tests::write_rotated_jpeg(&folder.join("photo.jpg"));
+        let exif_of = |path: &Path| {
+            let mut decoder = image::ImageReader::open(path)
+                .unwrap()
+                .into_decoder()
+                .unwrap();
+            image::ImageDecoder::exif_metadata(&mut decoder).unwrap()
+        };
+
+        let convert = Operation::Convert {
+            delete_originals: false,
+        };
+        let keep = OutputOptions::new(Some(folder.join("keep")), None, None)
+            .unwrap()
+            .with_metadata(MetadataMode::Keep);
+        let report = process_request(
+            convert,
+            Mode::Single,
+            &folder.join("photo.jpg"),
+            &keep,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            (report.processed[0].width, report.processed[0].height),
+            (20, 40)
+        );
+        assert!(exif_of(&folder.join("keep/photo.png")).is_some());
+
+        let strip = OutputOptions::new(Some(folder.join("strip")), None, None).unwrap();
+        process_request(
+            convert,
+            Mode::Single,
+            &folder.join("photo.jpg"),
+            &strip,
+            false,
+        )
+        .unwrap();
+        assert!(exif_of(&folder.join("strip/photo.png")).is_none());
+    }
+
+    #[test]
+    fn test_overwrite_policies() {
+        let dir = fixtures::empty_folder();
+        let folder = dir.path();
+        fixtures::write_jpeg(&folder.join("image2.jpg"), 600, 900);
+        let resize = |overwrite| {
+            let output = OutputOptions::default().with_overwrite(overwrite);
+            process_request(small(), Mode::All, folder, &output, false).unwrap()
+        };
+
+        assert_eq!(resize(Overwrite::Replace).processed.len(), 1);
+        let skipped = resize(Overwrite::Skip);
+        assert_eq!((skipped.processed.len(), skipped.skipped.len()), (0, 1));
+        let renamed = resize(Overwrite::Rename);
+        assert_eq!(renamed.processed[0].dest, folder.join("tmp/image2_1.png"));
+        assert!(folder.join("tmp/image2.png").exists());
+    }
+
+    #[test]
+    fn test_dry_run_plans_without_writing() {
+        let dir = fixtures::empty_folder();
+        let folder = dir.path();
+        fixtures::write_jpeg(&folder.join("image1.jpg"), 1200, 800);
+        fs::write(folder.join("broken.jpg"), b"not an image").unwrap();
+
+        let output = OutputOptions::new(
+            None,
+            Some("{stem}_{width}x{height}.{ext}".to_string()),
+            None,
+        )
+        .unwrap();
+        let plan = plan_request(small(), Mode::All, folder, &output, false).unwrap();
+
+        assert_eq!(plan.planned.len(), 1);
+        assert_eq!(plan.failed.len(), 1);
+        assert_eq!(plan.planned[0].dest, folder.join("tmp/image1_200x133.png"));
+        assert!(!folder.join("tmp").exists());
+
+        let report = process_request(small(), Mode::All, folder, &output, false).unwrap();
+        assert_eq!(report.processed[0].dest, plan.planned[0].dest);
+        assert_eq!(
+            (report.processed[0].width, report.processed[0].height),
+            (plan.planned[0].width, plan.planned[0].height)
+        );
+    }
+
+    #[test]
+    fn test_reads_every_input_format() {
+        let dir = fixtures::empty_folder();
+        let folder = dir.path();
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::new(300, 150));
+        for (name, format) in [
+            ("a.webp", OutputFormat::WebP),
+            ("b.TIFF", OutputFormat::Tiff),
+            ("c.bmp", OutputFormat::Bmp),
+            ("d.gif", OutputFormat::Gif),
+            ("e.JPEG", OutputFormat::Jpeg),
+        ] {
+            let mut file = fs::File::create(folder.join(name)).unwrap();
+            format
+                .encode(&img, 90, &ImageMetadata::default(), &mut file)
+                .unwrap();
+        }
+        fs::write(folder.join("notes.txt"), b"not an image").unwrap();
+
+        let report =
+            process_request(small(), Mode::All, folder, &OutputOptions::default(), false).unwrap();
+
+        assert_eq!(report.processed.len(), 5);
+        assert!(report.failed.is_empty());
+        assert!(
+            report
+                .processed
+                .iter()
+                .all(|processed| (processed.width, processed.height) == (200, 100))
+        );
+    }
+
+    #[test]
+    fn test_convert_into_same_file_keeps_it() {
+        let dir = fixtures::empty_folder();
+        let folder = dir.path();
+        fixtures::write_jpeg(&folder.join("image1.jpg"), 1200, 800);
+
+        let output = OutputOptions::new(Some(folder.to_path_buf()), None, None)
+            .unwrap()
+            .with_format(OutputFormat::Jpeg, 50)
+            .unwrap();
+        process_request(
+            Operation::Convert {
+                delete_originals: true,
+            },
+            Mode::Single,
+            &folder.join("image1.jpg"),
+            &output,
+            false,
+        )
+        .unwrap();
+
+        assert!(folder.join("image1.jpg").exists());
+    }
+}
@@ -1,56 +1,111 @@
-use std::{convert::From, io, fmt};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
 
-/// Data strcture for handling the multiple kinderrors in the library
-#[derive(Debug)]
+use super::operation::OperationError;
+
+/// Data strcture for handling the errors of the library, the ones about a file carry its path and the error behind them
+#[derive(Debug, Error)]
 pub enum ImagixError {
-    FileIOError(String),
-    UserInputError(String),
-    ImageResizingError(String),
-    FormatError(String),
+    /// A file or folder could not be read or written
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// An image could not be decoded or encoded
+    #[error("{}: {source}", path.display())]
+    Image {
+        path: PathBuf,
+        #[source]
+        source: image::ImageError,
+    },
+    /// The operation cannot be applied to an image, like a crop outside of it
+    #[error("{}: {source}", path.display())]
+    Operation {
+        path: PathBuf,
+        #[source]
+        source: OperationError,
+    },
+    #[error("{}: only png and jpeg images can be optimized", path.display())]
+    NotOptimizable { path: PathBuf },
+    #[error("{}: invalid file name", path.display())]
+    InvalidFileName { path: PathBuf },
+    /// The source folder cannot be watched for changes
+    #[error("unable to watch {}: {source}", path.display())]
+    Watch {
+        path: PathBuf,
+        #[source]
+        source: notify_debouncer_mini::notify::Error,
+    },
+    /// The worker pool processing the images cannot be started, or it was already started
+    #[error("unable to start the worker pool: {0}")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+    #[error("unable to write the stats as JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A flag or a value given by the user is not valid
+    #[error("{0}")]
+    InvalidInput(String),
+}
+
+/// Attaches the path of the file being handled to the errors of the crates used by the library
+pub trait WithPath<T> {
+    fn with_path(self, path: &Path) -> Result<T, ImagixError>;
 }
 
-impl From<io::Error> for ImagixError {
-    fn from(error: io::Error) -> Self {
-        match error.kind() {
-            io::ErrorKind::NotFound => ImagixError::FileIOError("File or folder doesn't exist".to_string()),
-            io::ErrorKind::PermissionDenied => ImagixError::FileIOError("Denied action by permissions".to_string()),
-            _ => ImagixError::FileIOError("Unexpected error ocurrer".to_string()),
-        }
+impl<T> WithPath<T> for io::Result<T> {
+    fn with_path(self, path: &Path) -> Result<T, ImagixError> {
+        self.map_err(|source| ImagixError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
     }
 }
 
-impl From<io::ErrorKind> for ImagixError {
-    fn from(error: io::ErrorKind) -> Self {
-        match error {
-            io::ErrorKind::InvalidData => ImagixError::UserInputError("Invalid data was used".to_string()),
-            _ => ImagixError::FileIOError("Unexpected error ocurred".to_string()),
-        }
+impl<T> WithPath<T> for image::ImageResult<T> {
+    fn with_path(self, path: &Path) -> Result<T, ImagixError> {
+        self.map_err(|source| ImagixError::Image {
+            path: path.to_path_buf(),
+            source,
+        })
     }
 }
 
-impl From<image::ImageError> for ImagixError {
-    fn from(error: image::ImageError) -> Self {
-        match error {
-            _ => ImagixError::ImageResizingError("Error related to image resizing".to_string()),
-        }
+impl<T> WithPath<T> for Result<T, OperationError> {
+    fn with_path(self, path: &Path) -> Result<T, ImagixError> {
+        self.map_err(|source| ImagixError::Operation {
+            path: path.to_path_buf(),
+            source,
+        })
     }
 }
 
-impl fmt::Display for ImagixError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ImagixError::FileIOError(e) => {
-                write!(f, "FileIOError: {e}")
-            },
-            ImagixError::UserInputError(e) => {
-                write!(f, "UserInputError: {e}")
-            },
-            ImagixError::ImageResizingError(e) => {
-                write!(f, "ImageResizingError: {e}")
-            },
-            ImagixError::FormatError(e) => {
-                write!(f, "FormatError: {e}")
-            },
-        }
+impl<T> WithPath<T> for Result<T, notify_debouncer_mini::notify::Error> {
+    fn with_path(self, path: &Path) -> Result<T, ImagixError> {
+        self.map_err(|source| ImagixError::Watch {
+            path: path.to_path_buf(),
+            source,
+        })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_names_the_file() {
+        let error = std::fs::read("/missing/cat.jpg")
+            .with_path(Path::new("/missing/cat.jpg"))
+            .unwrap_err();
+
+        assert!(
+            matches!(&error, ImagixError::Io { path, .. } if path == Path::new("/missing/cat.jpg"))
+        );
+        assert!(error.to_string().starts_with("/missing/cat.jpg: "));
+        assert!(std::error::Error::source(&error).is_some());
+    }
+}
@@ -0,0 +1,167 @@
+use image::{DynamicImage, GenericImageView, RgbaImage, imageops};
+use std::str::FromStr;
+
+use super::error::ImagixError;
+
+/// Data structure that specifies how the images are shaped into the target size
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Fit {
+    /// Shrinks the image until it fits in the target size, keeping its aspect ratio
+    #[default]
+    Contain,
+    /// Covers the whole target size keeping the aspect ratio, the overflow is cropped around the center
+    Fill,
+    /// Cuts the center of the image to the target size, without scaling it
+    Crop,
+    /// Scales the image to the target size, distorting it when the aspect ratio differs
+    Stretch,
+    /// Fits the image in the target size and pads it with transparent borders, so the output has exactly that size
+    Exact,
+}
+
+impl FromStr for Fit {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fit" | "Fit" | "contain" => Ok(Fit::Contain),
+            "fill" | "Fill" | "cover" => Ok(Fit::Fill),
+            "crop" | "Crop" => Ok(Fit::Crop),
+            "stretch" | "Stretch" => Ok(Fit::Stretch),
+            "exact" | "Exact" => Ok(Fit::Exact),
+            _ => Err(ImagixError::InvalidInput(format!(
+                "Invalid fit, expected fit, fill, crop, stretch or exact: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl Fit {
+    /// Shapes the image into the target size, a side of u32::MAX follows the aspect ratio of the image
+    pub fn apply(self, img: &DynamicImage, (width, height): (u32, u32)) -> DynamicImage {
+        let (width, height) = target_size(img.dimensions(), (width, height));
+
+        match self {
+            Fit::Contain => img.thumbnail(width, height),
+            Fit::Fill => img.resize_to_fill(width, height, imageops::FilterType::Lanczos3),
+            Fit::Crop => {
+                let (width, height) = (width.min(img.width()), height.min(img.height()));
+                img.crop_imm(
+                    (img.width() - width) / 2,
+                    (img.height() - height) / 2,
+                    width,
+                    height,
+                )
+            }
+            Fit::Stretch => img.resize_exact(width, height, imageops::FilterType::Lanczos3),
+            Fit::Exact => {
+                let scaled = img.thumbnail(width, height);
+                let mut canvas = RgbaImage::new(width, height);
+                let x = (width - scaled.width()) / 2;
+                let y = (height - scaled.height()) / 2;
+                imageops::overlay(&mut canvas, &scaled.to_rgba8(), x as i64, y as i64);
+                DynamicImage::ImageRgba8(canvas)
+            }
+        }
+    }
+
+    /// Size `apply` gives to an image of `dimensions`, without touching its pixels
+    pub fn output_size(self, (img_width, img_height): (u32, u32), size: (u32, u32)) -> (u32, u32) {
+        let (width, height) = target_size((img_width, img_height), size);
+
+        match self {
+            // Same rounding as `DynamicImage::thumbnail`
+            Fit::Contain => {
+                let ratio = f64::min(
+                    width as f64 / img_width.max(1) as f64,
+                    height as f64 / img_height.max(1) as f64,
+                );
+                let side = |from: u32| ((from as f64 * ratio).round() as u32).max(1);
+                (side(img_width), side(img_height))
+            }
+            Fit::Crop => (width.min(img_width), height.min(img_height)),
+            Fit::Fill | Fit::Stretch | Fit::Exact => (width, height),
+        }
+    }
+}
+
+/// Replaces the unbounded sides of the target size by the size keeping the aspect ratio of the image
+fn target_size((img_width, img_height): (u32, u32), (width, height): (u32, u32)) -> (u32, u32) {
+    let scale = |side: u32, from: u32, to: u32| {
+        ((side as u64 * to as u64) / from.max(1) as u64).max(1) as u32
+    };
+
+    match (width, height) {
+        (u32::MAX, u32::MAX) => (img_width, img_height),
+        (width, u32::MAX) => (width, scale(img_height, img_width, width)),
+        (u32::MAX, height) => (scale(img_width, img_height, height), height),
+        size => size,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::new(width, height))
+    }
+
+    #[test]
+    fn test_fit_modes_dimensions() {
+        let img = image(400, 200);
+
+        assert_eq!(Fit::Contain.apply(&img, (100, 100)).dimensions(), (100, 50));
+        assert_eq!(Fit::Fill.apply(&img, (100, 100)).dimensions(), (100, 100));
+        assert_eq!(Fit::Crop.apply(&img, (100, 100)).dimensions(), (100, 100));
+        assert_eq!(
+            Fit::Stretch.apply(&img, (100, 100)).dimensions(),
+            (100, 100)
+        );
+        assert_eq!(Fit::Exact.apply(&img, (100, 100)).dimensions(), (100, 100));
+    }
+
+    #[test]
+    fn test_predicted_size() {
+        let sizes = [(40, 20), (20, 40), (33, 77), (5, 30), (120, 80)];
+        let targets = [(10, 10), (20, u32::MAX), (u32::MAX, 9), (100, 100)];
+
+        for fit in [Fit::Contain, Fit::Fill, Fit::Crop, Fit::Stretch, Fit::Exact] {
+            for (width, height) in sizes {
+                for target in targets {
+                    let img = image(width, height);
+                    assert_eq!(
+                        fit.output_size(img.dimensions(), target),
+                        fit.apply(&img, target).dimensions(),
+                        "{:?} {:?}",
+                        fit,
+                        target
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_crop_smaller_image() {
+        assert_eq!(
+            Fit::Crop.apply(&image(50, 300), (100, 100)).dimensions(),
+            (50, 100)
+        );
+    }
+
+    #[test]
+    fn test_unbounded_side() {
+        let img = image(400, 200);
+
+        assert_eq!(
+            Fit::Fill.apply(&img, (200, u32::MAX)).dimensions(),
+            (200, 100)
+        );
+        assert_eq!(
+            Fit::Stretch.apply(&img, (u32::MAX, 50)).dimensions(),
+            (100, 50)
+        );
+    }
+}
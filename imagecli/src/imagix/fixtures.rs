@@ -0,0 +1,28 @@
+use image::{ImageEncoder, Rgb, RgbImage, codecs::jpeg::JpegEncoder};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Writes a gradient jpeg of the given size
+pub fn write_jpeg(path: &Path, width: u32, height: u32) {
+    let img = RgbImage::from_fn(width, height, |x, y| {
+        Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+    });
+    let mut bytes = vec![];
+    JpegEncoder::new(&mut bytes)
+        .write_image(img.as_raw(), width, height, image::ExtendedColorType::Rgb8)
+        .unwrap();
+    std::fs::write(path, bytes).unwrap();
+}
+
+/// Creates an empty folder removed when dropped
+pub fn empty_folder() -> TempDir {
+    tempfile::tempdir().unwrap()
+}
+
+/// Creates a folder with a landscape image1.jpg of 1200x800 and a portrait image2.jpg of 600x900
+pub fn image_folder() -> TempDir {
+    let folder = empty_folder();
+    write_jpeg(&folder.path().join("image1.jpg"), 1200, 800);
+    write_jpeg(&folder.path().join("image2.jpg"), 600, 900);
+    folder
+}
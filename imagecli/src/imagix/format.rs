@@ -0,0 +1,238 @@
+use image::{
+    DynamicImage, ImageEncoder, ImageFormat, ImageResult,
+    codecs::{jpeg::JpegEncoder, png::PngEncoder, tiff::TiffEncoder, webp::WebPEncoder},
+};
+use std::{
+    io::{Seek, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use super::{error::ImagixError, metadata::ImageMetadata};
+
+/// Extensions of the images that are read, matched ignoring the case. Gif images are read from their first frame
+pub const INPUT_EXTENSIONS: [&str; 8] = ["jpg", "jpeg", "png", "webp", "tif", "tiff", "bmp", "gif"];
+
+/// Extensions read when the heic feature is enabled
+#[cfg(feature = "heic")]
+pub const HEIC_EXTENSIONS: [&str; 2] = ["heic", "heif"];
+
+/// Quality of the jpeg images when none is given
+pub const DEFAULT_QUALITY: u8 = 85;
+
+/// Data structure that specifies the format the images are written in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Jpeg,
+    /// Lossless webp, the encoder of the image crate has no lossy mode
+    WebP,
+    Bmp,
+    Tiff,
+    Gif,
+}
+
+impl FromStr for OutputFormat {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "png" => Ok(OutputFormat::Png),
+            "jpg" | "jpeg" => Ok(OutputFormat::Jpeg),
+            "webp" => Ok(OutputFormat::WebP),
+            "bmp" => Ok(OutputFormat::Bmp),
+            "tif" | "tiff" => Ok(OutputFormat::Tiff),
+            "gif" => Ok(OutputFormat::Gif),
+            _ => Err(ImagixError::InvalidInput(format!(
+                "Unsupported output format: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Extension of the files written in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::WebP => "webp",
+            OutputFormat::Bmp => "bmp",
+            OutputFormat::Tiff => "tiff",
+            OutputFormat::Gif => "gif",
+        }
+    }
+
+    fn image_format(self) -> ImageFormat {
+        match self {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Jpeg => ImageFormat::Jpeg,
+            OutputFormat::WebP => ImageFormat::WebP,
+            OutputFormat::Bmp => ImageFormat::Bmp,
+            OutputFormat::Tiff => ImageFormat::Tiff,
+            OutputFormat::Gif => ImageFormat::Gif,
+        }
+    }
+
+    /// Encodes the image in this format, `quality` (1 to 100) is only used by jpeg.
+    /// The metadata is written by the formats able to hold it and left out by the others
+    pub fn encode<W: Write + Seek>(
+        self,
+        img: &DynamicImage,
+        quality: u8,
+        metadata: &ImageMetadata,
+        writer: &mut W,
+    ) -> ImageResult<()> {
+        match self {
+            // Jpeg has no alpha channel and the webp encoder only takes 8 bit images
+            OutputFormat::Jpeg => write_with(
+                JpegEncoder::new_with_quality(writer, quality),
+                &DynamicImage::ImageRgb8(img.to_rgb8()),
+                metadata,
+            ),
+            OutputFormat::WebP => write_with(
+                WebPEncoder::new_lossless(writer),
+                &DynamicImage::ImageRgba8(img.to_rgba8()),
+                metadata,
+            ),
+            OutputFormat::Png => write_with(PngEncoder::new(writer), img, metadata),
+            OutputFormat::Tiff => write_with(TiffEncoder::new(writer), img, metadata),
+            format => img.write_to(writer, format.image_format()),
+        }
+    }
+}
+
+/// Writes the image with an encoder, along with the metadata the encoder supports
+pub fn write_with<E: ImageEncoder>(
+    mut encoder: E,
+    img: &DynamicImage,
+    metadata: &ImageMetadata,
+) -> ImageResult<()> {
+    if let Some(icc) = &metadata.icc {
+        let _ = encoder.set_icc_profile(icc.clone());
+    }
+    if let Some(exif) = &metadata.exif {
+        let _ = encoder.set_exif_metadata(exif.clone());
+    }
+
+    encoder.write_image(
+        img.as_bytes(),
+        img.width(),
+        img.height(),
+        img.color().into(),
+    )
+}
+
+/// Whether the file has the extension of an image that can be read
+pub fn is_supported_input(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+        return false;
+    };
+    let supported = |extensions: &[&str]| {
+        extensions
+            .iter()
+            .any(|supported| ext.eq_ignore_ascii_case(supported))
+    };
+
+    #[cfg(feature = "heic")]
+    if supported(&HEIC_EXTENSIONS) {
+        return true;
+    }
+
+    supported(&INPUT_EXTENSIONS)
+}
+
+/// Registers the decoders of the optional formats with the image crate, to be called before reading any image
+pub fn register_decoders() {
+    #[cfg(feature = "heic")]
+    {
+        libheif_rs::integration::image::register_heic_decoding_hook();
+        libheif_rs::integration::image::register_heif_decoding_hook();
+    }
+}
+
+/// Checks that a quality is between 1 and 100
+pub fn validate_quality(quality: u8) -> Result<u8, ImagixError> {
+    if !(1..=100).contains(&quality) {
+        return Err(ImagixError::InvalidInput(
+            "The quality must be between 1 and 100".to_string(),
+        ));
+    }
+
+    Ok(quality)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, RgbaImage};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("JPG".parse::<OutputFormat>().unwrap(), OutputFormat::Jpeg);
+        assert_eq!("webp".parse::<OutputFormat>().unwrap(), OutputFormat::WebP);
+        assert!("psd".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_supported_input() {
+        for name in [
+            "a.jpg", "a.JPEG", "a.Png", "a.webp", "a.TIF", "a.tiff", "a.bmp", "a.gif",
+        ] {
+            assert!(is_supported_input(Path::new(name)), "{}", name);
+        }
+        assert!(!is_supported_input(Path::new("a.txt")));
+        assert!(!is_supported_input(Path::new("jpg")));
+        assert_eq!(
+            is_supported_input(Path::new("a.HEIC")),
+            cfg!(feature = "heic")
+        );
+    }
+
+    #[test]
+    fn test_encode_every_format() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::new(20, 10));
+
+        for format in [
+            OutputFormat::Png,
+            OutputFormat::Jpeg,
+            OutputFormat::WebP,
+            OutputFormat::Bmp,
+            OutputFormat::Tiff,
+            OutputFormat::Gif,
+        ] {
+            let mut bytes = Cursor::new(vec![]);
+            format
+                .encode(&img, DEFAULT_QUALITY, &ImageMetadata::default(), &mut bytes)
+                .unwrap();
+
+            let decoded = image::load_from_memory(bytes.get_ref()).unwrap();
+            assert_eq!(decoded.dimensions(), (20, 10));
+            assert_eq!(
+                image::guess_format(bytes.get_ref()).unwrap(),
+                format.image_format()
+            );
+        }
+    }
+
+    #[test]
+    fn test_jpeg_quality() {
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_fn(64, 64, |x, y| {
+            image::Rgb([(x * 4) as u8, (y * 4) as u8, (x * y) as u8])
+        }));
+        let size = |quality| {
+            let mut bytes = Cursor::new(vec![]);
+            OutputFormat::Jpeg
+                .encode(&img, quality, &ImageMetadata::default(), &mut bytes)
+                .unwrap();
+            bytes.into_inner().len()
+        };
+
+        assert!(size(20) < size(95));
+        assert!(validate_quality(0).is_err());
+        assert!(validate_quality(101).is_err());
+    }
+}
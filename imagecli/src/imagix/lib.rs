@@ -0,0 +1,39 @@
+//! Image processing library behind imagecli: resizing, cropping, rotating, converting and optimizing
+//! a single image or the images of a folder, and gathering stats about them.
+//!
+//! A resize is described with a [`ResizeRequest`], the other operations with an [`Operation`] given
+//! to [`batch::process_request`]. Every image is planned before any is written, and the images that fail
+//! are reported in the [`BatchReport`] without stopping the others.
+//!
+//! ```no_run
+//! use imagix::{Fit, ResizeRequest, Size};
+//!
+//! let report = ResizeRequest::new("photos", Size::Medium)
+//!     .with_fit(Fit::Fill)
+//!     .with_recursive(true)
+//!     .run()?;
+//! println!("{}", report);
+//! # Ok::<(), imagix::ImagixError>(())
+//! ```
+
+pub mod batch;
+pub mod error;
+pub mod fit;
+#[cfg(test)]
+mod fixtures;
+pub mod format;
+pub mod metadata;
+pub mod operation;
+pub mod optimize;
+pub mod output;
+pub mod resize;
+pub mod stats;
+pub mod watch;
+
+pub use batch::{BatchPlan, BatchReport, Mode};
+pub use error::ImagixError;
+pub use fit::Fit;
+pub use format::OutputFormat;
+pub use operation::Operation;
+pub use output::OutputOptions;
+pub use resize::{ResizeRequest, Size};
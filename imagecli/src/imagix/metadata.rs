@@ -0,0 +1,137 @@
+use image::{DynamicImage, ImageDecoder, ImageReader, metadata::Orientation};
+use std::path::Path;
+
+use super::error::{ImagixError, WithPath};
+
+/// Data structure that specifies whether the metadata of the source images is copied into the processed images
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MetadataMode {
+    Keep,
+    #[default]
+    Strip,
+}
+
+impl MetadataMode {
+    /// Builds the mode from the --keep-metadata and --strip-metadata flags, stripping is the default
+    pub fn from_flags(keep: bool, strip: bool) -> Result<Self, ImagixError> {
+        match (keep, strip) {
+            (true, true) => Err(ImagixError::InvalidInput(
+                "--keep-metadata cannot be combined with --strip-metadata".to_string(),
+            )),
+            (true, false) => Ok(MetadataMode::Keep),
+            _ => Ok(MetadataMode::Strip),
+        }
+    }
+}
+
+/// EXIF and ICC data read from an image
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImageMetadata {
+    /// Raw EXIF chunk, with the orientation already applied to the pixels
+    pub exif: Option<Vec<u8>>,
+    pub icc: Option<Vec<u8>>,
+}
+
+/// Size of an image once turned upright, read from its header without decoding the pixels
+pub fn oriented_dimensions(path: &Path) -> Result<(u32, u32), ImagixError> {
+    let mut decoder = ImageReader::open(path)
+        .with_path(path)?
+        .with_guessed_format()
+        .with_path(path)?
+        .into_decoder()
+        .with_path(path)?;
+    let (width, height) = decoder.dimensions();
+
+    Ok(match decoder.orientation().with_path(path)? {
+        Orientation::Rotate90
+        | Orientation::Rotate270
+        | Orientation::Rotate90FlipH
+        | Orientation::Rotate270FlipH => (height, width),
+        _ => (width, height),
+    })
+}
+
+/// Opens an image turned upright with its EXIF orientation, along with its metadata
+pub fn open_oriented(path: &Path) -> Result<(DynamicImage, ImageMetadata), ImagixError> {
+    let mut decoder = ImageReader::open(path)
+        .with_path(path)?
+        .with_guessed_format()
+        .with_path(path)?
+        .into_decoder()
+        .with_path(path)?;
+
+    let orientation = decoder.orientation().with_path(path)?;
+    let icc = decoder.icc_profile().with_path(path)?;
+    let mut exif = decoder.exif_metadata().with_path(path)?;
+
+    // The pixels are rotated here, so the copied EXIF says they need no more turning
+    if let Some(exif) = exif.as_mut() {
+        let _ = Orientation::remove_from_exif_chunk(exif);
+    }
+
+    let mut img = DynamicImage::from_decoder(decoder).with_path(path)?;
+    img.apply_orientation(orientation);
+
+    Ok((img, ImageMetadata { exif, icc }))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use image::{GenericImageView, ImageEncoder, RgbImage, codecs::jpeg::JpegEncoder};
+    use std::io::Cursor;
+
+    /// Little endian EXIF chunk holding only an orientation tag
+    fn exif_with_orientation(orientation: u16) -> Vec<u8> {
+        let mut exif = b"II*\0\x08\0\0\0\x01\0\x12\x01\x03\0\x01\0\0\0".to_vec();
+        exif.extend_from_slice(&orientation.to_le_bytes());
+        exif.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        exif
+    }
+
+    /// Writes a 40x20 jpeg tagged to be shown turned by 90 degrees, so it is seen as 20x40
+    pub(crate) fn write_rotated_jpeg(path: &Path) {
+        let mut bytes = Cursor::new(vec![]);
+        let mut encoder = JpegEncoder::new(&mut bytes);
+        encoder.set_exif_metadata(exif_with_orientation(6)).unwrap();
+        encoder
+            .write_image(
+                RgbImage::new(40, 20).as_raw(),
+                40,
+                20,
+                image::ExtendedColorType::Rgb8,
+            )
+            .unwrap();
+        std::fs::write(path, bytes.into_inner()).unwrap();
+    }
+
+    #[test]
+    fn test_open_oriented() {
+        let folder = crate::fixtures::empty_folder();
+        let path = folder.path().join("oriented.jpg");
+        write_rotated_jpeg(&path);
+
+        let (img, metadata) = open_oriented(&path).unwrap();
+
+        assert_eq!(img.dimensions(), (20, 40));
+        assert_eq!(oriented_dimensions(&path).unwrap(), (20, 40));
+        let exif = metadata.exif.unwrap();
+        assert_eq!(
+            Orientation::from_exif_chunk(&exif),
+            Some(Orientation::NoTransforms)
+        );
+    }
+
+    #[test]
+    fn test_metadata_flags() {
+        assert_eq!(
+            MetadataMode::from_flags(false, false).unwrap(),
+            MetadataMode::Strip
+        );
+        assert_eq!(
+            MetadataMode::from_flags(true, false).unwrap(),
+            MetadataMode::Keep
+        );
+        assert!(MetadataMode::from_flags(true, true).is_err());
+    }
+}
@@ -0,0 +1,416 @@
+use image::{DynamicImage, GenericImageView};
+use std::str::FromStr;
+use thiserror::Error;
+
+use super::{error::ImagixError, fit::Fit, optimize::Reduction};
+
+/// Data structure that specifies the area cut by a crop, written `WxH+X+Y` or `WxH`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometry {
+    pub width: u32,
+    pub height: u32,
+    /// Offset of the area from the point given by the gravity
+    pub x: u32,
+    pub y: u32,
+}
+
+impl FromStr for Geometry {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || ImagixError::InvalidInput(format!("Invalid geometry, expected WxH+X+Y: {}", s));
+        let number = |text: &str| text.parse::<u32>().map_err(|_| invalid());
+
+        let (size, offset) = match s.split_once('+') {
+            Some((size, offset)) => (size, Some(offset)),
+            None => (s, None),
+        };
+        let (width, height) = size.split_once(['x', 'X']).ok_or_else(invalid)?;
+        let (x, y) = match offset {
+            Some(offset) => {
+                let (x, y) = offset.split_once('+').ok_or_else(invalid)?;
+                (number(x)?, number(y)?)
+            }
+            None => (0, 0),
+        };
+
+        let geometry = Geometry {
+            width: number(width)?,
+            height: number(height)?,
+            x,
+            y,
+        };
+        if geometry.width == 0 || geometry.height == 0 {
+            return Err(ImagixError::InvalidInput(
+                "Dimensions must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(geometry)
+    }
+}
+
+/// Data structure that specifies the point of the image a crop area is placed from
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Gravity {
+    #[default]
+    NorthWest,
+    North,
+    NorthEast,
+    West,
+    Center,
+    East,
+    SouthWest,
+    South,
+    SouthEast,
+}
+
+impl FromStr for Gravity {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "northwest" => Ok(Gravity::NorthWest),
+            "north" => Ok(Gravity::North),
+            "northeast" => Ok(Gravity::NorthEast),
+            "west" => Ok(Gravity::West),
+            "center" => Ok(Gravity::Center),
+            "east" => Ok(Gravity::East),
+            "southwest" => Ok(Gravity::SouthWest),
+            "south" => Ok(Gravity::South),
+            "southeast" => Ok(Gravity::SouthEast),
+            _ => Err(ImagixError::InvalidInput(format!("Invalid gravity: {}", s))),
+        }
+    }
+}
+
+impl Gravity {
+    /// Top left corner of an area of `size` placed in an image of `bounds` with this gravity
+    fn origin(
+        self,
+        (width, height): (u32, u32),
+        (bounds_width, bounds_height): (u32, u32),
+    ) -> (u32, u32) {
+        let left = 0;
+        let center = bounds_width.saturating_sub(width) / 2;
+        let right = bounds_width.saturating_sub(width);
+        let top = 0;
+        let middle = bounds_height.saturating_sub(height) / 2;
+        let bottom = bounds_height.saturating_sub(height);
+
+        match self {
+            Gravity::NorthWest => (left, top),
+            Gravity::North => (center, top),
+            Gravity::NorthEast => (right, top),
+            Gravity::West => (left, middle),
+            Gravity::Center => (center, middle),
+            Gravity::East => (right, middle),
+            Gravity::SouthWest => (left, bottom),
+            Gravity::South => (center, bottom),
+            Gravity::SouthEast => (right, bottom),
+        }
+    }
+}
+
+/// Data structure that specifies a clockwise rotation in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rotation {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl FromStr for Rotation {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "90" => Ok(Rotation::Rotate90),
+            "180" => Ok(Rotation::Rotate180),
+            "270" => Ok(Rotation::Rotate270),
+            _ => Err(ImagixError::InvalidInput(format!(
+                "Invalid rotation, expected 90, 180 or 270: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Data structure that specifies the axis an image is mirrored on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flip {
+    Horizontal,
+    Vertical,
+}
+
+impl FromStr for Flip {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "h" | "horizontal" => Ok(Flip::Horizontal),
+            "v" | "vertical" => Ok(Flip::Vertical),
+            _ => Err(ImagixError::InvalidInput(format!(
+                "Invalid flip, expected h or v: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Data structure that describes why an operation cannot be applied to an image
+#[derive(Debug, Error, PartialEq)]
+pub enum OperationError {
+    #[error("the crop area at {x},{y} is outside of the {width}x{height} image")]
+    CropOutside {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// Data structure that specifies what is done to each image of a request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    /// Shapes the image into a width and height, see `Size::dimensions`
+    Resize { size: (u32, u32), fit: Fit },
+    Crop {
+        geometry: Geometry,
+        gravity: Gravity,
+    },
+    /// Rotates the image and then mirrors it
+    Transform {
+        rotate: Option<Rotation>,
+        flip: Option<Flip>,
+    },
+    /// Keeps the image as it is, to write it in the format of the output options.
+    /// With `delete_originals` the source image is removed once it has been written
+    Convert { delete_originals: bool },
+    /// Keeps the image as it is, to recompress it in its own format, see `optimize::recompress`
+    Optimize { target: Option<Reduction> },
+}
+
+impl Operation {
+    /// Builds a transform, at least a rotation or a flip is needed
+    pub fn transform(
+        rotate: Option<Rotation>,
+        flip: Option<Flip>,
+    ) -> Result<Operation, ImagixError> {
+        if rotate.is_none() && flip.is_none() {
+            return Err(ImagixError::InvalidInput(
+                "Specify --rotate or --flip".to_string(),
+            ));
+        }
+
+        Ok(Operation::Transform { rotate, flip })
+    }
+
+    /// Word used to report the images processed
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Operation::Resize { .. } => "Thumbnailed",
+            Operation::Crop { .. } => "Cropped",
+            Operation::Transform { .. } => "Transformed",
+            Operation::Convert { .. } => "Converted",
+            Operation::Optimize { .. } => "Optimized",
+        }
+    }
+
+    /// Applies the operation to an image
+    pub fn apply(&self, img: &DynamicImage) -> Result<DynamicImage, OperationError> {
+        match *self {
+            Operation::Resize { size, fit } => Ok(fit.apply(img, size)),
+            Operation::Crop { geometry, gravity } => {
+                let (x, y, width, height) = crop_area(geometry, gravity, img.dimensions())?;
+                Ok(img.crop_imm(x, y, width, height))
+            }
+            Operation::Transform { rotate, flip } => {
+                let rotated = match rotate {
+                    Some(Rotation::Rotate90) => img.rotate90(),
+                    Some(Rotation::Rotate180) => img.rotate180(),
+                    Some(Rotation::Rotate270) => img.rotate270(),
+                    None => img.clone(),
+                };
+
+                Ok(match flip {
+                    Some(Flip::Horizontal) => rotated.fliph(),
+                    Some(Flip::Vertical) => rotated.flipv(),
+                    None => rotated,
+                })
+            }
+            Operation::Convert { .. } | Operation::Optimize { .. } => Ok(img.clone()),
+        }
+    }
+
+    /// Size `apply` gives to an image of `dimensions`, used to plan a request before running it
+    pub fn output_size(&self, dimensions: (u32, u32)) -> Result<(u32, u32), OperationError> {
+        match *self {
+            Operation::Resize { size, fit } => Ok(fit.output_size(dimensions, size)),
+            Operation::Crop { geometry, gravity } => {
+                let (_, _, width, height) = crop_area(geometry, gravity, dimensions)?;
+                Ok((width, height))
+            }
+            Operation::Transform {
+                rotate: Some(Rotation::Rotate90 | Rotation::Rotate270),
+                ..
+            } => Ok((dimensions.1, dimensions.0)),
+            Operation::Transform { .. }
+            | Operation::Convert { .. }
+            | Operation::Optimize { .. } => Ok(dimensions),
+        }
+    }
+}
+
+/// Position and size of the area a crop keeps from an image of `dimensions`
+fn crop_area(
+    geometry: Geometry,
+    gravity: Gravity,
+    (img_width, img_height): (u32, u32),
+) -> Result<(u32, u32, u32, u32), OperationError> {
+    let (origin_x, origin_y) =
+        gravity.origin((geometry.width, geometry.height), (img_width, img_height));
+    let (x, y) = (
+        origin_x.saturating_add(geometry.x),
+        origin_y.saturating_add(geometry.y),
+    );
+    if x >= img_width || y >= img_height {
+        return Err(OperationError::CropOutside {
+            x,
+            y,
+            width: img_width,
+            height: img_height,
+        });
+    }
+
+    // The area is cut down to the part inside the image
+    Ok((
+        x,
+        y,
+        geometry.width.min(img_width - x),
+        geometry.height.min(img_height - y),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_parse_geometry() {
+        assert_eq!(
+            "100x50+10+20".parse::<Geometry>().unwrap(),
+            Geometry {
+                width: 100,
+                height: 50,
+                x: 10,
+                y: 20
+            }
+        );
+        assert_eq!(
+            "100x50".parse::<Geometry>().unwrap(),
+            Geometry {
+                width: 100,
+                height: 50,
+                x: 0,
+                y: 0
+            }
+        );
+        assert!("100".parse::<Geometry>().is_err());
+        assert!("100x50+10".parse::<Geometry>().is_err());
+        assert!("0x50".parse::<Geometry>().is_err());
+    }
+
+    #[test]
+    fn test_crop() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::new(400, 200));
+        let crop = |geometry: &str, gravity| {
+            Operation::Crop {
+                geometry: geometry.parse().unwrap(),
+                gravity,
+            }
+            .apply(&img)
+        };
+
+        assert_eq!(
+            crop("100x50+10+20", Gravity::NorthWest)
+                .unwrap()
+                .dimensions(),
+            (100, 50)
+        );
+        assert_eq!(
+            crop("100x50+350+0", Gravity::NorthWest)
+                .unwrap()
+                .dimensions(),
+            (50, 50)
+        );
+        assert_eq!(
+            crop("500x500", Gravity::Center).unwrap().dimensions(),
+            (400, 200)
+        );
+        assert_eq!(
+            crop("10x10+400+0", Gravity::NorthWest).unwrap_err(),
+            OperationError::CropOutside {
+                x: 400,
+                y: 0,
+                width: 400,
+                height: 200
+            }
+        );
+    }
+
+    #[test]
+    fn test_crop_gravity() {
+        let mut img = RgbaImage::new(30, 30);
+        img.put_pixel(15, 15, Rgba([255, 0, 0, 255]));
+        let img = DynamicImage::ImageRgba8(img);
+
+        let geometry = "10x10".parse().unwrap();
+        let cropped = Operation::Crop {
+            geometry,
+            gravity: Gravity::Center,
+        }
+        .apply(&img)
+        .unwrap();
+
+        assert_eq!(cropped.get_pixel(5, 5), Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut img = RgbaImage::new(40, 20);
+        img.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        let img = DynamicImage::ImageRgba8(img);
+
+        let rotated = Operation::transform(Some(Rotation::Rotate90), None)
+            .unwrap()
+            .apply(&img)
+            .unwrap();
+        assert_eq!(rotated.dimensions(), (20, 40));
+        assert_eq!(rotated.get_pixel(19, 0), Rgba([255, 0, 0, 255]));
+
+        let flipped = Operation::transform(None, Some(Flip::Vertical))
+            .unwrap()
+            .apply(&img)
+            .unwrap();
+        assert_eq!(flipped.get_pixel(0, 19), Rgba([255, 0, 0, 255]));
+
+        assert!(Operation::transform(None, None).is_err());
+    }
+
+    #[test]
+    fn test_output_size() {
+        let crop = Operation::Crop {
+            geometry: "100x50+350+0".parse().unwrap(),
+            gravity: Gravity::NorthWest,
+        };
+        assert_eq!(crop.output_size((400, 200)).unwrap(), (50, 50));
+        assert!(crop.output_size((300, 200)).is_err());
+
+        let rotate =
+            Operation::transform(Some(Rotation::Rotate270), Some(Flip::Horizontal)).unwrap();
+        assert_eq!(rotate.output_size((400, 200)).unwrap(), (200, 400));
+    }
+}
@@ -0,0 +1,360 @@
+use image::{
+    ColorType, DynamicImage, ImageError, ImageFormat, ImageResult, RgbaImage,
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+    error::{EncodingError, ImageFormatHint},
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, hash_map::Entry},
+    fs,
+    io::Cursor,
+    path::Path,
+    str::FromStr,
+};
+
+use super::{
+    error::{ImagixError, WithPath},
+    format::{self, OutputFormat},
+    metadata::ImageMetadata,
+};
+
+/// Lowest jpeg quality tried to reach a target reduction
+pub const MIN_QUALITY: u8 = 40;
+
+/// Highest jpeg quality tried to reach a target reduction
+pub const MAX_QUALITY: u8 = 95;
+
+/// Data structure that specifies the percentage of their size the optimized jpeg images should lose, written `30%` or `30`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reduction(pub u8);
+
+impl FromStr for Reduction {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('%').unwrap_or(s).parse::<u8>() {
+            Ok(percent) if (1..=99).contains(&percent) => Ok(Reduction(percent)),
+            _ => Err(ImagixError::InvalidInput(format!(
+                "Invalid reduction, expected a percentage from 1 to 99: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl Reduction {
+    /// Largest size in bytes a file of `size` bytes can have once reduced
+    fn budget(self, size: u64) -> u64 {
+        size * (100 - self.0 as u64) / 100
+    }
+}
+
+/// Whether the file has the extension of a png or a jpeg, the formats that are optimized
+pub fn is_optimizable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ["png", "jpg", "jpeg"]
+                .iter()
+                .any(|supported| ext.eq_ignore_ascii_case(supported))
+        })
+}
+
+/// Recompresses the image of `src`, with `img` its pixels. Pngs are reduced without loss, jpegs are encoded with the highest
+/// quality that reaches the `target` reduction. The original file is returned when it is still the smallest, so an image never grows
+pub fn recompress(
+    src: &Path,
+    img: &DynamicImage,
+    target: Option<Reduction>,
+    metadata: &ImageMetadata,
+) -> Result<Vec<u8>, ImagixError> {
+    let original = fs::read(src).with_path(src)?;
+
+    let recompressed = match image::guess_format(&original).with_path(src)? {
+        ImageFormat::Png => reduce_png(img, metadata).with_path(src)?,
+        ImageFormat::Jpeg => {
+            search_jpeg_quality(img, original.len() as u64, target, metadata).with_path(src)?
+        }
+        _ => {
+            return Err(ImagixError::NotOptimizable {
+                path: src.to_path_buf(),
+            });
+        }
+    };
+
+    Ok(if recompressed.len() < original.len() {
+        recompressed
+    } else {
+        original
+    })
+}
+
+/// Encodes a jpeg with the highest quality that fits in the budget of the target, or with the default quality without a target.
+/// When even the lowest quality does not fit, the image at the lowest quality is returned
+fn search_jpeg_quality(
+    img: &DynamicImage,
+    size: u64,
+    target: Option<Reduction>,
+    metadata: &ImageMetadata,
+) -> ImageResult<Vec<u8>> {
+    let Some(target) = target else {
+        return encode_jpeg(img, format::DEFAULT_QUALITY, metadata);
+    };
+    let budget = target.budget(size);
+
+    let mut best = encode_jpeg(img, MIN_QUALITY, metadata)?;
+    if best.len() as u64 > budget {
+        return Ok(best);
+    }
+
+    // The size grows with the quality, so the highest quality in the budget is found by bisection
+    let (mut low, mut high) = (MIN_QUALITY + 1, MAX_QUALITY);
+    while low <= high {
+        let quality = low + (high - low) / 2;
+        let bytes = encode_jpeg(img, quality, metadata)?;
+        if bytes.len() as u64 <= budget {
+            best = bytes;
+            low = quality + 1;
+        } else {
+            high = quality - 1;
+        }
+    }
+
+    Ok(best)
+}
+
+fn encode_jpeg(img: &DynamicImage, quality: u8, metadata: &ImageMetadata) -> ImageResult<Vec<u8>> {
+    let mut bytes = Cursor::new(vec![]);
+    OutputFormat::Jpeg.encode(img, quality, metadata, &mut bytes)?;
+    Ok(bytes.into_inner())
+}
+
+/// Encodes a png in the smallest lossless form: a palette when it has 256 colors or less, and otherwise
+/// without the alpha channel when it is opaque and in grayscale when it has no color
+fn reduce_png(img: &DynamicImage, metadata: &ImageMetadata) -> ImageResult<Vec<u8>> {
+    if !fits_in_8_bits(img) {
+        let mut bytes = Cursor::new(vec![]);
+        let encoder =
+            PngEncoder::new_with_quality(&mut bytes, CompressionType::Best, FilterType::Adaptive);
+        format::write_with(encoder, img, metadata)?;
+        return Ok(bytes.into_inner());
+    }
+
+    let rgba = img.to_rgba8();
+    let mut smallest = encode_truecolor(&rgba, metadata)?;
+    if let Some(indexed) = encode_indexed(&rgba, metadata)?
+        && indexed.len() < smallest.len()
+    {
+        smallest = indexed;
+    }
+
+    Ok(smallest)
+}
+
+/// Whether the image can be written with 8 bits per sample without losing precision
+fn fits_in_8_bits(img: &DynamicImage) -> bool {
+    match img.color() {
+        ColorType::L8 | ColorType::La8 | ColorType::Rgb8 | ColorType::Rgba8 => true,
+        ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16 => {
+            img.to_rgba16().iter().all(|sample| sample % 257 == 0)
+        }
+        _ => false,
+    }
+}
+
+fn encode_truecolor(rgba: &RgbaImage, metadata: &ImageMetadata) -> ImageResult<Vec<u8>> {
+    let opaque = rgba.pixels().all(|pixel| pixel[3] == 255);
+    let gray = rgba
+        .pixels()
+        .all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2]);
+
+    let (color, channels): (png::ColorType, &[usize]) = match (gray, opaque) {
+        (true, true) => (png::ColorType::Grayscale, &[0]),
+        (true, false) => (png::ColorType::GrayscaleAlpha, &[0, 3]),
+        (false, true) => (png::ColorType::Rgb, &[0, 1, 2]),
+        (false, false) => (png::ColorType::Rgba, &[0, 1, 2, 3]),
+    };
+    let data: Vec<u8> = rgba
+        .pixels()
+        .flat_map(|pixel| channels.iter().map(|&channel| pixel[channel]))
+        .collect();
+
+    let mut info = png::Info::with_size(rgba.width(), rgba.height());
+    info.color_type = color;
+    write_png(info, &data, png::Filter::Adaptive, metadata)
+}
+
+/// Encodes the image with a palette, None when it has more than 256 colors
+fn encode_indexed(rgba: &RgbaImage, metadata: &ImageMetadata) -> ImageResult<Option<Vec<u8>>> {
+    let mut colors: Vec<[u8; 4]> = vec![];
+    let mut seen = HashMap::new();
+    for pixel in rgba.pixels() {
+        if let Entry::Vacant(entry) = seen.entry(pixel.0) {
+            if colors.len() == 256 {
+                return Ok(None);
+            }
+            entry.insert(0u8);
+            colors.push(pixel.0);
+        }
+    }
+
+    // The translucent colors go first, so the transparency chunk stops at the last of them
+    colors.sort_by_key(|color| color[3] == 255);
+    for (index, color) in colors.iter().enumerate() {
+        seen.insert(*color, index as u8);
+    }
+    let translucent = colors.iter().take_while(|color| color[3] != 255).count();
+
+    let depth = match colors.len() {
+        0..=2 => png::BitDepth::One,
+        3..=4 => png::BitDepth::Two,
+        5..=16 => png::BitDepth::Four,
+        _ => png::BitDepth::Eight,
+    };
+    let bits = depth as usize;
+
+    // Each row starts on a new byte, with the indexes packed from the high bits
+    let row_bytes = (rgba.width() as usize * bits).div_ceil(8);
+    let mut data = vec![0u8; row_bytes * rgba.height() as usize];
+    for (x, y, pixel) in rgba.enumerate_pixels() {
+        let bit = x as usize * bits;
+        let shift = 8 - bits - bit % 8;
+        data[y as usize * row_bytes + bit / 8] |= seen[&pixel.0] << shift;
+    }
+
+    let mut info = png::Info::with_size(rgba.width(), rgba.height());
+    info.color_type = png::ColorType::Indexed;
+    info.bit_depth = depth;
+    info.palette = Some(Cow::Owned(
+        colors
+            .iter()
+            .flat_map(|color| [color[0], color[1], color[2]])
+            .collect(),
+    ));
+    if translucent > 0 {
+        info.trns = Some(Cow::Owned(
+            colors[..translucent].iter().map(|color| color[3]).collect(),
+        ));
+    }
+
+    write_png(info, &data, png::Filter::NoFilter, metadata).map(Some)
+}
+
+fn write_png<'a>(
+    mut info: png::Info<'a>,
+    data: &[u8],
+    filter: png::Filter,
+    metadata: &'a ImageMetadata,
+) -> ImageResult<Vec<u8>> {
+    info.icc_profile = metadata.icc.as_deref().map(Cow::Borrowed);
+    info.exif_metadata = metadata.exif.as_deref().map(Cow::Borrowed);
+
+    let mut bytes = vec![];
+    let mut encoder = png::Encoder::with_info(&mut bytes, info).map_err(png_error)?;
+    encoder.set_compression(png::Compression::High);
+    encoder.set_filter(filter);
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(data).map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+
+    Ok(bytes)
+}
+
+/// Reports the errors of the png crate like the ones of the image crate
+fn png_error(error: png::EncodingError) -> ImageError {
+    ImageError::Encoding(EncodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Png),
+        error,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use image::{GenericImageView, Rgba};
+
+    fn write(path: &Path, img: &DynamicImage, format: OutputFormat, quality: u8) {
+        let mut file = fs::File::create(path).unwrap();
+        format
+            .encode(img, quality, &ImageMetadata::default(), &mut file)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_reduction() {
+        assert_eq!("30%".parse::<Reduction>().unwrap(), Reduction(30));
+        assert_eq!("5".parse::<Reduction>().unwrap(), Reduction(5));
+        assert!("0%".parse::<Reduction>().is_err());
+        assert!("100".parse::<Reduction>().is_err());
+    }
+
+    #[test]
+    fn test_png_palette_is_lossless() {
+        let folder = fixtures::empty_folder();
+        let path = folder.path().join("palette.png");
+        let colors = [
+            Rgba([255, 0, 0, 255]),
+            Rgba([0, 0, 255, 128]),
+            Rgba([0, 255, 0, 255]),
+        ];
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(101, 37, |x, y| {
+            colors[((x / 7 + y) % 3) as usize]
+        }));
+        write(&path, &img, OutputFormat::Png, 0);
+
+        let optimized = recompress(&path, &img, None, &ImageMetadata::default()).unwrap();
+
+        assert!(optimized.len() < fs::metadata(&path).unwrap().len() as usize);
+        let decoded = image::load_from_memory(&optimized).unwrap();
+        assert_eq!(decoded.to_rgba8(), img.to_rgba8());
+    }
+
+    #[test]
+    fn test_png_gray_is_lossless() {
+        let folder = fixtures::empty_folder();
+        let path = folder.path().join("gray.png");
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| {
+            let level = (x * 4 + y) as u8;
+            Rgba([level, level, level, 255])
+        }));
+        write(&path, &img, OutputFormat::Png, 0);
+
+        let optimized = recompress(&path, &img, None, &ImageMetadata::default()).unwrap();
+
+        let decoded = image::load_from_memory(&optimized).unwrap();
+        assert_eq!(decoded.color(), ColorType::L8);
+        assert_eq!(decoded.to_rgba8(), img.to_rgba8());
+    }
+
+    #[test]
+    fn test_jpeg_reaches_target() {
+        let folder = fixtures::empty_folder();
+        let path = folder.path().join("optimized.jpg");
+        fixtures::write_jpeg(&folder.path().join("image1.jpg"), 1200, 800);
+        let img = image::open(folder.path().join("image1.jpg")).unwrap();
+        write(&path, &img, OutputFormat::Jpeg, 95);
+        let size = fs::metadata(&path).unwrap().len();
+
+        let optimized =
+            recompress(&path, &img, Some(Reduction(30)), &ImageMetadata::default()).unwrap();
+
+        assert!(optimized.len() as u64 <= size * 70 / 100);
+        assert_eq!(
+            image::load_from_memory(&optimized).unwrap().dimensions(),
+            (1200, 800)
+        );
+    }
+
+    #[test]
+    fn test_never_grows() {
+        let folder = fixtures::empty_folder();
+        let path = folder.path().join("small.jpg");
+        let img = DynamicImage::ImageRgb8(image::RgbImage::new(8, 8));
+        write(&path, &img, OutputFormat::Jpeg, 10);
+
+        let optimized = recompress(&path, &img, None, &ImageMetadata::default()).unwrap();
+
+        assert_eq!(optimized, fs::read(&path).unwrap());
+    }
+}
@@ -0,0 +1,304 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use super::{
+    error::ImagixError,
+    format::{self, OutputFormat},
+    metadata::MetadataMode,
+};
+
+/// Name of the folder created next to the source images when no output folder is given
+pub const DEFAULT_DIR: &str = "tmp";
+
+/// Name of the resized images when no template is given
+pub const DEFAULT_TEMPLATE: &str = "{stem}.{ext}";
+
+/// Placeholders that can be used in a name template
+const PLACEHOLDERS: [&str; 4] = ["stem", "ext", "width", "height"];
+
+/// Data structure that specifies what is done when the destination of an image already exists
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Overwrite {
+    #[default]
+    Replace,
+    Skip,
+    /// Writes the image next to the existing file, with a `_1`, `_2`... suffix
+    Rename,
+}
+
+impl FromStr for Overwrite {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "replace" => Ok(Overwrite::Replace),
+            "skip" => Ok(Overwrite::Skip),
+            "rename" => Ok(Overwrite::Rename),
+            _ => Err(ImagixError::InvalidInput(format!(
+                "Invalid overwrite policy, expected replace, skip or rename: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Data structure that specifies where the resized images are written, how they are named and encoded
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputOptions {
+    /// Output folder, by default a tmp folder next to each source image
+    pub dir: Option<PathBuf>,
+    /// Name of each output file, with `{stem}`, `{ext}`, `{width}` and `{height}` replaced.
+    /// It can hold `/` to write the images in nested folders
+    pub name_template: String,
+    /// Format the images are encoded in, png by default
+    pub format: OutputFormat,
+    /// Quality of the lossy formats, from 1 to 100
+    pub quality: u8,
+    /// Whether the EXIF and ICC data of the source images is copied, stripped by default
+    pub metadata: MetadataMode,
+    /// What is done when a destination already exists, replaced by default
+    pub overwrite: Overwrite,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            dir: None,
+            name_template: DEFAULT_TEMPLATE.to_string(),
+            format: OutputFormat::default(),
+            quality: format::DEFAULT_QUALITY,
+            metadata: MetadataMode::default(),
+            overwrite: Overwrite::default(),
+        }
+    }
+}
+
+impl OutputOptions {
+    /// Builds the options from the --out, --name-template and --suffix flags, --suffix is a shorthand for `{stem}<suffix>.{ext}`
+    pub fn new(
+        dir: Option<PathBuf>,
+        name_template: Option<String>,
+        suffix: Option<String>,
+    ) -> Result<Self, ImagixError> {
+        let name_template = match (name_template, suffix) {
+            (Some(_), Some(_)) => {
+                return Err(ImagixError::InvalidInput(
+                    "--name-template cannot be combined with --suffix".to_string(),
+                ));
+            }
+            (Some(template), None) => template,
+            (None, Some(suffix)) => format!("{{stem}}{}.{{ext}}", suffix),
+            (None, None) => DEFAULT_TEMPLATE.to_string(),
+        };
+
+        validate_template(&name_template)?;
+
+        Ok(OutputOptions {
+            dir,
+            name_template,
+            ..OutputOptions::default()
+        })
+    }
+
+    /// Sets the format and the quality the images are encoded with
+    pub fn with_format(self, format: OutputFormat, quality: u8) -> Result<Self, ImagixError> {
+        Ok(OutputOptions {
+            format,
+            quality: format::validate_quality(quality)?,
+            ..self
+        })
+    }
+
+    /// Sets whether the metadata of the source images is copied
+    pub fn with_metadata(self, metadata: MetadataMode) -> Self {
+        OutputOptions { metadata, ..self }
+    }
+
+    /// Sets what is done when a destination already exists
+    pub fn with_overwrite(self, overwrite: Overwrite) -> Self {
+        OutputOptions { overwrite, ..self }
+    }
+}
+
+/// Checks that the template only uses known placeholders and names a file
+fn validate_template(template: &str) -> Result<(), ImagixError> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').ok_or_else(|| {
+            ImagixError::InvalidInput(format!(
+                "Unclosed placeholder in name template: {}",
+                template
+            ))
+        })?;
+        let name = &rest[start + 1..start + end];
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(ImagixError::InvalidInput(format!(
+                "Unknown placeholder in name template: {{{}}}",
+                name
+            )));
+        }
+        rest = &rest[start + end + 1..];
+    }
+
+    if template.trim().is_empty() || template.ends_with('/') {
+        return Err(ImagixError::InvalidInput(
+            "The name template must name a file".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Folder the resized images of the images found under `root` are written to
+pub fn output_dir(root: &Path, output: &OutputOptions) -> PathBuf {
+    match &output.dir {
+        Some(dir) => dir.clone(),
+        None => root.join(DEFAULT_DIR),
+    }
+}
+
+/// Path of the resized image of `src`, with `size` the dimensions of the resized image and `ext` the extension of its format.
+/// The folders between `root` and the image are kept under the output folder, so a tree of images gives the same tree of resized images
+pub fn plan_destination(
+    src: &Path,
+    root: &Path,
+    output: &OutputOptions,
+    (width, height): (u32, u32),
+    ext: &str,
+) -> Result<PathBuf, ImagixError> {
+    let stem = src
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| ImagixError::InvalidFileName {
+            path: src.to_path_buf(),
+        })?;
+
+    let name = output
+        .name_template
+        .replace("{stem}", stem)
+        .replace("{ext}", ext)
+        .replace("{width}", &width.to_string())
+        .replace("{height}", &height.to_string());
+
+    let parent = src.parent().unwrap_or(Path::new(""));
+    let relative = parent.strip_prefix(root).unwrap_or(Path::new(""));
+
+    Ok(output_dir(root, output).join(relative).join(name))
+}
+
+/// Path of `dest` with a `_<n>` suffix added to its name, used to rename the images instead of replacing a file
+pub fn numbered(dest: &Path, n: u32) -> PathBuf {
+    let stem = dest
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = match dest.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, n),
+    };
+
+    dest.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_destination() {
+        let destination = plan_destination(
+            Path::new("/photos/cat.jpg"),
+            Path::new("/photos"),
+            &OutputOptions::default(),
+            (200, 100),
+            "png",
+        );
+
+        assert_eq!(destination.unwrap(), PathBuf::from("/photos/tmp/cat.png"));
+    }
+
+    #[test]
+    fn test_output_dir_and_template() {
+        let output = OutputOptions::new(
+            Some(PathBuf::from("out")),
+            Some("{width}x{height}/{stem}_{width}.{ext}".to_string()),
+            None,
+        )
+        .unwrap();
+        let destination = plan_destination(
+            Path::new("/photos/cat.jpg"),
+            Path::new("/photos"),
+            &output,
+            (200, 100),
+            "png",
+        );
+
+        assert_eq!(
+            destination.unwrap(),
+            PathBuf::from("out/200x100/cat_200.png")
+        );
+    }
+
+    #[test]
+    fn test_suffix() {
+        let output = OutputOptions::new(None, None, Some("_thumb".to_string())).unwrap();
+        let destination = plan_destination(
+            Path::new("cat.jpg"),
+            Path::new(""),
+            &output,
+            (200, 100),
+            "png",
+        );
+
+        assert_eq!(destination.unwrap(), PathBuf::from("tmp/cat_thumb.png"));
+    }
+
+    #[test]
+    fn test_mirrored_tree() {
+        let output = OutputOptions::new(Some(PathBuf::from("/thumbs")), None, None).unwrap();
+        let destination = plan_destination(
+            Path::new("/photos/2023/summer/beach.jpg"),
+            Path::new("/photos"),
+            &output,
+            (200, 100),
+            "png",
+        );
+        assert_eq!(
+            destination.unwrap(),
+            PathBuf::from("/thumbs/2023/summer/beach.png")
+        );
+
+        let destination = plan_destination(
+            Path::new("/photos/2023/beach.jpg"),
+            Path::new("/photos"),
+            &OutputOptions::default(),
+            (200, 100),
+            "png",
+        );
+        assert_eq!(
+            destination.unwrap(),
+            PathBuf::from("/photos/tmp/2023/beach.png")
+        );
+    }
+
+    #[test]
+    fn test_numbered() {
+        assert_eq!(
+            numbered(Path::new("/thumbs/cat.png"), 2),
+            PathBuf::from("/thumbs/cat_2.png")
+        );
+        assert_eq!(numbered(Path::new("cat"), 1), PathBuf::from("cat_1"));
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(OutputOptions::new(None, Some("{stem}_{size}.png".to_string()), None).is_err());
+        assert!(OutputOptions::new(None, Some("{stem.png".to_string()), None).is_err());
+        assert!(OutputOptions::new(None, Some("thumbs/".to_string()), None).is_err());
+        assert!(
+            OutputOptions::new(None, Some("{stem}".to_string()), Some("_a".to_string())).is_err()
+        );
+    }
+}
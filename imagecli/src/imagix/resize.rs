@@ -1,194 +1,286 @@
-use std::{fs, io, path::PathBuf, str::FromStr, time::Instant};
-use image::ImageFormat;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use super::{error::ImagixError, stats::Elapsed};
+use super::{
+    batch::{self, BatchPlan, BatchReport, Mode},
+    error::ImagixError,
+    fit::Fit,
+    operation::Operation,
+    output::OutputOptions,
+    watch,
+};
 
-/// Data structure that specifies the scope of the process
-#[derive(Debug)]
-pub enum Mode {
-    Single,
-    All,
+/// Data structure that specifies the output size of the given images
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    Small,  // size = 200px
+    Medium, // size = 400px
+    Large,  // size = 800px
+    /// Maximum width and height in pixels, u32::MAX leaves a side unbounded
+    Custom(u32, u32),
 }
 
-impl FromStr for Mode {
-    type Err = ImagixError;
+impl Size {
+    /// Builds the size from the --size preset or from the --width, --height and --max-dim flags, only one of them can be used
+    pub fn from_flags(
+        size: Option<Size>,
+        width: Option<u32>,
+        height: Option<u32>,
+        max_dim: Option<u32>,
+    ) -> Result<Size, ImagixError> {
+        if [width, height, max_dim].contains(&Some(0)) {
+            return Err(ImagixError::InvalidInput(
+                "Dimensions must be greater than 0".to_string(),
+            ));
+        }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "single" | "Single" => return Ok(Mode::Single),
-            "all" | "All"       => return Ok(Mode::All),
-            _ => return Err(ImagixError::FormatError("Invalid input".to_string()))
+        match (size, width, height, max_dim) {
+            (Some(size), None, None, None) => Ok(size),
+            (Some(_), ..) => Err(ImagixError::InvalidInput(
+                "--size cannot be combined with --width, --height or --max-dim".to_string(),
+            )),
+            (None, None, None, Some(max_dim)) => Ok(Size::Custom(max_dim, max_dim)),
+            (None, _, _, Some(_)) => Err(ImagixError::InvalidInput(
+                "--max-dim cannot be combined with --width or --height".to_string(),
+            )),
+            (None, None, None, None) => Err(ImagixError::InvalidInput(
+                "Specify --size or the --width, --height or --max-dim of the images".to_string(),
+            )),
+            (None, width, height, None) => Ok(Size::Custom(
+                width.unwrap_or(u32::MAX),
+                height.unwrap_or(u32::MAX),
+            )),
         }
-    }   
-}
+    }
 
-/// Data structure that specifies the output size of the given images
-#[derive(Debug)]
-pub enum SizeOption {
-    Small, // size = 200px
-    Medium, // size = 400px
-    Large, // size = 800px
+    /// Width and height the images are resized to fit in
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Size::Small => (200, 200),
+            Size::Medium => (400, 400),
+            Size::Large => (800, 800),
+            Size::Custom(width, height) => (*width, *height),
+        }
+    }
 }
 
-impl FromStr for SizeOption {
+impl FromStr for Size {
     type Err = ImagixError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "small" | "Small" => {
-                return Ok(SizeOption::Small);
-            },
-            "medium" | "Medium" => {
-                return Ok(SizeOption::Medium);
-            },
-            "large" | "Large" => {
-                return Ok(SizeOption::Large);
-            },
-            _ => return Err(ImagixError::FormatError("Invalid input".to_string()))
+            "small" | "Small" => Ok(Size::Small),
+            "medium" | "Medium" => Ok(Size::Medium),
+            "large" | "Large" => Ok(Size::Large),
+            _ => Err(ImagixError::InvalidInput(format!(
+                "Invalid size, expected small, medium or large: {}",
+                s
+            ))),
         }
-    }   
+    }
 }
 
-
-/// Public interface for interacting with the library
-pub fn process_resize_request(size: SizeOption, mode: Mode, src_folder: &mut PathBuf) -> Result<(), ImagixError> {
-    match mode {
-        Mode::Single => {
-            resize_single(src_folder, size)
-        },
-        Mode::All => {
-            resize_all(src_folder, size)
-        },
-    }
+/// Builder of the resize of an image or of the images of a folder. By default the images are shrunk to fit
+/// in the size, into a tmp folder next to them, and a folder has all its images resized but not the ones of its subfolders
+#[derive(Debug, Clone)]
+pub struct ResizeRequest {
+    src: PathBuf,
+    size: Size,
+    fit: Fit,
+    mode: Mode,
+    output: OutputOptions,
+    recursive: bool,
 }
 
-/// This functions wrap the functionality of resize image for a specified image
-fn resize_single(path: &mut PathBuf, size: SizeOption) -> Result<(), ImagixError> {
-    let size: u32 = match size {
-        SizeOption::Large => {
-            200
-        },
-        SizeOption::Medium => {
-            400
-        }
-        SizeOption::Small => {
-            800
+impl ResizeRequest {
+    pub fn new(src: impl Into<PathBuf>, size: Size) -> Self {
+        let src = src.into();
+        ResizeRequest {
+            mode: Mode::for_path(&src),
+            src,
+            size,
+            fit: Fit::default(),
+            output: OutputOptions::default(),
+            recursive: false,
         }
-    };
+    }
 
-    resize_image(size, path)
-}
+    pub fn with_fit(mut self, fit: Fit) -> Self {
+        self.fit = fit;
+        self
+    }
 
-/// This function wrap the functionality of resize image for a all folder
-fn resize_all(path: &mut PathBuf, size: SizeOption) -> Result<(), ImagixError>{
-    let size: u32 = match size {
-        SizeOption::Large => {
-            200
-        },
-        SizeOption::Medium => {
-            400
-        }
-        SizeOption::Small => {
-            800
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Where and how the resized images are written
+    pub fn with_output(mut self, output: OutputOptions) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// With `recursive` the images of the subfolders are resized too, in the same subfolders of the output folder
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    pub fn operation(&self) -> Operation {
+        Operation::Resize {
+            size: self.size.dimensions(),
+            fit: self.fit,
         }
-    };
+    }
 
-    if let Ok(mut entries) = get_images_files(path.clone()) {
-        for entry in &mut entries {
-            resize_image(size, entry)?
-        };
+    /// Resizes the images
+    pub fn run(&self) -> Result<BatchReport, ImagixError> {
+        batch::process_request(
+            self.operation(),
+            self.mode,
+            &self.src,
+            &self.output,
+            self.recursive,
+        )
+    }
 
-        Ok(())
-    } else {
-        Err(ImagixError::FileIOError("Unable to read images!".to_string()))
+    /// Destination and size of each image, without writing anything
+    pub fn plan(&self) -> Result<BatchPlan, ImagixError> {
+        batch::plan_request(
+            self.operation(),
+            self.mode,
+            &self.src,
+            &self.output,
+            self.recursive,
+        )
     }
-}
 
-/// This functions generetes the resize image and the necesary folder
-fn resize_image(size: u32, src_folder: &mut PathBuf) -> Result<(), ImagixError>{
-    // Cosntruct destination filename with .png extension
-    let new_file_name = src_folder
-        .file_stem()
-        .expect("We are working with only valid inputs")
-        .to_str().ok_or(std::io::ErrorKind::InvalidInput)
-        .map(|f| format!("{}.png", f));
-
-    // Construct path to destination folder i.e. create /tmp
-    // under source folder if not exists
-    let mut dest_folder = src_folder.clone();
-    dest_folder.pop();
-    dest_folder.push("tmp/");
-    if !dest_folder.exists() {
-        fs::create_dir(&dest_folder)?;
-    }
-    dest_folder.pop();
-    dest_folder.push("tmp/tmp.png");
-    dest_folder.set_file_name(new_file_name?.as_str());
-
-    //dbg!(&src_folder);
-    // Resize image and take some measuraments
-    let timer = Instant::now();
-    let img = image::open(&src_folder)?;
-    let scaled = img.thumbnail(size, size);
-    let mut output = fs::File::create(&dest_folder)?;
-    scaled.write_to(&mut output, ImageFormat::Png)?;
-    println!(
-        "Thumbnailed file: {:?} to size {}x{} in {}. Output file in {:?}",
-        src_folder,
-        size,
-        size,
-        Elapsed::from(&timer),
-        dest_folder
-    );
-    Ok(())
+    /// Resizes the images, then the new or changed ones as they appear, see `watch::watch`
+    pub fn watch(
+        &self,
+        on_report: impl FnMut(Result<BatchReport, ImagixError>) -> bool,
+    ) -> Result<(), ImagixError> {
+        watch::watch(
+            self.operation(),
+            self.mode,
+            &self.src,
+            &self.output,
+            self.recursive,
+            on_report,
+        )
+    }
 }
 
-/// This function retrieves the list of images files contained in a source folder
-pub fn get_images_files(src_folder: PathBuf) -> Result<Vec<PathBuf>, ImagixError> {
-
-    // 1. retrieve the directory entries in the source folder and collect tem in a vector
-    let entries: Vec<PathBuf> = fs::read_dir(src_folder)
-        .map_err(|e| ImagixError::from(e))?
-        .map(|res| res.map(|e| e.path()))
-        .collect::<Result<Vec<_>, io::Error>>()?
-        .into_iter()
-        .filter(|r| {
-            r.extension() == Some("JPG".as_ref())
-                || r.extension() == Some("jpg".as_ref())
-                || r.extension() == Some("PNG".as_ref())
-                || r.extension() == Some("png".as_ref())
-        })
-        .collect();
-    Ok(entries)
+/// Resizes an image or the images of a folder, a shorthand for a `ResizeRequest`
+pub fn process_resize_request(
+    size: Size,
+    fit: Fit,
+    mode: Mode,
+    src_folder: &Path,
+    output: &OutputOptions,
+    recursive: bool,
+) -> Result<BatchReport, ImagixError> {
+    ResizeRequest::new(src_folder, size)
+        .with_fit(fit)
+        .with_mode(mode)
+        .with_output(output.clone())
+        .with_recursive(recursive)
+        .run()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures;
 
     #[test]
     fn test_single_image_resize() {
-        let mut path = PathBuf::from("/tmp/images/image1.jpg");
+        let folder = fixtures::image_folder();
+        let path = folder.path().join("image1.jpg");
 
-        let destination_path = PathBuf::from("/tmp/images/tmp/image1.png");
+        let destination_path = folder.path().join("tmp/image1.png");
 
-        match process_resize_request(SizeOption::Small, Mode::Single, &mut path) {
+        match process_resize_request(
+            Size::Small,
+            Fit::Contain,
+            Mode::Single,
+            &path,
+            &OutputOptions::default(),
+            false,
+        ) {
             Ok(_) => println!("Successful resize of single image"),
             Err(e) => println!("Error in single image: {:?}", e),
         }
 
-        assert_eq!(true, destination_path.exists())
+        assert!(destination_path.exists())
     }
 
     #[test]
     fn test_multiple_image_resize() {
-        let mut path = PathBuf::from("/tmp/images/");
-        let _res = process_resize_request(SizeOption::Small, Mode::All, &mut path);
+        let folder = fixtures::image_folder();
+        let _res = process_resize_request(
+            Size::Small,
+            Fit::Contain,
+            Mode::All,
+            folder.path(),
+            &OutputOptions::default(),
+            false,
+        );
 
-        let destination_path1 = PathBuf::from("/tmp/images/tmp/image1.png");
-        let destination_path2 = PathBuf::from("/tmp/images/tmp/image2.png");
+        let destination_path1 = folder.path().join("tmp/image1.png");
+        let destination_path2 = folder.path().join("tmp/image2.png");
 
-        assert_eq!(true, destination_path1.exists());
-        assert_eq!(true, destination_path2.exists());
+        assert!(destination_path1.exists());
+        assert!(destination_path2.exists());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_resize_request_builder() {
+        let folder = fixtures::image_folder();
+        let request = ResizeRequest::new(folder.path(), Size::Custom(100, 100)).with_fit(Fit::Fill);
+
+        let plan = request.plan().unwrap();
+        assert_eq!(plan.planned.len(), 2);
+        assert!(!folder.path().join("tmp").exists());
+
+        let report = request.run().unwrap();
+        assert_eq!(report.processed.len(), 2);
+        assert!(
+            report
+                .processed
+                .iter()
+                .all(|processed| (processed.width, processed.height) == (100, 100))
+        );
+    }
+
+    #[test]
+    fn test_size_from_flags() {
+        assert_eq!(
+            Size::from_flags(Some(Size::Medium), None, None, None).unwrap(),
+            Size::Medium
+        );
+        assert_eq!(
+            Size::from_flags(None, Some(1024), Some(768), None).unwrap(),
+            Size::Custom(1024, 768)
+        );
+        assert_eq!(
+            Size::from_flags(None, Some(1024), None, None).unwrap(),
+            Size::Custom(1024, u32::MAX)
+        );
+        assert_eq!(
+            Size::from_flags(None, None, None, Some(1200)).unwrap(),
+            Size::Custom(1200, 1200)
+        );
+    }
+
+    #[test]
+    fn test_invalid_size_flags() {
+        assert!(Size::from_flags(None, None, None, None).is_err());
+        assert!(Size::from_flags(None, Some(0), None, None).is_err());
+        assert!(Size::from_flags(Some(Size::Small), Some(100), None, None).is_err());
+        assert!(Size::from_flags(None, Some(100), None, Some(200)).is_err());
+    }
+}
@@ -1,53 +1,243 @@
-use std::path::PathBuf;
+use image::ImageReader;
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use super::{error::ImagixError, resize::get_images_files};
+use super::{
+    batch::get_images_files,
+    error::{ImagixError, WithPath},
+    metadata,
+};
 
-use std::{fmt, convert::From, time};
+use std::{convert::From, fmt, time};
 
 /// Data structure that stores the duration of the resize process.
+#[derive(Debug)]
 pub struct Elapsed {
-    duration: u32,
+    duration: time::Duration,
+}
+
+impl Elapsed {
+    pub fn duration(&self) -> time::Duration {
+        self.duration
+    }
 }
 
 impl fmt::Display for Elapsed {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Elapsed {{ duration: {} }}", self.duration)
+        write!(f, "{:.2?}", self.duration)
     }
 }
 
 impl From<&time::Instant> for Elapsed {
     fn from(input: &time::Instant) -> Self {
         Elapsed {
-            duration: input.elapsed().as_secs() as u32,
+            duration: input.elapsed(),
+        }
+    }
+}
+
+/// Data structure that specifies how the stats are printed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StatsFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for StatsFormat {
+    type Err = ImagixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(StatsFormat::Text),
+            "json" => Ok(StatsFormat::Json),
+            _ => Err(ImagixError::InvalidInput(format!(
+                "Invalid stats format, expected text or json: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Data structure that describes an image of the analyzed folder
+#[derive(Debug, Serialize)]
+pub struct ImageStats {
+    pub path: PathBuf,
+    /// Width and height of the image once turned upright
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+    /// Size of the file in bytes
+    pub size: u64,
+}
+
+impl fmt::Display for ImageStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}: {}x{} {} {} KB",
+            self.path,
+            self.width,
+            self.height,
+            self.format,
+            self.size / 1000
+        )
+    }
+}
+
+/// Data structure that stores the stats of the images of a folder, the sizes are in bytes
+#[derive(Debug, Serialize)]
+pub struct FolderStats {
+    pub images: Vec<ImageStats>,
+    pub count: usize,
+    pub total_size: u64,
+    pub average_size: u64,
+    /// Path of the largest file
+    pub largest: Option<PathBuf>,
+    /// Files named like images that could not be read, they are left out of the stats
+    pub unreadable: Vec<PathBuf>,
+}
+
+impl fmt::Display for FolderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Found {:?} image files with aggreate size of {:?} KB",
+            self.count,
+            (self.total_size / 1000) as f64
+        )?;
+        if let Some(largest) = &self.largest {
+            write!(
+                f,
+                ", average size of {} KB, largest file {:?}",
+                self.average_size / 1000,
+                largest
+            )?;
         }
+        Ok(())
     }
 }
 
-/// Function that analyze the number of images and the total size of the given path.
-pub fn get_stats(src_folder: PathBuf) -> Result<(usize, f64), ImagixError>{
-    let image_files = get_images_files(src_folder.to_path_buf())?;
-    let size = image_files
-        .iter()
-        .map(move |f| f.metadata().unwrap().len())
-        .sum::<u64>();
-    Ok((image_files.len(), (size / 1000) as f64))
+impl FolderStats {
+    /// Stats printed in the given format
+    pub fn render(&self, format: StatsFormat) -> Result<String, ImagixError> {
+        match format {
+            StatsFormat::Text => {
+                let mut text: Vec<String> =
+                    self.images.iter().map(|image| image.to_string()).collect();
+                text.push(self.to_string());
+                Ok(text.join("\n"))
+            }
+            StatsFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+        }
+    }
+}
+
+/// Function that analyze the images of the given path: the dimensions, format and size of each one and their totals.
+pub fn get_stats(src_folder: PathBuf) -> Result<FolderStats, ImagixError> {
+    let mut image_files = get_images_files(src_folder.to_path_buf(), false)?;
+    image_files.sort();
+
+    let mut images = vec![];
+    let mut unreadable = vec![];
+    for file in image_files {
+        match image_stats(&file) {
+            Ok(image) => images.push(image),
+            Err(_) => unreadable.push(file),
+        }
+    }
+
+    let total_size = images.iter().map(|image| image.size).sum::<u64>();
+    Ok(FolderStats {
+        count: images.len(),
+        total_size,
+        average_size: total_size / images.len().max(1) as u64,
+        largest: images
+            .iter()
+            .max_by_key(|image| image.size)
+            .map(|image| image.path.clone()),
+        images,
+        unreadable,
+    })
+}
+
+/// Reads the header of an image, its format comes from its content and not from its extension
+fn image_stats(path: &Path) -> Result<ImageStats, ImagixError> {
+    let format = ImageReader::open(path)
+        .with_path(path)?
+        .with_guessed_format()
+        .with_path(path)?
+        .format();
+    let (width, height) = metadata::oriented_dimensions(path)?;
+
+    Ok(ImageStats {
+        path: path.to_path_buf(),
+        width,
+        height,
+        format: match format {
+            Some(format) => format!("{:?}", format).to_lowercase(),
+            None => path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+        },
+        size: path.metadata().with_path(path)?.len(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures;
 
     #[test]
     fn test_get_stats() {
-        let path = PathBuf::from("/tmp/images");
+        let folder = fixtures::image_folder();
 
-        match get_stats(path) {
-            Ok((size,num)) => {
-                println!("{} images t => {}KB", size, num);
+        match get_stats(folder.path().to_path_buf()) {
+            Ok(stats) => {
+                println!("{} images t => {}KB", stats.count, stats.total_size / 1000);
             }
             Err(_) => {
                 panic!("Error in test!!!");
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_stats_per_image() {
+        let dir = fixtures::empty_folder();
+        let folder = dir.path().to_path_buf();
+        fixtures::write_jpeg(&folder.join("image1.jpg"), 1200, 800);
+        // The format is read from the content, not from the extension
+        fixtures::write_jpeg(&folder.join("image2.png"), 600, 900);
+        std::fs::write(folder.join("broken.jpg"), b"not an image").unwrap();
+
+        let stats = get_stats(folder.clone()).unwrap();
+
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.unreadable, vec![folder.join("broken.jpg")]);
+        let image2 = stats
+            .images
+            .iter()
+            .find(|image| image.path == folder.join("image2.png"))
+            .unwrap();
+        assert_eq!(
+            (image2.width, image2.height, image2.format.as_str()),
+            (600, 900, "jpeg")
+        );
+        assert_eq!(
+            stats.total_size,
+            stats.images.iter().map(|image| image.size).sum::<u64>()
+        );
+        assert_eq!(stats.average_size, stats.total_size / 2);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&stats.render(StatsFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["count"], 2);
+        assert_eq!(json["images"].as_array().unwrap().len(), 2);
+    }
+}
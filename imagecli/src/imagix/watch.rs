@@ -0,0 +1,165 @@
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, SystemTime},
+};
+
+use super::{
+    batch::{self, BatchReport, Mode},
+    error::{ImagixError, WithPath},
+    format,
+    operation::Operation,
+    output::OutputOptions,
+};
+
+/// Time the events of a file are gathered before it is processed, so an image being copied is read once it is complete
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Processes the images of the request, then keeps watching the source for new or changed images and processes them as they appear.
+/// Each batch is given to `on_report`, which returns false to stop watching
+pub fn watch(
+    operation: Operation,
+    mode: Mode,
+    src_folder: &Path,
+    output: &OutputOptions,
+    recursive: bool,
+    mut on_report: impl FnMut(Result<BatchReport, ImagixError>) -> bool,
+) -> Result<(), ImagixError> {
+    // A single image is watched through its folder, as editors often replace a file instead of writing into it
+    let root = match mode {
+        Mode::Single => src_folder
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new(".")),
+        Mode::All => src_folder,
+    };
+    let root = fs::canonicalize(root).with_path(root)?;
+    let single = match mode {
+        Mode::Single => Some(fs::canonicalize(src_folder).with_path(src_folder)?),
+        Mode::All => None,
+    };
+
+    // The watcher is started before the first batch, so the images copied meanwhile are not missed
+    let (sender, receiver) = mpsc::channel();
+    let mut debouncer = new_debouncer(DEBOUNCE, sender).with_path(&root)?;
+    let recursive_mode = if recursive && single.is_none() {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    debouncer
+        .watcher()
+        .watch(&root, recursive_mode)
+        .with_path(&root)?;
+
+    // Modification time of the images read and written by the batches. Reading an image is an event too,
+    // and the images written can land in the watched folder, so only the files changed since are processed
+    let mut known = HashMap::new();
+
+    let initial = batch::process_request(operation, mode, src_folder, output, recursive);
+    if let Ok(report) = &initial {
+        remember(&mut known, report);
+    }
+    if !on_report(initial) {
+        return Ok(());
+    }
+
+    let excluded = batch::excluded_dir(&root, output);
+    for events in receiver {
+        let mut files: Vec<PathBuf> = events
+            .with_path(&root)?
+            .into_iter()
+            .filter(|event| event.path.is_file() && format::is_supported_input(&event.path))
+            .filter_map(|event| fs::canonicalize(&event.path).ok())
+            .filter(|path| match &single {
+                Some(single) => path == single,
+                None => recursive || path.parent() == Some(root.as_path()),
+            })
+            .filter(|path| {
+                excluded
+                    .as_ref()
+                    .is_none_or(|excluded| !path.starts_with(excluded))
+            })
+            .filter(|path| known.get(path) != modified(path).as_ref())
+            .collect();
+        files.sort();
+        files.dedup();
+        if files.is_empty() {
+            continue;
+        }
+
+        let report = batch::process_files(operation, files, &root, output);
+        remember(&mut known, &report);
+        if !on_report(Ok(report)) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Records the modification time of the images read and written by a batch, the ones that failed included
+fn remember(known: &mut HashMap<PathBuf, SystemTime>, report: &BatchReport) {
+    let paths = report
+        .processed
+        .iter()
+        .flat_map(|processed| [&processed.src, &processed.dest])
+        .chain(report.failed.iter().map(|(path, _)| path))
+        .chain(report.skipped.iter());
+
+    for path in paths {
+        if let Ok(path) = fs::canonicalize(path)
+            && let Some(time) = modified(&path)
+        {
+            known.insert(path, time);
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fit::Fit, fixtures, resize::Size};
+    use std::thread;
+
+    #[test]
+    fn test_watch_resizes_new_images() {
+        let dir = fixtures::empty_folder();
+        let folder = dir.path().to_path_buf();
+        fixtures::write_jpeg(&folder.join("image1.jpg"), 1200, 800);
+
+        let operation = Operation::Resize {
+            size: Size::Small.dimensions(),
+            fit: Fit::Contain,
+        };
+        let (sender, receiver) = mpsc::channel();
+        let watched = folder.clone();
+        thread::spawn(move || {
+            watch(
+                operation,
+                Mode::All,
+                &watched,
+                &OutputOptions::default(),
+                false,
+                |report| sender.send(report.unwrap().processed.len()).is_ok(),
+            )
+        });
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(10)).unwrap(), 1);
+        fixtures::write_jpeg(&folder.join("image2.jpg"), 600, 900);
+        fs::write(folder.join("notes.txt"), b"not an image").unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(10)).unwrap(), 1);
+        assert!(folder.join("tmp/image2.png").exists());
+        // The resized images written into the watched folder do not start another batch
+        assert!(receiver.recv_timeout(Duration::from_secs(2)).is_err());
+    }
+}
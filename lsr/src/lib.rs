@@ -0,0 +1,637 @@
+//! Rust version of `ls`, the `run` function is called by the `lsr` binary and by `ruty`
+
+use clap::{ArgAction, Parser, ValueEnum};
+
+#[derive(Debug, Parser)]
+#[command(about, version, disable_help_flag = true)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `ls`
+pub struct Cli {
+    #[arg(value_name("PATH"), default_value("."))]
+    /// Files and/or directories
+    paths: Vec<String>,
+
+    #[arg(short, long)]
+    /// Long listing
+    long: bool,
+
+    #[arg(short('a'), long("all"))]
+    /// Show all files
+    show_hidden: bool,
+
+    #[arg(long, value_name("WORD"), default_value("name"))]
+    /// Sort by WORD instead of name
+    sort: SortBy,
+
+    #[arg(short('t'), conflicts_with_all(["sort", "size"]))]
+    /// Sort by modification time, newest first
+    time: bool,
+
+    #[arg(short('S'), conflicts_with("sort"))]
+    /// Sort by file size, largest first
+    size: bool,
+
+    #[arg(short, long)]
+    /// Reverse the order of the sort
+    reverse: bool,
+
+    #[arg(short('L'), long)]
+    /// Show the files the symbolic links point to instead of the links
+    dereference: bool,
+
+    #[arg(short('h'), long)]
+    /// With --long, print the sizes like 1.2K, 3.4M or 2.1G
+    human_readable: bool,
+
+    #[arg(long, action = ArgAction::Help)]
+    /// Print help
+    help: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum SortBy {
+    Name,
+    Time,
+    Size,
+}
+
+impl Cli {
+    fn sort_by(&self) -> SortBy {
+        if self.time {
+            SortBy::Time
+        } else if self.size {
+            SortBy::Size
+        } else {
+            self.sort
+        }
+    }
+}
+
+mod helpers {
+    use std::{cmp::Ordering, fs, os::unix::fs::MetadataExt, path};
+
+    use tabular::{Row, Table};
+
+    use crate::SortBy;
+
+    /// Path found by `find_files` with its metadata, read once for sorting and for the long listing
+    #[derive(Debug)]
+    pub struct Entry {
+        pub path: path::PathBuf,
+        pub metadata: fs::Metadata,
+    }
+
+    impl Entry {
+        /// Entry of a path with the metadata of a link itself, like `find_files` without `dereference`
+        #[cfg(test)]
+        pub fn new(path: path::PathBuf) -> std::io::Result<Entry> {
+            let metadata = fs::symlink_metadata(&path)?;
+            Ok(Entry { path, metadata })
+        }
+    }
+
+    pub fn run(args: super::Cli) -> anyhow::Result<()> {
+        let mut entries = find_files(&args.paths, args.show_hidden, args.dereference)?;
+        sort_entries(&mut entries, args.sort_by(), args.reverse);
+
+        if args.long {
+            // Like `ls`, only the listings of directories have a total
+            if args.paths.iter().any(|path| path::Path::new(path).is_dir()) {
+                println!("total {}", format_total(&entries, args.human_readable));
+            }
+            print!("{}", format_output(&entries, args.human_readable)?);
+        } else {
+            entries
+                .iter()
+                .for_each(|entry| println!("{}", entry.path.display()));
+        }
+
+        Ok(())
+    }
+
+    /// Metadata of a link itself, or with `dereference` of the file it points to, broken links having no
+    /// file to describe keep their own
+    fn entry_metadata(path: &path::Path, dereference: bool) -> std::io::Result<fs::Metadata> {
+        if dereference {
+            fs::metadata(path).or_else(|_| fs::symlink_metadata(path))
+        } else {
+            fs::symlink_metadata(path)
+        }
+    }
+
+    /// Entries of the paths, the contents of the directories, links to directories included like `ls`
+    pub fn find_files(
+        paths: &[String],
+        show_hidden: bool,
+        dereference: bool,
+    ) -> anyhow::Result<Vec<Entry>> {
+        let mut ouput: Vec<Entry> = Vec::new();
+
+        for path in paths {
+            let path = std::path::Path::new(path);
+
+            let metadata = match entry_metadata(path, dereference) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    eprintln!("{}: {e}", path.display());
+                    continue;
+                }
+            };
+
+            if !path.is_dir() {
+                ouput.push(Entry {
+                    path: path::PathBuf::from(path),
+                    metadata,
+                });
+            } else {
+                fs::read_dir(path)?.for_each(|entry| match entry {
+                    Ok(direntry) => {
+                        let path = direntry.path();
+
+                        if !show_hidden
+                            && path
+                                .file_name()
+                                .and_then(|name| name.to_str())
+                                .is_none_or(|name| name.starts_with("."))
+                        {
+                            return;
+                        }
+
+                        match entry_metadata(&path, dereference) {
+                            Ok(metadata) => ouput.push(Entry { path, metadata }),
+                            Err(e) => eprintln!("{}: {e}", path.display()),
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{e}")
+                    }
+                });
+            }
+        }
+
+        Ok(ouput)
+    }
+
+    /// Sorts by name, or by modification time or size with the newest or largest first and ties
+    /// sorted by name, like `ls`
+    pub fn sort_entries(entries: &mut [Entry], sort_by: SortBy, reverse: bool) {
+        entries.sort_by(|a, b| {
+            let order = match sort_by {
+                SortBy::Name => Ordering::Equal,
+                SortBy::Time => modified(b).cmp(&modified(a)),
+                SortBy::Size => b.metadata.len().cmp(&a.metadata.len()),
+            };
+            let order = order.then_with(|| a.path.cmp(&b.path));
+
+            if reverse { order.reverse() } else { order }
+        });
+    }
+
+    fn modified(entry: &Entry) -> Option<std::time::SystemTime> {
+        entry.metadata.modified().ok()
+    }
+
+    /// Disk usage of the entries in 1K blocks, or with `human_readable` in bytes like the sizes
+    pub fn format_total(entries: &[Entry], human_readable: bool) -> String {
+        // The blocks of the metadata are always of 512 bytes
+        let blocks: u64 = entries.iter().map(|entry| entry.metadata.blocks()).sum();
+
+        if human_readable {
+            format_size(blocks * 512)
+        } else {
+            blocks.div_ceil(2).to_string()
+        }
+    }
+
+    /// Size in bytes below 1K, else in the largest unit of powers of 1024 with a decimal below 10,
+    /// rounded up like `ls -h`
+    pub fn format_size(len: u64) -> String {
+        let mut size = len as f64;
+
+        if len < 1024 {
+            return len.to_string();
+        }
+
+        for unit in ["K", "M", "G", "T", "P", "E"] {
+            size /= 1024.0;
+
+            let tenths = (size * 10.0).ceil();
+            if tenths < 100.0 {
+                return format!("{:.1}{unit}", tenths / 10.0);
+            }
+
+            // Rounding up to 1024 goes on to the next unit
+            if size.ceil() < 1024.0 {
+                return format!("{}{unit}", size.ceil());
+            }
+        }
+
+        len.to_string()
+    }
+
+    #[allow(dead_code)]
+    pub fn format_output(entries: &[Entry], human_readable: bool) -> anyhow::Result<String> {
+        //                       1   2     3     4     5     6     7     8
+        let fmt = "{:<}{:<}  {:>}  {:<}  {:<}  {:>}  {:<}  {:<}";
+        let mut table = Table::new(fmt);
+
+        for Entry { path, metadata } in entries {
+            let name = if metadata.is_symlink() {
+                match fs::read_link(path) {
+                    Ok(target) => format!("{} -> {}", path.display(), target.display()),
+                    Err(e) => {
+                        eprintln!("{}: {e}", path.display());
+                        path.display().to_string()
+                    }
+                }
+            } else {
+                path.display().to_string()
+            };
+
+            let user_name = match users::get_user_by_uid(metadata.uid()) {
+                Some(user) => {
+                    if let Some(user_name) = user.name().to_str() {
+                        String::from(user_name)
+                    } else {
+                        eprintln!("{}: Missing owner.", path.display());
+                        "????".to_string()
+                    }
+                }
+                None => {
+                    eprintln!("{}: Missing owner.", path.display());
+                    "????".to_string()
+                }
+            };
+
+            let group_name = match users::get_group_by_gid(metadata.gid()) {
+                Some(group) => {
+                    if let Some(group_name) = group.name().to_str() {
+                        String::from(group_name)
+                    } else {
+                        eprintln!("{}: Missing group.", path.display());
+                        "????".to_string()
+                    }
+                }
+                None => {
+                    eprintln!("{}: Missing group.", path.display());
+                    "????".to_string()
+                }
+            };
+
+            let size = if human_readable {
+                format_size(metadata.len())
+            } else {
+                metadata.len().to_string()
+            };
+
+            table.add_row(
+                Row::new()
+                    .with_cell(file_type(metadata)) // 1 "d", "l" or "-"
+                    .with_cell(format_mode(metadata.mode())) // 2 permissions
+                    .with_cell(metadata.nlink()) // 3 number of links
+                    .with_cell(user_name) // 4 user name
+                    .with_cell(group_name) // 5 group name
+                    .with_cell(size) // 6 size
+                    .with_cell(last_modified(metadata)) // 7 modifications
+                    .with_cell(name), // 8 path, and target of links
+            );
+        }
+
+        Ok(format!("{table}"))
+    }
+
+    fn file_type(metadata: &fs::Metadata) -> &'static str {
+        if metadata.is_dir() {
+            "d"
+        } else if metadata.is_symlink() {
+            "l"
+        } else {
+            "-"
+        }
+    }
+
+    #[allow(dead_code)]
+    fn last_modified(metadata: &fs::Metadata) -> String {
+        if let Ok(time) = metadata.modified() {
+            let (sec, nsec) = match time.duration_since(std::time::UNIX_EPOCH) {
+                Ok(duration) => (duration.as_secs() as i64, duration.subsec_nanos()),
+                Err(e) => {
+                    let dur = e.duration();
+                    let (sec, nsec) = (dur.as_secs() as i64, dur.subsec_nanos());
+
+                    if nsec == 0 {
+                        (-sec, 0)
+                    } else {
+                        (-sec - 1, 1_000_000_000 - nsec)
+                    }
+                }
+            };
+
+            let dt = chrono::DateTime::from_timestamp(sec, nsec).expect("Valid timespant");
+
+            format!("{}", dt.format("%B %e %R"))
+        } else {
+            String::from("Not supported for this platform")
+        }
+    }
+
+    pub fn format_mode(mode: u32) -> String {
+        let uread = if mode & 0o400 != 0 { "r" } else { "-" };
+        let uwrite = if mode & 0o200 != 0 { "w" } else { "-" };
+        let uexecute = if mode & 0o100 != 0 { "x" } else { "-" };
+
+        let gread = if mode & 0o040 != 0 { "r" } else { "-" };
+        let gwrite = if mode & 0o020 != 0 { "w" } else { "-" };
+        let gexecute = if mode & 0o010 != 0 { "x" } else { "-" };
+
+        let oread = if mode & 0o004 != 0 { "r" } else { "-" };
+        let owrite = if mode & 0o002 != 0 { "w" } else { "-" };
+        let oexecute = if mode & 0o001 != 0 { "x" } else { "-" };
+
+        format!("{uread}{uwrite}{uexecute}{gread}{gwrite}{gexecute}{oread}{owrite}{oexecute}")
+    }
+}
+
+pub use helpers::run;
+
+#[cfg(test)]
+mod test {
+    use crate::SortBy;
+    use crate::helpers::{
+        Entry, find_files, format_mode, format_output, format_size, format_total, sort_entries,
+    };
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_find_files() {
+        // Find all non-hidden entries in a directory
+        let res = find_files(&["tests/inputs".to_string()], false, false);
+        assert!(res.is_ok());
+        let mut filenames: Vec<_> = res
+            .unwrap()
+            .iter()
+            .map(|entry| entry.path.display().to_string())
+            .collect();
+        filenames.sort();
+        assert_eq!(
+            filenames,
+            [
+                "tests/inputs/bustle.txt",
+                "tests/inputs/dir",
+                "tests/inputs/empty.txt",
+                "tests/inputs/fox.txt",
+            ]
+        );
+
+        // Any existing file should be found even if hidden
+        let res = find_files(&["tests/inputs/.hidden".to_string()], false, false);
+        assert!(res.is_ok());
+        let filenames: Vec<_> = res
+            .unwrap()
+            .iter()
+            .map(|entry| entry.path.display().to_string())
+            .collect();
+        assert_eq!(filenames, ["tests/inputs/.hidden"]);
+
+        // Test multiple path arguments
+        let res = find_files(
+            &[
+                "tests/inputs/bustle.txt".to_string(),
+                "tests/inputs/dir".to_string(),
+            ],
+            false,
+            false,
+        );
+        assert!(res.is_ok());
+        let mut filenames: Vec<_> = res
+            .unwrap()
+            .iter()
+            .map(|entry| entry.path.display().to_string())
+            .collect();
+        filenames.sort();
+        assert_eq!(
+            filenames,
+            ["tests/inputs/bustle.txt", "tests/inputs/dir/spiders.txt"]
+        );
+    }
+
+    #[test]
+    fn test_find_files_hidden() {
+        // Find all entries in a directory including hidden
+        let res = find_files(&["tests/inputs".to_string()], true, false);
+        assert!(res.is_ok());
+        let mut filenames: Vec<_> = res
+            .unwrap()
+            .iter()
+            .map(|entry| entry.path.display().to_string())
+            .collect();
+        filenames.sort();
+        assert_eq!(
+            filenames,
+            [
+                "tests/inputs/.hidden",
+                "tests/inputs/bustle.txt",
+                "tests/inputs/dir",
+                "tests/inputs/empty.txt",
+                "tests/inputs/fox.txt",
+            ]
+        );
+    }
+
+    fn long_match(
+        line: &str,
+        expected_name: &str,
+        expected_perms: &str,
+        expected_size: Option<&str>,
+    ) {
+        let parts: Vec<_> = line.split_whitespace().collect();
+        assert!(!parts.is_empty() && parts.len() <= 10);
+
+        let perms = parts.first().unwrap();
+        assert_eq!(perms, &expected_perms);
+
+        if let Some(size) = expected_size {
+            let file_size = parts.get(4).unwrap();
+            assert_eq!(file_size, &size);
+        }
+
+        let display_name = parts.last().unwrap();
+        assert_eq!(display_name, &expected_name);
+    }
+
+    #[test]
+    fn test_format_output_one() {
+        let bustle_path = "tests/inputs/bustle.txt";
+        let bustle = Entry::new(PathBuf::from(bustle_path)).unwrap();
+
+        let res = format_output(&[bustle], false);
+        assert!(res.is_ok());
+
+        let out = res.unwrap();
+        let lines: Vec<&str> = out.split('\n').filter(|s| !s.is_empty()).collect();
+        assert_eq!(lines.len(), 1);
+
+        let line1 = lines.first().unwrap();
+        long_match(line1, bustle_path, "-rw-r--r--", Some("193"));
+    }
+
+    #[test]
+    fn test_format_output_two() {
+        let res = format_output(
+            &[
+                Entry::new(PathBuf::from("tests/inputs/dir")).unwrap(),
+                Entry::new(PathBuf::from("tests/inputs/empty.txt")).unwrap(),
+            ],
+            false,
+        );
+        assert!(res.is_ok());
+
+        let out = res.unwrap();
+        let mut lines: Vec<&str> = out.split('\n').filter(|s| !s.is_empty()).collect();
+        lines.sort();
+        assert_eq!(lines.len(), 2);
+
+        let empty_line = lines.remove(0);
+        long_match(
+            empty_line,
+            "tests/inputs/empty.txt",
+            "-rw-r--r--",
+            Some("0"),
+        );
+
+        let dir_line = lines.remove(0);
+        long_match(dir_line, "tests/inputs/dir", "drwxr-xr-x", None);
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let files = [
+            "tests/inputs/fox.txt",
+            "tests/inputs/empty.txt",
+            "tests/inputs/bustle.txt",
+        ];
+        let mut entries = find_files(&files.map(String::from), false, false).unwrap();
+        let names = |entries: &[Entry]| -> Vec<String> {
+            entries
+                .iter()
+                .map(|entry| entry.path.display().to_string())
+                .collect()
+        };
+
+        sort_entries(&mut entries, SortBy::Name, false);
+        assert_eq!(
+            names(&entries),
+            [
+                "tests/inputs/bustle.txt",
+                "tests/inputs/empty.txt",
+                "tests/inputs/fox.txt"
+            ]
+        );
+
+        sort_entries(&mut entries, SortBy::Name, true);
+        assert_eq!(
+            names(&entries),
+            [
+                "tests/inputs/fox.txt",
+                "tests/inputs/empty.txt",
+                "tests/inputs/bustle.txt"
+            ]
+        );
+
+        // Largest first
+        sort_entries(&mut entries, SortBy::Size, false);
+        assert_eq!(
+            names(&entries),
+            [
+                "tests/inputs/bustle.txt",
+                "tests/inputs/fox.txt",
+                "tests/inputs/empty.txt"
+            ]
+        );
+
+        sort_entries(&mut entries, SortBy::Size, true);
+        assert_eq!(
+            names(&entries),
+            [
+                "tests/inputs/empty.txt",
+                "tests/inputs/fox.txt",
+                "tests/inputs/bustle.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_files_links() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("file"), "contents").unwrap();
+        std::os::unix::fs::symlink("file", dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink("missing", dir.path().join("broken")).unwrap();
+        let path = dir.path().display().to_string();
+
+        let find = |dereference| {
+            let mut entries = find_files(std::slice::from_ref(&path), false, dereference).unwrap();
+            sort_entries(&mut entries, SortBy::Name, false);
+            entries
+        };
+
+        // The links themselves, broken ones included
+        let entries = find(false);
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].metadata.is_symlink());
+        assert!(entries[1].metadata.is_file());
+        assert!(entries[2].metadata.is_symlink());
+
+        let out = format_output(&entries, false).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with('l'));
+        assert!(lines[0].ends_with(&format!("{path}/broken -> missing")));
+        assert!(lines[2].starts_with('l'));
+        assert!(lines[2].ends_with(&format!("{path}/link -> file")));
+
+        // The files they point to with -L, broken links have none
+        let entries = find(true);
+        assert!(entries[0].metadata.is_symlink());
+        assert!(entries[2].metadata.is_file());
+        assert_eq!(entries[2].metadata.len(), 8);
+
+        let out = format_output(&entries, false).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[2].starts_with('-'));
+        assert!(lines[2].ends_with(&format!("{path}/link")));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0");
+        assert_eq!(format_size(1023), "1023");
+        assert_eq!(format_size(1024), "1.0K");
+        assert_eq!(format_size(1025), "1.1K");
+        assert_eq!(format_size(1536), "1.5K");
+        assert_eq!(format_size(10 * 1024), "10K");
+        assert_eq!(format_size(10 * 1024 + 1), "11K");
+        assert_eq!(format_size(1024 * 1024 - 1), "1.0M");
+        assert_eq!(format_size(3_565_158), "3.4M");
+        assert_eq!(format_size(3_565_159), "3.5M");
+        assert_eq!(format_size(2_254_857_830), "2.1G");
+        assert_eq!(format_size(u64::MAX), "16E");
+    }
+
+    #[test]
+    fn test_format_total() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("file"), vec![b'x'; 5000]).unwrap();
+        let entries = find_files(&[dir.path().display().to_string()], false, false).unwrap();
+
+        // The blocks depend on the file system, but cover at least the contents
+        let total: u64 = format_total(&entries, false).parse().unwrap();
+        assert!(total >= 5);
+        assert_eq!(format_total(&entries, true), format_size(total * 1024));
+    }
+
+    #[test]
+    fn test_format_mode() {
+        assert_eq!(format_mode(0o755), "rwxr-xr-x");
+        assert_eq!(format_mode(0o421), "r---w---x");
+    }
+}
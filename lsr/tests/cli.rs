@@ -175,7 +175,8 @@ fn dir2_all() -> Result<()> {
 fn dir_long(args: &[&str], expected: &[(&str, &str, &str)]) -> Result<()> {
     let cmd = Command::cargo_bin(PRG)?.args(args).assert().success();
     let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
-    let lines: Vec<&str> = stdout.split('\n').filter(|s| !s.is_empty()).collect();
+    let mut lines: Vec<&str> = stdout.split('\n').filter(|s| !s.is_empty()).collect();
+    assert!(lines.remove(0).starts_with("total "));
     assert_eq!(lines.len(), expected.len());
 
     let mut check = vec![];
@@ -243,3 +244,171 @@ fn dir2_long_all() -> Result<()> {
         ],
     )
 }
+
+// --------------------------------------------------
+fn run_order(args: &[&str], expected: &[&str]) -> Result<()> {
+    let cmd = Command::cargo_bin(PRG)?.args(args).assert().success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines, expected);
+    Ok(())
+}
+
+#[test]
+fn dir1_sorted_by_name() -> Result<()> {
+    run_order(
+        &["tests/inputs"],
+        &[
+            "tests/inputs/bustle.txt",
+            "tests/inputs/dir",
+            "tests/inputs/empty.txt",
+            "tests/inputs/fox.txt",
+        ],
+    )
+}
+
+#[test]
+fn files_sorted_by_size() -> Result<()> {
+    run_order(&["-S", FOX, EMPTY, BUSTLE], &[BUSTLE, FOX, EMPTY])
+}
+
+#[test]
+fn files_sorted_by_size_reversed() -> Result<()> {
+    run_order(
+        &["--sort", "size", "-r", FOX, EMPTY, BUSTLE],
+        &[EMPTY, FOX, BUSTLE],
+    )
+}
+
+#[test]
+fn files_sorted_by_name_reversed() -> Result<()> {
+    run_order(&["--reverse", FOX, EMPTY, BUSTLE], &[FOX, EMPTY, BUSTLE])
+}
+
+#[test]
+fn dir_sorted_by_time() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let now = std::time::SystemTime::now();
+
+    // Newest first, whatever the order of the names
+    let mut expected = Vec::new();
+    for (i, name) in ["b", "c", "a"].iter().enumerate() {
+        let path = dir.path().join(name);
+        let file = fs::File::create(&path)?;
+        file.set_modified(now - std::time::Duration::from_secs(60 * i as u64))?;
+        expected.push(path.display().to_string());
+    }
+    let dir = dir.path().display().to_string();
+    let expected: Vec<&str> = expected.iter().map(String::as_str).collect();
+
+    run_order(&["-t", &dir], &expected)?;
+    run_order(&["--sort", "time", &dir], &expected)?;
+
+    let reversed: Vec<&str> = expected.iter().rev().copied().collect();
+    run_order(&["-t", "-r", &dir], &reversed)
+}
+
+#[test]
+fn dies_bad_sort() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--sort", "color"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'color'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn links_long() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let link = dir.path().join("fox");
+    std::os::unix::fs::symlink(fs::canonicalize(FOX)?, &link)?;
+    let link = link.display().to_string();
+
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["-l", &link])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(stdout.starts_with("lrwxrwxrwx"));
+    assert!(
+        stdout
+            .trim_end()
+            .ends_with(&format!("{link} -> {}", fs::canonicalize(FOX)?.display()))
+    );
+
+    // The file it points to with -L
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["-lL", &link])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let parts: Vec<&str> = stdout.split_whitespace().collect();
+    assert_eq!(parts[0], "-rw-------");
+    assert_eq!(parts[4], "45");
+    assert_eq!(parts.last(), Some(&link.as_str()));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn broken_link() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let link = dir.path().join("broken");
+    std::os::unix::fs::symlink("missing", &link)?;
+    let link = link.display().to_string();
+
+    for args in [["-l", &link], ["-lL", &link]] {
+        let cmd = Command::cargo_bin(PRG)?
+            .args(args)
+            .assert()
+            .success()
+            .stderr("");
+        let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+        assert!(stdout.starts_with('l'));
+        assert!(stdout.trim_end().ends_with(&format!("{link} -> missing")));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn human_readable_long() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::File::create(dir.path().join("big"))?.set_len(5 * 1024 * 1024 + 1)?;
+    fs::File::create(dir.path().join("small"))?.set_len(1536)?;
+    fs::File::create(dir.path().join("tiny"))?.set_len(193)?;
+
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["-lh", &dir.path().display().to_string()])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let sizes: Vec<&str> = stdout
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().nth(4).unwrap())
+        .collect();
+    assert_eq!(sizes, ["5.1M", "1.5K", "193"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn total_only_for_dirs() -> Result<()> {
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["-l", BUSTLE])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(!stdout.contains("total"));
+
+    // The help is still printed by --help, -h being taken
+    Command::cargo_bin(PRG)?
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--human-readable"));
+    Ok(())
+}
@@ -0,0 +1,555 @@
+use crate::error::RefituiError;
+use crate::pagedfile::PagedFile;
+use crate::state::{self, FileState};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Files larger than this are read lazily from disk and opened read-only
+const LARGE_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Number of bytes shown on each line of the hex view
+const HEX_WIDTH: usize = 16;
+
+/// Number of bytes looked at to tell binary files from text files
+const BINARY_PROBE: usize = 8000;
+
+/// Maximum number of positions kept in the jump list
+const MAX_JUMPS: usize = 100;
+
+/// File name given on the command line to read the document from stdin
+pub const STDIN_NAME: &str = "-";
+
+/// Data strcuture that stores the document parsed in lines
+pub enum Doc {
+    /// Whole document loaded in memory, it can be edited
+    Memory(Vec<String>),
+    /// Large document read from disk as it is displayed
+    Paged(PagedFile),
+    /// Bytes of a binary file, shown as hex and ASCII
+    Hex(Vec<u8>),
+}
+
+/// Binary files have a NUL byte near their start, like git detects them
+fn is_binary(data: &[u8]) -> bool {
+    data.iter().take(BINARY_PROBE).any(|byte| *byte == 0)
+}
+
+/// Line number `line` of the hex view: offset, bytes in hex and printable ASCII characters
+fn hex_line(data: &[u8], line: usize) -> Option<String> {
+    let start = line * HEX_WIDTH;
+    let bytes = data.get(start..(start + HEX_WIDTH).min(data.len()))?;
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let hex: Vec<String> = (0..HEX_WIDTH)
+        .map(|i| {
+            bytes
+                .get(i)
+                .map_or("  ".to_string(), |byte| format!("{:02x}", byte))
+        })
+        .collect();
+    let ascii: String = bytes
+        .iter()
+        .map(|byte| {
+            if byte.is_ascii_graphic() || *byte == b' ' {
+                *byte as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+
+    Some(format!(
+        "{:08x}  {}  {}  |{}|",
+        start,
+        hex[..8].join(" "),
+        hex[8..].join(" "),
+        ascii
+    ))
+}
+
+impl Doc {
+    /// Number of lines of the document, for paged documents only the lines indexed so far
+    pub fn len(&self) -> usize {
+        match self {
+            Doc::Memory(lines) => lines.len(),
+            Doc::Paged(file) => file.len(),
+            Doc::Hex(data) => data.len().div_ceil(HEX_WIDTH),
+        }
+    }
+
+    /// Whether the number of lines returned by `len` is final
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Doc::Memory(_) | Doc::Hex(_) => true,
+            Doc::Paged(file) => file.is_complete(),
+        }
+    }
+
+    /// Makes sure at least `lines` lines are known when the document has that many
+    pub fn ensure_indexed(&self, lines: usize) {
+        if let Doc::Paged(file) = self {
+            file.ensure_indexed(lines);
+        }
+    }
+
+    /// Indexes the whole document so its length is known
+    pub fn index_all(&self) {
+        if let Doc::Paged(file) = self {
+            file.index_all();
+        }
+    }
+
+    /// Returns the line number `line` (starting at 0)
+    pub fn line(&self, line: usize) -> Option<String> {
+        match self {
+            Doc::Memory(lines) => lines.get(line).cloned(),
+            Doc::Paged(file) => file.line(line),
+            Doc::Hex(data) => hex_line(data, line),
+        }
+    }
+
+    /// Calls `f` with the number and the text of every line of the document
+    pub fn for_each_line(&self, mut f: impl FnMut(usize, &str)) {
+        match self {
+            Doc::Memory(lines) => lines
+                .iter()
+                .enumerate()
+                .for_each(|(number, line)| f(number, line)),
+            Doc::Paged(file) => file.for_each_line(f),
+            Doc::Hex(data) => (0..self.len())
+                .for_each(|number| f(number, &hex_line(data, number).unwrap_or_default())),
+        }
+    }
+
+    /// Lines of the document when it can be edited
+    fn lines_mut(&mut self) -> Option<&mut Vec<String>> {
+        match self {
+            Doc::Memory(lines) => Some(lines),
+            Doc::Paged(_) | Doc::Hex(_) => None,
+        }
+    }
+}
+
+/// Data structure that stores the curso position and to record the current size of the terminal
+#[derive(Debug)]
+pub struct Coordinates {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Data structure that stores the state of the incremental search
+#[derive(Debug, Default)]
+pub struct Search {
+    /// Pattern typed after `/`
+    pub pattern: String,
+    /// Line and column (0 based) of every match of the pattern in the document
+    pub matches: Vec<(usize, usize)>,
+    /// Index of the match the cursor is placed on
+    pub current: Option<usize>,
+    /// Cursor position before the search started, restored when the search is cancelled
+    pub origin: (usize, usize),
+}
+
+/// An open file together with its own cursor, scroll position and search
+pub struct Buffer {
+    pub doc: Doc,
+    /// Cursor position in the document, column and line starting at 1
+    pub cur_pos: Coordinates,
+    /// Index of the first document line shown on the screen
+    pub offset: usize,
+    /// Index of the first column shown on the screen when lines are not wrapped
+    pub col_offset: usize,
+    pub file_name: String,
+    pub search: Search,
+    /// The document has changes that are not saved yet
+    pub dirty: bool,
+    /// The document cannot be edited nor saved
+    pub read_only: bool,
+    /// Size of the file when it was last read, `None` when the document does not come from a file
+    pub file_len: Option<u64>,
+    /// Line and column (starting at 1) of the marks set with `m`
+    pub marks: BTreeMap<char, (usize, usize)>,
+    /// Line and column (starting at 1) of the positions the cursor jumped from, oldest first
+    pub jumps: Vec<(usize, usize)>,
+    /// Position in the jump list while moving through it, the length of the list otherwise
+    pub jump_index: usize,
+}
+
+impl Buffer {
+    /// Loads the file into a new buffer with the cursor at the start of the document.
+    /// Large files are not loaded, their lines are read from disk when they are displayed
+    pub fn open(file: &str) -> Result<Self, RefituiError> {
+        let error = |e| RefituiError::File(file.to_string(), e);
+
+        let size = fs::metadata(file).map_err(error)?.len();
+
+        let mut buffer = if size > LARGE_FILE_SIZE {
+            Self::new(
+                Doc::Paged(PagedFile::open(file).map_err(error)?),
+                file,
+                true,
+            )
+        } else {
+            Self::new(Self::parse(&fs::read(file).map_err(error)?), file, false)
+        };
+        buffer.file_len = Some(size);
+        buffer.restore(state::load(file));
+
+        Ok(buffer)
+    }
+
+    /// Loads the data piped to the process into a new read-only buffer
+    pub fn from_stdin() -> Result<Self, RefituiError> {
+        let mut data = vec![];
+        io::stdin()
+            .read_to_end(&mut data)
+            .map_err(RefituiError::Stdin)?;
+
+        Ok(Self::new(Self::parse(&data), "[stdin]", true))
+    }
+
+    /// Splits the contents of a file in lines, invalid UTF-8 sequences are replaced instead of failing.
+    /// Binary files are shown in the hex view
+    fn parse(data: &[u8]) -> Doc {
+        if is_binary(data) {
+            return Doc::Hex(data.to_vec());
+        }
+
+        Self::parse_text(data)
+    }
+
+    fn parse_text(data: &[u8]) -> Doc {
+        let file_handle = String::from_utf8_lossy(data);
+
+        // Read each line from the file and store it in ht Doc buffer
+        Doc::Memory(
+            file_handle
+                .lines()
+                .map(|doc_line| doc_line.to_string())
+                .collect(),
+        )
+    }
+
+    /// Read-only buffer holding the lines given, not backed by a file
+    pub fn from_lines(lines: Vec<String>, file_name: &str) -> Self {
+        Self::new(Doc::Memory(lines), file_name, true)
+    }
+
+    fn new(doc: Doc, file_name: &str, read_only: bool) -> Self {
+        Self {
+            doc,
+            cur_pos: Coordinates { x: 1, y: 1 },
+            offset: 0,
+            col_offset: 0,
+            file_name: file_name.into(),
+            search: Search::default(),
+            dirty: false,
+            read_only,
+            file_len: None,
+            marks: BTreeMap::new(),
+            jumps: vec![],
+            jump_index: 0,
+        }
+    }
+
+    /// Number of lines of the document known so far
+    pub fn doc_length(&self) -> usize {
+        self.doc.len()
+    }
+
+    /// The document cannot be edited, the hex view is always read-only
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || matches!(self.doc, Doc::Hex(_))
+    }
+
+    pub fn is_hex(&self) -> bool {
+        matches!(self.doc, Doc::Hex(_))
+    }
+
+    /// Switches between the hex view and the text view of the document
+    pub fn toggle_hex(&mut self) -> Result<(), String> {
+        if self.dirty {
+            return Err("Save the changes before switching to the hex view".to_string());
+        }
+
+        self.doc = match &self.doc {
+            Doc::Hex(data) => Self::parse_text(data),
+            Doc::Paged(_) => {
+                return Err("The hex view is not available for large files".to_string())
+            }
+            // Files are read again since the lines do not keep the original bytes
+            Doc::Memory(lines) => match self.file_len {
+                Some(_) => Doc::Hex(
+                    fs::read(&self.file_name).map_err(|e| format!("{}: {}", self.file_name, e))?,
+                ),
+                None => Doc::Hex(lines.join("\n").into_bytes()),
+            },
+        };
+
+        self.cur_pos = Coordinates { x: 1, y: 1 };
+        self.offset = 0;
+        self.col_offset = 0;
+        self.find_matches();
+
+        Ok(())
+    }
+
+    /// Number of characters of the line number `line` of the document
+    pub fn line_len(&self, line: usize) -> usize {
+        self.doc.line(line).map_or(0, |text| text.chars().count())
+    }
+
+    /// Keeps the cursor column within the current line, one past its end at most
+    pub fn clamp_x(&mut self) {
+        let max = self.line_len(self.cur_pos.y.saturating_sub(1)) + 1;
+        self.cur_pos.x = self.cur_pos.x.min(max);
+    }
+
+    /// Byte index in `text` of the character at column `x` (starting at 1)
+    fn byte_index(text: &str, x: usize) -> usize {
+        text.char_indices()
+            .nth(x - 1)
+            .map_or(text.len(), |(i, _)| i)
+    }
+
+    /// Called after every change of the document
+    fn modified(&mut self) {
+        self.dirty = true;
+        self.find_matches();
+    }
+
+    /// Makes sure there is a line to edit when the document is empty
+    pub fn ensure_line(&mut self) {
+        if let Some(lines) = self.doc.lines_mut() {
+            if lines.is_empty() {
+                lines.push(String::new());
+            }
+        }
+    }
+
+    /// Inserts a character at the cursor position
+    pub fn insert_char(&mut self, c: char) {
+        let Coordinates { x, y } = self.cur_pos;
+        let Some(lines) = self.doc.lines_mut() else {
+            return;
+        };
+
+        let index = Self::byte_index(&lines[y - 1], x);
+        lines[y - 1].insert(index, c);
+        self.cur_pos.x += 1;
+        self.modified();
+    }
+
+    /// Splits the current line at the cursor position, the cursor moves to the start of the new line
+    pub fn split_line(&mut self) {
+        let Coordinates { x, y } = self.cur_pos;
+        let Some(lines) = self.doc.lines_mut() else {
+            return;
+        };
+
+        let index = Self::byte_index(&lines[y - 1], x);
+        let rest = lines[y - 1].split_off(index);
+        lines.insert(y, rest);
+        self.modified();
+        self.cur_pos = Coordinates { x: 1, y: y + 1 };
+    }
+
+    /// Deletes the character before the cursor, at the start of a line it is joined with the previous one
+    pub fn delete_backward(&mut self) {
+        let Coordinates { x, y } = self.cur_pos;
+        let Some(lines) = self.doc.lines_mut() else {
+            return;
+        };
+        let line = y - 1;
+
+        if x > 1 {
+            let index = Self::byte_index(&lines[line], x - 1);
+            lines[line].remove(index);
+            self.cur_pos.x -= 1;
+            self.modified();
+        } else if line > 0 {
+            let text = lines.remove(line);
+            let x = lines[line - 1].chars().count() + 1;
+            lines[line - 1].push_str(&text);
+            self.modified();
+            self.cur_pos = Coordinates { x, y: line };
+        }
+    }
+
+    /// Deletes the character under the cursor, at the end of a line the next one is joined to it
+    pub fn delete_forward(&mut self) {
+        let Coordinates { x, y } = self.cur_pos;
+        let Some(lines) = self.doc.lines_mut() else {
+            return;
+        };
+        let line = y - 1;
+
+        if x <= lines[line].chars().count() {
+            let index = Self::byte_index(&lines[line], x);
+            lines[line].remove(index);
+            self.modified();
+        } else if line + 1 < lines.len() {
+            let text = lines.remove(line + 1);
+            lines[line].push_str(&text);
+            self.modified();
+        }
+    }
+
+    /// Reads the data appended to the file since it was last read, returns whether the document changed.
+    /// A file that shrank is loaded again, documents with unsaved changes are left untouched
+    pub fn read_appended(&mut self) -> bool {
+        let (Some(file_len), Ok(metadata)) = (self.file_len, fs::metadata(&self.file_name)) else {
+            return false;
+        };
+        let len = metadata.len();
+        if len == file_len || self.dirty {
+            return false;
+        }
+
+        match &mut self.doc {
+            Doc::Paged(file) if len > file_len => file.refresh(),
+            Doc::Paged(file) => match PagedFile::open(&self.file_name) {
+                Ok(reopened) => *file = reopened,
+                Err(_) => return false,
+            },
+            Doc::Memory(lines) if len > file_len => {
+                // Read from the last known byte to know whether the last line was complete
+                let skip = file_len.min(1);
+                let mut data = vec![];
+                let read = File::open(&self.file_name).and_then(|mut file| {
+                    file.seek(SeekFrom::Start(file_len - skip))?;
+                    file.read_to_end(&mut data)
+                });
+                if read.is_err() || data.is_empty() {
+                    return false;
+                }
+
+                let continues = skip == 1 && data[0] != b'\n';
+                let text = String::from_utf8_lossy(&data[skip as usize..]).into_owned();
+                let mut new_lines = text.lines();
+
+                if continues {
+                    match (lines.last_mut(), new_lines.next()) {
+                        (Some(last), Some(rest)) => last.push_str(rest),
+                        (None, Some(rest)) => lines.push(rest.to_string()),
+                        _ => {}
+                    }
+                }
+                lines.extend(new_lines.map(|line| line.to_string()));
+            }
+            Doc::Memory(_) => match fs::read(&self.file_name) {
+                Ok(data) => self.doc = Self::parse(&data),
+                Err(_) => return false,
+            },
+            Doc::Hex(data) => match fs::read(&self.file_name) {
+                Ok(read) => *data = read,
+                Err(_) => return false,
+            },
+        }
+
+        self.file_len = Some(len);
+        self.find_matches();
+
+        true
+    }
+
+    /// Marks and jump list to save when the viewer exits
+    pub fn file_state(&self) -> FileState {
+        FileState {
+            marks: self
+                .marks
+                .iter()
+                .map(|(name, pos)| (name.to_string(), *pos))
+                .collect(),
+            jumps: self.jumps.clone(),
+        }
+    }
+
+    /// Restores the marks and the jump list saved by a previous session
+    fn restore(&mut self, saved: FileState) {
+        self.marks = saved
+            .marks
+            .into_iter()
+            .filter_map(|(name, pos)| name.chars().next().map(|name| (name, pos)))
+            .collect();
+        self.jumps = saved.jumps;
+        self.jump_index = self.jumps.len();
+    }
+
+    /// Adds the line and column `pos` to the jump list before jumping somewhere else.
+    /// The positions visited with `jump_back` that come after the current one are forgotten
+    pub fn record_jump(&mut self, pos: (usize, usize)) {
+        self.jumps.truncate(self.jump_index);
+        self.jumps.retain(|jump| jump.0 != pos.0);
+        self.jumps.push(pos);
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.jump_index = self.jumps.len();
+    }
+
+    /// Previous position of the jump list, the cursor position is kept so `jump_forward` can come back to it
+    pub fn jump_back(&mut self) -> Option<(usize, usize)> {
+        if self.jump_index == 0 {
+            return None;
+        }
+
+        if self.jump_index == self.jumps.len() {
+            self.record_jump((self.cur_pos.y, self.cur_pos.x));
+            self.jump_index = self.jumps.len() - 1;
+            if self.jump_index == 0 {
+                return None;
+            }
+        }
+
+        self.jump_index -= 1;
+        Some(self.jumps[self.jump_index])
+    }
+
+    /// Next position of the jump list after going back with `jump_back`
+    pub fn jump_forward(&mut self) -> Option<(usize, usize)> {
+        if self.jump_index + 1 >= self.jumps.len() {
+            return None;
+        }
+
+        self.jump_index += 1;
+        Some(self.jumps[self.jump_index])
+    }
+
+    /// Writes the document back to its file
+    pub fn save(&mut self) -> Result<(), String> {
+        let lines = match self.doc.lines_mut() {
+            Some(lines) if !self.read_only => lines,
+            _ => return Err(format!("{} is opened read-only", self.file_name)),
+        };
+        let mut content = lines.join("\n");
+        content.push('\n');
+
+        fs::write(&self.file_name, &content)
+            .map_err(|e| format!("Cannot save {}: {}", self.file_name, e))?;
+        self.dirty = false;
+        self.file_len = Some(content.len() as u64);
+
+        Ok(())
+    }
+
+    /// Looks for the search pattern in every line of the document
+    pub fn find_matches(&mut self) {
+        self.search.matches.clear();
+        self.search.current = None;
+
+        if !self.search.pattern.is_empty() {
+            let pattern = &self.search.pattern;
+            let matches = &mut self.search.matches;
+
+            self.doc.for_each_line(|number, line| {
+                for (byte, _) in line.match_indices(pattern.as_str()) {
+                    matches.push((number, line[..byte].chars().count()));
+                }
+            });
+        }
+    }
+}
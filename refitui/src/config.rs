@@ -0,0 +1,478 @@
+use crate::terminal::Key;
+use crate::textviewer::LineNumbers;
+use crossterm::style::Color;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::{env, fs, io, path::PathBuf};
+
+/// Actions that can be bound to a key in view mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    Save,
+    Insert,
+    NextBuffer,
+    PreviousBuffer,
+    ToggleWrap,
+    ToggleLineNumbers,
+    Command,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    PageUp,
+    PageDown,
+    Start,
+    End,
+    Follow,
+    ToggleHex,
+    SetMark,
+    GotoMark,
+    JumpBack,
+    JumpForward,
+    ToggleSplit,
+    SwitchPane,
+    NextHunk,
+    PreviousHunk,
+    Help,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Keys bound to each action when the config file does not say otherwise
+const DEFAULT_KEYS: [(Action, &[&str]); 30] = [
+    (Action::Quit, &["ctrl-q"]),
+    (Action::Save, &["ctrl-s"]),
+    (Action::Insert, &["i"]),
+    (Action::NextBuffer, &["tab"]),
+    (Action::PreviousBuffer, &["backtab"]),
+    (Action::ToggleWrap, &["w"]),
+    (Action::ToggleLineNumbers, &["l"]),
+    (Action::Command, &[":"]),
+    (Action::Search, &["/"]),
+    (Action::NextMatch, &["n"]),
+    (Action::PreviousMatch, &["N"]),
+    (Action::PageUp, &["pageup"]),
+    (Action::PageDown, &["pagedown"]),
+    (Action::Start, &["home", "g"]),
+    (Action::End, &["end", "G"]),
+    (Action::Follow, &["F"]),
+    (Action::ToggleHex, &["x"]),
+    (Action::SetMark, &["m"]),
+    (Action::GotoMark, &["'"]),
+    (Action::JumpBack, &["ctrl-o"]),
+    (Action::JumpForward, &["ctrl-i"]),
+    (Action::ToggleSplit, &["s"]),
+    (Action::SwitchPane, &["ctrl-w"]),
+    (Action::NextHunk, &["]"]),
+    (Action::PreviousHunk, &["["]),
+    (Action::Help, &["?", "f1"]),
+    (Action::Left, &["left", "backspace"]),
+    (Action::Right, &["right"]),
+    (Action::Up, &["up"]),
+    (Action::Down, &["down"]),
+];
+
+impl Action {
+    /// Every action, in the order they are listed in the help
+    pub fn all() -> impl Iterator<Item = Action> {
+        DEFAULT_KEYS.iter().map(|(action, _)| *action)
+    }
+
+    /// What the action does, shown in the help
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit, twice to discard unsaved changes",
+            Action::Save => "Save the file",
+            Action::Insert => "Edit the file, Esc goes back to view mode",
+            Action::NextBuffer => "Show the next open file",
+            Action::PreviousBuffer => "Show the previous open file",
+            Action::ToggleWrap => "Wrap long lines",
+            Action::ToggleLineNumbers => {
+                "Cycle the line numbers between off, absolute and relative"
+            }
+            Action::Command => "Open the command prompt",
+            Action::Search => "Search a pattern",
+            Action::NextMatch => "Jump to the next match",
+            Action::PreviousMatch => "Jump to the previous match",
+            Action::PageUp => "Move a screen up",
+            Action::PageDown => "Move a screen down",
+            Action::Start => "Jump to the start of the file",
+            Action::End => "Jump to the end of the file",
+            Action::Follow => "Follow the file as it grows",
+            Action::ToggleHex => "Switch between the hex and the text view",
+            Action::SetMark => "Set a mark, followed by its letter",
+            Action::GotoMark => "Jump to a mark, followed by its letter",
+            Action::JumpBack => "Go back to the position before the last jump",
+            Action::JumpForward => "Undo a jump back",
+            Action::ToggleSplit => "Show two files side by side",
+            Action::SwitchPane => "Move to the other pane",
+            Action::NextHunk => "Jump to the next difference",
+            Action::PreviousHunk => "Jump to the previous difference",
+            Action::Help => "Show this help",
+            Action::Left => "Move left",
+            Action::Right => "Move right",
+            Action::Up => "Move up",
+            Action::Down => "Move down",
+        }
+    }
+}
+
+/// Colors used to draw the screen
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub header_fg: Color,
+    pub header_bg: Color,
+    pub status_fg: Color,
+    pub status_bg: Color,
+    /// Messages shown in the footer
+    pub message: Color,
+    /// Line numbers gutter
+    pub gutter: Color,
+    pub match_fg: Color,
+    pub match_bg: Color,
+    /// Backgrounds of the lines only in the left file, only in the right file and changed when comparing files
+    pub diff_removed: Color,
+    pub diff_added: Color,
+    pub diff_changed: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            header_fg: Color::AnsiValue(0),
+            header_bg: Color::AnsiValue(7),
+            status_fg: Color::AnsiValue(0),
+            status_bg: Color::AnsiValue(7),
+            message: Color::AnsiValue(1),
+            gutter: Color::AnsiValue(8),
+            match_fg: Color::AnsiValue(0),
+            match_bg: Color::AnsiValue(3),
+            diff_removed: Color::AnsiValue(52),
+            diff_added: Color::AnsiValue(22),
+            diff_changed: Color::AnsiValue(17),
+        }
+    }
+}
+
+/// Settings of the text viewer
+pub struct Config {
+    /// Number of columns a tab is displayed with
+    pub tab_width: usize,
+    pub line_numbers: LineNumbers,
+    pub wrap: bool,
+    /// The wheel and the clicks are handled by the viewer instead of the terminal
+    pub mouse: bool,
+    /// Action triggered by each key in view mode
+    pub keys: HashMap<Key, Action>,
+    pub theme: Theme,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let keys = DEFAULT_KEYS
+            .iter()
+            .flat_map(|(action, keys)| {
+                keys.iter()
+                    .map(move |key| (parse_key(key).unwrap(), *action))
+            })
+            .collect();
+
+        Self {
+            tab_width: 4,
+            line_numbers: LineNumbers::Off,
+            wrap: false,
+            mouse: true,
+            keys,
+            theme: Theme::default(),
+        }
+    }
+}
+
+/// One key or a list of keys bound to an action
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Contents of the config file, every setting is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    tab_width: Option<usize>,
+    line_numbers: Option<LineNumbers>,
+    wrap: Option<bool>,
+    mouse: Option<bool>,
+    keys: HashMap<Action, Keys>,
+    colors: ColorsFile,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ColorsFile {
+    header_fg: Option<String>,
+    header_bg: Option<String>,
+    status_fg: Option<String>,
+    status_bg: Option<String>,
+    message: Option<String>,
+    gutter: Option<String>,
+    match_fg: Option<String>,
+    match_bg: Option<String>,
+    diff_removed: Option<String>,
+    diff_added: Option<String>,
+    diff_changed: Option<String>,
+}
+
+/// Parses a key name like `ctrl-q`, `alt-x`, `pageup` or a single character
+fn parse_key(name: &str) -> Result<Key, String> {
+    let single = |text: &str| {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+
+    let key = match name.to_lowercase().as_str() {
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "tab" => Key::Char('\t'),
+        "backtab" => Key::BackTab,
+        "enter" => Key::Char('\n'),
+        "space" => Key::Char(' '),
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "insert" => Key::Insert,
+        "esc" => Key::Esc,
+        lower => {
+            if let Some(c) = lower.strip_prefix("ctrl-").and_then(single) {
+                Key::Ctrl(c)
+            } else if let Some(c) = name
+                .get(4..)
+                .filter(|_| lower.starts_with("alt-"))
+                .and_then(single)
+            {
+                Key::Alt(c)
+            } else if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Key::F(n)
+            } else if let Some(c) = single(name) {
+                Key::Char(c)
+            } else {
+                return Err(format!("Unknown key: {}", name));
+            }
+        }
+    };
+
+    Ok(key)
+}
+
+/// Name of a key as written in the config file, the reverse of `parse_key`
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char('\t') => "tab".to_string(),
+        Key::Char('\n') => "enter".to_string(),
+        Key::Char(' ') => "space".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("ctrl-{}", c),
+        Key::Alt(c) => format!("alt-{}", c),
+        Key::F(n) => format!("f{}", n),
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::PageUp => "pageup".to_string(),
+        Key::PageDown => "pagedown".to_string(),
+        Key::Home => "home".to_string(),
+        Key::End => "end".to_string(),
+        Key::BackTab => "backtab".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Delete => "delete".to_string(),
+        Key::Insert => "insert".to_string(),
+        Key::Esc => "esc".to_string(),
+    }
+}
+
+/// Parses a color name (`red`, `lightblue`...) or an ANSI color number from 0 to 255
+fn parse_color(name: &str) -> Result<Color, String> {
+    const NAMES: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    ];
+
+    let lower = name.to_lowercase();
+    if let Ok(value) = lower.parse::<u8>() {
+        return Ok(Color::AnsiValue(value));
+    }
+
+    let (light, base) = match lower.strip_prefix("light") {
+        Some(base) => (8, base),
+        None => (0, lower.as_str()),
+    };
+
+    NAMES
+        .iter()
+        .position(|color| *color == base)
+        .map(|i| Color::AnsiValue(light + i as u8))
+        .ok_or_else(|| format!("Unknown color: {}", name))
+}
+
+impl Config {
+    /// Path of the config file, inside `$XDG_CONFIG_HOME` or `~/.config`
+    fn path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        Some(base.join("refitui").join("config.toml"))
+    }
+
+    /// Reads the config file, the defaults are used when it does not exist
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// Builds the config from the contents of a config file on top of the defaults
+    fn parse(text: &str) -> Result<Self, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| e.to_string())?;
+        let mut config = Self::default();
+
+        if let Some(tab_width) = file.tab_width {
+            config.tab_width = tab_width.max(1);
+        }
+        if let Some(line_numbers) = file.line_numbers {
+            config.line_numbers = line_numbers;
+        }
+        if let Some(wrap) = file.wrap {
+            config.wrap = wrap;
+        }
+        if let Some(mouse) = file.mouse {
+            config.mouse = mouse;
+        }
+
+        // The keys given for an action replace its default keys
+        for (action, keys) in file.keys {
+            let keys = match keys {
+                Keys::One(key) => vec![key],
+                Keys::Many(keys) => keys,
+            };
+
+            config.keys.retain(|_, bound| *bound != action);
+            for key in keys {
+                config.keys.insert(parse_key(&key)?, action);
+            }
+        }
+
+        let colors = file.colors;
+        let theme = &mut config.theme;
+        for (value, color) in [
+            (&colors.header_fg, &mut theme.header_fg),
+            (&colors.header_bg, &mut theme.header_bg),
+            (&colors.status_fg, &mut theme.status_fg),
+            (&colors.status_bg, &mut theme.status_bg),
+            (&colors.message, &mut theme.message),
+            (&colors.gutter, &mut theme.gutter),
+            (&colors.match_fg, &mut theme.match_fg),
+            (&colors.match_bg, &mut theme.match_bg),
+            (&colors.diff_removed, &mut theme.diff_removed),
+            (&colors.diff_added, &mut theme.diff_added),
+            (&colors.diff_changed, &mut theme.diff_changed),
+        ] {
+            if let Some(value) = value {
+                *color = parse_color(value)?;
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_keys_parse() {
+        for (action, keys) in DEFAULT_KEYS {
+            for key in keys {
+                let parsed = parse_key(key).unwrap_or_else(|e| panic!("{:?}: {}", action, e));
+                assert_eq!(parse_key(&key_name(parsed)), Ok(parsed), "{}", key);
+            }
+        }
+
+        // No default key is bound to two actions
+        let bound: usize = DEFAULT_KEYS.iter().map(|(_, keys)| keys.len()).sum();
+        assert_eq!(Config::default().keys.len(), bound);
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("ctrl-q"), Ok(Key::Ctrl('q')));
+        assert_eq!(parse_key("Alt-X"), Ok(Key::Alt('X')));
+        assert_eq!(parse_key("F12"), Ok(Key::F(12)));
+        assert_eq!(parse_key("space"), Ok(Key::Char(' ')));
+        assert_eq!(parse_key("N"), Ok(Key::Char('N')));
+        assert!(parse_key("ctrl-").is_err());
+        assert!(parse_key("hyper-a").is_err());
+    }
+
+    #[test]
+    fn test_parse_sample_config() {
+        let config = Config::parse(
+            r#"
+            tab_width = 8
+            line_numbers = "relative"
+            wrap = true
+            mouse = false
+
+            [keys]
+            quit = "ctrl-x"
+            down = ["down", "j"]
+
+            [colors]
+            status_bg = "lightblue"
+            gutter = "244"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.tab_width, 8);
+        assert_eq!(config.line_numbers, LineNumbers::Relative);
+        assert!(config.wrap);
+        assert!(!config.mouse);
+
+        assert_eq!(config.keys.get(&Key::Ctrl('x')), Some(&Action::Quit));
+        assert_eq!(config.keys.get(&Key::Ctrl('q')), None);
+        assert_eq!(config.keys.get(&Key::Char('j')), Some(&Action::Down));
+        assert_eq!(config.keys.get(&Key::Down), Some(&Action::Down));
+        assert_eq!(config.keys.get(&Key::Up), Some(&Action::Up));
+
+        assert_eq!(config.theme.status_bg, Color::AnsiValue(12));
+        assert_eq!(config.theme.gutter, Color::AnsiValue(244));
+        assert_eq!(config.theme.header_bg, Theme::default().header_bg);
+    }
+
+    #[test]
+    fn test_parse_invalid_config() {
+        assert!(Config::parse("tab_size = 4").is_err());
+        assert!(Config::parse("[keys]\nquit = \"hyper-q\"").is_err());
+        assert!(Config::parse("[keys]\nfly = \"f\"").is_err());
+        assert!(Config::parse("[colors]\nmessage = \"pink\"").is_err());
+    }
+}
@@ -0,0 +1,270 @@
+/// How a row of the side-by-side diff differs between both files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    /// The line only exists in the left file
+    Removed,
+    /// The line only exists in the right file
+    Added,
+    /// The line was replaced by another one
+    Changed,
+}
+
+/// Row of the side-by-side diff, with the number (starting at 0) of the line shown on each side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Row {
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+    pub change: Change,
+}
+
+/// Step of the edit script turning the left file into the right file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Furthest x reached on the diagonal `k` with `d` edits, `row` holds the diagonals `-d..=d`
+fn furthest(row: &[isize], d: isize, k: isize) -> isize {
+    row[((k + d) / 2) as usize]
+}
+
+/// Whether the best path reaching the diagonal `k` with `d` edits comes from the diagonal `k + 1`,
+/// that is with an insertion, `previous` being the row of `d - 1` edits
+fn from_insertion(previous: &[isize], d: isize, k: isize) -> bool {
+    k == -d || (k != d && furthest(previous, d - 1, k - 1) < furthest(previous, d - 1, k + 1))
+}
+
+/// Shortest edit script between `a` and `b` with the Myers diff algorithm
+fn edit_script(a: &[String], b: &[String]) -> Vec<Edit> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+
+    // Only the diagonals reachable with d edits are kept for every d, O(D²) for D edits
+    let mut trace: Vec<Vec<isize>> = vec![];
+
+    'search: for d in 0..=n + m {
+        let mut row = Vec::with_capacity(d as usize + 1);
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = match trace.last() {
+                None => 0,
+                Some(previous) if from_insertion(previous, d, k) => {
+                    furthest(previous, d - 1, k + 1)
+                }
+                Some(previous) => furthest(previous, d - 1, k - 1) + 1,
+            };
+            let mut y = x - k;
+
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            row.push(x);
+
+            if x >= n && y >= m {
+                trace.push(row);
+                break 'search;
+            }
+        }
+
+        trace.push(row);
+    }
+
+    // Walk the trace backwards from the end of both files
+    let mut edits = vec![];
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len()).rev() {
+        let (previous, d) = (&trace[d - 1], d as isize);
+        let k = x - y;
+        let previous_k = if from_insertion(previous, d, k) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = furthest(previous, d - 1, previous_k);
+        let previous_y = previous_x - previous_k;
+
+        while x > previous_x && y > previous_y {
+            edits.push(Edit::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        edits.push(if x == previous_x {
+            Edit::Insert
+        } else {
+            Edit::Delete
+        });
+
+        x = previous_x;
+        y = previous_y;
+    }
+    // What is left is the common start of both files
+    edits.extend((0..x).map(|_| Edit::Equal));
+
+    edits.reverse();
+    edits
+}
+
+/// Aligns the lines of both files, the removed and added lines next to each other are paired as changed lines
+pub fn diff_lines(a: &[String], b: &[String]) -> Vec<Row> {
+    let mut rows = vec![];
+    let (mut deleted, mut inserted) = (vec![], vec![]);
+    let (mut i, mut j) = (0, 0);
+
+    // Pairs the lines removed and added since the last equal line
+    let flush = |rows: &mut Vec<Row>, deleted: &mut Vec<usize>, inserted: &mut Vec<usize>| {
+        for z in 0..deleted.len().max(inserted.len()) {
+            let (left, right) = (deleted.get(z).copied(), inserted.get(z).copied());
+            let change = match (left, right) {
+                (Some(_), Some(_)) => Change::Changed,
+                (Some(_), None) => Change::Removed,
+                _ => Change::Added,
+            };
+            rows.push(Row {
+                left,
+                right,
+                change,
+            });
+        }
+        deleted.clear();
+        inserted.clear();
+    };
+
+    for edit in edit_script(a, b) {
+        match edit {
+            Edit::Equal => {
+                flush(&mut rows, &mut deleted, &mut inserted);
+                rows.push(Row {
+                    left: Some(i),
+                    right: Some(j),
+                    change: Change::Same,
+                });
+                i += 1;
+                j += 1;
+            }
+            Edit::Delete => {
+                deleted.push(i);
+                i += 1;
+            }
+            Edit::Insert => {
+                inserted.push(j);
+                j += 1;
+            }
+        }
+    }
+    flush(&mut rows, &mut deleted, &mut inserted);
+
+    rows
+}
+
+/// Index of the first row of every group of consecutive rows that differ
+pub fn hunks(rows: &[Row]) -> Vec<usize> {
+    (0..rows.len())
+        .filter(|&i| {
+            rows[i].change != Change::Same && (i == 0 || rows[i - 1].change == Change::Same)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.chars().map(String::from).collect()
+    }
+
+    /// Replays `edits` on `a` and checks that it gives `b`
+    fn apply(edits: &[Edit], a: &[String], b: &[String]) {
+        let (mut i, mut j) = (0, 0);
+        for edit in edits {
+            match edit {
+                Edit::Equal => {
+                    assert_eq!(a[i], b[j]);
+                    i += 1;
+                    j += 1;
+                }
+                Edit::Delete => i += 1,
+                Edit::Insert => j += 1,
+            }
+        }
+        assert_eq!((i, j), (a.len(), b.len()));
+    }
+
+    #[test]
+    fn test_edit_script() {
+        let cases = [
+            ("", "", 0),
+            ("abc", "abc", 0),
+            ("", "abc", 3),
+            ("abc", "", 3),
+            ("abcabba", "cbabac", 5),
+            ("abcdef", "abXdeYf", 3),
+        ];
+
+        for (a, b, edits) in cases {
+            let (a, b) = (lines(a), lines(b));
+            let script = edit_script(&a, &b);
+
+            apply(&script, &a, &b);
+            assert_eq!(script.iter().filter(|&&e| e != Edit::Equal).count(), edits);
+        }
+    }
+
+    #[test]
+    fn test_edit_script_order() {
+        use Edit::*;
+
+        assert_eq!(
+            edit_script(&lines("abc"), &lines("aXc")),
+            [Equal, Delete, Insert, Equal]
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_pairs_changes() {
+        let row = |left, right, change| Row {
+            left,
+            right,
+            change,
+        };
+
+        assert_eq!(
+            diff_lines(&lines("abcde"), &lines("aXYde")),
+            [
+                row(Some(0), Some(0), Change::Same),
+                row(Some(1), Some(1), Change::Changed),
+                row(Some(2), Some(2), Change::Changed),
+                row(Some(3), Some(3), Change::Same),
+                row(Some(4), Some(4), Change::Same),
+            ]
+        );
+        assert_eq!(
+            diff_lines(&lines("abcd"), &lines("aXd")),
+            [
+                row(Some(0), Some(0), Change::Same),
+                row(Some(1), Some(1), Change::Changed),
+                row(Some(2), None, Change::Removed),
+                row(Some(3), Some(2), Change::Same),
+            ]
+        );
+        assert_eq!(
+            diff_lines(&lines("ad"), &lines("aXYd")),
+            [
+                row(Some(0), Some(0), Change::Same),
+                row(None, Some(1), Change::Added),
+                row(None, Some(2), Change::Added),
+                row(Some(1), Some(3), Change::Same),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hunks() {
+        let rows = diff_lines(&lines("abcdef"), &lines("Xbcdeg"));
+
+        assert_eq!(hunks(&rows), [0, 5]);
+    }
+}
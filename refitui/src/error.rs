@@ -0,0 +1,33 @@
+use std::{error::Error, fmt, io};
+
+/// Errors that stop the viewer, they are printed once the terminal is restored
+#[derive(Debug)]
+pub enum RefituiError {
+    /// Wrong command line arguments or config file
+    Usage(String),
+    /// A file could not be opened or read
+    File(String, io::Error),
+    /// The document piped to stdin could not be read
+    Stdin(io::Error),
+    /// The terminal could not be set up, read or drawn on
+    Terminal(io::Error),
+}
+
+impl From<io::Error> for RefituiError {
+    fn from(error: io::Error) -> Self {
+        RefituiError::Terminal(error)
+    }
+}
+
+impl fmt::Display for RefituiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefituiError::Usage(e) => write!(f, "{e}"),
+            RefituiError::File(file, e) => write!(f, "{file}: {e}"),
+            RefituiError::Stdin(e) => write!(f, "Cannot read stdin: {e}"),
+            RefituiError::Terminal(e) => write!(f, "Terminal error: {e}"),
+        }
+    }
+}
+
+impl Error for RefituiError {}
@@ -1,37 +1,115 @@
+mod buffer;
+mod config;
+mod diff;
+mod error;
+mod pagedfile;
+mod state;
+mod terminal;
 mod textviewer;
 
+use error::RefituiError;
 use structopt::StructOpt;
 
-
-#[derive(Debug,StructOpt)]
+#[derive(Debug, StructOpt)]
 #[structopt(
-    name    = "refitui",
-    about   = "Basic terminal text viewer implemented in rust",
-    author  = "Author: Eduardo",
-    version = "1.0.0",
+    name = "refitui",
+    about = "Basic terminal text viewer implemented in rust",
+    author = "Author: Eduardo",
+    version = "1.0.0"
 )]
 struct Command {
-    // This option specified the path to the file to be printed in the terminal
-    // This option is positional, meaning it is the first unadorned string you provide
-    file: String,
+    // This option specified the paths to the files to be printed in the terminal
+    // This option is positional, meaning they are the unadorned strings you provide
+    #[structopt(required = true, min_values = 1)]
+    files: Vec<String>,
+
+    // Show the line numbers gutter
+    #[structopt(short = "n", long = "line-numbers")]
+    line_numbers: bool,
+
+    // Show the line numbers relative to the cursor line
+    #[structopt(long = "relative")]
+    relative: bool,
+
+    // Stay at the end of the file and show the new lines as it grows
+    #[structopt(short = "f", long = "follow")]
+    follow: bool,
+
+    // Leave the mouse to the terminal, so text can be selected natively
+    #[structopt(long = "no-mouse")]
+    no_mouse: bool,
+
+    // Compare two files side by side
+    #[structopt(short = "d", long = "diff")]
+    diff: bool,
 }
 
 fn main() {
+    // A panic restores the terminal before printing its message
+    terminal::install_panic_hook();
+
+    // Errors are printed once the viewer is dropped, with the terminal back to normal
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), RefituiError> {
     // Get arguments from command line
     let opt: Command = Command::from_args();
 
-    // Check if file exists. If not, print error
-    // message and exit process
-    if !std::path::Path::new(&opt.file).exists() {
-        eprintln!("File does not exists");
-        std::process::exit(0);
-    } 
+    // Check if file exists. If not, return an error
+    // and exit process. `-` reads the document piped to stdin
+    let from_stdin = opt
+        .files
+        .iter()
+        .filter(|file| *file == buffer::STDIN_NAME)
+        .count();
+    if from_stdin > 1 {
+        return Err(RefituiError::Usage(
+            "stdin can only be opened once".to_string(),
+        ));
+    }
+
+    for file in opt.files.iter().filter(|file| *file != buffer::STDIN_NAME) {
+        if !std::path::Path::new(file).exists() {
+            return Err(RefituiError::Usage(format!(
+                "File does not exists: {}",
+                file
+            )));
+        }
+    }
+
+    if opt.diff && opt.files.len() != 2 {
+        return Err(RefituiError::Usage(
+            "--diff compares exactly two files".to_string(),
+        ));
+    }
+
+    // Load the settings of ~/.config/refitui/config.toml
+    let mut config = config::Config::load()
+        .map_err(|e| RefituiError::Usage(format!("Invalid config file {}", e)))?;
+
+    // The command line flags override the config file
+    if opt.relative {
+        config.line_numbers = textviewer::LineNumbers::Relative;
+    } else if opt.line_numbers {
+        config.line_numbers = textviewer::LineNumbers::Absolute;
+    }
+    if opt.no_mouse {
+        config.mouse = false;
+    }
 
-    // Open file and load into struct
-    println!("{}", termion::cursor::Show);
+    // Iniatialize viewer
+    // When stdin holds the document the keys are read from the terminal itself
+    let terminal = Box::new(terminal::Crossterm::new());
+    let mut viewer = textviewer::TextViewer::init(&opt.files, config, terminal)?;
+    if opt.diff {
+        viewer.start_diff();
+    } else if opt.follow {
+        viewer.start_follow();
+    }
 
-    // Iniatialize viewer 
-    let mut viewer = textviewer::TextViewer::init(&opt.file);
-    viewer.show_document();
-    viewer.run();
+    viewer.run()
 }
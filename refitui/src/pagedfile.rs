@@ -0,0 +1,278 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+
+/// Number of lines between two entries of the line offsets index
+const CHECKPOINT: usize = 1024;
+
+/// Read-only document that reads from disk only the lines being displayed.
+/// The line offsets are indexed lazily as the user moves through the file and only
+/// the offset of one line out of every `CHECKPOINT` is kept, so memory stays bounded.
+pub struct PagedFile {
+    inner: RefCell<Inner>,
+}
+
+struct Inner {
+    reader: BufReader<File>,
+    /// Byte offset of the lines 0, CHECKPOINT, 2 * CHECKPOINT...
+    checkpoints: Vec<u64>,
+    /// Number of lines indexed so far
+    indexed: usize,
+    /// Byte offset right after the last indexed line
+    indexed_end: u64,
+    /// Byte offset of the last indexed line
+    last_start: u64,
+    /// The whole file has been indexed
+    complete: bool,
+    /// The last indexed line has no line terminator, more data may still be appended to it
+    partial: bool,
+    /// Number of the first line stored in the window
+    window_start: usize,
+    /// Lines of the last block read from disk
+    window: Vec<String>,
+}
+
+/// Removes the line terminator and replaces the invalid UTF-8 sequences
+fn decode_line(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+impl PagedFile {
+    pub fn open(file: &str) -> io::Result<Self> {
+        Ok(Self {
+            inner: RefCell::new(Inner {
+                reader: BufReader::new(File::open(file)?),
+                checkpoints: vec![0],
+                indexed: 0,
+                indexed_end: 0,
+                last_start: 0,
+                complete: false,
+                partial: false,
+                window_start: 0,
+                window: vec![],
+            }),
+        })
+    }
+
+    /// Number of lines indexed so far, the real number of lines is only known once the file is complete
+    pub fn len(&self) -> usize {
+        self.inner.borrow().indexed
+    }
+
+    /// Whether every line of the file has been indexed
+    pub fn is_complete(&self) -> bool {
+        self.inner.borrow().complete
+    }
+
+    /// Indexes the file until it knows at least `lines` lines or it reaches the end of the file
+    pub fn ensure_indexed(&self, lines: usize) {
+        let mut inner = self.inner.borrow_mut();
+        let start = inner.indexed_end;
+
+        if inner.complete
+            || inner.indexed >= lines
+            || inner.reader.seek(SeekFrom::Start(start)).is_err()
+        {
+            return;
+        }
+
+        let mut buffer = vec![];
+        while inner.indexed < lines {
+            buffer.clear();
+            match inner.reader.read_until(b'\n', &mut buffer) {
+                Ok(0) | Err(_) => {
+                    inner.complete = true;
+                    break;
+                }
+                Ok(read) => {
+                    inner.partial = buffer.last() != Some(&b'\n');
+                    inner.last_start = inner.indexed_end;
+                    inner.indexed += 1;
+                    inner.indexed_end += read as u64;
+
+                    if inner.indexed.is_multiple_of(CHECKPOINT) {
+                        let end = inner.indexed_end;
+                        inner.checkpoints.push(end);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lets the indexing continue after the file grew. An unterminated last line is indexed again
+    /// since the new data completes it
+    pub fn refresh(&self) {
+        let mut inner = self.inner.borrow_mut();
+        if !inner.complete {
+            return;
+        }
+
+        inner.complete = false;
+        inner.window.clear();
+
+        if inner.partial {
+            if inner.indexed.is_multiple_of(CHECKPOINT) {
+                inner.checkpoints.pop();
+            }
+            inner.indexed -= 1;
+            inner.indexed_end = inner.last_start;
+            inner.partial = false;
+        }
+    }
+
+    /// Indexes the whole file
+    pub fn index_all(&self) {
+        self.ensure_indexed(usize::MAX);
+    }
+
+    /// Returns the line number `line` (starting at 0), reading its block from disk when it is not in memory
+    pub fn line(&self, line: usize) -> Option<String> {
+        self.ensure_indexed(line + 1);
+
+        let mut inner = self.inner.borrow_mut();
+        if line >= inner.indexed {
+            return None;
+        }
+
+        if line < inner.window_start || line >= inner.window_start + inner.window.len() {
+            let block = line / CHECKPOINT;
+            let offset = inner.checkpoints[block];
+
+            inner.window_start = block * CHECKPOINT;
+            inner.window.clear();
+            inner.reader.seek(SeekFrom::Start(offset)).ok()?;
+
+            let mut buffer = vec![];
+            while inner.window.len() < CHECKPOINT {
+                buffer.clear();
+                match inner.reader.read_until(b'\n', &mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => inner.window.push(decode_line(&buffer)),
+                }
+            }
+        }
+
+        inner.window.get(line - inner.window_start).cloned()
+    }
+
+    /// Calls `f` with every line of the file in order, reading it sequentially from the start
+    pub fn for_each_line(&self, mut f: impl FnMut(usize, &str)) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.reader.seek(SeekFrom::Start(0)).is_err() {
+            return;
+        }
+
+        let mut buffer = vec![];
+        let mut number = 0;
+        loop {
+            buffer.clear();
+            match inner.reader.read_until(b'\n', &mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => f(number, &decode_line(&buffer)),
+            }
+            number += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn paged(contents: &str) -> (NamedTempFile, PagedFile) {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        let paged = PagedFile::open(file.path().to_str().unwrap()).unwrap();
+
+        (file, paged)
+    }
+
+    fn append(file: &NamedTempFile, contents: &str) {
+        let mut file = OpenOptions::new().append(true).open(file.path()).unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_seek_across_checkpoints() {
+        let lines = 3 * CHECKPOINT + 10;
+        let contents: String = (0..lines).map(|i| format!("line {}\n", i)).collect();
+        let (_file, paged) = paged(&contents);
+
+        // Jumping forward indexes everything up to the line, going back rereads earlier blocks
+        for line in [
+            2 * CHECKPOINT + 5,
+            3,
+            CHECKPOINT,
+            CHECKPOINT - 1,
+            lines - 1,
+            2 * CHECKPOINT,
+        ] {
+            assert_eq!(paged.line(line), Some(format!("line {}", line)));
+        }
+        assert!(!paged.is_complete());
+
+        paged.index_all();
+        assert!(paged.is_complete());
+        assert_eq!(paged.len(), lines);
+        assert_eq!(paged.line(lines), None);
+    }
+
+    #[test]
+    fn test_last_line_without_newline() {
+        let (_file, paged) = paged("first\r\nsecond\nlast");
+
+        paged.index_all();
+        assert_eq!(paged.len(), 3);
+        assert_eq!(paged.line(0).as_deref(), Some("first"));
+        assert_eq!(paged.line(2).as_deref(), Some("last"));
+        assert_eq!(paged.line(3), None);
+
+        let mut lines = vec![];
+        paged.for_each_line(|number, line| lines.push((number, line.to_string())));
+        assert_eq!(lines.last(), Some(&(2, "last".to_string())));
+    }
+
+    #[test]
+    fn test_refresh_after_growth() {
+        let (file, paged) = paged("one\ntw");
+
+        paged.index_all();
+        assert_eq!(paged.line(1).as_deref(), Some("tw"));
+
+        append(&file, "o\nthree\n");
+        paged.index_all();
+        assert_eq!(paged.len(), 2);
+
+        // The unterminated line is completed by the new data instead of being counted twice
+        paged.refresh();
+        paged.index_all();
+        assert_eq!(paged.len(), 3);
+        assert_eq!(paged.line(1).as_deref(), Some("two"));
+        assert_eq!(paged.line(2).as_deref(), Some("three"));
+    }
+
+    #[test]
+    fn test_refresh_partial_line_on_checkpoint() {
+        let contents: String = (0..CHECKPOINT - 1)
+            .map(|i| format!("line {}\n", i))
+            .collect();
+        let (file, paged) = paged(&(contents + "last"));
+
+        paged.index_all();
+        assert_eq!(paged.len(), CHECKPOINT);
+
+        append(&file, " line\nnext\n");
+        paged.refresh();
+        paged.index_all();
+        assert_eq!(paged.len(), CHECKPOINT + 1);
+        assert_eq!(paged.line(CHECKPOINT - 1).as_deref(), Some("last line"));
+        assert_eq!(paged.line(CHECKPOINT).as_deref(), Some("next"));
+        assert_eq!(paged.line(0).as_deref(), Some("line 0"));
+    }
+}
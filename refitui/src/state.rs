@@ -0,0 +1,74 @@
+use crate::buffer::Buffer;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::{env, fs, path::PathBuf};
+
+/// Marks and jump list of a file, kept between sessions
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileState {
+    /// Line and column (starting at 1) of each mark
+    pub marks: BTreeMap<String, (usize, usize)>,
+    /// Line and column (starting at 1) of the recent jumps, oldest first
+    pub jumps: Vec<(usize, usize)>,
+}
+
+/// Contents of the state file, a `FileState` for each file by its absolute path
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct StateFile {
+    files: BTreeMap<String, FileState>,
+}
+
+/// Path of the state file, inside `$XDG_STATE_HOME` or `~/.local/state`
+fn path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+
+    Some(base.join("refitui").join("state.toml"))
+}
+
+/// Absolute path of a file, used to find its state
+fn key(file: &str) -> Option<String> {
+    fs::canonicalize(file)
+        .ok()
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+/// Reads the state file, a missing or invalid file is an empty state
+fn read() -> StateFile {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| toml::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Marks and jump list saved for `file`
+pub fn load(file: &str) -> FileState {
+    key(file)
+        .and_then(|key| read().files.remove(&key))
+        .unwrap_or_default()
+}
+
+/// Saves the marks and the jump list of every buffer opened from a file
+pub fn save(buffers: &[Buffer]) -> Result<(), String> {
+    let Some(path) = path() else {
+        return Ok(());
+    };
+
+    let mut state = read();
+    for buf in buffers.iter().filter(|buf| buf.file_len.is_some()) {
+        if let Some(key) = key(&buf.file_name) {
+            state.files.insert(key, buf.file_state());
+        }
+    }
+
+    let text = toml::to_string(&state).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))
+}
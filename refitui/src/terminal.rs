@@ -0,0 +1,215 @@
+use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind};
+use crossterm::{cursor, event, execute, terminal};
+use std::io::{self, stdout};
+use std::panic;
+use std::time::Duration;
+
+/// Key pressed by the user, the same on every platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// Printable character, tab is `'\t'` and enter is `'\n'`
+    Char(char),
+    Ctrl(char),
+    Alt(char),
+    F(u8),
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    BackTab,
+    Backspace,
+    Delete,
+    Insert,
+    Esc,
+}
+
+/// Mouse actions the viewer reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mouse {
+    WheelUp,
+    WheelDown,
+    /// Left click on the column and row given (starting at 1)
+    Click(usize, usize),
+}
+
+/// User input or change of the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Key(Key),
+    Mouse(Mouse),
+    /// New number of columns and rows of the terminal
+    Resize(usize, usize),
+}
+
+/// Terminal the viewer reads the user inputs from
+pub trait Terminal {
+    /// Number of columns and rows of the terminal
+    fn size(&self) -> io::Result<(usize, usize)>;
+
+    /// Switches to raw mode, reporting the mouse events when `mouse` is set
+    fn enable(&mut self, mouse: bool) -> io::Result<()>;
+
+    /// Restores the terminal as it was before `enable`
+    fn disable(&mut self) -> io::Result<()>;
+
+    /// Waits up to `timeout` for the next input, `None` when there was none
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>>;
+}
+
+/// Terminal implemented with crossterm, it works on Linux, macOS and Windows.
+/// When stdin is not a terminal the keys are read from the terminal itself
+#[derive(Default)]
+pub struct Crossterm {
+    enabled: bool,
+    mouse: bool,
+    /// The terminal reports keys like Ctrl+I apart from Tab
+    enhanced_keys: bool,
+}
+
+impl Crossterm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Converts a crossterm key into a `Key`, key releases (only reported on Windows) are ignored
+fn normalize_key(key: event::KeyEvent) -> Option<Key> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+    let key = match key.code {
+        KeyCode::Char(c) if ctrl => Key::Ctrl(c.to_ascii_lowercase()),
+        KeyCode::Char(c) if alt => Key::Alt(c),
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Tab => Key::Char('\t'),
+        KeyCode::Enter => Key::Char('\n'),
+        KeyCode::F(n) => Key::F(n),
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::BackTab => Key::BackTab,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::Esc => Key::Esc,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+fn normalize_mouse(mouse: event::MouseEvent) -> Option<Mouse> {
+    match mouse.kind {
+        MouseEventKind::ScrollUp => Some(Mouse::WheelUp),
+        MouseEventKind::ScrollDown => Some(Mouse::WheelDown),
+        MouseEventKind::Down(MouseButton::Left) => Some(Mouse::Click(
+            mouse.column as usize + 1,
+            mouse.row as usize + 1,
+        )),
+        _ => None,
+    }
+}
+
+impl Terminal for Crossterm {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        terminal::size().map(|(columns, rows)| (columns as usize, rows as usize))
+    }
+
+    fn enable(&mut self, mouse: bool) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        if mouse {
+            execute!(stdout(), event::EnableMouseCapture)?;
+        }
+
+        // Terminals supporting the kitty keyboard protocol can tell Ctrl+I from Tab
+        self.enhanced_keys = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if self.enhanced_keys {
+            execute!(
+                stdout(),
+                event::PushKeyboardEnhancementFlags(
+                    event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                )
+            )?;
+        }
+
+        self.enabled = true;
+        self.mouse = mouse;
+
+        Ok(())
+    }
+
+    fn disable(&mut self) -> io::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        if self.enhanced_keys {
+            execute!(stdout(), event::PopKeyboardEnhancementFlags)?;
+        }
+        if self.mouse {
+            execute!(stdout(), event::DisableMouseCapture)?;
+        }
+        execute!(stdout(), cursor::Show)?;
+        terminal::disable_raw_mode()?;
+        self.enabled = false;
+
+        Ok(())
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+
+        let event = match event::read()? {
+            event::Event::Key(key) => normalize_key(key).map(Event::Key),
+            event::Event::Mouse(mouse) => normalize_mouse(mouse).map(Event::Mouse),
+            event::Event::Resize(columns, rows) => {
+                Some(Event::Resize(columns as usize, rows as usize))
+            }
+            _ => None,
+        };
+
+        Ok(event)
+    }
+}
+
+/// Restores the terminal whatever state it was left in, used when the viewer panics
+pub fn restore() {
+    let _ = execute!(
+        stdout(),
+        event::PopKeyboardEnhancementFlags,
+        event::DisableMouseCapture,
+        cursor::Show
+    );
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Restores the terminal before the panic message is printed, so a crash does not leave the shell in raw mode
+pub fn install_panic_hook() {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        println!();
+        hook(info);
+    }));
+}
+
+impl Drop for Crossterm {
+    fn drop(&mut self) {
+        let _ = self.disable();
+    }
+}
@@ -1,206 +1,1579 @@
-use std::fs;
-use std::io::{stdin, stdout, Write};
-use termion::{
-    event::Key,
-    input::TermRead,
-    raw::IntoRawMode,
-    color,
-    style,
+use crate::buffer::{Buffer, Coordinates, Search, STDIN_NAME};
+use crate::config::{self, Action, Config};
+use crate::diff::{self, Change, Row};
+use crate::error::RefituiError;
+use crate::state;
+use crate::terminal::{Event, Key, Mouse, Terminal};
+use crossterm::{
+    cursor,
+    style::{Attribute, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
 };
+use serde::Deserialize;
+use std::io::{self, stdout, Write};
+use std::path::Path;
+use std::time::Duration;
 
-/// Data strcuture that stores the document parsed in lines
-struct Doc {
-    lines: Vec<String>
+/// Time between two checks of the file for new lines in follow mode
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Number of lines scrolled by each step of the mouse wheel
+const WHEEL_LINES: usize = 3;
+
+/// Commands of the prompt opened with `:`, listed in the help
+const COMMANDS: [(&str, &str); 10] = [
+    (":NNN", "Jump to the line NNN"),
+    (":open FILE", "Open a file in a new tab, also :e FILE"),
+    (":w", "Save the file"),
+    (":q", "Quit, :q! discards the unsaved changes"),
+    (":wq", "Save the file and quit"),
+    (":set wrap", "Wrap long lines, :set nowrap clips them"),
+    (
+        ":set number",
+        "Show the line numbers, :set nonumber hides them",
+    ),
+    (
+        ":set relativenumber",
+        "Show the line numbers relative to the cursor line",
+    ),
+    (":set tabwidth=N", "Show tabs N columns wide"),
+    (":help", "Show this help"),
+];
+
+/// Modes of the text viewer, keys are interpreted differently on each one
+#[derive(Debug, PartialEq)]
+enum Mode {
+    View,
+    Search,
+    /// Prompt opened with `:`, a number jumps to that line
+    Command,
+    /// Typed characters are inserted in the document
+    Insert,
+    /// Waiting for the letter of the mark to set at the cursor line
+    SetMark,
+    /// Waiting for the letter of the mark to jump to
+    GotoMark,
+    /// The keys and the commands are listed over the document
+    Help,
 }
 
-/// Data structure that stores the curso position and to record the current size of the terminal
-#[derive(Debug)] 
-struct Coordinates {
-    pub x: usize,
-    pub y: usize,
+/// How the line numbers gutter is displayed
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineNumbers {
+    Off,
+    /// Number of each line in the document
+    Absolute,
+    /// Distance from each line to the cursor line, the cursor line shows its own number
+    Relative,
+}
+
+impl LineNumbers {
+    /// Next mode when the gutter is toggled
+    fn next(self) -> Self {
+        match self {
+            LineNumbers::Off => LineNumbers::Absolute,
+            LineNumbers::Absolute => LineNumbers::Relative,
+            LineNumbers::Relative => LineNumbers::Off,
+        }
+    }
 }
 
 /// Main data structure representing the text viewer
 pub struct TextViewer {
-    doc: Doc,
-    doc_length: usize,
-    cur_pos: Coordinates,
+    /// Open files, each one with its own cursor and scroll position
+    buffers: Vec<Buffer>,
+    /// Index of the buffer shown on the screen
+    active: usize,
+    /// Long lines are wrapped over several rows instead of being clipped
+    wrap: bool,
     terminal_size: Coordinates,
-    file_name: String,
-} 
-
-impl TextViewer {
-    /// Instantiate TextViewer and initializate
-    pub fn init(file: &str) -> Self {
-
-        // Initialize the buffer that is used to store the file contents
-        let mut doc_file = Doc {
-            lines: vec![]
-        };
-
-        // Read the file contents as a string
-        let file_handle = fs::read_to_string(file).unwrap();
+    mode: Mode,
+    /// Text typed in the command prompt
+    command: String,
+    /// Ctrl+Q was pressed once with unsaved changes, a second press quits
+    quit_pending: bool,
+    /// The viewer exits after the current key
+    quitting: bool,
+    /// First line of the help shown on the screen
+    help_offset: usize,
+    /// Message shown in the footer until the next key press
+    message: Option<String>,
+    line_numbers: LineNumbers,
+    /// The view stays at the end of the document and shows the lines appended to the file
+    follow: bool,
+    /// Key bindings, colors and tab width
+    config: Config,
+    /// Terminal the user inputs are read from
+    terminal: Box<dyn Terminal>,
+    /// Rows printed by the last redraw, only the rows that change are printed again
+    frame: Vec<String>,
+    /// Buffers shown on the left and on the right pane when the view is split, the active one has the cursor
+    split: Option<(usize, usize)>,
+    /// Alignment of both buffers when comparing two files
+    diff: Option<DiffView>,
+}
 
-        // Read each line from the file and store it in ht Doc buffer
-        for doc_line in file_handle.lines() {
-            doc_file.lines.push(doc_line.to_string());
-        }
+/// Side-by-side comparison of the two open buffers, which hold the aligned lines of both files
+struct DiffView {
+    rows: Vec<Row>,
+    /// First row of each group of differences
+    hunks: Vec<usize>,
+}
 
-        // Initailize the doc_length variable with the number of lines of the file
-        let doc_length = file_handle.lines().count();
+impl TextViewer {
+    /// Instantiate TextViewer and initializate, opening a buffer for each file
+    pub fn init(
+        files: &[String],
+        config: Config,
+        terminal: Box<dyn Terminal>,
+    ) -> Result<Self, RefituiError> {
+        // Ask the terminal for its size
+        let size = terminal.size()?;
 
-        // Use the termion crate to get the terminal size
-        let size = termion::terminal_size().unwrap();
+        let buffers = files
+            .iter()
+            .map(|file| match file.as_str() {
+                STDIN_NAME => Buffer::from_stdin(),
+                _ => Buffer::open(file),
+            })
+            .collect::<Result<_, _>>()?;
 
         // Create a new struct of the TextViewer type and return it from the init() method
-        Self {
-            doc: doc_file,
-            cur_pos: Coordinates {
-                x: 1,
-                y: doc_length,
-            },
-            doc_length,
+        Ok(Self {
+            buffers,
+            active: 0,
+            wrap: config.wrap,
             terminal_size: Coordinates {
-                x: size.0 as usize,
-                y: size.1 as usize
+                x: size.0,
+                y: size.1,
             },
-            file_name: file.into(),
-        }
+            mode: Mode::View,
+            command: String::new(),
+            quit_pending: false,
+            quitting: false,
+            help_offset: 0,
+            message: None,
+            line_numbers: config.line_numbers,
+            follow: false,
+            config,
+            terminal,
+            frame: vec![],
+            split: None,
+            diff: None,
+        })
     }
 
-    /// Displays the contents of the file on the terminal screen
-    pub fn show_document(&mut self) {
+    /// Buffer shown on the screen
+    fn buf(&self) -> &Buffer {
+        &self.buffers[self.active]
+    }
 
-        // 1. Store the current position of the cursor x and y coordinates in temp variables. 
-        // This will be used to restore the cursor position in later step.
-        let pos = &self.cur_pos;
-        let (old_x, old_y) = (pos.x, pos.y);
+    /// Mutable access to the buffer shown on the screen
+    fn buf_mut(&mut self) -> &mut Buffer {
+        &mut self.buffers[self.active]
+    }
 
-        // 2. Using the Termination crate, clear the entire screen and move the cursor to row 1 and column 1 on the screen.
-        println!("{}{}", termion::clear::All, termion::cursor::Goto(1,1));
+    /// Displays the contents of the file on the terminal screen
+    fn show_document(&mut self) -> io::Result<()> {
+        // 1. Scroll the document so the cursor stays on the screen.
+        self.scroll();
 
-        // 3. Print the header bar of the text viewer. A background color of black and foreground color of the white is used to print text.
-        println!("{}{} Welcome to Super text viewer\r{}", color::Bg(color::White), color::Fg(color::Black), style::Reset);
+        // 2. Draw the whole screen in memory, a string for each row of the terminal.
+        let frame = self.render_frame();
 
-        // 4. Display each line from the internal document buffer to the terminal screen.
-        // Check whether the number of lines in the document is less than the terminal height.        
-        if self.doc_length < self.terminal_size.y {
-            // If so, display all lines from the input document on the terminal screen
-            for line in 0..self.doc_length {
-                println!("{}\r", self.doc.lines[line as usize]);
+        // 3. Only print the rows that changed since the previous frame, so moving the cursor does not repaint
+        // the whole screen. Without a previous frame (at startup or after a resize) the screen is cleared first.
+        let mut output = String::new();
+        if self.frame.is_empty() {
+            output.push_str(&Clear(ClearType::All).to_string());
+        }
+        for (row, text) in frame.iter().enumerate() {
+            if self.frame.get(row) != Some(text) {
+                output.push_str(&format!(
+                    "{}{}{}",
+                    cursor::MoveTo(0, row as u16),
+                    Clear(ClearType::CurrentLine),
+                    text
+                ));
             }
+        }
+        self.frame = frame;
+
+        // 4. Place the cursor back at its position in the document
+        let (column, row) = self.cursor_position();
+        output.push_str(&cursor::MoveTo(column, row).to_string());
+
+        let mut stdout = stdout();
+        write!(stdout, "{}", output)?;
+        stdout.flush()
+    }
+
+    /// Rows of the screen: the header, the lines of the document and the footer
+    fn render_frame(&mut self) -> Vec<String> {
+        let mut frame = Vec::with_capacity(self.terminal_size.y);
+
+        // Header bar of the text viewer. A background color of white and foreground color of the black is used to print text.
+        // With several open files the header shows a tab for each buffer instead.
+        if self.buffers.len() > 1 {
+            frame.push(self.tab_bar());
         } else {
-            // If the number of lines is greater than the terminal height, we have to display the document in parts.
-            if pos.y <= self.terminal_size.y {
-                for line in 0..self.terminal_size.y - 3 {
-                    println!("{}\r", self.doc.lines[line as usize]);
-                }
-            } else {
-                for line in pos.y - (self.terminal_size.y -3)..pos.y {
-                    println!("{}\r", self.doc.lines[line as usize]);
-                }
+            let theme = &self.config.theme;
+            frame.push(format!(
+                "{}{} Welcome to Super text viewer{}",
+                SetBackgroundColor(theme.header_bg),
+                SetForegroundColor(theme.header_fg),
+                SetAttribute(Attribute::Reset)
+            ));
+        }
+
+        // Lines of the document that fit between the header and the footer,
+        // starting at the scroll offset. Wrapped lines can take more than one row.
+        // A split view shows the rows of both panes next to each other and the help is shown instead of the document.
+        match self.split {
+            _ if self.mode == Mode::Help => {
+                frame.extend(
+                    self.help_lines()
+                        .into_iter()
+                        .skip(self.help_offset)
+                        .take(self.text_height()),
+                );
             }
+            None => frame.extend(self.render_rows()),
+            Some((left, right)) => {
+                let active = self.active;
+                self.active = left;
+                let left_rows = self.render_rows();
+                self.active = right;
+                let right_rows = self.render_rows();
+                self.active = active;
 
+                let separator = format!(
+                    "{}│{}",
+                    SetForegroundColor(self.config.theme.gutter),
+                    SetAttribute(Attribute::Reset)
+                );
+                frame.extend(
+                    left_rows
+                        .iter()
+                        .zip(right_rows)
+                        .map(|(left, right)| format!("{}{}{}", left, separator, right)),
+                );
+            }
         }
+        frame.resize(self.text_height() + 1, String::new());
 
-        // 5. Move the cursor to the bottom of the screen (using the terminal size y coordinate) to print the footer.
-        println!("{}", termion::cursor::Goto(0, (self.terminal_size.y - 2) as u16));
+        // Status bar with the mode, the filename and the cursor position in the document.
+        // While searching the footer shows the search prompt instead and messages are printed in red and with bold style.
+        let footer = if self.mode == Mode::Command {
+            format!(
+                "{}:{}{}",
+                SetAttribute(Attribute::Bold),
+                self.command,
+                SetAttribute(Attribute::Reset)
+            )
+        } else if self.mode == Mode::Search {
+            format!(
+                "{}/{}{}",
+                SetAttribute(Attribute::Bold),
+                self.buf().search.pattern,
+                SetAttribute(Attribute::Reset)
+            )
+        } else if self.mode == Mode::Help {
+            let theme = &self.config.theme;
+            let text = format!(
+                " HELP | {} lines, arrows to scroll, Esc to close",
+                self.help_lines().len()
+            );
+            format!(
+                "{}{}{:<w$}{}",
+                SetBackgroundColor(theme.status_bg),
+                SetForegroundColor(theme.status_fg),
+                text,
+                SetAttribute(Attribute::Reset),
+                w = self.terminal_size.x
+            )
+        } else if let Some(message) = &self.message {
+            format!(
+                "{}{} {}{}",
+                SetForegroundColor(self.config.theme.message),
+                SetAttribute(Attribute::Bold),
+                message,
+                SetAttribute(Attribute::Reset)
+            )
+        } else {
+            let theme = &self.config.theme;
+            format!(
+                "{}{}{}{}",
+                SetBackgroundColor(theme.status_bg),
+                SetForegroundColor(theme.status_fg),
+                self.status_bar(),
+                SetAttribute(Attribute::Reset)
+            )
+        };
+        frame.push(footer);
 
-        // 6. Print the footer text in red and with bold style. Print the number of lines in the document and filename to the footer.
-        println!("{}{} line-count={} Filename: {}{}", color::Fg(color::Red), style::Bold, self.doc_length, self.file_name, style::Reset);
+        // The last row of the terminal is left empty
+        frame.resize(self.terminal_size.y.max(frame.len()), String::new());
 
-        // 7. Reset the cursor to the original position (which was saved to the temporary variable in step 1)
-        self.set_pos(old_x, old_y)
+        frame
+    }
+
+    /// Rows of the active buffer between the header and the footer, padded to the width of the pane
+    fn render_rows(&self) -> Vec<String> {
+        // Large files are indexed a little past the screen so moving down does not stall.
+        self.buf()
+            .doc
+            .ensure_indexed(self.buf().offset + 2 * self.text_height());
+
+        let mut rows: Vec<String> = (self.buf().offset..self.buf().doc_length())
+            .flat_map(|line| self.render_line(line))
+            .take(self.text_height())
+            .collect();
+
+        let blank = if self.split.is_some() {
+            " ".repeat(self.pane_width())
+        } else {
+            String::new()
+        };
+        rows.resize(self.text_height(), blank);
+        rows
     }
 
     /// Waits for user inputs to the process.
-    /// If the user presses Ctrl + Q, the program exits. 
-    pub fn run(&mut self) {
-        // TODO: Handle posible error case.
-        // stdout is used for display text to the terminal
-        let mut stdout = stdout().into_raw_mode().unwrap();
-        let stdin = stdin();
-        
-        // stdin.keys method is used for listen for the user inputs in a loop
-        for c in stdin.keys() {
-            match c.unwrap() {
-                Key::Ctrl('q') => {
-                    // Exit the aplication
+    /// If the user presses Ctrl + Q, the program exits.
+    /// An error of the terminal stops the viewer, the terminal is restored when it is dropped
+    pub fn run(&mut self) -> Result<(), RefituiError> {
+        // The terminal is switched to raw mode, with mouse reporting unless it is disabled
+        self.terminal.enable(self.config.mouse)?;
+        self.show_document()?;
+
+        // The events are polled with a timeout so the file can be checked for new lines in follow mode
+        loop {
+            let key = match self.terminal.poll_event(FOLLOW_INTERVAL)? {
+                Some(Event::Key(key)) => key,
+                Some(Event::Mouse(mouse)) => {
+                    self.message = None;
+                    self.handle_mouse(mouse);
+                    self.stop_following();
+                    self.show_document()?;
+                    continue;
+                }
+                Some(Event::Resize(columns, rows)) => {
+                    // Redraw the whole document with the new size of the terminal
+                    self.terminal_size = Coordinates {
+                        x: columns,
+                        y: rows,
+                    };
+                    self.frame.clear();
+                    self.show_document()?;
+                    continue;
+                }
+                None => {
+                    if self.follow && self.mode == Mode::View && self.buf_mut().read_appended() {
+                        self.goto_end();
+                        self.show_document()?;
+                    }
+                    continue;
+                }
+            };
+            self.message = None;
+
+            // Shortcuts available in every mode, keys bound to characters only work in view mode so they can still be typed
+            let action = self.config.keys.get(&key).copied();
+            let global = self.mode == Mode::View || !matches!(key, Key::Char(_));
+            match action.filter(|_| global) {
+                Some(Action::Quit) => {
+                    // Exit the aplication, asking for confirmation when there are unsaved changes
+                    self.quit(false);
+                    if self.quitting {
+                        break;
+                    }
+                    self.show_document()?;
+                    continue;
+                }
+                Some(Action::Save) => {
+                    // Save the document
+                    self.save();
+                    self.show_document()?;
+                    continue;
+                }
+                _ => self.quit_pending = false,
+            }
+
+            // In insert mode the keys edit the document
+            if self.mode == Mode::Insert {
+                self.handle_insert_key(key);
+                self.show_document()?;
+                continue;
+            }
+
+            // While the search prompt is open every key edits the pattern
+            if self.mode == Mode::Search {
+                self.handle_search_key(key);
+                self.show_document()?;
+                continue;
+            }
+
+            // Same for the command prompt
+            if self.mode == Mode::Command {
+                self.handle_command_key(key);
+                if self.quitting {
                     break;
-                }, 
-                Key::Left => {
+                }
+                self.show_document()?;
+                continue;
+            }
+
+            // The help is scrolled until it is closed
+            if self.mode == Mode::Help {
+                self.handle_help_key(key);
+                self.show_document()?;
+                continue;
+            }
+
+            // After `m` or `'` the next key is the letter of the mark
+            if self.mode == Mode::SetMark || self.mode == Mode::GotoMark {
+                self.handle_mark_key(key);
+                self.show_document()?;
+                continue;
+            }
+
+            match action {
+                Some(Action::Insert) if self.buf().is_read_only() => {
+                    self.message = Some(format!("{} is opened read-only", self.buf().file_name));
+                }
+                Some(Action::Insert) => {
+                    // Start editing the document
+                    self.mode = Mode::Insert;
+                    self.buf_mut().ensure_line();
+                }
+                Some(Action::NextBuffer) => {
+                    // Switch to the next open file
+                    self.switch_buffer((self.active + 1) % self.buffers.len());
+                }
+                Some(Action::PreviousBuffer) => {
+                    // Switch to the previous open file
+                    self.switch_buffer((self.active + self.buffers.len() - 1) % self.buffers.len());
+                }
+                Some(Action::ToggleSplit) => {
+                    // Show two files next to each other
+                    self.toggle_split();
+                }
+                Some(Action::SwitchPane) => {
+                    // Move the cursor to the other pane
+                    if let Some((left, right)) = self.split {
+                        self.active = if self.active == left { right } else { left };
+                    }
+                }
+                Some(Action::NextHunk) => {
+                    // Jump to the next difference between the compared files
+                    self.next_hunk(true);
+                }
+                Some(Action::PreviousHunk) => {
+                    // Jump to the previous difference
+                    self.next_hunk(false);
+                }
+                Some(Action::ToggleWrap) => {
+                    // Toggle soft wrapping of long lines
+                    self.wrap = !self.wrap;
+                }
+                Some(Action::ToggleLineNumbers) => {
+                    // Cycle the line numbers gutter between off, absolute and relative
+                    self.line_numbers = self.line_numbers.next();
+                }
+                Some(Action::Help) => {
+                    // List the keys and the commands
+                    self.show_help();
+                }
+                Some(Action::Command) => {
+                    // Open the command prompt
+                    self.mode = Mode::Command;
+                    self.command.clear();
+                }
+                Some(Action::PageUp) => {
+                    // Move a screen up
+                    let y = self
+                        .buf()
+                        .cur_pos
+                        .y
+                        .saturating_sub(self.text_height())
+                        .max(1);
+                    self.set_pos(self.buf().cur_pos.x, y);
+                }
+                Some(Action::PageDown) => {
+                    // Move a screen down
+                    self.buf()
+                        .doc
+                        .ensure_indexed(self.buf().cur_pos.y + self.text_height());
+                    let y = (self.buf().cur_pos.y + self.text_height())
+                        .min(self.buf().doc_length().max(1));
+                    self.set_pos(self.buf().cur_pos.x, y);
+                }
+                Some(Action::Start) => {
+                    // Jump to the start of the document
+                    self.record_jump();
+                    self.set_pos(1, 1);
+                }
+                Some(Action::End) => {
+                    // Jump to the end of the document
+                    self.record_jump();
+                    self.goto_end();
+                }
+                Some(Action::ToggleHex) => {
+                    // Switch between the text and the hex view
+                    if let Err(e) = self.buf_mut().toggle_hex() {
+                        self.message = Some(e);
+                    }
+                }
+                Some(Action::SetMark) => {
+                    self.mode = Mode::SetMark;
+                }
+                Some(Action::GotoMark) => {
+                    self.mode = Mode::GotoMark;
+                }
+                Some(Action::JumpBack) => {
+                    // Go back to where the cursor was before the last jump
+                    if let Some((y, x)) = self.buf_mut().jump_back() {
+                        self.goto(x, y);
+                    }
+                }
+                Some(Action::JumpForward) => {
+                    // Undo a jump back
+                    if let Some((y, x)) = self.buf_mut().jump_forward() {
+                        self.goto(x, y);
+                    }
+                }
+                Some(Action::Follow) => {
+                    // Follow the file as it grows, like tail -f
+                    self.start_follow();
+                }
+                Some(Action::Search) => {
+                    // Open the search prompt
+                    self.start_search();
+                }
+                Some(Action::NextMatch) => {
+                    // Jump to the next match
+                    self.record_jump();
+                    self.next_match(true);
+                }
+                Some(Action::PreviousMatch) => {
+                    // Jump to the previous match
+                    self.record_jump();
+                    self.next_match(false);
+                }
+                Some(Action::Left) => {
                     // Move a cell to the left
                     self.dec_x();
-                    self.show_document();
                 }
-                Key::Right => {
+                Some(Action::Right) => {
                     // Move a cell to the right
                     self.inc_x();
-                    self.show_document();
                 }
-                Key::Up => {
+                Some(Action::Up) => {
                     // Move a cell up
                     self.dec_y();
-                    self.show_document();
                 }
-                Key::Down => {
+                Some(Action::Down) => {
                     // Move a cell down
                     self.inc_y();
-                    self.show_document();
-                }
-                Key::Backspace => {
-                    // ¿?
-                    self.dec_x();
                 }
                 _ => {}
             }
-            stdout.flush().unwrap();
+
+            self.stop_following();
+            self.show_document()?;
+        }
+
+        // Leave the shell prompt below the viewer
+        let mut stdout = stdout();
+        write!(
+            stdout,
+            "{}",
+            cursor::MoveTo(0, self.terminal_size.y.saturating_sub(1) as u16)
+        )?;
+        stdout.flush()?;
+        self.terminal.disable()?;
+
+        // Keep the marks and the jump lists for the next time the files are opened
+        if let Err(e) = state::save(&self.buffers) {
+            eprintln!("Cannot save the marks: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// Scrolling up stops following the file
+    fn stop_following(&mut self) {
+        if self.follow && self.buf().cur_pos.y < self.buf().doc_length() {
+            self.follow = false;
+        }
+    }
+
+    /// The wheel scrolls the document and a left click moves the cursor to the clicked character
+    fn handle_mouse(&mut self, mouse: Mouse) {
+        if self.mode == Mode::Help {
+            match mouse {
+                Mouse::WheelUp => self.scroll_help(-(WHEEL_LINES as isize)),
+                Mouse::WheelDown => self.scroll_help(WHEEL_LINES as isize),
+                Mouse::Click(..) => {}
+            }
+            return;
+        }
+        if self.mode != Mode::View && self.mode != Mode::Insert {
+            return;
+        }
+
+        match mouse {
+            Mouse::WheelUp => self.scroll_view(false),
+            Mouse::WheelDown => self.scroll_view(true),
+            Mouse::Click(column, row) => self.click(column, row),
+        }
+    }
+
+    /// Moves the viewport a few lines, the cursor is dragged along when it would leave the screen
+    fn scroll_view(&mut self, down: bool) {
+        let offset = self.buf().offset;
+        let offset = if down {
+            self.buf()
+                .doc
+                .ensure_indexed(offset + WHEEL_LINES + 2 * self.text_height());
+            (offset + WHEEL_LINES).min(self.buf().doc_length().saturating_sub(1))
+        } else {
+            offset.saturating_sub(WHEEL_LINES)
+        };
+        self.buf_mut().offset = offset;
+
+        if self.buf().cur_pos.y <= offset {
+            self.buf_mut().cur_pos.y = offset + 1;
+        }
+        while self.buf().cur_pos.y > offset + 1 && self.cursor_row() >= self.text_height() {
+            self.buf_mut().cur_pos.y -= 1;
+        }
+        self.buf_mut().clamp_x();
+    }
+
+    /// Moves the cursor to the character shown at the terminal `column` and `row` (starting at 1)
+    fn click(&mut self, column: usize, row: usize) {
+        // In a split view the click moves to the pane under it, the separator column is ignored
+        let column = match self.split {
+            Some((left, right)) => {
+                let width = self.pane_width();
+                if column <= width {
+                    self.active = left;
+                    column
+                } else if column > width + 1 {
+                    self.active = right;
+                    column - width - 1
+                } else {
+                    return;
+                }
+            }
+            None => column,
+        };
+
+        // The first row is the header, the document starts right below it
+        let Some(mut row) = row.checked_sub(2).filter(|row| *row < self.text_height()) else {
+            return;
+        };
+        let Some(column) = column.checked_sub(self.gutter_width() + 1) else {
+            return;
+        };
+
+        let mut line = self.buf().offset;
+        while line < self.buf().doc_length() && row >= self.line_rows(line) {
+            row -= self.line_rows(line);
+            line += 1;
+        }
+        if line >= self.buf().doc_length() {
+            return;
         }
+
+        let column = if self.wrap {
+            row * self.text_width() + column
+        } else {
+            self.buf().col_offset + column
+        };
+
+        // Find the character covering the screen column, tabs take several columns
+        let text = self.buf().doc.line(line).unwrap_or_default();
+        let chars = text.chars().count();
+        let x = (0..chars)
+            .find(|&i| self.display_width(&text, i + 1) > column)
+            .unwrap_or(chars);
+
+        self.buf_mut().cur_pos = Coordinates {
+            x: x + 1,
+            y: line + 1,
+        };
+    }
+
+    /// Shows the buffer `index` in the active pane, a buffer already shown in the other pane takes its place
+    fn switch_buffer(&mut self, index: usize) {
+        if self.diff.is_some() {
+            self.message = Some("The compared files cannot be switched".to_string());
+            return;
+        }
+
+        if let Some((left, right)) = self.split {
+            self.split = Some(
+                match (self.active == left, index == left || index == right) {
+                    (true, false) => (index, right),
+                    (false, false) => (left, index),
+                    // Both panes swap their buffers
+                    _ => (right, left),
+                },
+            );
+        }
+        self.active = index;
+    }
+
+    /// Splits the screen between the active buffer and the next one, or goes back to a single pane
+    fn toggle_split(&mut self) {
+        if self.diff.is_some() {
+            self.message = Some("The compared files are always shown side by side".to_string());
+            return;
+        }
+        if self.split.take().is_some() {
+            return;
+        }
+        if self.buffers.len() < 2 {
+            self.message = Some("Open two files to split the view".to_string());
+            return;
+        }
+
+        self.split = Some((self.active, (self.active + 1) % self.buffers.len()));
+    }
+
+    /// Compares the two open files: both buffers are replaced by their aligned lines, shown side by side
+    pub fn start_diff(&mut self) {
+        let lines = |buf: &Buffer| {
+            let mut lines = vec![];
+            buf.doc.index_all();
+            buf.doc
+                .for_each_line(|_, line| lines.push(line.to_string()));
+            lines
+        };
+        let (left, right) = (lines(&self.buffers[0]), lines(&self.buffers[1]));
+        let rows = diff::diff_lines(&left, &right);
+
+        // Each side gets an empty line where the other file has a line it does not have
+        let aligned = |lines: &[String], side: fn(&Row) -> Option<usize>| {
+            rows.iter()
+                .map(|row| side(row).map(|i| lines[i].clone()).unwrap_or_default())
+                .collect::<Vec<String>>()
+        };
+        let left = Buffer::from_lines(aligned(&left, |row| row.left), &self.buffers[0].file_name);
+        let right =
+            Buffer::from_lines(aligned(&right, |row| row.right), &self.buffers[1].file_name);
+
+        let hunks = diff::hunks(&rows);
+        self.message = Some(match hunks.len() {
+            0 => "The files are identical".to_string(),
+            1 => "1 difference".to_string(),
+            n => format!("{} differences", n),
+        });
+
+        self.buffers = vec![left, right];
+        self.active = 0;
+        self.split = Some((0, 1));
+        self.diff = Some(DiffView { rows, hunks });
+    }
+
+    /// Moves the cursor to the next (or previous) group of differences, wrapping around the document
+    fn next_hunk(&mut self, forward: bool) {
+        let Some(diff) = &self.diff else {
+            self.message = Some("No files compared, open them with --diff".to_string());
+            return;
+        };
+        if diff.hunks.is_empty() {
+            self.message = Some("The files are identical".to_string());
+            return;
+        }
+
+        let row = self.buf().cur_pos.y - 1;
+        let hunk = if forward {
+            diff.hunks
+                .iter()
+                .find(|&&hunk| hunk > row)
+                .or(diff.hunks.first())
+        } else {
+            diff.hunks
+                .iter()
+                .rev()
+                .find(|&&hunk| hunk < row)
+                .or(diff.hunks.last())
+        };
+        let y = hunk.copied().unwrap_or(0) + 1;
+
+        self.record_jump();
+        self.set_pos(1, y);
+    }
+
+    /// Moves the cursor to the last line of the document
+    fn goto_end(&mut self) {
+        self.buf().doc.index_all();
+        self.set_pos(1, self.buf().doc_length().max(1));
+    }
+
+    /// Pins the view to the end of the document, the lines appended to the file are shown as they arrive
+    pub fn start_follow(&mut self) {
+        if self.buf().file_len.is_none() {
+            self.message = Some(format!("Cannot follow {}", self.buf().file_name));
+            return;
+        }
+
+        self.follow = true;
+        self.buf_mut().read_appended();
+        self.goto_end();
+    }
+
+    /// Header listing the open buffers, the active one highlighted and the modified ones marked with `*`
+    fn tab_bar(&self) -> String {
+        self.buffers
+            .iter()
+            .enumerate()
+            .map(|(i, buf)| {
+                let modified = if buf.dirty { "*" } else { "" };
+                let tab = format!(" {}:{}{} ", i + 1, buf.file_name, modified);
+
+                if i == self.active {
+                    format!(
+                        "{}{}{}{}",
+                        SetBackgroundColor(self.config.theme.header_bg),
+                        SetForegroundColor(self.config.theme.header_fg),
+                        tab,
+                        SetAttribute(Attribute::Reset)
+                    )
+                } else {
+                    tab
+                }
+            })
+            .collect()
+    }
+
+    /// Handles a key pressed in insert mode
+    fn handle_insert_key(&mut self, key: Key) {
+        match key {
+            Key::Esc => self.mode = Mode::View,
+            Key::Left => self.dec_x(),
+            Key::Right => self.inc_x(),
+            Key::Up => self.dec_y(),
+            Key::Down => self.inc_y(),
+            Key::Char('\n') => self.buf_mut().split_line(),
+            Key::Backspace => self.buf_mut().delete_backward(),
+            Key::Delete => self.buf_mut().delete_forward(),
+            Key::Char(c) => self.buf_mut().insert_char(c),
+            _ => {}
+        }
+    }
+
+    /// Handles a key pressed while the command prompt is open
+    fn handle_command_key(&mut self, key: Key) {
+        match key {
+            Key::Esc => {
+                self.mode = Mode::View;
+            }
+            Key::Char('\n') => {
+                self.mode = Mode::View;
+                self.execute_command();
+            }
+            Key::Backspace => {
+                self.command.pop();
+            }
+            Key::Char(c) => {
+                self.command.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    /// Runs the command typed in the prompt
+    fn execute_command(&mut self) {
+        let command = self.command.trim().to_string();
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command.as_str(), ""),
+        };
+
+        // `:NNN` jumps to the line NNN
+        if let Ok(line) = name.parse::<usize>() {
+            self.record_jump();
+            self.goto(1, line);
+            return;
+        }
+
+        match name {
+            "" => {}
+            "q" | "quit" => self.quit(false),
+            "q!" | "quit!" => self.quit(true),
+            "w" | "write" => self.save(),
+            "wq" | "x" => {
+                self.save();
+                if !self.buf().dirty {
+                    self.quit(false);
+                }
+            }
+            "e" | "open" => self.open(argument),
+            "set" => self.set_option(argument),
+            "h" | "help" => self.show_help(),
+            _ => self.message = Some(format!("Unknown command: {}", name)),
+        }
+    }
+
+    /// Exits the viewer, unless there are unsaved changes and the user did not confirm it or `force` it
+    fn quit(&mut self, force: bool) {
+        if force || !self.buffers.iter().any(|buf| buf.dirty) || self.quit_pending {
+            self.quitting = true;
+            return;
+        }
+
+        self.quit_pending = true;
+        self.message = Some(
+            "Unsaved changes, quit again or :q! to discard them or save them first".to_string(),
+        );
+    }
+
+    /// Saves the active buffer, the footer tells whether it worked
+    fn save(&mut self) {
+        self.message = Some(match self.buf_mut().save() {
+            Ok(()) => format!("Saved {}", self.buf().file_name),
+            Err(e) => e,
+        });
+    }
+
+    /// Opens `file` in a new buffer and shows it, a file already open is shown again instead
+    fn open(&mut self, file: &str) {
+        if file.is_empty() {
+            self.message = Some("Missing file name".to_string());
+            return;
+        }
+        if self.diff.is_some() {
+            self.message = Some("No files can be opened while comparing files".to_string());
+            return;
+        }
+        if !Path::new(file).is_file() {
+            self.message = Some(format!("File does not exists: {}", file));
+            return;
+        }
+
+        let index = match self.buffers.iter().position(|buf| buf.file_name == file) {
+            Some(index) => index,
+            None => match Buffer::open(file) {
+                Ok(buf) => {
+                    self.buffers.push(buf);
+                    self.buffers.len() - 1
+                }
+                Err(e) => {
+                    self.message = Some(e.to_string());
+                    return;
+                }
+            },
+        };
+        self.switch_buffer(index);
+    }
+
+    /// Changes a setting with `:set`, named like the vim options
+    fn set_option(&mut self, option: &str) {
+        match option {
+            "wrap" => self.wrap = true,
+            "nowrap" => self.wrap = false,
+            "nu" | "number" => self.line_numbers = LineNumbers::Absolute,
+            "rnu" | "relativenumber" => self.line_numbers = LineNumbers::Relative,
+            "nonu" | "nonumber" | "nornu" | "norelativenumber" => {
+                self.line_numbers = LineNumbers::Off
+            }
+            _ => match option
+                .strip_prefix("tabwidth=")
+                .or_else(|| option.strip_prefix("ts="))
+                .map(str::parse::<usize>)
+            {
+                Some(Ok(width)) if width > 0 => self.config.tab_width = width,
+                Some(_) => self.message = Some(format!("Invalid tab width: {}", option)),
+                None => self.message = Some(format!("Unknown option: {}", option)),
+            },
+        }
+    }
+
+    /// Opens the help at its first line
+    fn show_help(&mut self) {
+        self.mode = Mode::Help;
+        self.help_offset = 0;
+    }
+
+    /// Lines of the help: the keys bound to each action, taken from the key bindings, and the commands of the prompt
+    fn help_lines(&self) -> Vec<String> {
+        let title = |text: &str| {
+            format!(
+                "{}{}{}",
+                SetAttribute(Attribute::Bold),
+                text,
+                SetAttribute(Attribute::Reset)
+            )
+        };
+
+        let mut lines = vec![title(" Keys")];
+        for action in Action::all() {
+            let mut keys: Vec<String> = self
+                .config
+                .keys
+                .iter()
+                .filter(|(_, bound)| **bound == action)
+                .map(|(key, _)| config::key_name(*key))
+                .collect();
+            keys.sort();
+            let keys = if keys.is_empty() {
+                "-".to_string()
+            } else {
+                keys.join(", ")
+            };
+
+            lines.push(format!("   {:<22}{}", keys, action.description()));
+        }
+
+        lines.push(String::new());
+        lines.push(title(" Commands"));
+        lines.extend(
+            COMMANDS
+                .iter()
+                .map(|(command, description)| format!("   {:<22}{}", command, description)),
+        );
+
+        lines
+    }
+
+    /// Handles a key pressed while the help is shown
+    fn handle_help_key(&mut self, key: Key) {
+        let page = self.text_height() as isize;
+
+        match key {
+            Key::Up => self.scroll_help(-1),
+            Key::Down => self.scroll_help(1),
+            Key::PageUp => self.scroll_help(-page),
+            Key::PageDown => self.scroll_help(page),
+            Key::Home => self.help_offset = 0,
+            Key::End => self.scroll_help(isize::MAX),
+            // The keys showing the help close it too
+            Key::Esc | Key::Char('q') | Key::Char('\n') => self.mode = Mode::View,
+            _ if self.config.keys.get(&key) == Some(&Action::Help) => self.mode = Mode::View,
+            _ => {}
+        }
+    }
+
+    /// Moves the help `lines` down (or up when negative), the last page stays full
+    fn scroll_help(&mut self, lines: isize) {
+        let last = self.help_lines().len().saturating_sub(self.text_height());
+        self.help_offset = self.help_offset.saturating_add_signed(lines).min(last);
+    }
+
+    /// Sets or jumps to the mark named by the key typed after `m` or `'`
+    fn handle_mark_key(&mut self, key: Key) {
+        let set = self.mode == Mode::SetMark;
+        self.mode = Mode::View;
+
+        let Key::Char(name) = key else {
+            return;
+        };
+        if !name.is_alphanumeric() {
+            return;
+        }
+
+        if set {
+            let buf = self.buf_mut();
+            let pos = (buf.cur_pos.y, buf.cur_pos.x);
+            buf.marks.insert(name, pos);
+            self.message = Some(format!("Mark {} set", name));
+        } else if let Some(&(y, x)) = self.buf().marks.get(&name) {
+            self.record_jump();
+            self.goto(x, y);
+        } else {
+            self.message = Some(format!("Mark {} is not set", name));
+        }
+    }
+
+    /// Adds the cursor position to the jump list of the buffer
+    fn record_jump(&mut self) {
+        let buf = self.buf_mut();
+        let pos = (buf.cur_pos.y, buf.cur_pos.x);
+        buf.record_jump(pos);
+    }
+
+    /// Moves the cursor to the column `x` of the line `y`, which are kept within the document
+    fn goto(&mut self, x: usize, y: usize) {
+        self.buf().doc.ensure_indexed(y);
+        self.set_pos(x, y.clamp(1, self.buf().doc_length().max(1)));
+    }
+
+    /// Opens the search prompt remembering where the cursor was
+    fn start_search(&mut self) {
+        self.mode = Mode::Search;
+
+        let buf = self.buf_mut();
+        buf.search = Search {
+            origin: (buf.cur_pos.x, buf.cur_pos.y),
+            ..Search::default()
+        };
+    }
+
+    /// Handles a key pressed while the search prompt is open
+    fn handle_search_key(&mut self, key: Key) {
+        match key {
+            Key::Esc => {
+                // Cancel the search and go back to where the search started
+                let (x, y) = self.buf().search.origin;
+                self.buf_mut().search = Search::default();
+                self.mode = Mode::View;
+                self.set_pos(x, y);
+            }
+            Key::Char('\n') => {
+                // Keep the pattern and jump to the next match, the jump list remembers where the search started
+                self.mode = Mode::View;
+                let (x, y) = self.buf().search.origin;
+                self.buf_mut().record_jump((y, x));
+                self.next_match(true);
+            }
+            Key::Backspace => {
+                self.buf_mut().search.pattern.pop();
+                self.update_matches();
+            }
+            Key::Char(c) => {
+                self.buf_mut().search.pattern.push(c);
+                self.update_matches();
+            }
+            _ => {}
+        }
+    }
+
+    /// Looks for the pattern in every line and moves the cursor to the first match after the search origin
+    fn update_matches(&mut self) {
+        self.buf_mut().find_matches();
+
+        let (x, y) = self.buf().search.origin;
+        let first = self
+            .buf()
+            .search
+            .matches
+            .iter()
+            .position(|&(line, column)| (line + 1, column + 1) >= (y, x))
+            .or(if self.buf().search.matches.is_empty() {
+                None
+            } else {
+                Some(0)
+            });
+
+        match first {
+            Some(i) => self.jump_to_match(i),
+            None => self.set_pos(x, y),
+        }
+    }
+
+    /// Moves to the next match (or the previous one when `forward` is false), wrapping around the document
+    fn next_match(&mut self, forward: bool) {
+        let total = self.buf().search.matches.len();
+        if total == 0 {
+            return;
+        }
+
+        let next = match self.buf().search.current {
+            Some(i) if forward => (i + 1) % total,
+            Some(i) => (i + total - 1) % total,
+            None => 0,
+        };
+
+        self.jump_to_match(next);
+    }
+
+    /// Places the cursor at the start of the match number `index`
+    fn jump_to_match(&mut self, index: usize) {
+        let (line, column) = self.buf().search.matches[index];
+
+        self.buf_mut().search.current = Some(index);
+        self.set_pos(column + 1, line + 1);
+    }
+
+    /// Width of the line numbers gutter, including the space that separates it from the text
+    fn gutter_width(&self) -> usize {
+        match self.line_numbers {
+            LineNumbers::Off => 0,
+            _ => self.buf().doc_length().max(1).to_string().len() + 1,
+        }
+    }
+
+    /// Number of columns available for the text, next to the gutter
+    fn text_width(&self) -> usize {
+        self.pane_width().saturating_sub(self.gutter_width()).max(1)
+    }
+
+    /// Number of columns of the pane showing the active buffer, the panes of a split view are separated by a column
+    fn pane_width(&self) -> usize {
+        match self.split {
+            Some(_) => self.terminal_size.x.saturating_sub(1) / 2,
+            None => self.terminal_size.x,
+        }
+    }
+
+    /// Screen column where the pane showing the active buffer starts
+    fn pane_start(&self) -> usize {
+        match self.split {
+            Some((_, right)) if right == self.active => self.pane_width() + 1,
+            _ => 0,
+        }
+    }
+
+    /// Number of screen rows taken by the line number `line` of the document
+    fn line_rows(&self, line: usize) -> usize {
+        if self.wrap {
+            let text = self.buf().doc.line(line).unwrap_or_default();
+            self.display_width(&text, usize::MAX)
+                .div_ceil(self.text_width())
+                .max(1)
+        } else {
+            1
+        }
+    }
+
+    /// Number of screen columns taken by the first `chars` characters of `text`, tabs reach the next tab stop
+    fn display_width(&self, text: &str, chars: usize) -> usize {
+        text.chars().take(chars).fold(0, |column, c| match c {
+            '\t' => column + self.config.tab_width - column % self.config.tab_width,
+            _ => column + 1,
+        })
+    }
+
+    /// Replaces the tabs of `text` with spaces up to the next tab stop, other control characters are shown as `.`
+    /// so they cannot mess up the terminal
+    fn expand_tabs(&self, text: &str) -> String {
+        let mut expanded = String::with_capacity(text.len());
+        for c in text.chars() {
+            if c == '\t' {
+                let spaces =
+                    self.config.tab_width - expanded.chars().count() % self.config.tab_width;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+            } else if c.is_control() {
+                expanded.push('.');
+            } else {
+                expanded.push(c);
+            }
+        }
+        expanded
+    }
+
+    /// Screen column of the cursor in its line (starting at 1), before scrolling
+    fn display_x(&self) -> usize {
+        let buf = self.buf();
+        let text = buf
+            .doc
+            .line(buf.cur_pos.y.saturating_sub(1))
+            .unwrap_or_default();
+        self.display_width(&text, buf.cur_pos.x - 1) + 1
+    }
+
+    /// Number of lines shown in the footer, a `+` marks large files not fully indexed yet
+    fn line_count(buf: &Buffer) -> String {
+        match buf.doc.is_complete() {
+            true => buf.doc_length().to_string(),
+            false => format!("{}+", buf.doc_length()),
+        }
+    }
+
+    /// Footer with the mode, the file name and its state on the left and the cursor position on the right,
+    /// filling the terminal width
+    fn status_bar(&self) -> String {
+        let buf = self.buf();
+
+        let mode = match self.mode {
+            Mode::Insert => "EDIT",
+            Mode::Search => "SEARCH",
+            Mode::SetMark | Mode::GotoMark => "MARK",
+            _ if self.follow => "FOLLOW",
+            _ => "VIEW",
+        };
+        let mut left = format!(" {} | {}", mode, buf.file_name);
+        if buf.is_hex() {
+            left.push_str(" [hex]");
+        }
+        if buf.is_read_only() {
+            left.push_str(" [read-only]");
+        }
+        if buf.dirty {
+            left.push_str(" [modified]");
+        }
+        if !buf.search.pattern.is_empty() {
+            let current = buf.search.current.map_or(0, |i| i + 1);
+            left.push_str(&format!(
+                " | match {}/{} for /{}",
+                current,
+                buf.search.matches.len(),
+                buf.search.pattern
+            ));
+        }
+
+        // The percentage is only known once the whole document has been read
+        let percent = match (buf.doc.is_complete(), buf.doc_length()) {
+            (false, _) => "?".to_string(),
+            (true, 0) => "100".to_string(),
+            (true, length) => (buf.cur_pos.y * 100 / length).to_string(),
+        };
+        let right = format!(
+            "Ln {}/{}, Col {} | {}% ",
+            buf.cur_pos.y,
+            Self::line_count(buf),
+            buf.cur_pos.x,
+            percent
+        );
+
+        let width = self.terminal_size.x;
+        let space = width.saturating_sub(right.chars().count());
+        let left: String = left.chars().take(space.saturating_sub(1)).collect();
+        format!("{:<space$}{}", left, right, space = space)
+    }
+
+    /// Returns the line number `line` of the document as displayed: with its gutter, clipped to the
+    /// terminal width (or split in rows when wrapping) and with the search matches highlighted
+    fn render_line(&self, line: usize) -> Vec<String> {
+        let width = self.gutter_width();
+        let gutter = match (self.line_numbers, &self.diff) {
+            (LineNumbers::Off, _) => String::new(),
+            // Compared files show the number of the line in its file, the rows aligning the other file have none
+            (_, Some(diff)) => {
+                let row = diff.rows.get(line);
+                let number = if self.active == 0 {
+                    row.and_then(|row| row.left)
+                } else {
+                    row.and_then(|row| row.right)
+                };
+                number.map_or(" ".repeat(width), |number| {
+                    format!("{:>w$} ", number + 1, w = width - 1)
+                })
+            }
+            (LineNumbers::Relative, None) if line + 1 != self.buf().cur_pos.y => {
+                format!(
+                    "{:>w$} ",
+                    (line + 1).abs_diff(self.buf().cur_pos.y),
+                    w = width - 1
+                )
+            }
+            _ => format!("{:>w$} ", line + 1, w = width - 1),
+        };
+
+        // Compared lines are colored by how they changed
+        let change = self
+            .diff
+            .as_ref()
+            .and_then(|diff| diff.rows.get(line))
+            .map(|row| row.change)
+            .unwrap_or(Change::Same);
+        let background = match change {
+            Change::Same => None,
+            Change::Removed => Some(self.config.theme.diff_removed),
+            Change::Added => Some(self.config.theme.diff_added),
+            Change::Changed => Some(self.config.theme.diff_changed),
+        };
+
+        let chars: Vec<char> = self
+            .expand_tabs(&self.buf().doc.line(line).unwrap_or_default())
+            .chars()
+            .collect();
+        let mut chunks: Vec<String> = if self.wrap {
+            chars
+                .chunks(self.text_width())
+                .map(|chunk| chunk.iter().collect())
+                .collect()
+        } else {
+            vec![chars
+                .iter()
+                .skip(self.buf().col_offset)
+                .take(self.text_width())
+                .collect()]
+        };
+
+        if chunks.is_empty() {
+            chunks.push(String::new());
+        }
+
+        chunks
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                // Only the first row of a wrapped line shows its number
+                let gutter = if i == 0 {
+                    gutter.clone()
+                } else {
+                    " ".repeat(width)
+                };
+
+                // The panes of a split view are padded so the separator stays aligned
+                let text = match self.split {
+                    Some(_) => format!("{:<w$}", text, w = self.text_width()),
+                    None => text.clone(),
+                };
+                let text = match background {
+                    Some(color) => format!(
+                        "{}{}{}",
+                        SetBackgroundColor(color),
+                        text,
+                        SetAttribute(Attribute::Reset)
+                    ),
+                    None => self.highlight(&text),
+                };
+
+                format!(
+                    "{}{}{}{}",
+                    SetForegroundColor(self.config.theme.gutter),
+                    gutter,
+                    SetAttribute(Attribute::Reset),
+                    text
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the text with the search matches highlighted
+    fn highlight(&self, text: &str) -> String {
+        if self.buf().search.pattern.is_empty() {
+            return text.to_string();
+        }
+
+        let theme = &self.config.theme;
+        let highlighted = format!(
+            "{}{}{}{}",
+            SetBackgroundColor(theme.match_bg),
+            SetForegroundColor(theme.match_fg),
+            self.buf().search.pattern,
+            SetAttribute(Attribute::Reset)
+        );
+
+        text.replace(&self.buf().search.pattern, &highlighted)
+    }
+
+    /// Number of document lines that fit between the header and the footer
+    fn text_height(&self) -> usize {
+        self.terminal_size.y.saturating_sub(3).max(1)
+    }
+
+    /// Row of the cursor counted from the first row of the viewport
+    fn cursor_row(&self) -> usize {
+        let line = self.buf().cur_pos.y.saturating_sub(1);
+        let rows: usize = (self.buf().offset..line)
+            .map(|line| self.line_rows(line))
+            .sum();
+
+        if self.wrap {
+            rows + (self.display_x() - 1) / self.text_width()
+        } else {
+            rows
+        }
+    }
+
+    /// Helper method that scrolls the viewport so the cursor stays on the screen
+    fn scroll(&mut self) {
+        let line = self.buf().cur_pos.y.saturating_sub(1);
+
+        if line < self.buf().offset {
+            self.buf_mut().offset = line;
+        }
+        while self.buf().offset < line && self.cursor_row() >= self.text_height() {
+            self.buf_mut().offset += 1;
+        }
+
+        // Horizontal scrolling, wrapped lines always start at the first column
+        let column = self.display_x() - 1;
+        if self.wrap || column < self.buf().col_offset {
+            self.buf_mut().col_offset = if self.wrap { 0 } else { column };
+        } else if column >= self.buf().col_offset + self.text_width() {
+            self.buf_mut().col_offset = column + 1 - self.text_width();
+        }
+
+        self.sync_panes();
+    }
+
+    /// Compared files scroll together, the other pane follows the cursor line of the active one
+    fn sync_panes(&mut self) {
+        let (Some(_), Some((left, right))) = (&self.diff, self.split) else {
+            return;
+        };
+
+        let other = if self.active == left { right } else { left };
+        let (y, offset, col_offset) = (
+            self.buf().cur_pos.y,
+            self.buf().offset,
+            self.buf().col_offset,
+        );
+        let buf = &mut self.buffers[other];
+        buf.cur_pos.y = y.min(buf.doc_length().max(1));
+        buf.offset = offset;
+        buf.col_offset = col_offset;
+        buf.clamp_x();
+    }
+
+    /// Helper method that places the on-screen cursor at the cursor position, past the header and the line numbers gutter
+    fn move_cursor(&mut self) {
+        self.scroll();
+
+        let (column, row) = self.cursor_position();
+        print!("{}", cursor::MoveTo(column, row));
+    }
+
+    /// Column and row of the screen (starting at 0) where the cursor is shown
+    fn cursor_position(&self) -> (u16, u16) {
+        let column = if self.wrap {
+            (self.display_x() - 1) % self.text_width()
+        } else {
+            self.display_x() - 1 - self.buf().col_offset
+        };
+
+        (
+            (self.pane_start() + column + self.gutter_width()) as u16,
+            (self.cursor_row() + 1) as u16,
+        )
     }
 
     /// Helper method that synchronizes the internal cursor tracking field (the cur_pos field of the TextViewer strcut)
     ///  and the on-screen cursor position
     fn set_pos(&mut self, x: usize, y: usize) {
-        self.cur_pos.x = x;
-        self.cur_pos.y = y;
+        self.buf_mut().cur_pos.x = x;
+        self.buf_mut().cur_pos.y = y;
+        self.buf_mut().clamp_x();
 
-        println!("{}", termion::cursor::Goto(self.cur_pos.x as u16, self.cur_pos.y as u16));
+        self.move_cursor();
     }
 
-    /// Helper method decrement the coordinate x and repositionate the cursor on the screen 
+    /// Helper method decrement the coordinate x and repositionate the cursor on the screen
     fn dec_x(&mut self) {
-        if self.cur_pos.x > 1 {
-            self.cur_pos.x -= 1;
+        if self.buf().cur_pos.x > 1 {
+            self.buf_mut().cur_pos.x -= 1;
         }
 
-        println!("{}", termion::cursor::Goto(self.cur_pos.x as u16, self.cur_pos.y as u16));
+        self.move_cursor();
     }
 
-    /// Helper method decrement the coordinate y and repositionate the cursor on the screen 
+    /// Helper method decrement the coordinate y and repositionate the cursor on the screen
     fn dec_y(&mut self) {
-        if self.cur_pos.y > 1 {
-            self.cur_pos.y -= 1;
+        if self.buf().cur_pos.y > 1 {
+            self.buf_mut().cur_pos.y -= 1;
         }
+        self.buf_mut().clamp_x();
 
-        println!("{}", termion::cursor::Goto(self.cur_pos.x as u16, self.cur_pos.y as u16));
+        self.move_cursor();
     }
 
-    /// Helper method increment the coordinate x and repositionate the cursor on the screen 
+    /// Helper method increment the coordinate x and repositionate the cursor on the screen
     fn inc_x(&mut self) {
-        if self.cur_pos.x < self.terminal_size.x {
-            self.cur_pos.x += 1;
+        if self.buf().cur_pos.x <= self.buf().line_len(self.buf().cur_pos.y - 1) {
+            self.buf_mut().cur_pos.x += 1;
         }
 
-        println!("{}", termion::cursor::Goto(self.cur_pos.x as u16, self.cur_pos.y as u16));        
+        self.move_cursor();
     }
 
-    /// Helper method increment the coordinate y and repositionate the cursor on the screen 
+    /// Helper method increment the coordinate y and repositionate the cursor on the screen
     fn inc_y(&mut self) {
-        if self.cur_pos.y < self.doc_length {
-            self.cur_pos.y += 1;
+        self.buf().doc.ensure_indexed(self.buf().cur_pos.y + 1);
+        if self.buf().cur_pos.y < self.buf().doc_length() {
+            self.buf_mut().cur_pos.y += 1;
         }
+        self.buf_mut().clamp_x();
 
-        println!("{}", termion::cursor::Goto(self.cur_pos.x as u16, self.cur_pos.y as u16));
+        self.move_cursor();
     }
-
-}
\ No newline at end of file
+}
@@ -1,134 +1,247 @@
 pub mod errors;
+pub mod language;
 
-use std::ffi;
+use binaryornot;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path;
 use std::vec;
-use std::fmt;
-use binaryornot;
 
 use errors::StatsError;
+use language::{Language, LineKind};
 
 /// Code metrics definition
+#[derive(Clone, Default)]
 pub struct SrcStats {
-    number_of_files:    u32,
-    lines_of_code:      u32,
-    comments:           u32,
-    blanks:             u32,
+    number_of_files: u32,
+    lines_of_code: u32,
+    comments: u32,
+    blanks: u32,
+}
+
+impl SrcStats {
+    /// Adds the metrics of `other` to these ones
+    fn add(&mut self, other: &SrcStats) {
+        self.number_of_files += other.number_of_files;
+        self.lines_of_code += other.lines_of_code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+    }
 }
 
 /// Binary medtrics definition
 pub struct BinStats {
     number_of_files: u32,
-    weight:          u32,
+    weight: u32,
 }
 
 impl fmt::Display for SrcStats {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, " >>> number_of_files: {},
+        write!(
+            f,
+            " >>> number_of_files: {},
                 >>> loc: {},
                 >>> comments: {},
                 >>> blanks: {}",
-         self.number_of_files, self.lines_of_code, self.comments, self.blanks)
+            self.number_of_files, self.lines_of_code, self.comments, self.blanks
+        )
     }
 }
 
 impl fmt::Display for BinStats {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, " >>> number_of_files: {},
-                >>> weight: {} KB", self.number_of_files, self.weight)
+        write!(
+            f,
+            " >>> number_of_files: {},
+                >>> weight: {} KB",
+            self.number_of_files, self.weight
+        )
     }
 }
 
-/// Calculate source metrics for single file
-fn get_src_stats_for_file(file: &path::Path) -> Result<SrcStats, StatsError> {
+/// Calculate source metrics for single file with the comment rules of its language
+fn get_src_stats_for_file(file: &path::Path, language: Language) -> Result<SrcStats, StatsError> {
     let file_contents = fs::read_to_string(file)?;
 
     let mut loc = 0;
     let mut blanks = 0;
-    let mut comments =0;
+    let mut comments = 0;
+    let mut in_block = false;
 
     for line in file_contents.lines() {
-        if line.len() == 0 {
-            blanks += 1;
-        } else if line.trim_start().starts_with("//") {
-            comments += 1;
-        } else {
-            loc += 1;
+        match language.classify(line, &mut in_block) {
+            LineKind::Blank => blanks += 1,
+            LineKind::Comment => comments += 1,
+            LineKind::Code => loc += 1,
         }
     }
 
     Ok(SrcStats {
-        number_of_files: u32::try_from(file_contents.lines().count())?,
+        number_of_files: 1,
         lines_of_code: loc,
         comments,
-        blanks  
+        blanks,
     })
 }
 
 /// Calculate binary metrics for single file
 fn get_bin_stats_for_file(file: &path::Path) -> Result<BinStats, StatsError> {
-    let weight = file.metadata().unwrap().len()/1000;
+    let weight = file.metadata().unwrap().len() / 1000;
 
     Ok(BinStats {
         number_of_files: 0,
         weight: u32::try_from(weight)?,
-        
     })
 }
 
+/// Recursively iterate over directory entries to get flat list of the files of a known language
+fn find_src_files(folder: &path::Path) -> Result<Vec<(path::PathBuf, Language)>, StatsError> {
+    let mut dir_entries: Vec<path::PathBuf> = vec![folder.to_path_buf()];
+    let mut file_entries: Vec<(path::PathBuf, Language)> = vec![];
+
+    while let Some(entry) = dir_entries.pop() {
+        for entry in fs::read_dir(&entry)?.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                dir_entries.push(path);
+            } else if let Some(language) = Language::from_path(&path) {
+                file_entries.push((path, language));
+            }
+        }
+    }
+
+    Ok(file_entries)
+}
+
 /// Calculate source metrics for all files in a directory root
 pub fn get_summary_src_stats(folder: &path::Path) -> Result<SrcStats, StatsError> {
+    let mut total = SrcStats::default();
 
-    let mut total_loc = 0;
-    let mut total_comments = 0;
-    let mut total_blanks = 0;
+    // Compute stats
+    for (file, language) in find_src_files(folder)? {
+        total.add(&get_src_stats_for_file(&file, language)?);
+    }
 
-    let mut dir_entries: Vec<path::PathBuf> = vec![folder.to_path_buf()]; 
-    let mut file_entries: Vec<fs::DirEntry> = vec![];
+    Ok(total)
+}
 
-    // Recursively iterate over directory entries to get flat
-    // list of .rs file
-    while let Some(entry) = dir_entries.pop() {
-        for inner_entry in fs::read_dir(&entry)? {
-            if let Ok(entry) = inner_entry {
-                if entry.path().is_dir() {
-                    dir_entries.push(entry.path());
-                } else {
-                    if entry.path().extension() == Some(ffi::OsStr::new("rs")) {
-                        file_entries.push(entry);
-                    }
-                }
-            }
+/// Calculate source metrics for each file in a directory root, the largest files first
+pub fn get_detail_src_stats(
+    folder: &path::Path,
+) -> Result<Vec<(path::PathBuf, SrcStats)>, StatsError> {
+    let mut stats = vec![];
+
+    for (file, language) in find_src_files(folder)? {
+        let stat = get_src_stats_for_file(&file, language)?;
+        stats.push((file, stat));
+    }
+
+    sort_by_loc(&mut stats);
+
+    Ok(stats)
+}
+
+/// Aggregate the metrics of the files by the directory that holds them, the largest directories first
+pub fn group_by_dir(files: &[(path::PathBuf, SrcStats)]) -> Vec<(path::PathBuf, SrcStats)> {
+    let mut dirs: HashMap<path::PathBuf, SrcStats> = HashMap::new();
+
+    for (file, stat) in files {
+        let dir = file.parent().unwrap_or(path::Path::new("")).to_path_buf();
+        dirs.entry(dir).or_default().add(stat);
+    }
+
+    let mut dirs: Vec<(path::PathBuf, SrcStats)> = dirs.into_iter().collect();
+    sort_by_loc(&mut dirs);
+
+    dirs
+}
+
+/// Aggregate the metrics of the files by their language, the largest languages first
+pub fn group_by_language(files: &[(path::PathBuf, SrcStats)]) -> Vec<(Language, SrcStats)> {
+    let mut languages: HashMap<Language, SrcStats> = HashMap::new();
+
+    for (file, stat) in files {
+        // Only the files of a known language are analysed
+        if let Some(language) = Language::from_path(file) {
+            languages.entry(language).or_default().add(stat);
         }
     }
-    
-    let file_count = file_entries.len();
 
-    // Compute stats
-    for entry in file_entries {
-        let stat = get_src_stats_for_file(&entry.path())?;
+    let mut languages: Vec<(Language, SrcStats)> = languages.into_iter().collect();
+    languages.sort_by(|(a_language, a), (b_language, b)| {
+        b.lines_of_code
+            .cmp(&a.lines_of_code)
+            .then_with(|| a_language.cmp(b_language))
+    });
 
-        total_blanks += stat.blanks;
-        total_comments += stat.comments;
-        total_loc += stat.lines_of_code;
+    languages
+}
+
+/// Sum the metrics of several files or directories
+pub fn total_src_stats(stats: &[(path::PathBuf, SrcStats)]) -> SrcStats {
+    let mut total = SrcStats::default();
+
+    for (_, stat) in stats {
+        total.add(stat);
     }
 
-    Ok(SrcStats {
-        number_of_files: u32::try_from(file_count)?,
-        lines_of_code: total_loc,
-        comments: total_comments,
-        blanks: total_blanks,
-    })
+    total
+}
+
+/// Sort by lines of code, the largest first, and by path when they have the same size
+fn sort_by_loc(stats: &mut [(path::PathBuf, SrcStats)]) {
+    stats.sort_by(|(a_path, a), (b_path, b)| {
+        b.lines_of_code
+            .cmp(&a.lines_of_code)
+            .then_with(|| a_path.cmp(b_path))
+    });
+}
+
+/// Table with one row of metrics per path
+pub fn format_detail(title: &str, stats: &[(path::PathBuf, SrcStats)]) -> String {
+    let rows: Vec<(path::Display, &SrcStats)> = stats
+        .iter()
+        .map(|(path, stat)| (path.display(), stat))
+        .collect();
+
+    format_table(title, &rows)
+}
+
+/// Table with one row of metrics per language
+pub fn format_languages(stats: &[(Language, SrcStats)]) -> String {
+    let rows: Vec<(&Language, &SrcStats)> = stats
+        .iter()
+        .map(|(language, stat)| (language, stat))
+        .collect();
+
+    format_table("language", &rows)
+}
+
+/// Table with one row of metrics per name
+fn format_table<T: fmt::Display>(title: &str, rows: &[(T, &SrcStats)]) -> String {
+    let mut table = format!(
+        "{:>8} {:>8} {:>8} {:>8}  {}\n",
+        "files", "loc", "comments", "blanks", title
+    );
+
+    for (name, stat) in rows {
+        table.push_str(&format!(
+            "{:>8} {:>8} {:>8} {:>8}  {}\n",
+            stat.number_of_files, stat.lines_of_code, stat.comments, stat.blanks, name
+        ));
+    }
+
+    table
 }
 
 /// Calculate binary metrics for all files in a directory root
 pub fn get_summary_bin_stats(folder: &path::Path) -> Result<BinStats, StatsError> {
-
     let mut total_weight: u32 = 0;
 
-    let mut dir_entries: Vec<path::PathBuf> = vec![folder.to_path_buf()]; 
+    let mut dir_entries: Vec<path::PathBuf> = vec![folder.to_path_buf()];
     let mut bin_entries: Vec<fs::DirEntry> = vec![];
 
     // Recursively iterate over directory entries to get flat
@@ -148,8 +261,8 @@ pub fn get_summary_bin_stats(folder: &path::Path) -> Result<BinStats, StatsError
                                         bin_entries.push(entry);
                                     }
                                 }
-                            },
-                            Err(_) => {},
+                            }
+                            Err(_) => {}
                         }
                     }
                 }
@@ -172,3 +285,66 @@ pub fn get_summary_bin_stats(folder: &path::Path) -> Result<BinStats, StatsError
     })
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(loc: u32, comments: u32, blanks: u32) -> SrcStats {
+        SrcStats {
+            number_of_files: 1,
+            lines_of_code: loc,
+            comments,
+            blanks,
+        }
+    }
+
+    fn summary(stat: &SrcStats) -> (u32, u32, u32, u32) {
+        (
+            stat.number_of_files,
+            stat.lines_of_code,
+            stat.comments,
+            stat.blanks,
+        )
+    }
+
+    #[test]
+    fn test_group_by_dir() {
+        let files = vec![
+            (path::PathBuf::from("src/lib.rs"), stats(10, 2, 1)),
+            (path::PathBuf::from("src/main.rs"), stats(5, 1, 0)),
+            (path::PathBuf::from("tests/cli.rs"), stats(15, 0, 3)),
+            (path::PathBuf::from("benches/bench.rs"), stats(15, 4, 2)),
+            (path::PathBuf::from("build.rs"), stats(1, 0, 0)),
+        ];
+
+        let dirs: Vec<(path::PathBuf, (u32, u32, u32, u32))> = group_by_dir(&files)
+            .iter()
+            .map(|(dir, stat)| (dir.clone(), summary(stat)))
+            .collect();
+
+        // The largest first, the ties sorted by path
+        assert_eq!(
+            dirs,
+            vec![
+                (path::PathBuf::from("benches"), (1, 15, 4, 2)),
+                (path::PathBuf::from("src"), (2, 15, 3, 1)),
+                (path::PathBuf::from("tests"), (1, 15, 0, 3)),
+                (path::PathBuf::from(""), (1, 1, 0, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_total_src_stats() {
+        assert_eq!(summary(&total_src_stats(&[])), (0, 0, 0, 0));
+
+        let files = vec![
+            (path::PathBuf::from("src/lib.rs"), stats(10, 2, 1)),
+            (path::PathBuf::from("src/main.rs"), stats(5, 1, 0)),
+            (path::PathBuf::from("tests/cli.rs"), stats(15, 0, 3)),
+        ];
+
+        // One file each, not its number of lines
+        assert_eq!(summary(&total_src_stats(&files)), (3, 30, 3, 4));
+    }
+}
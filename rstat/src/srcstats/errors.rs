@@ -1,21 +1,16 @@
-use std::{
-    fmt,
-    io,
-    num,
-};
+use std::{fmt, io, num};
 
 #[derive(Debug)]
-pub struct StatsError{
+pub struct StatsError {
     pub warn: String,
 }
 
-
 impl fmt::Display for StatsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self)
     }
 }
-    
+
 impl From<&str> for StatsError {
     fn from(value: &str) -> Self {
         StatsError {
@@ -28,7 +23,7 @@ impl From<io::Error> for StatsError {
     fn from(value: io::Error) -> Self {
         StatsError {
             warn: value.to_string(),
-        }   
+        }
     }
 }
 
@@ -38,4 +33,4 @@ impl From<num::TryFromIntError> for StatsError {
             warn: "Number conversion error".to_string(),
         }
     }
-}
\ No newline at end of file
+}
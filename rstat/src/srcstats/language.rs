@@ -0,0 +1,218 @@
+use std::fmt;
+use std::path;
+
+/// Languages of the source files, selected by the extension of the file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Language {
+    Rust,
+    C,
+    Cpp,
+    Python,
+    Shell,
+    Toml,
+}
+
+/// Kind of a line of a source file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineKind {
+    Code,
+    Comment,
+    Blank,
+}
+
+/// Comment syntax of a language
+struct Syntax {
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+}
+
+impl Language {
+    /// Language of a file by its extension, `None` for the files that are not sources
+    pub fn from_path(file: &path::Path) -> Option<Language> {
+        let extension = file.extension()?.to_str()?;
+
+        match extension {
+            "rs" => Some(Language::Rust),
+            "c" | "h" => Some(Language::C),
+            "cc" | "cpp" | "cxx" | "hh" | "hpp" => Some(Language::Cpp),
+            "py" => Some(Language::Python),
+            "sh" | "bash" => Some(Language::Shell),
+            "toml" => Some(Language::Toml),
+            _ => None,
+        }
+    }
+
+    fn syntax(self) -> Syntax {
+        match self {
+            Language::Rust | Language::C | Language::Cpp => Syntax {
+                line: &["//"],
+                block: Some(("/*", "*/")),
+            },
+            Language::Python | Language::Shell | Language::Toml => Syntax {
+                line: &["#"],
+                block: None,
+            },
+        }
+    }
+
+    /// Classify a line, `in_block` tells whether a block comment of a previous line is still open
+    /// and it is updated for the next line. A line with any code outside the comments is code.
+    /// Comment markers inside strings are not told apart from the real ones
+    pub fn classify(self, line: &str, in_block: &mut bool) -> LineKind {
+        let syntax = self.syntax();
+        let mut rest = line.trim();
+        let mut comment = false;
+        let mut code = false;
+
+        if rest.is_empty() {
+            return LineKind::Blank;
+        }
+
+        loop {
+            if *in_block {
+                comment = true;
+
+                // The block is open until its end, which is always set when in a block
+                let (_, end) = syntax.block.unwrap_or(("", ""));
+                match rest.find(end) {
+                    Some(i) => {
+                        rest = rest[i + end.len()..].trim_start();
+                        *in_block = false;
+                    }
+                    None => break,
+                }
+            }
+
+            if rest.is_empty() {
+                break;
+            }
+
+            // The earliest comment marker wins: a block start after a line comment is commented out
+            let line_at = syntax
+                .line
+                .iter()
+                .filter_map(|start| rest.find(start))
+                .min();
+            let block_at = syntax
+                .block
+                .and_then(|(start, _)| Some((rest.find(start)?, start)));
+
+            match (line_at, block_at) {
+                (Some(i), Some((j, _))) if i < j => {
+                    code |= i > 0;
+                    comment = true;
+                    break;
+                }
+                (Some(i), None) => {
+                    code |= i > 0;
+                    comment = true;
+                    break;
+                }
+                (_, Some((j, start))) => {
+                    code |= j > 0;
+                    rest = &rest[j + start.len()..];
+                    *in_block = true;
+                }
+                (None, None) => {
+                    code = true;
+                    break;
+                }
+            }
+        }
+
+        if code {
+            LineKind::Code
+        } else if comment {
+            LineKind::Comment
+        } else {
+            LineKind::Blank
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Language::Rust => "Rust",
+            Language::C => "C",
+            Language::Cpp => "C++",
+            Language::Python => "Python",
+            Language::Shell => "Shell",
+            Language::Toml => "TOML",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kinds of the lines of a source, the block comments carried from line to line
+    fn classify_all(language: Language, source: &str) -> Vec<LineKind> {
+        let mut in_block = false;
+
+        source
+            .lines()
+            .map(|line| language.classify(line, &mut in_block))
+            .collect()
+    }
+
+    #[test]
+    fn test_classify_line_comments() {
+        use LineKind::*;
+
+        let source = "// header\nlet x = 1; // see /* note\nlet y = 2;\n    //indented";
+        assert_eq!(
+            classify_all(Language::Rust, source),
+            vec![Comment, Code, Code, Comment]
+        );
+    }
+
+    #[test]
+    fn test_classify_block_comments() {
+        use LineKind::*;
+
+        let source = "/* inline */\nlet x = /* inline */ 1;\n/* one */ /* two */\nlet y = 2; /* open\n still\n*/ let z = 3;\n/* open\n*/\n//* line";
+        assert_eq!(
+            classify_all(Language::C, source),
+            vec![
+                Comment, Code, Comment, Code, Comment, Code, Comment, Comment, Comment
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_hash_comments() {
+        use LineKind::*;
+
+        let source = "# comment\nname = \"x\" # trailing\n/* not a comment */\n  #indented";
+        assert_eq!(
+            classify_all(Language::Python, source),
+            vec![Comment, Code, Code, Comment]
+        );
+        assert_eq!(
+            classify_all(Language::Toml, source),
+            classify_all(Language::Shell, source)
+        );
+    }
+
+    #[test]
+    fn test_classify_blanks() {
+        use LineKind::*;
+
+        let source = "\n   \n\t \nx\n";
+        assert_eq!(
+            classify_all(Language::Rust, source),
+            vec![Blank, Blank, Blank, Code]
+        );
+
+        // Blank lines inside a block comment are still blank
+        let source = "/*\n\n*/";
+        assert_eq!(
+            classify_all(Language::Cpp, source),
+            vec![Comment, Blank, Comment]
+        );
+    }
+}
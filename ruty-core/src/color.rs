@@ -0,0 +1,127 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+
+pub use ansi_term::{Colour, Style};
+
+/// When to color the output, the value of the `--color` flag of the tools.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colors when writing to a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color an output that is or is not a terminal.
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        self.enabled_with(is_terminal, env::var_os("NO_COLOR"))
+    }
+
+    /// `NO_COLOR` only turns colors off when it is set to a non empty value, see <https://no-color.org>.
+    fn enabled_with(self, is_terminal: bool, no_color: Option<OsString>) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => is_terminal && no_color.is_none_or(|value| value.is_empty()),
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "invalid color \"{}\", expected auto, always or never",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let choice = match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        };
+        write!(f, "{}", choice)
+    }
+}
+
+/// Styles text only when colors are enabled for the output it is written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Painter {
+    enabled: bool,
+}
+
+impl Painter {
+    pub fn new(enabled: bool) -> Self {
+        Painter { enabled }
+    }
+
+    /// Painter of the text written to stdout.
+    pub fn stdout(choice: ColorChoice) -> Self {
+        Painter::new(choice.enabled(io::stdout().is_terminal()))
+    }
+
+    /// Painter of the text written to stderr.
+    pub fn stderr(choice: ColorChoice) -> Self {
+        Painter::new(choice.enabled(io::stderr().is_terminal()))
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the text with the style, or unchanged when colors are disabled.
+    ///
+    /// ```
+    /// use ruty_core::color::{Painter, Style};
+    ///
+    /// assert_eq!(Painter::new(true).paint(Style::new().reverse(), "7"), "\u{1b}[7m7\u{1b}[0m");
+    /// assert_eq!(Painter::new(false).paint(Style::new().reverse(), "7"), "7");
+    /// ```
+    pub fn paint(&self, style: Style, text: &str) -> String {
+        if self.enabled {
+            style.paint(text).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_choice_enabled() {
+        assert!(ColorChoice::Auto.enabled_with(true, None));
+        assert!(!ColorChoice::Auto.enabled_with(false, None));
+        assert!(!ColorChoice::Auto.enabled_with(true, Some("1".into())));
+        assert!(ColorChoice::Auto.enabled_with(true, Some("".into())));
+
+        assert!(ColorChoice::Always.enabled_with(false, Some("1".into())));
+        assert!(!ColorChoice::Never.enabled_with(true, None));
+    }
+
+    #[test]
+    fn test_parse_color_choice() {
+        assert_eq!("auto".parse(), Ok(ColorChoice::Auto));
+        assert_eq!("always".parse(), Ok(ColorChoice::Always));
+        assert_eq!("never".parse(), Ok(ColorChoice::Never));
+        assert!("yes".parse::<ColorChoice>().is_err());
+        assert_eq!(ColorChoice::Never.to_string(), "never");
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::{self, BufRead};
+
+use crate::files::open_input;
+
+/// Compression formats recognized by their magic number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Format of the data starting with `magic`, if any.
+    pub fn detect(magic: &[u8]) -> Option<Compression> {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Whether this build can decode the format, each one is behind its cargo feature.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Compression::Gzip => cfg!(feature = "gzip"),
+            Compression::Xz => cfg!(feature = "xz"),
+            Compression::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+}
+
+/// Opens a file like `open_input`, decompressing it when it is gzip, xz or zstd data.
+///
+/// The format is found from the first bytes, not from the extension, so compressed data piped into
+/// stdin is decoded as well. Formats whose feature is not enabled are read as they are.
+pub fn open_maybe_compressed(filename: &str) -> io::Result<Box<dyn BufRead>> {
+    decompress(open_input(filename)?)
+}
+
+/// Returns the reader decoding `reader` when its data is compressed, otherwise its data unchanged.
+///
+/// The magic number is sniffed from what a single read returns, so a terminal or a pipe is not
+/// waited on for more data than it has: a short first line is read at once.
+pub fn decompress<'a>(mut reader: impl BufRead + 'a) -> io::Result<Box<dyn BufRead + 'a>> {
+    let compression = Compression::detect(reader.fill_buf()?).filter(|c| c.is_supported());
+
+    match compression {
+        #[cfg(feature = "gzip")]
+        Some(Compression::Gzip) => Ok(Box::new(io::BufReader::new(
+            flate2::read::MultiGzDecoder::new(reader),
+        ))),
+        #[cfg(feature = "xz")]
+        Some(Compression::Xz) => Ok(Box::new(io::BufReader::new(
+            xz2::read::XzDecoder::new_multi_decoder(reader),
+        ))),
+        #[cfg(feature = "zstd")]
+        Some(Compression::Zstd) => Ok(Box::new(io::BufReader::new(zstd::Decoder::with_buffer(
+            reader,
+        )?))),
+        _ => Ok(Box::new(reader)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    const TEXT: &[u8] = b"The quick brown fox\njumps over the lazy dog.\n";
+
+    fn read_all(data: Vec<u8>) -> Vec<u8> {
+        let mut output = Vec::new();
+        decompress(Cursor::new(data))
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Compression::detect(&[0x1f, 0x8b, 0x08]),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(Compression::detect(b"\xfd7zXZ\x00"), Some(Compression::Xz));
+        assert_eq!(
+            Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::detect(b"\xfd7zXZ"), None);
+        assert_eq!(Compression::detect(TEXT), None);
+        assert_eq!(Compression::detect(b""), None);
+    }
+
+    #[test]
+    fn test_plain_data_is_unchanged() {
+        assert_eq!(read_all(TEXT.to_vec()), TEXT);
+        assert_eq!(read_all(b"a".to_vec()), b"a");
+        assert_eq!(read_all(Vec::new()), b"");
+    }
+
+    /// Reader giving its data one line per read, like a terminal, that fails when read past it
+    struct Terminal(Vec<&'static [u8]>);
+
+    impl Read for Terminal {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let line = self
+                .0
+                .pop()
+                .ok_or_else(|| io::Error::other("waiting for more input"))?;
+            buf[..line.len()].copy_from_slice(line);
+            Ok(line.len())
+        }
+    }
+
+    #[test]
+    fn test_short_first_read() {
+        let mut reader = decompress(io::BufReader::new(Terminal(vec![b"hi\n"]))).unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "hi\n");
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(TEXT).unwrap();
+        let mut data = encoder.finish().unwrap();
+
+        // Concatenated members are read one after the other, like zcat does
+        data.extend(data.clone());
+        assert_eq!(read_all(data), [TEXT, TEXT].concat());
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn test_xz() {
+        use std::io::Write;
+
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(TEXT).unwrap();
+        assert_eq!(read_all(encoder.finish().unwrap()), TEXT);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd() {
+        assert_eq!(read_all(zstd::encode_all(TEXT, 0).unwrap()), TEXT);
+    }
+}
@@ -0,0 +1,24 @@
+use std::fmt::Display;
+use std::process;
+
+/// Exit code of a command that failed.
+pub const FAILURE: i32 = 1;
+
+/// Returns the value of a successful run, otherwise prints the error to stderr and exits with `FAILURE`.
+///
+/// ```no_run
+/// fn run() -> Result<(), String> {
+///     Err("something went wrong".to_string())
+/// }
+///
+/// ruty_core::exit_on_error(run());
+/// ```
+pub fn exit_on_error<T, E: Display>(result: Result<T, E>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(FAILURE);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+/// Name standing for the standard input or output in the command line
+pub const STDIO: &str = "-";
+
+/// Opens a file for buffered reading, `-` being the standard input.
+pub fn open_input(filename: &str) -> io::Result<Box<dyn BufRead>> {
+    match filename {
+        STDIO => Ok(Box::new(BufReader::new(io::stdin()))),
+        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+    }
+}
+
+/// Creates a file for buffered writing, the standard output when there is no file or it is `-`.
+pub fn open_output(filename: Option<&str>) -> io::Result<Box<dyn Write>> {
+    match filename {
+        None | Some(STDIO) => Ok(Box::new(BufWriter::new(io::stdout()))),
+        Some(filename) => Ok(Box::new(BufWriter::new(File::create(filename)?))),
+    }
+}
+
+/// Header printed before the output of each file when there are several of them,
+/// separated from the output of the previous file by a blank line.
+pub fn header(filename: &str, first: bool) -> String {
+    if first {
+        format!("==> {} <==\n", filename)
+    } else {
+        format!("\n==> {} <==\n", filename)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_input() {
+        let mut file = open_input("Cargo.toml").unwrap();
+        let mut line = String::new();
+        file.read_line(&mut line).unwrap();
+        assert_eq!(line, "[package]\n");
+
+        let err = open_input("missing.txt").err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_header() {
+        assert_eq!(header("a.txt", true), "==> a.txt <==\n");
+        assert_eq!(header("b.txt", false), "\n==> b.txt <==\n");
+    }
+}
@@ -0,0 +1,17 @@
+//! Helpers shared by the ruty commands: opening the files given on the command line, compressed or not, reading
+//! newline or NUL separated records, parsing human sizes, coloring the output only where it makes sense
+//! and leaving `main` with an exit code.
+
+pub mod color;
+pub mod compressed;
+pub mod exit;
+pub mod files;
+pub mod records;
+pub mod size;
+
+pub use color::{ColorChoice, Painter};
+pub use compressed::open_maybe_compressed;
+pub use exit::exit_on_error;
+pub use files::{header, open_input, open_output};
+pub use records::{Records, lines, nul_records};
+pub use size::{ParseSizeError, parse_size};
@@ -0,0 +1,67 @@
+use std::io::{self, BufRead};
+
+/// Iterator over the records of a reader separated by a delimiter byte, without the delimiter.
+/// A last record without delimiter is returned too, an empty one after the last delimiter is not.
+pub struct Records<R> {
+    reader: R,
+    delimiter: u8,
+}
+
+impl<R: BufRead> Records<R> {
+    pub fn new(reader: R, delimiter: u8) -> Self {
+        Records { reader, delimiter }
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = Vec::new();
+
+        match self.reader.read_until(self.delimiter, &mut record) {
+            Err(e) => Some(Err(e)),
+            Ok(0) => None,
+            Ok(_) => {
+                if record.last() == Some(&self.delimiter) {
+                    record.pop();
+                }
+                Some(Ok(record))
+            }
+        }
+    }
+}
+
+/// Records separated by newlines, the bytes of a line can be invalid UTF-8.
+pub fn lines<R: BufRead>(reader: R) -> Records<R> {
+    Records::new(reader, b'\n')
+}
+
+/// Records separated by NUL bytes, like the file names given by `find -print0`.
+pub fn nul_records<R: BufRead>(reader: R) -> Records<R> {
+    Records::new(reader, b'\0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_records() {
+        let records: Vec<Vec<u8>> = nul_records(Cursor::new("a.txt\0b c.txt\0"))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(records, vec![b"a.txt".to_vec(), b"b c.txt".to_vec()]);
+
+        let records: Vec<Vec<u8>> = lines(Cursor::new(b"one\n\ntwo\xff"))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            records,
+            vec![b"one".to_vec(), b"".to_vec(), b"two\xff".to_vec()]
+        );
+
+        assert_eq!(lines(Cursor::new("")).count(), 0);
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+/// Error of a size that is not a number followed by a known suffix.
+#[derive(Debug, PartialEq)]
+pub struct ParseSizeError(String);
+
+impl fmt::Display for ParseSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid size: \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ParseSizeError {}
+
+/// Parses a size like `head` and `tail` do: a number with an optional suffix, `b` for 512,
+/// `K`, `M`, `G`, `T` (or `KiB`, `MiB`...) for powers of 1024 and `KB`, `MB`, `GB`, `TB` for powers of 1000.
+pub fn parse_size(size: &str) -> Result<u64, ParseSizeError> {
+    let error = || ParseSizeError(size.to_string());
+
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, suffix) = size.split_at(split);
+    let number: u64 = number.parse().map_err(|_| error())?;
+
+    let multiplier: u64 = match suffix {
+        "" => 1,
+        "b" => 512,
+        "K" | "k" | "KiB" => 1 << 10,
+        "M" | "MiB" => 1 << 20,
+        "G" | "GiB" => 1 << 30,
+        "T" | "TiB" => 1 << 40,
+        "KB" | "kB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return Err(error()),
+    };
+
+    number.checked_mul(multiplier).ok_or_else(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("10"), Ok(10));
+        assert_eq!(parse_size("2b"), Ok(1024));
+        assert_eq!(parse_size("1K"), Ok(1024));
+        assert_eq!(parse_size("1KiB"), Ok(1024));
+        assert_eq!(parse_size("3KB"), Ok(3000));
+        assert_eq!(parse_size("2M"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_size("1GB"), Ok(1_000_000_000));
+
+        assert!(parse_size("").is_err());
+        assert!(parse_size("K").is_err());
+        assert!(parse_size("-1").is_err());
+        assert!(parse_size("1X").is_err());
+        assert!(parse_size("1.5K").is_err());
+        assert_eq!(
+            parse_size("99999999999T").unwrap_err().to_string(),
+            r#"invalid size: "99999999999T""#
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::Path;
+
+use clap::Parser;
+use ruty_core::exit_on_error;
+
+/// Commands of the multi-call binary, each one runs the tool of the same name with an `r`
+const APPLETS: [&str; 12] = [
+    "cal", "cat", "comm", "cut", "find", "fortune", "grep", "head", "ls", "tail", "uniq", "wc",
+];
+
+const USAGE: &str = "Usage: ruty <COMMAND> [ARGS]...
+   or: <COMMAND> [ARGS]...  (with <COMMAND> a link to ruty)
+
+Rust versions of the classic command-line tools in a single binary.
+Run `ruty --list` for the commands, and `ruty <COMMAND> --help` for their options.";
+
+/// Name of the command a program name stands for: `head`, `headr` and `/usr/bin/head` are all `head`
+fn applet(program: &OsString) -> Option<&'static str> {
+    let name = Path::new(program).file_stem()?.to_str()?;
+    let name = if APPLETS.contains(&name) {
+        name
+    } else {
+        name.strip_suffix('r')?
+    };
+
+    APPLETS.iter().copied().find(|applet| *applet == name)
+}
+
+/// Runs a command, `args` starting with the name it is called with. Returns once the command
+/// succeeds, a failing command exits the process with the failure status
+fn dispatch(applet: &str, args: Vec<OsString>) {
+    match applet {
+        "cal" => exit_on_error(calr::run(calr::Cli::parse_from(args))),
+        "cat" => exit_on_error(catr::run(&catr::Args::parse_from(args))),
+        "comm" => exit_on_error(commr::run(commr::Cli::parse_from(args))),
+        "cut" => exit_on_error(cutr::run(cutr::Cli::parse_from(args))),
+        "find" => exit_on_error(findr::run(findr::Args::parse_from(args))),
+        "fortune" => exit_on_error(fortuner::run(fortuner::Cli::parse_from(args))),
+        "grep" => exit_on_error(grepr::run(grepr::Cli::parse_from(args))),
+        "head" => exit_on_error(headr::run(headr::Args::parse_from(args))),
+        "ls" => exit_on_error(lsr::run(lsr::Cli::parse_from(args))),
+        "tail" => exit_on_error(tailr::run(tailr::Cli::parse_from(args))),
+        "uniq" => exit_on_error(uniqr::run(uniqr::Args::parse_from(args))),
+        "wc" => exit_on_error(wcr::run(&wcr::Args::parse_from(args))),
+        _ => unreachable!("{} is not a command of ruty", applet),
+    }
+}
+
+fn main() {
+    let mut args: Vec<OsString> = env::args_os().collect();
+
+    // Called through a link named like a command
+    if let Some(applet) = args.first().and_then(applet) {
+        return dispatch(applet, args);
+    }
+
+    // Called as ruty, the command is the first argument
+    args.remove(0);
+    match args.first().and_then(|arg| arg.to_str()) {
+        None => exit_on_error(Err(USAGE)),
+        Some("-h" | "--help") => println!("{}", USAGE),
+        Some("-V" | "--version") => println!("ruty {}", env!("CARGO_PKG_VERSION")),
+        Some("--list") => APPLETS.iter().for_each(|applet| println!("{}", applet)),
+        Some(_) => match applet(&args[0]) {
+            Some(applet) => dispatch(applet, args),
+            None => exit_on_error(Err(format!(
+                "ruty: unknown command {:?}, run `ruty --list` for the commands",
+                args[0]
+            ))),
+        },
+    }
+}
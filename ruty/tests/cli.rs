@@ -0,0 +1,98 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use predicates::prelude::*;
+use pretty_assertions::assert_eq;
+
+const PRG: &str = "ruty";
+const FOX: &str = "tests/inputs/fox.txt";
+
+// --------------------------------------------------
+#[test]
+fn usage_without_command() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage: ruty <COMMAND>"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn lists_commands() -> Result<()> {
+    let output = Command::cargo_bin(PRG)?.arg("--list").output()?;
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout.lines().count(), 12);
+    assert!(stdout.lines().any(|line| line == "head"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unknown_command() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .arg("sed")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"unknown command "sed""#));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn command_as_first_argument() -> Result<()> {
+    for command in ["wc", "wcr"] {
+        Command::cargo_bin(PRG)?
+            .args([command, "-l", FOX])
+            .assert()
+            .success()
+            .stdout(format!("       1 {FOX}\n"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn command_errors_and_help() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["head", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Usage: head"));
+
+    Command::cargo_bin(PRG)?
+        .args(["head", "-n", "0", FOX])
+        .assert()
+        .success()
+        .stdout("");
+
+    Command::cargo_bin(PRG)?
+        .args(["head", "-n", "1X", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid size: \"1X\""));
+
+    Command::cargo_bin(PRG)?
+        .args(["cut", "-f", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(r#"illegal list value: "0""#.to_string() + "\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(unix)]
+#[test]
+fn command_from_link_name() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let link = dir.path().join("cat");
+    std::os::unix::fs::symlink(assert_cmd::cargo::cargo_bin(PRG), &link)?;
+
+    Command::new(&link)
+        .args(["-n", FOX])
+        .assert()
+        .success()
+        .stdout(format!("     1\t{}", std::fs::read_to_string(FOX)?));
+    Ok(())
+}
@@ -0,0 +1,567 @@
+//! Rust version of `tail`, the `run` function is called by the `tailr` binary and by `ruty`
+
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(about, version = "0.1.0", author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `tail`
+pub struct Cli {
+    #[arg(value_name = "FILE", required = true)]
+    /// Input file(s)
+    files: Vec<String>,
+
+    #[arg(short('n'), long, default_value = "10", conflicts_with = "bytes")]
+    /// Number of lines
+    lines: String,
+
+    #[arg(short('c'), long)]
+    /// Number of bytes
+    bytes: Option<String>,
+
+    #[arg(short, long)]
+    /// Suppress headers
+    quiet: bool,
+
+    #[arg(short, long)]
+    /// Output appended data as the files grow
+    follow: bool,
+
+    #[arg(
+        short('s'),
+        long,
+        value_name = "N",
+        default_value = "1.0",
+        requires = "follow"
+    )]
+    /// With --follow, sleep N seconds between checks of the files
+    sleep_interval: f64,
+
+    #[arg(
+        long,
+        value_name = "PID",
+        value_parser = clap::value_parser!(i32).range(1..),
+        requires = "follow"
+    )]
+    /// With --follow, exit once the process PID is gone
+    pid: Option<i32>,
+}
+
+mod helpers {
+    use ruty_core::compressed::{Compression, decompress};
+    use std::collections::VecDeque;
+    use std::fs::{self, File, Metadata};
+    use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+    use std::thread;
+    use std::time::Duration;
+
+    /// Bytes read at a time when looking for the last lines from the end of a file
+    const BLOCK_SIZE: usize = 64 * 1024;
+
+    #[derive(PartialEq, Debug)]
+    pub enum TakeValue {
+        PlusZero,
+        TakeNum(i64),
+    }
+
+    /// Where the output of a file starts
+    #[derive(PartialEq, Debug)]
+    pub enum Start {
+        /// After skipping this many lines or bytes
+        Skip(u64),
+        /// This many lines or bytes before the end
+        Last(u64),
+        /// Nothing is printed
+        Nothing,
+    }
+
+    /// Input of a file, a plain regular file can seek to where the output starts
+    /// instead of reading what precedes it
+    enum Input {
+        Seekable(File, u64),
+        Stream(Box<dyn BufRead>),
+    }
+
+    impl Input {
+        fn into_reader(self) -> Box<dyn BufRead> {
+            match self {
+                Input::Seekable(file, _) => Box::new(BufReader::new(file)),
+                Input::Stream(reader) => reader,
+            }
+        }
+    }
+
+    pub fn run(args: super::Cli) -> anyhow::Result<()> {
+        let lines =
+            parse_num(args.lines).map_err(|e| anyhow::anyhow!("illegal line count -- {}", e))?;
+
+        let bytes = args
+            .bytes
+            .map(parse_num)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("illegal byte count -- {}", e))?;
+
+        let interval = Duration::try_from_secs_f64(args.sleep_interval)
+            .map_err(|_| anyhow::anyhow!("invalid number of seconds: {}", args.sleep_interval))?;
+
+        let num_files = args.files.len();
+        let mut followed: Vec<Followed> = Vec::new();
+        // Followed file the output ends with
+        let mut current = None;
+
+        for (i, filename) in args.files.iter().enumerate() {
+            current = None;
+            if num_files > 1 && !args.quiet {
+                print!("{}", ruty_core::header(filename, i == 0));
+            }
+            match open(filename) {
+                Err(e) => eprintln!("{}: {}", filename, e),
+                Ok((input, file)) => {
+                    let mut stdout = io::stdout().lock();
+                    match bytes {
+                        None => print_lines(input, &lines, &mut stdout)?,
+                        Some(ref bytes) => print_bytes(input, bytes, &mut stdout)?,
+                    }
+                    stdout.flush()?;
+
+                    if args.follow
+                        && let Some(file) = file
+                    {
+                        current = Some(followed.len());
+                        followed.push(Followed::new(filename, file)?);
+                    }
+                }
+            }
+        }
+
+        if args.follow {
+            let headers = num_files > 1 && !args.quiet;
+            follow(followed, current, headers, interval, args.pid)?;
+        }
+
+        Ok(())
+    }
+
+    /// File followed by `--follow`, read from the end of what was already printed
+    struct Followed {
+        filename: String,
+        file: File,
+        metadata: Metadata,
+        position: u64,
+    }
+
+    impl Followed {
+        /// Follows the file from where its handle is, the end of what was printed of it
+        fn new(filename: &str, mut file: File) -> io::Result<Followed> {
+            Ok(Followed {
+                filename: filename.to_string(),
+                position: file.stream_position()?,
+                metadata: file.metadata()?,
+                file,
+            })
+        }
+
+        /// Returns the data appended since the last call. A truncated file is read again from
+        /// its start, and so is the new file when the name has been rotated to another one
+        fn read_appended(&mut self) -> io::Result<Vec<u8>> {
+            if let Ok(metadata) = fs::metadata(&self.filename)
+                && !same_file(&metadata, &self.metadata)
+            {
+                eprintln!(
+                    "tailr: {}: file replaced, following the new file",
+                    self.filename
+                );
+                *self = Followed::new(&self.filename, File::open(&self.filename)?)?;
+            }
+
+            if self.file.metadata()?.len() < self.position {
+                eprintln!("tailr: {}: file truncated", self.filename);
+                self.position = 0;
+            }
+
+            let mut data = Vec::new();
+            self.file.seek(SeekFrom::Start(self.position))?;
+            self.file.read_to_end(&mut data)?;
+            self.position += data.len() as u64;
+
+            Ok(data)
+        }
+    }
+
+    #[cfg(unix)]
+    fn same_file(a: &Metadata, b: &Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        a.dev() == b.dev() && a.ino() == b.ino()
+    }
+
+    #[cfg(not(unix))]
+    fn same_file(_: &Metadata, _: &Metadata) -> bool {
+        true
+    }
+
+    /// Whether the process `pid` exists, a process of another user counts too
+    #[cfg(unix)]
+    fn is_alive(pid: i32) -> bool {
+        // Signal 0 only checks that the process could be signaled
+        let result = unsafe { libc::kill(pid, 0) };
+
+        result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    #[cfg(not(unix))]
+    fn is_alive(_: i32) -> bool {
+        true
+    }
+
+    /// Prints the data appended to the files until killed, or until the process `pid` is gone,
+    /// with the header of a file every time the output switches to it, `current` being the one
+    /// the output ends with. Only the regular files that could be opened are followed
+    fn follow(
+        mut followed: Vec<Followed>,
+        mut current: Option<usize>,
+        headers: bool,
+        interval: Duration,
+        pid: Option<i32>,
+    ) -> anyhow::Result<()> {
+        let mut stdout = io::stdout();
+
+        loop {
+            // Checked before reading so what the process wrote before dying is printed
+            let alive = pid.is_none_or(is_alive);
+
+            for (i, file) in followed.iter_mut().enumerate() {
+                match file.read_appended() {
+                    Err(e) => eprintln!("{}: {}", file.filename, e),
+                    Ok(data) if data.is_empty() => {}
+                    Ok(data) => {
+                        if headers && current != Some(i) {
+                            print!("{}", ruty_core::header(&file.filename, false));
+                        }
+                        current = Some(i);
+
+                        stdout.write_all(&data)?;
+                        stdout.flush()?;
+                    }
+                }
+            }
+
+            if !alive {
+                return Ok(());
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    pub fn parse_num(val: String) -> anyhow::Result<TakeValue> {
+        if val == "+0" {
+            return Ok(TakeValue::PlusZero);
+        }
+
+        match val.parse::<i64>() {
+            Err(_) => anyhow::bail!("{}", val),
+            Ok(v) => {
+                if !val.contains("+") && !val.contains("-") {
+                    return Ok(TakeValue::TakeNum(-v));
+                }
+
+                Ok(TakeValue::TakeNum(v))
+            }
+        }
+    }
+
+    /// Opens a file like `open_maybe_compressed`, keeping plain regular files seekable. A file
+    /// comes with a handle sharing its offset, which is left at the end of what is printed of it
+    fn open(filename: &str) -> io::Result<(Input, Option<File>)> {
+        if filename == "-" {
+            return Ok((
+                decompress(BufReader::new(io::stdin())).map(Input::Stream)?,
+                None,
+            ));
+        }
+
+        let mut file = File::open(filename)?;
+        let metadata = file.metadata()?;
+
+        // Pipes and devices can not seek back after the magic number is read, nor be followed
+        if !metadata.is_file() {
+            return Ok((decompress(BufReader::new(file)).map(Input::Stream)?, None));
+        }
+        let handle = Some(file.try_clone()?);
+
+        // Six bytes hold the longest magic number, the one of xz
+        let mut magic = Vec::new();
+        (&mut file).take(6).read_to_end(&mut magic)?;
+        file.rewind()?;
+
+        let input = match Compression::detect(&magic) {
+            Some(compression) if compression.is_supported() => {
+                decompress(BufReader::new(file)).map(Input::Stream)?
+            }
+            _ => Input::Seekable(file, metadata.len()),
+        };
+        Ok((input, handle))
+    }
+
+    pub fn start(take_val: &TakeValue) -> Start {
+        match take_val {
+            TakeValue::PlusZero => Start::Skip(0),
+            TakeValue::TakeNum(0) => Start::Nothing,
+            TakeValue::TakeNum(num) if num.is_positive() => Start::Skip(num.unsigned_abs() - 1),
+            TakeValue::TakeNum(num) => Start::Last(num.unsigned_abs()),
+        }
+    }
+
+    fn print_lines(
+        input: Input,
+        num_lines: &TakeValue,
+        out: &mut impl Write,
+    ) -> anyhow::Result<()> {
+        match (start(num_lines), input) {
+            // Nothing is printed, a followed file goes on from its end
+            (Start::Nothing, Input::Seekable(mut file, len)) => {
+                file.seek(SeekFrom::Start(len))?;
+            }
+            (Start::Nothing, _) => {}
+            (Start::Last(num), Input::Seekable(mut file, len)) => {
+                let start_index = last_lines_start(&mut file, len, num, BLOCK_SIZE)?;
+                file.seek(SeekFrom::Start(start_index))?;
+                io::copy(&mut file, out)?;
+            }
+            (Start::Last(num), input) => print_last_lines(input.into_reader(), num, out)?,
+            (Start::Skip(num), input) => {
+                let mut reader = input.into_reader();
+                let mut buff: Vec<u8> = Vec::new();
+
+                for _ in 0..num {
+                    buff.clear();
+                    if reader.read_until(b'\n', &mut buff)? == 0 {
+                        return Ok(());
+                    }
+                }
+
+                io::copy(&mut reader, out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn print_bytes(
+        input: Input,
+        num_bytes: &TakeValue,
+        out: &mut impl Write,
+    ) -> anyhow::Result<()> {
+        match (start(num_bytes), input) {
+            (Start::Nothing, Input::Seekable(mut file, len)) => {
+                file.seek(SeekFrom::Start(len))?;
+            }
+            (Start::Nothing, _) => {}
+            (Start::Last(num), Input::Seekable(mut file, len)) => {
+                file.seek(SeekFrom::Start(len.saturating_sub(num)))?;
+                io::copy(&mut file, out)?;
+            }
+            (Start::Last(num), input) => print_last_bytes(input.into_reader(), num, out)?,
+            (Start::Skip(num), input) => {
+                // Streams can not seek, the bytes before the start are read and dropped
+                let mut reader = input.into_reader();
+                io::copy(&mut reader.by_ref().take(num), &mut io::sink())?;
+                io::copy(&mut reader, out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Offset where the last `num` lines of a file of `len` bytes start, found by reading blocks
+    /// backwards from its end. A newline ending the file ends the last line, it does not start another
+    pub fn last_lines_start(
+        file: &mut (impl Read + Seek),
+        len: u64,
+        num: u64,
+        block_size: usize,
+    ) -> io::Result<u64> {
+        let mut buff = vec![0; block_size];
+        let mut position = len;
+        let mut newlines = 0;
+
+        while position > 0 {
+            let size = block_size.min(position as usize);
+            position -= size as u64;
+
+            file.seek(SeekFrom::Start(position))?;
+            file.read_exact(&mut buff[..size])?;
+
+            for (i, byte) in buff[..size].iter().enumerate().rev() {
+                let offset = position + i as u64;
+
+                if *byte == b'\n' && offset + 1 != len {
+                    newlines += 1;
+                    if newlines == num {
+                        return Ok(offset + 1);
+                    }
+                }
+            }
+        }
+
+        Ok(0)
+    }
+
+    /// Prints the last lines of a stream, only those are kept in memory while it is read
+    fn print_last_lines(
+        mut reader: impl BufRead,
+        num: u64,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let mut last: VecDeque<Vec<u8>> = VecDeque::new();
+
+        loop {
+            let mut line = Vec::new();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+
+            if last.len() as u64 == num {
+                last.pop_front();
+            }
+            last.push_back(line);
+        }
+
+        last.iter().try_for_each(|line| out.write_all(line))
+    }
+
+    /// Prints the last bytes of a stream, only those are kept in memory while it is read
+    fn print_last_bytes(mut reader: impl Read, num: u64, out: &mut impl Write) -> io::Result<()> {
+        let mut last: Vec<u8> = Vec::new();
+        let mut buff = vec![0; BLOCK_SIZE];
+
+        loop {
+            let n = reader.read(&mut buff)?;
+            if n == 0 {
+                break;
+            }
+
+            last.extend_from_slice(&buff[..n]);
+
+            // Dropping the front once in a while instead of at every read
+            let extra = (last.len() as u64).saturating_sub(num) as usize;
+            if extra > BLOCK_SIZE {
+                last.drain(..extra);
+            }
+        }
+
+        let extra = (last.len() as u64).saturating_sub(num) as usize;
+        out.write_all(&last[extra..])
+    }
+}
+
+pub use helpers::run;
+
+#[cfg(test)]
+mod tests {
+    use super::helpers::{Start, TakeValue::*, last_lines_start, parse_num, start};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_start() {
+        // +0 prints everything
+        assert_eq!(start(&PlusZero), Start::Skip(0));
+
+        // Taking 0 lines/bytes prints nothing
+        assert_eq!(start(&TakeNum(0)), Start::Nothing);
+
+        // A positive number skips one less than that number
+        assert_eq!(start(&TakeNum(1)), Start::Skip(0));
+        assert_eq!(start(&TakeNum(3)), Start::Skip(2));
+
+        // A negative number counts from the end
+        assert_eq!(start(&TakeNum(-1)), Start::Last(1));
+        assert_eq!(start(&TakeNum(-20)), Start::Last(20));
+
+        // Test boundaries
+        assert_eq!(start(&TakeNum(i64::MAX)), Start::Skip(i64::MAX as u64 - 1));
+        assert_eq!(
+            start(&TakeNum(i64::MIN)),
+            Start::Last(i64::MIN.unsigned_abs())
+        );
+    }
+
+    #[test]
+    fn test_last_lines_start() {
+        let text = b"one\ntwo\nthree\n";
+        let len = text.len() as u64;
+
+        // Blocks smaller than the lines make the newlines cross block boundaries
+        for block_size in [1, 2, 4, 1024] {
+            let mut file = Cursor::new(text);
+            assert_eq!(last_lines_start(&mut file, len, 1, block_size).unwrap(), 8);
+            assert_eq!(last_lines_start(&mut file, len, 2, block_size).unwrap(), 4);
+            assert_eq!(last_lines_start(&mut file, len, 3, block_size).unwrap(), 0);
+            assert_eq!(last_lines_start(&mut file, len, 10, block_size).unwrap(), 0);
+        }
+
+        // Without a final newline the last line still counts
+        let text = b"one\ntwo";
+        let mut file = Cursor::new(text);
+        assert_eq!(last_lines_start(&mut file, 7, 1, 3).unwrap(), 4);
+
+        // Empty lines are lines
+        let text = b"one\n\n\n";
+        let mut file = Cursor::new(text);
+        assert_eq!(last_lines_start(&mut file, 6, 2, 4).unwrap(), 4);
+
+        assert_eq!(last_lines_start(&mut Cursor::new(b""), 0, 1, 4).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_parse_num() {
+        // All integers should be interpreted as negative numbers
+        let res = parse_num("3".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), TakeNum(-3));
+
+        // A leading "+" should result in a positive number
+        let res = parse_num("+3".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), TakeNum(3));
+
+        // An explicit "-" value should result in a negative number
+        let res = parse_num("-3".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), TakeNum(-3));
+
+        // Zero is zero
+        let res = parse_num("0".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), TakeNum(0));
+
+        // Plus zero is special
+        let res = parse_num("+0".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), PlusZero);
+
+        // Test boundaries
+        let res = parse_num(i64::MAX.to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), TakeNum(i64::MIN + 1));
+
+        let res = parse_num((i64::MIN + 1).to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), TakeNum(i64::MIN + 1));
+
+        let res = parse_num(format!("+{}", i64::MAX));
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), TakeNum(i64::MAX));
+
+        let res = parse_num(i64::MIN.to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), TakeNum(i64::MIN));
+
+        // A floating-point value is invalid
+        let res = parse_num("3.14".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "3.14");
+
+        // Any non-integer string is invalid
+        let res = parse_num("foo".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "foo");
+    }
+}
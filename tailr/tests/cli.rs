@@ -2,9 +2,13 @@ use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
-use rand::{distr::Alphanumeric, Rng};
+use rand::{Rng, distr::Alphanumeric};
 use std::fs::{self, File};
-use std::io::Read;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command as Process, Stdio};
+use std::thread;
+use std::time::Duration;
 
 const PRG: &str = "tailr";
 const EMPTY: &str = "tests/inputs/empty.txt";
@@ -828,3 +832,276 @@ fn multiple_files_c_plus_3() -> Result<()> {
         "tests/expected/all.c+3.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+#[cfg(feature = "compressed")]
+fn twelve_gzip() -> Result<()> {
+    let twelve_gz = "tests/inputs/twelve.txt.gz";
+    run(&[twelve_gz, "-n", "3"], "tests/expected/twelve.txt.n3.out")?;
+    run(&[twelve_gz, "-c", "8"], "tests/expected/twelve.txt.c8.out")
+}
+
+// --------------------------------------------------
+fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> Result<()> {
+    let input = fs::read(input_file)?;
+    let expected = fs::read(expected_file)?;
+
+    let output = Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin(input)
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&expected)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn twelve_stdin() -> Result<()> {
+    // Stdin can not seek nor be read twice, it is read once keeping only the end
+    run_stdin(
+        &["-n", "3", "-"],
+        TWELVE,
+        "tests/expected/twelve.txt.n3.out",
+    )?;
+    run_stdin(
+        &["-n", "200", "-"],
+        TWELVE,
+        "tests/expected/twelve.txt.n200.out",
+    )?;
+    run_stdin(
+        &["-n", "+2", "-"],
+        TWELVE,
+        "tests/expected/twelve.txt.n+2.out",
+    )?;
+    run_stdin(
+        &["-c", "8", "-"],
+        TWELVE,
+        "tests/expected/twelve.txt.c8.out",
+    )?;
+    run_stdin(
+        &["-c", "+2", "-"],
+        TWELVE,
+        "tests/expected/twelve.txt.c+2.out",
+    )
+}
+
+#[test]
+#[cfg(feature = "compressed")]
+fn twelve_gzip_stdin() -> Result<()> {
+    let twelve_gz = "tests/inputs/twelve.txt.gz";
+    run_stdin(
+        &["-n", "3", "-"],
+        twelve_gz,
+        "tests/expected/twelve.txt.n3.out",
+    )?;
+    run_stdin(
+        &["-c", "8", "-"],
+        twelve_gz,
+        "tests/expected/twelve.txt.c8.out",
+    )
+}
+
+// --------------------------------------------------
+fn append(path: &Path, text: &str) -> Result<()> {
+    fs::OpenOptions::new()
+        .append(true)
+        .open(path)?
+        .write_all(text.as_bytes())?;
+    Ok(())
+}
+
+// --------------------------------------------------
+/// Runs tailr --follow on the files while `changes` modifies them, returns its stdout and stderr
+fn follow(
+    args: &[&str],
+    files: &[&Path],
+    changes: impl FnOnce() -> Result<()>,
+) -> Result<(String, String)> {
+    let mut child = Process::new(env!("CARGO_BIN_EXE_tailr"))
+        .args(["-f", "-s", "0.05"])
+        .args(args)
+        .args(files)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    thread::sleep(Duration::from_millis(300));
+    let result = changes();
+    thread::sleep(Duration::from_millis(300));
+    child.kill()?;
+    result?;
+
+    let output = child.wait_with_output()?;
+    Ok((
+        String::from_utf8(output.stdout)?,
+        String::from_utf8(output.stderr)?,
+    ))
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_appended_and_truncated() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log.txt");
+    fs::write(&log, "one\n")?;
+
+    let (stdout, stderr) = follow(&[], &[&log], || {
+        append(&log, "two\nthree\n")?;
+        thread::sleep(Duration::from_millis(300));
+        fs::write(&log, "new\n")?;
+        Ok(())
+    })?;
+
+    assert_eq!(stdout, "one\ntwo\nthree\nnew\n");
+    assert!(stderr.contains("file truncated"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_rotated() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log.txt");
+    fs::write(&log, "old\n")?;
+
+    let (stdout, stderr) = follow(&[], &[&log], || {
+        fs::rename(&log, dir.path().join("log.txt.1"))?;
+        fs::write(&log, "rotated\n")?;
+        Ok(())
+    })?;
+
+    assert_eq!(stdout, "old\nrotated\n");
+    assert!(stderr.contains("following the new file"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_multiple_files_headers() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let first = dir.path().join("first.txt");
+    let second = dir.path().join("second.txt");
+    fs::write(&first, "1\n")?;
+    fs::write(&second, "2\n")?;
+
+    let (stdout, _) = follow(&[], &[&first, &second], || {
+        append(&second, "3\n")?;
+        thread::sleep(Duration::from_millis(300));
+        append(&first, "4\n")
+    })?;
+
+    let (first, second) = (first.display(), second.display());
+    assert_eq!(
+        stdout,
+        format!("==> {first} <==\n1\n\n==> {second} <==\n2\n3\n\n==> {first} <==\n4\n")
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_nothing_printed() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log.txt");
+    fs::write(&log, "one\n")?;
+
+    // Followed from the end even though nothing of the file was read
+    let (stdout, _) = follow(&["-n", "0"], &[&log], || append(&log, "two\n"))?;
+    assert_eq!(stdout, "two\n");
+
+    let (stdout, _) = follow(&["-c", "2"], &[&log], || append(&log, "three\n"))?;
+    assert_eq!(stdout, "o\nthree\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn follow_appended_while_printing() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log.txt");
+    let fifo = dir.path().join("fifo");
+    fs::write(&log, "one\n")?;
+    Process::new("mkfifo").arg(&fifo).status()?;
+
+    // tailr waits for the fifo after printing the log, what is appended meanwhile is not lost
+    let (stdout, _) = follow(&[], &[&log, &fifo], || {
+        append(&log, "two\n")?;
+        fs::write(&fifo, "fifo\n")?;
+        Ok(())
+    })?;
+
+    let (log, fifo) = (log.display(), fifo.display());
+    assert_eq!(
+        stdout,
+        format!("==> {log} <==\none\n\n==> {fifo} <==\nfifo\n\n==> {log} <==\ntwo\n")
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_sleep_interval_without_follow() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-s", "1", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_sleep_interval() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "--sleep-interval=-1", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid number of seconds: -1"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn follow_until_pid_exits() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log.txt");
+    fs::write(&log, "one\n")?;
+
+    // The process is reaped as soon as it exits, a zombie would still be found
+    let mut writer = Process::new("sh")
+        .arg("-c")
+        .arg(format!("sleep 0.3; echo two >> '{}'", log.display()))
+        .spawn()?;
+    let pid = writer.id().to_string();
+    let reaper = thread::spawn(move || writer.wait());
+
+    Command::cargo_bin(PRG)?
+        .args(["-f", "-s", "0.05", "--pid", &pid])
+        .arg(&log)
+        .timeout(Duration::from_secs(10))
+        .assert()
+        .success()
+        .stdout("one\ntwo\n");
+
+    reaper.join().unwrap()?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_pid_without_follow() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--pid", "1", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+    Ok(())
+}
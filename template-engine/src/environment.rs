@@ -0,0 +1,122 @@
+use crate::filters::FilterRegistry;
+use crate::loader::{FileLoader, TemplateLoader};
+use crate::template::Template;
+use std::{cell::RefCell, collections::HashMap, rc::Rc, str::FromStr, time::SystemTime};
+
+/// Maximum number of nested includes before rendering gives up
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// What the generator does when a template uses a variable missing from the context
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MissingVariable {
+    /// Rendering fails with an error
+    #[default]
+    Strict,
+    /// The variable renders as an empty string and conditions on it are false
+    Lenient,
+    /// The `{{var}}` placeholder is left in the output as written
+    Keep,
+}
+
+impl FromStr for MissingVariable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "strict" => Ok(MissingVariable::Strict),
+            "lenient" => Ok(MissingVariable::Lenient),
+            "keep" => Ok(MissingVariable::Keep),
+            _ => Err(format!("Unknown missing variable strategy: {}", s)),
+        }
+    }
+}
+
+/// Compiled templates by name, with the modification time they were loaded with
+type TemplateCache = HashMap<String, (Option<SystemTime>, Rc<Template>)>;
+
+/// Shared configuration used by the generator while rendering a template
+pub struct Environment {
+    pub filters: FilterRegistry,
+    pub loader: Box<dyn TemplateLoader>,
+    pub max_include_depth: usize,
+    /// Escapes substituted values unless they are piped through `safe` or `raw`
+    pub autoescape: bool,
+    pub missing_variable: MissingVariable,
+    /// Names of the templates currently being included, outermost first
+    pub(crate) include_stack: RefCell<Vec<String>>,
+    cache: RefCell<TemplateCache>,
+}
+
+impl Environment {
+    /// Creates an environment with the built-in filters and the given template loader
+    pub fn new(loader: impl TemplateLoader + 'static) -> Self {
+        Environment {
+            filters: FilterRegistry::new(),
+            loader: Box::new(loader),
+            max_include_depth: MAX_INCLUDE_DEPTH,
+            autoescape: true,
+            missing_variable: MissingVariable::default(),
+            include_stack: RefCell::new(vec![]),
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Loads and parses the template called `name`, reusing the compiled template
+    /// while the loader reports it as unchanged
+    pub fn get_template(&self, name: &str) -> Result<Rc<Template>, String> {
+        let modified = self.loader.modified(name);
+
+        if let Some((cached_modified, template)) = self.cache.borrow().get(name)
+            && *cached_modified == modified
+        {
+            return Ok(Rc::clone(template));
+        }
+
+        let source = self.loader.load(name)?;
+        let template = Rc::new(Template::parse(&source).map_err(|e| format!("{}: {}", name, e))?);
+
+        self.cache
+            .borrow_mut()
+            .insert(name.to_string(), (modified, Rc::clone(&template)));
+
+        Ok(template)
+    }
+}
+
+impl Default for Environment {
+    /// Environment loading included templates from the current directory
+    fn default() -> Self {
+        Self::new(FileLoader::new("."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{Context, Value};
+    use std::{fs, thread, time::Duration};
+
+    #[test]
+    fn check_template_cache() {
+        let root = std::env::temp_dir().join(format!("template-cache-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("hello.html"), "Hello {{name}}").unwrap();
+
+        let env = Environment::new(FileLoader::new(&root));
+        let mut context = Context::new();
+        context.insert("name".to_string(), Value::from("Bob"));
+
+        let first = env.get_template("hello.html").unwrap();
+        let second = env.get_template("hello.html").unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(second.render_with(&context, &env).unwrap(), "Hello Bob");
+
+        // A newer modification time invalidates the compiled template
+        thread::sleep(Duration::from_millis(20));
+        fs::write(root.join("hello.html"), "Bye {{name}}").unwrap();
+        let third = env.get_template("hello.html").unwrap();
+        assert_eq!(third.render_with(&context, &env).unwrap(), "Bye Bob");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+use std::{error::Error, fmt};
+
+/// Kinds of errors found while parsing the source of a template
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    /// An if or for tag that does not follow `{% if a = b %} ... {% endif %}`, or a closing tag
+    /// that does not match the open block or tag
+    InvalidTag,
+    /// A condition without a supported operator or operands
+    InvalidCondition,
+    /// A statement with unbalanced delimiters that is neither a tag nor a variable
+    UnrecognizedStatement,
+}
+
+/// Error found while parsing the source of a template, with the location of the offending text
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Malformed tag or condition text
+    pub text: String,
+    /// Line of the template where the error was found, starting at 1 (0 when unknown)
+    pub line: usize,
+    /// Column of the malformed text within the line, starting at 1 (0 when unknown)
+    pub column: usize,
+    /// Full source line holding the error, used as context snippet
+    pub snippet: String,
+}
+
+impl ParseError {
+    /// Creates an error that is not yet tied to a position in the template
+    pub fn new(kind: ParseErrorKind, text: &str) -> Self {
+        ParseError {
+            kind,
+            text: text.to_string(),
+            line: 0,
+            column: 0,
+            snippet: String::new(),
+        }
+    }
+
+    /// Places the error in `source_line`, the line number `line` of the template
+    pub fn at(mut self, line: usize, source_line: &str) -> Self {
+        let column = source_line
+            .find(self.text.trim())
+            .or_else(|| source_line.find("{%"))
+            .unwrap_or(0);
+
+        self.line = line;
+        self.column = source_line[..column].chars().count() + 1;
+        self.snippet = source_line.to_string();
+        self
+    }
+}
+
+/// Errors found while rendering a parsed template
+#[derive(Debug, PartialEq, Clone)]
+pub enum RenderError {
+    MissingVariable(String),
+    FilterError(String),
+    IncludeError(String),
+    ExtendsError(String),
+    ExpressionError(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            ParseErrorKind::InvalidTag => "InvalidTag",
+            ParseErrorKind::InvalidCondition => "InvalidCondition",
+            ParseErrorKind::UnrecognizedStatement => "UnrecognizedStatement",
+        };
+
+        if self.line == 0 {
+            return write!(f, "{kind}: {}", self.text);
+        }
+
+        let gutter = self.line.to_string();
+        writeln!(
+            f,
+            "{kind} at line {}, column {}: {}",
+            self.line, self.column, self.text
+        )?;
+        writeln!(f, "{gutter} | {}", self.snippet)?;
+        write!(
+            f,
+            "{} | {}^",
+            " ".repeat(gutter.len()),
+            " ".repeat(self.column - 1)
+        )
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::MissingVariable(e) => write!(f, "MissingVariable: {e}"),
+            RenderError::FilterError(e) => write!(f, "FilterError: {e}"),
+            RenderError::IncludeError(e) => write!(f, "IncludeError: {e}"),
+            RenderError::ExtendsError(e) => write!(f, "ExtendsError: {e}"),
+            RenderError::ExpressionError(e) => write!(f, "ExpressionError: {e}"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl Error for RenderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_parse_error_location() {
+        let error = ParseError::new(ParseErrorKind::InvalidCondition, "name")
+            .at(12, "<p>{% if name %} hi {% endif %}</p>");
+
+        assert_eq!(error.line, 12);
+        assert_eq!(error.column, 10);
+        assert_eq!(
+            error.to_string(),
+            "InvalidCondition at line 12, column 10: name\n\
+             12 | <p>{% if name %} hi {% endif %}</p>\n   \
+                |          ^"
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use crate::error::RenderError;
+use crate::value::{Context, Value, lookup};
+use std::cmp::Ordering;
+
+/// Condition of an if tag such as `items.len > 3 and not (user.active or admin)`
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Literal(Value),
+    /// Dotted path looked up in the context
+    Variable(String),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+}
+
+/// Operators accepted between two expressions
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Name(String),
+    Operator(&'static str),
+    OpenParen,
+    CloseParen,
+}
+
+/// Symbols recognized by the tokenizer, two-character operators first
+const OPERATORS: [&str; 13] = [
+    "==", "!=", ">=", "<=", "=", ">", "<", "+", "-", "*", "/", "%", "!",
+];
+
+/// Splits a condition into numbers, quoted strings, names, operators and parenthesis
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = input.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let length = if c == '(' || c == ')' {
+            tokens.push(if c == '(' {
+                Token::OpenParen
+            } else {
+                Token::CloseParen
+            });
+            1
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or(format!("Unclosed string: {}", rest))?;
+            tokens.push(Token::Text(rest[1..end + 1].to_string()));
+            end + 2
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..end]
+                .parse()
+                .map_err(|_| format!("Invalid number: {}", &rest[..end]))?;
+            tokens.push(Token::Number(number));
+            end
+        } else if c.is_alphanumeric() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..end].to_string()));
+            end
+        } else if let Some(operator) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Operator(operator));
+            operator.len()
+        } else {
+            return Err(format!("Unexpected character: {}", c));
+        };
+
+        rest = rest[length..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, each method handles one precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Consumes the next token when it is the keyword `word`
+    fn keyword(&mut self, word: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Name(name)) if name == word);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    /// Consumes the next token when it is the operator `symbol`
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Operator(op)) if *op == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    /// Consumes the next token when it is one of `operators`
+    fn operator(&mut self, operators: &[(&str, BinaryOp)]) -> Option<BinaryOp> {
+        let op = operators.iter().find(|(symbol, _)| self.symbol(symbol))?;
+        Some(op.1)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while self.keyword("or") {
+            left = Expr::Binary(Box::new(left), BinaryOp::Or, Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.not()?;
+        while self.keyword("and") {
+            left = Expr::Binary(Box::new(left), BinaryOp::And, Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") || self.symbol("!") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.additive()?;
+        let operators = [
+            ("==", BinaryOp::Equal),
+            ("=", BinaryOp::Equal),
+            ("!=", BinaryOp::NotEqual),
+            (">", BinaryOp::Greater),
+            (">=", BinaryOp::GreaterEqual),
+            ("<", BinaryOp::Less),
+            ("<=", BinaryOp::LessEqual),
+        ];
+
+        match self.operator(&operators) {
+            Some(op) => Ok(Expr::Binary(Box::new(left), op, Box::new(self.additive()?))),
+            None => Ok(left),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        let mut left = self.multiplicative()?;
+        let operators = [("+", BinaryOp::Add), ("-", BinaryOp::Subtract)];
+        while let Some(op) = self.operator(&operators) {
+            left = Expr::Binary(Box::new(left), op, Box::new(self.multiplicative()?));
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        let operators = [
+            ("*", BinaryOp::Multiply),
+            ("/", BinaryOp::Divide),
+            ("%", BinaryOp::Remainder),
+        ];
+        while let Some(op) = self.operator(&operators) {
+            left = Expr::Binary(Box::new(left), op, Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.symbol("-") {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Literal(Value::Number(n))),
+            Some(Token::Text(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::Name(name)) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "and" | "or" | "not" => Err(format!("Unexpected keyword: {}", name)),
+                _ => Ok(Expr::Variable(name)),
+            },
+            Some(Token::OpenParen) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::CloseParen) => Ok(expr),
+                    _ => Err("Missing closing parenthesis".to_string()),
+                }
+            }
+            Some(token) => Err(format!("Unexpected token: {:?}", token)),
+            None => Err("Unexpected end of condition".to_string()),
+        }
+    }
+}
+
+/// Parses a condition with `and`, `or`, `not`, comparisons, arithmetic and parenthesis
+pub fn parse_expression(input: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        position: 0,
+    };
+
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(format!("Unexpected token: {:?}", token)),
+    }
+}
+
+/// Checks a comparison between two values, numbers compare numerically
+pub fn compare(left: &Value, op: BinaryOp, right: &Value) -> bool {
+    let ordering = left.compare(right);
+
+    match op {
+        BinaryOp::Equal => ordering == Ordering::Equal,
+        BinaryOp::NotEqual => ordering != Ordering::Equal,
+        BinaryOp::Greater => ordering == Ordering::Greater,
+        BinaryOp::GreaterEqual => ordering != Ordering::Less,
+        BinaryOp::Less => ordering == Ordering::Less,
+        _ => ordering != Ordering::Greater,
+    }
+}
+
+/// Looks up a variable, `len` and `length` after a list, map or string give its size
+fn lookup_variable(path: &str, context: &Context) -> Result<Value, RenderError> {
+    if let Some(value) = lookup(context, path) {
+        return Ok(value.clone());
+    }
+
+    let size = path
+        .rsplit_once('.')
+        .filter(|(_, field)| matches!(*field, "len" | "length"))
+        .and_then(|(parent, _)| match lookup(context, parent)? {
+            Value::List(items) => Some(items.len()),
+            Value::Map(map) => Some(map.len()),
+            Value::String(s) => Some(s.chars().count()),
+            _ => None,
+        });
+
+    match size {
+        Some(size) => Ok(Value::Number(size as f64)),
+        None => Err(RenderError::MissingVariable(path.to_string())),
+    }
+}
+
+/// Applies an arithmetic operator, both operands must be numbers
+fn arithmetic(left: &Value, op: BinaryOp, right: &Value) -> Result<Value, RenderError> {
+    let (Some(l), Some(r)) = (left.as_number(), right.as_number()) else {
+        return Err(RenderError::ExpressionError(format!(
+            "Arithmetic on non numeric values: {} and {}",
+            left, right
+        )));
+    };
+
+    let result = match op {
+        BinaryOp::Add => l + r,
+        BinaryOp::Subtract => l - r,
+        BinaryOp::Multiply => l * r,
+        BinaryOp::Divide | BinaryOp::Remainder if r == 0.0 => {
+            return Err(RenderError::ExpressionError("Division by zero".to_string()));
+        }
+        BinaryOp::Divide => l / r,
+        _ => l % r,
+    };
+
+    Ok(Value::Number(result))
+}
+
+/// Evaluates an expression against the context
+pub fn evaluate(expr: &Expr, context: &Context) -> Result<Value, RenderError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Variable(path) => lookup_variable(path, context),
+        Expr::Not(inner) => Ok(Value::Bool(!evaluate(inner, context)?.is_truthy())),
+        Expr::Negate(inner) => arithmetic(
+            &Value::Number(0.0),
+            BinaryOp::Subtract,
+            &evaluate(inner, context)?,
+        ),
+        Expr::Binary(left, BinaryOp::And, right) => Ok(Value::Bool(
+            evaluate(left, context)?.is_truthy() && evaluate(right, context)?.is_truthy(),
+        )),
+        Expr::Binary(left, BinaryOp::Or, right) => Ok(Value::Bool(
+            evaluate(left, context)?.is_truthy() || evaluate(right, context)?.is_truthy(),
+        )),
+        Expr::Binary(left, op, right) => {
+            let left = evaluate(left, context)?;
+            let right = match (right.as_ref(), evaluate(right, context)) {
+                // A bare word missing from the context is compared as text: `name == Bob`
+                (Expr::Variable(text), Err(RenderError::MissingVariable(_))) => {
+                    Value::from(text.as_str())
+                }
+                (_, value) => value?,
+            };
+
+            match op {
+                BinaryOp::Add
+                | BinaryOp::Subtract
+                | BinaryOp::Multiply
+                | BinaryOp::Divide
+                | BinaryOp::Remainder => arithmetic(&left, *op, &right),
+                _ => Ok(Value::Bool(compare(&left, *op, &right))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn context() -> Context {
+        let mut user = HashMap::new();
+        user.insert("active".to_string(), Value::Bool(true));
+        user.insert("age".to_string(), Value::Number(30.0));
+
+        let mut context = Context::new();
+        context.insert("user".to_string(), Value::Map(user));
+        context.insert("items".to_string(), vec!["a", "b", "c", "d"].into());
+        context
+    }
+
+    fn check(input: &str) -> bool {
+        evaluate(&parse_expression(input).unwrap(), &context())
+            .unwrap()
+            .is_truthy()
+    }
+
+    #[test]
+    fn check_boolean_expressions() {
+        assert!(check("items.len > 3 and user.active"));
+        assert!(check("not user.active or user.age >= 30"));
+        assert!(!check("not (user.active or false)"));
+        assert!(check("user.age * 2 - 10 == 50"));
+        assert!(check("(user.age + 6) % 4 = 0 and -user.age < 0"));
+        assert!(check("user.age / 3 == \"10\""));
+    }
+
+    #[test]
+    fn check_expression_errors() {
+        assert!(parse_expression("a and").is_err());
+        assert!(parse_expression("(a or b").is_err());
+        assert!(parse_expression("a b").is_err());
+        assert_eq!(
+            evaluate(&parse_expression("missing and true").unwrap(), &context()),
+            Err(RenderError::MissingVariable("missing".to_string()))
+        );
+        assert!(evaluate(&parse_expression("user.age / 0").unwrap(), &context()).is_err());
+    }
+}
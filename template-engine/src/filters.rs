@@ -0,0 +1,265 @@
+use crate::value::Value;
+use std::collections::HashMap;
+
+/// A filter receives the piped value plus its arguments and returns the transformed value
+pub type Filter = Box<dyn Fn(&Value, &[Value]) -> Result<Value, String>>;
+
+/// Stores every filter that can be used with the pipe syntax (`{{ name | upper }}`)
+pub struct FilterRegistry {
+    filters: HashMap<String, Filter>,
+}
+
+impl FilterRegistry {
+    /// Creates a registry holding the built-in filters
+    pub fn new() -> Self {
+        let mut registry = FilterRegistry {
+            filters: HashMap::new(),
+        };
+
+        registry.register("upper", |value, _| {
+            Ok(value.to_string().to_uppercase().into())
+        });
+        registry.register("lower", |value, _| {
+            Ok(value.to_string().to_lowercase().into())
+        });
+        registry.register("trim", |value, _| Ok(value.to_string().trim().into()));
+        registry.register("length", length);
+        registry.register("default", default);
+        registry.register("date", date);
+        registry.register("escape", |value, _| {
+            Ok(escape_html(&value.to_string()).into())
+        });
+        // Marks the value as trusted HTML, the generator skips auto-escaping for it
+        registry.register("safe", |value, _| Ok(value.clone()));
+        registry.register("raw", |value, _| Ok(value.clone()));
+
+        registry
+    }
+
+    /// Adds a new filter, replacing any previous filter with the same name
+    pub fn register<F>(&mut self, name: &str, filter: F)
+    where
+        F: Fn(&Value, &[Value]) -> Result<Value, String> + 'static,
+    {
+        self.filters.insert(name.to_string(), Box::new(filter));
+    }
+
+    /// Applies the filter called `name` to a value
+    pub fn apply(&self, name: &str, value: &Value, args: &[Value]) -> Result<Value, String> {
+        match self.filters.get(name) {
+            Some(filter) => filter(value, args),
+            None => Err(format!("Unknown filter: {}", name)),
+        }
+    }
+}
+
+impl Default for FilterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Replaces the characters with a special meaning in HTML by their entities
+pub fn escape_html(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        match c {
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '&' => output.push_str("&amp;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// Number of characters of a string or elements of a list or map
+fn length(value: &Value, _: &[Value]) -> Result<Value, String> {
+    let len = match value {
+        Value::String(s) => s.chars().count(),
+        Value::List(items) => items.len(),
+        Value::Map(map) => map.len(),
+        other => other.to_string().chars().count(),
+    };
+
+    Ok(Value::Number(len as f64))
+}
+
+/// Replaces an empty value with the first argument
+fn default(value: &Value, args: &[Value]) -> Result<Value, String> {
+    let fallback = args.first().ok_or("default filter expects one argument")?;
+
+    match value {
+        Value::String(s) if s.is_empty() => Ok(fallback.clone()),
+        _ => Ok(value.clone()),
+    }
+}
+
+/// Formats a unix timestamp or an ISO date (`2024-01-31` or `2024-01-31T10:00:00`)
+fn date(value: &Value, args: &[Value]) -> Result<Value, String> {
+    let format = match args.first() {
+        Some(format) => format.to_string(),
+        None => "%Y-%m-%d".to_string(),
+    };
+
+    let (date, time) = match value {
+        Value::Number(timestamp) => {
+            let timestamp = *timestamp as i64;
+            let seconds = timestamp.rem_euclid(86_400);
+            (
+                civil_from_days(timestamp.div_euclid(86_400)),
+                (seconds / 3600, seconds % 3600 / 60, seconds % 60),
+            )
+        }
+        Value::String(s) => parse_iso_date(s).ok_or(format!("Invalid date: {}", s))?,
+        other => return Err(format!("Invalid date: {}", other)),
+    };
+
+    let mut output = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => output.push_str(&format!("{:04}", date.0)),
+            Some('y') => output.push_str(&format!("{:02}", date.0.rem_euclid(100))),
+            Some('m') => output.push_str(&format!("{:02}", date.1)),
+            Some('d') => output.push_str(&format!("{:02}", date.2)),
+            Some('H') => output.push_str(&format!("{:02}", time.0)),
+            Some('M') => output.push_str(&format!("{:02}", time.1)),
+            Some('S') => output.push_str(&format!("{:02}", time.2)),
+            Some('%') => output.push('%'),
+            Some(other) => return Err(format!("Unsupported date specifier: %{}", other)),
+            None => output.push('%'),
+        }
+    }
+
+    Ok(output.into())
+}
+
+type Date = (i64, i64, i64);
+type Time = (i64, i64, i64);
+
+/// Parses `YYYY-MM-DD` optionally followed by `THH:MM:SS` or ` HH:MM:SS`
+fn parse_iso_date(input: &str) -> Option<(Date, Time)> {
+    let input = input.trim();
+    let (date, time) = match input.find(['T', ' ']) {
+        Some(i) => (&input[..i], Some(&input[i + 1..])),
+        None => (input, None),
+    };
+
+    let date: Vec<i64> = date
+        .split('-')
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    let time: Vec<i64> = match time {
+        Some(time) => time
+            .split(':')
+            .map(|n| n.parse().ok())
+            .collect::<Option<_>>()?,
+        None => vec![0, 0, 0],
+    };
+
+    match (date.as_slice(), time.as_slice()) {
+        ([y, m, d], [hh, mm, ss]) if (1..=12).contains(m) && (1..=31).contains(d) => {
+            Some(((*y, *m, *d), (*hh, *mm, *ss)))
+        }
+        _ => None,
+    }
+}
+
+/// Converts days since the unix epoch into a (year, month, day) triple
+fn civil_from_days(days: i64) -> Date {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_builtin_filters() {
+        let filters = FilterRegistry::new();
+
+        assert_eq!(
+            filters.apply("upper", &"bob".into(), &[]).unwrap(),
+            "BOB".into()
+        );
+        assert_eq!(
+            filters.apply("trim", &"  bob ".into(), &[]).unwrap(),
+            "bob".into()
+        );
+        assert_eq!(
+            filters
+                .apply("length", &vec!["a", "b"].into(), &[])
+                .unwrap(),
+            Value::Number(2.0)
+        );
+        assert_eq!(
+            filters.apply("default", &"".into(), &["x".into()]).unwrap(),
+            "x".into()
+        );
+    }
+
+    #[test]
+    fn check_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">Tom & 'Jerry'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn check_date_filter() {
+        let filters = FilterRegistry::new();
+
+        assert_eq!(
+            filters
+                .apply("date", &Value::Number(86_400.0 * 365.0), &[])
+                .unwrap(),
+            "1971-01-01".into()
+        );
+        assert_eq!(
+            filters
+                .apply(
+                    "date",
+                    &"2024-02-29T13:05:00".into(),
+                    &["%d/%m/%Y %H:%M".into()]
+                )
+                .unwrap(),
+            "29/02/2024 13:05".into()
+        );
+    }
+
+    #[test]
+    fn check_register_filter() {
+        let mut filters = FilterRegistry::new();
+        filters.register("reverse", |value, _| {
+            Ok(value.to_string().chars().rev().collect::<String>().into())
+        });
+
+        assert_eq!(
+            filters.apply("reverse", &"abc".into(), &[]).unwrap(),
+            "cba".into()
+        );
+        assert!(filters.apply("missing", &"abc".into(), &[]).is_err());
+    }
+}
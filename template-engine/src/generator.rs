@@ -1,125 +1,322 @@
+use crate::environment::{Environment, MissingVariable};
+use crate::error::RenderError;
+use crate::expression::{BinaryOp, compare, evaluate};
+use crate::filters::escape_html;
 use crate::parser::{
-    get_index_for_symbol, Conditional, ContentType, ExpressionData, OperationType, TagType,
+    ConditionData, Conditional, ContentType, ExpressionData, OperationType, TagType,
+    WhitespaceControl, get_variable_expression,
 };
+use crate::value::{Context, Value, lookup};
 use std::collections::HashMap;
 
 /// Generates HTML code for a template var token
-pub fn generate_html_template_var<'a>(
-    content: &'a mut ExpressionData,
-    context: &HashMap<String, Vec<String>>,
-) -> &'a mut ExpressionData {
-    content.gen_html = content.expression.clone();
+pub fn generate_html_template_var(
+    content: &ExpressionData,
+    context: &Context,
+    env: &Environment,
+) -> Result<String, RenderError> {
+    let mut html = content.expression.clone();
 
     for var in &content.var_map {
-        let i = get_index_for_symbol(&var, '{').unwrap();
-        let k = get_index_for_symbol(&var, '}').unwrap();
-        let var_without_braces = &var[(i + 2)..k];
+        let val = generate_variable(&var[2..var.len() - 2], context, env)?;
 
-        let val = &context.get(var_without_braces).unwrap()[0];
+        html = html.replace(var, &val)
+    }
+
+    Ok(html)
+}
+
+/// Looks up a single variable and pipes it through its filters
+fn generate_variable(
+    input: &str,
+    context: &Context,
+    env: &Environment,
+) -> Result<String, RenderError> {
+    let expression = get_variable_expression(input);
+    let has_default = expression.filters.iter().any(|f| f.name == "default");
+
+    let mut value = match lookup(context, &expression.path) {
+        Some(value) => value.clone(),
+        None if has_default => Value::from(""),
+        None => match env.missing_variable {
+            MissingVariable::Strict => {
+                return Err(RenderError::MissingVariable(expression.path));
+            }
+            MissingVariable::Lenient => Value::from(""),
+            MissingVariable::Keep => return Ok(format!("{{{{{}}}}}", input)),
+        },
+    };
+
+    for filter in &expression.filters {
+        value = env
+            .filters
+            .apply(&filter.name, &value, &filter.args)
+            .map_err(RenderError::FilterError)?;
+    }
+
+    let is_safe = expression
+        .filters
+        .iter()
+        .any(|f| matches!(f.name.as_str(), "safe" | "raw" | "escape"));
+
+    if env.autoescape && !is_safe {
+        Ok(escape_html(&value.to_string()))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// Resolves the right side of a comparison: a quoted literal, a context variable or bare text
+fn get_operand_value(operand: &str, context: &Context) -> Value {
+    for quote in ['"', '\''] {
+        if let Some(text) = operand
+            .strip_prefix(quote)
+            .and_then(|s| s.strip_suffix(quote))
+        {
+            return Value::from(text);
+        }
+    }
+
+    match lookup(context, operand) {
+        Some(v) => v.clone(),
+        None => Value::from(operand),
+    }
+}
+
+/// Generates HTML code for any parsed content, nested or not
+pub fn generate_html_content(
+    content: &ContentType,
+    context: &Context,
+    env: &Environment,
+) -> Result<String, RenderError> {
+    match content {
+        ContentType::Literal(text) => Ok(text.clone()),
+        ContentType::Tag(TagType::IfTag(data)) | ContentType::Tag(TagType::ForTag(data)) => {
+            generate_html_tag(data, context, env)
+        }
+        ContentType::TemplateVariable(data) => generate_html_template_var(data, context, env),
+        ContentType::Tag(TagType::IncludeTag(name)) => generate_html_include(name, context, env),
+    }
+}
+
+/// Loads and renders the template referenced by an include tag
+fn generate_html_include(
+    name: &str,
+    context: &Context,
+    env: &Environment,
+) -> Result<String, RenderError> {
+    let depth = env.include_stack.borrow().len();
 
-        content.gen_html = content.gen_html.replace(var, val)
+    if env
+        .include_stack
+        .borrow()
+        .iter()
+        .any(|included| included == name)
+    {
+        return Err(RenderError::IncludeError(format!(
+            "Include cycle detected: {}",
+            name
+        )));
     }
+    if depth >= env.max_include_depth {
+        return Err(RenderError::IncludeError(format!(
+            "Include depth limit of {} reached",
+            env.max_include_depth
+        )));
+    }
+
+    let template = env.get_template(name).map_err(RenderError::IncludeError)?;
+
+    env.include_stack.borrow_mut().push(name.to_string());
+    let html = template.render_with(context, env);
+    env.include_stack.borrow_mut().pop();
+
+    html
+}
 
-    content
+/// Builds the implicit `loop` object exposed inside for tags
+fn loop_metadata(index: usize, length: usize) -> Value {
+    let mut metadata = HashMap::new();
+
+    metadata.insert("index".to_string(), Value::Number((index + 1) as f64));
+    metadata.insert("index0".to_string(), Value::Number(index as f64));
+    metadata.insert(
+        "revindex".to_string(),
+        Value::Number((length - index) as f64),
+    );
+    metadata.insert("length".to_string(), Value::Number(length as f64));
+    metadata.insert("first".to_string(), Value::Bool(index == 0));
+    metadata.insert("last".to_string(), Value::Bool(index + 1 == length));
+
+    Value::Map(metadata)
+}
+
+/// Applies the inner trim markers of an if or for tag to the HTML of its body
+pub fn trim_body(html: &str, whitespace: WhitespaceControl) -> &str {
+    let mut body = html;
+    if whitespace.trim_body_start {
+        body = body.trim_start();
+    }
+    if whitespace.trim_body_end {
+        body = body.trim_end();
+    }
+
+    body
+}
+
+/// Generates the body of an if or for tag honoring its inner trim markers
+fn generate_html_body(
+    expression: &ContentType,
+    whitespace: WhitespaceControl,
+    context: &Context,
+    env: &Environment,
+) -> Result<String, RenderError> {
+    let html = generate_html_content(expression, context, env)?;
+
+    Ok(trim_body(&html, whitespace).to_string())
+}
+
+/// Output of an if or for tag whose operand is missing from the context
+fn missing_operand(name: &str, env: &Environment) -> Result<String, RenderError> {
+    match env.missing_variable {
+        MissingVariable::Strict => Err(RenderError::MissingVariable(name.to_string())),
+        MissingVariable::Lenient | MissingVariable::Keep => Ok(String::new()),
+    }
 }
 
 /// Generates HTML code for a if or for tag tokens
 pub fn generate_html_tag(
-    content: &mut Conditional,
-    context: &HashMap<String, Vec<String>>,
-) -> String {
-    let mut html = String::new();
+    content: &Conditional,
+    context: &Context,
+    env: &Environment,
+) -> Result<String, RenderError> {
+    let Conditional {
+        condition,
+        expression,
+        whitespace,
+    } = content;
+    let separator = if whitespace.trim_body_end { "" } else { "\n" };
 
-    match &content.condition.operation {
-        OperationType::Equal => {
-            let right_operand: Vec<&str> = content.condition.right_operand.split(" ").collect();
+    generate_html_condition(condition, context, env, separator, |scope| {
+        generate_html_body(expression, *whitespace, scope, env)
+    })
+}
+
+/// Evaluates the condition of an if or for tag calling `body` once when the condition holds or
+/// once per element of a loop, with the context of the iteration. `separator` is appended after
+/// each iteration
+pub fn generate_html_condition(
+    condition: &ConditionData,
+    context: &Context,
+    env: &Environment,
+    separator: &str,
+    mut body: impl FnMut(&Context) -> Result<String, RenderError>,
+) -> Result<String, RenderError> {
+    let mut html = String::new();
 
-            let left_operand: &Vec<String> = match context.get(&content.condition.left_operand) {
+    match &condition.operation {
+        OperationType::Equal
+        | OperationType::NotEqual
+        | OperationType::Greater
+        | OperationType::GreaterEqual
+        | OperationType::Less
+        | OperationType::LessEqual => {
+            let left_operand = match lookup(context, &condition.left_operand) {
                 Some(v) => v,
-                None => return " ".to_string(),
+                None => return missing_operand(&condition.left_operand, env),
             };
+            let right_operand = get_operand_value(&condition.right_operand, context);
 
-            if right_operand == *left_operand {
-                match &mut *content.expression {
-                    ContentType::Literal(text) => html.push_str(&text),
-                    ContentType::Tag(tag) => match tag {
-                        TagType::IfTag(data) => {
-                            html.push_str(&generate_html_tag(&mut *data, context))
-                        }
-                        TagType::ForTag(data) => {
-                            html.push_str(&generate_html_tag(&mut *data, context))
-                        }
-                    },
-                    ContentType::TemplateVariable(data) => {
-                        html.push_str(&generate_html_template_var(data, context).gen_html)
-                    }
-                    ContentType::Unrecognized => html.push_str(""),
-                }
+            let op = match condition.operation {
+                OperationType::Equal => BinaryOp::Equal,
+                OperationType::NotEqual => BinaryOp::NotEqual,
+                OperationType::Greater => BinaryOp::Greater,
+                OperationType::GreaterEqual => BinaryOp::GreaterEqual,
+                OperationType::Less => BinaryOp::Less,
+                _ => BinaryOp::LessEqual,
+            };
+            let is_true = compare(left_operand, op, &right_operand);
+
+            if is_true {
+                html.push_str(&body(context)?);
             }
         }
         OperationType::In => {
-            let right_operand: &Vec<String> = match context.get(&content.condition.right_operand) {
-                Some(v) => v,
-                None => return " ".to_string(),
+            let right_operand = match lookup(context, &condition.right_operand) {
+                Some(Value::List(items)) => items,
+                Some(_) => return Ok(" ".to_string()),
+                None => return missing_operand(&condition.right_operand, env),
             };
 
-            for element in right_operand {
-                match *content.expression {
-                    ContentType::Literal(ref text) => {
-                        html.push_str(&text);
-                    }
-                    ContentType::TemplateVariable(ref mut data) => {
-                        data.gen_html = data.expression.clone();
-                        data.gen_html = data.gen_html.replace(&data.var_map[0], &element);
-
-                        html.push_str(&data.gen_html);
-                    }
-                    _ => {}
-                }
-                html.push_str("\n");
+            // Each iteration sees the loop variable on top of the outer context
+            let mut scope = context.clone();
+
+            for (i, element) in right_operand.iter().enumerate() {
+                scope.insert(condition.left_operand.clone(), element.clone());
+                scope.insert("loop".to_string(), loop_metadata(i, right_operand.len()));
+
+                html.push_str(&body(&scope)?);
+                html.push_str(separator);
             }
         }
-        OperationType::Nosoported(e) => return e.to_string(),
+        OperationType::Expression(expr) => match evaluate(expr, context) {
+            Ok(value) if value.is_truthy() => {
+                html.push_str(&body(context)?);
+            }
+            Ok(_) => {}
+            Err(RenderError::MissingVariable(name)) => return missing_operand(&name, env),
+            Err(e) => return Err(e),
+        },
+        OperationType::Nosoported(e) => return Ok(e.to_string()),
     }
 
-    html
+    Ok(html)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::vec;
-
-    use crate::parser::{get_conditional_data, ConditionData};
+    use crate::loader::MemoryLoader;
+    use crate::parser::{
+        ConditionData, WhitespaceControl, get_conditional_data, get_expression_data,
+    };
+    use crate::template::Template;
 
     use super::*;
 
+    fn render(source: &str, context: &Context, env: &Environment) -> String {
+        Template::parse(source)
+            .unwrap()
+            .render_with(context, env)
+            .unwrap()
+    }
+
     #[test]
     fn check_literals() {
-        let mut context: HashMap<String, Vec<String>> = HashMap::new();
+        let mut context = Context::new();
 
-        context.insert("name".to_string(), vec!["Bob".to_string()]);
-        context.insert("city".to_string(), vec!["Boston".to_string()]);
+        context.insert("name".to_string(), Value::from("Bob"));
+        context.insert("city".to_string(), Value::from("Boston"));
 
         assert_eq!(
             generate_html_template_var(
-                &mut ExpressionData {
+                &ExpressionData {
                     expression: "{{name}}".to_string(),
                     var_map: vec!["{{name}}".to_string()],
-                    gen_html: "".to_string(),
                 },
-                &context
+                &context,
+                &Environment::new(MemoryLoader::new())
             )
-            .gen_html,
+            .unwrap(),
             "Bob".to_string()
         )
     }
 
     #[test]
     fn check_if_tag() {
-        let mut context: HashMap<String, Vec<String>> = HashMap::new();
+        let mut context = Context::new();
 
-        context.insert("name".to_string(), vec!["Bob".to_string()]);
-        context.insert("city".to_string(), vec!["Boston".to_string()]);
+        context.insert("name".to_string(), Value::from("Bob"));
+        context.insert("city".to_string(), Value::from("Boston"));
 
         assert_eq!(
             get_conditional_data("{% if name = Bob %} <h1> hello Bob </h1> {% endif %}")
@@ -130,26 +327,29 @@ mod tests {
                     operation: OperationType::Equal,
                     right_operand: "Bob".to_string(),
                 },
-                expression: Box::new(ContentType::Literal("<h1> hello Bob </h1>".to_string()))
+                expression: Box::new(ContentType::Literal("<h1> hello Bob </h1>".to_string())),
+                whitespace: WhitespaceControl::default(),
             }
         );
 
         assert_eq!(
             generate_html_tag(
-                &mut get_conditional_data("{% if name = Bob %} <h1> hello Bob </h1> {% endif %}")
+                &get_conditional_data("{% if name = Bob %} <h1> hello Bob </h1> {% endif %}")
                     .expect("Input for test"),
-                &context
-            ),
+                &context,
+                &Environment::new(MemoryLoader::new())
+            )
+            .unwrap(),
             "<h1> hello Bob </h1>".to_string()
         )
     }
 
     #[test]
     fn check_if_tag_var() {
-        let mut context: HashMap<String, Vec<String>> = HashMap::new();
+        let mut context = Context::new();
 
-        context.insert("name".to_string(), vec!["Bob".to_string()]);
-        context.insert("city".to_string(), vec!["Boston".to_string()]);
+        context.insert("name".to_string(), Value::from("Bob"));
+        context.insert("city".to_string(), Value::from("Boston"));
 
         assert_eq!(
             get_conditional_data("{% if name = Bob %} <h1> hello {{name}} </h1> {% endif %}")
@@ -163,40 +363,330 @@ mod tests {
                 expression: Box::new(ContentType::TemplateVariable(ExpressionData {
                     expression: "<h1> hello {{name}} </h1>".to_string(),
                     var_map: vec!["{{name}}".to_string()],
-                    gen_html: "".into()
-                }))
+                })),
+                whitespace: WhitespaceControl::default(),
             }
         );
 
         assert_eq!(
             generate_html_tag(
-                &mut get_conditional_data("{% if name = Bob %} <h1> hello Bob </h1> {% endif %}")
+                &get_conditional_data("{% if name = Bob %} <h1> hello Bob </h1> {% endif %}")
                     .expect("Input for test"),
-                &context
-            ),
+                &context,
+                &Environment::new(MemoryLoader::new())
+            )
+            .unwrap(),
             "<h1> hello Bob </h1>".to_string()
         )
     }
 
     #[test]
     fn check_for_tag_one() {
-        let mut context: HashMap<String, Vec<String>> = HashMap::new();
+        let mut context = Context::new();
 
-        context.insert(
-            "name".to_string(),
-            vec!["Bob".to_string(), "Lisa".to_string()],
-        );
-        context.insert("city".to_string(), vec!["Boston".to_string()]);
+        context.insert("name".to_string(), Value::from(vec!["Bob", "Lisa"]));
+        context.insert("city".to_string(), Value::from("Boston"));
 
         assert_eq!(
             generate_html_tag(
-                &mut get_conditional_data(
-                    "{% for costumer in name %} <li> {{customer}} </li> {% endfor %}"
+                &get_conditional_data(
+                    "{% for customer in name %} <li> {{customer}} </li> {% endfor %}"
                 )
                 .expect("Hardcoded input"),
-                &context
-            ),
+                &context,
+                &Environment::new(MemoryLoader::new())
+            )
+            .unwrap(),
+            "<li> Bob </li>\n<li> Lisa </li>\n".to_string()
+        )
+    }
+
+    #[test]
+    fn check_for_tag_objects() {
+        let users = ["Bob", "Lisa"]
+            .iter()
+            .map(|name| {
+                let mut user = HashMap::new();
+                user.insert("name".to_string(), Value::from(*name));
+                Value::Map(user)
+            })
+            .collect();
+
+        let mut context = Context::new();
+        context.insert("users".to_string(), Value::List(users));
+
+        assert_eq!(
+            generate_html_tag(
+                &get_conditional_data(
+                    "{% for user in users %} <li> {{ user.name }} </li> {% endfor %}"
+                )
+                .expect("Hardcoded input"),
+                &context,
+                &Environment::new(MemoryLoader::new())
+            )
+            .unwrap(),
             "<li> Bob </li>\n<li> Lisa </li>\n".to_string()
         )
     }
+
+    #[test]
+    fn check_filters() {
+        let mut context = Context::new();
+        context.insert("name".to_string(), Value::from(" bob "));
+
+        assert_eq!(
+            generate_html_template_var(
+                &get_expression_data(
+                    "<p>{{ name | trim | upper }} {{ city | default(\"Boston\") }}</p>"
+                ),
+                &context,
+                &Environment::new(MemoryLoader::new())
+            )
+            .unwrap(),
+            "<p>BOB Boston</p>".to_string()
+        )
+    }
+
+    #[test]
+    fn check_if_tag_comparisons() {
+        let mut context = Context::new();
+        context.insert("amount".to_string(), Value::Number(2000.0));
+        context.insert("limit".to_string(), Value::from("500"));
+        context.insert("name".to_string(), Value::from("Bob"));
+
+        let conditions = [
+            ("amount > 1000", true),
+            ("amount >= 2000", true),
+            ("amount < 300", false),
+            ("amount <= limit", false),
+            ("amount != 2000", false),
+            ("limit < 1000", true),
+            ("name == \"Bob\"", true),
+            ("name > Alice", true),
+            ("name != Bob", false),
+        ];
+
+        for (condition, expected) in conditions {
+            let html = generate_html_tag(
+                &get_conditional_data(&format!("{{% if {} %}} yes {{% endif %}}", condition))
+                    .expect("Hardcoded input"),
+                &context,
+                &Environment::new(MemoryLoader::new()),
+            )
+            .unwrap();
+            assert_eq!(html == "yes", expected, "{}", condition);
+        }
+    }
+
+    #[test]
+    fn check_include_tag() {
+        let mut loader = MemoryLoader::new();
+        loader.insert(
+            "header.html",
+            "<h1>{{ name }}</h1>\n{% include \"nav.html\" %}",
+        );
+        loader.insert("nav.html", "<nav></nav>");
+
+        let mut context = Context::new();
+        context.insert("name".to_string(), Value::from("Bob"));
+
+        assert_eq!(
+            render(
+                "{% include \"header.html\" %}",
+                &context,
+                &Environment::new(loader)
+            ),
+            "<h1>Bob</h1>\n<nav></nav>".to_string()
+        )
+    }
+
+    #[test]
+    fn check_include_cycle() {
+        let mut loader = MemoryLoader::new();
+        loader.insert("a.html", "{% include \"b.html\" %}");
+        loader.insert("b.html", "{% include \"a.html\" %}");
+
+        let env = Environment::new(loader);
+
+        assert_eq!(
+            Template::parse("{% include \"a.html\" %}")
+                .unwrap()
+                .render_with(&Context::new(), &env),
+            Err(RenderError::IncludeError(
+                "Include cycle detected: a.html".to_string()
+            ))
+        );
+        assert!(env.include_stack.borrow().is_empty());
+    }
+
+    #[test]
+    fn check_include_depth_limit() {
+        let mut loader = MemoryLoader::new();
+        loader.insert("a.html", "{% include \"b.html\" %}");
+        loader.insert("b.html", "b");
+
+        let mut env = Environment::new(loader);
+        env.max_include_depth = 1;
+
+        assert_eq!(
+            Template::parse("{% include \"a.html\" %}")
+                .unwrap()
+                .render_with(&Context::new(), &env),
+            Err(RenderError::IncludeError(
+                "Include depth limit of 1 reached".to_string()
+            ))
+        )
+    }
+
+    #[test]
+    fn check_autoescape() {
+        let mut context = Context::new();
+        context.insert("bio".to_string(), Value::from("<b>Bob & co</b>"));
+
+        let mut env = Environment::new(MemoryLoader::new());
+
+        assert_eq!(
+            render("<p>{{ bio }}</p>", &context, &env),
+            "<p>&lt;b&gt;Bob &amp; co&lt;/b&gt;</p>".to_string()
+        );
+        assert_eq!(
+            render("<p>{{ bio | safe }}</p>", &context, &env),
+            "<p><b>Bob & co</b></p>".to_string()
+        );
+
+        env.autoescape = false;
+        assert_eq!(
+            render("<p>{{ bio }}</p>", &context, &env),
+            "<p><b>Bob & co</b></p>".to_string()
+        );
+    }
+
+    #[test]
+    fn check_whitespace_control() {
+        let mut context = Context::new();
+        context.insert("names".to_string(), Value::from(vec!["Bob", "Lisa"]));
+
+        let env = Environment::new(MemoryLoader::new());
+
+        assert_eq!(
+            render(
+                "<ul>\n{% for name in names %} <li>{{name}}</li> {% endfor -%}\n</ul>",
+                &context,
+                &env
+            ),
+            "<ul>\n<li>Bob</li>\n<li>Lisa</li>\n</ul>".to_string()
+        );
+        assert_eq!(
+            render(
+                "<p>\n  {%- for name in names %}{{name}}, {%- endfor -%}\n</p>",
+                &context,
+                &env
+            ),
+            "<p>Bob,Lisa,</p>".to_string()
+        );
+    }
+
+    #[test]
+    fn check_comments() {
+        let mut context = Context::new();
+        context.insert("name".to_string(), Value::from("Bob"));
+
+        assert_eq!(
+            render(
+                "{# header\n   section #}\n<h1>{{name}}</h1>{# inline #}",
+                &context,
+                &Environment::new(MemoryLoader::new())
+            ),
+            "<h1>Bob</h1>".to_string()
+        );
+    }
+
+    #[test]
+    fn check_loop_metadata() {
+        let mut context = Context::new();
+        context.insert("names".to_string(), Value::from(vec!["Bob", "Lisa", "Tom"]));
+
+        let env = Environment::new(MemoryLoader::new());
+
+        assert_eq!(
+            render(
+                "{% for name in names %} {{loop.index}}/{{ loop.length }} {{name}} {% endfor %}",
+                &context,
+                &env
+            ),
+            "1/3 Bob\n2/3 Lisa\n3/3 Tom\n".to_string()
+        );
+        assert_eq!(
+            render(
+                "{% for name in names -%} {% if loop.last = false %} {{name}}, {% endif %} {%- endfor %}",
+                &context,
+                &env
+            ),
+            "Bob,Lisa,".to_string()
+        );
+    }
+
+    #[test]
+    fn check_missing_variable_strategies() {
+        let mut context = Context::new();
+        context.insert("name".to_string(), Value::from("Bob"));
+
+        let template = Template::parse("<p>{{name}} {{ city | upper }}</p>").unwrap();
+        let mut env = Environment::new(MemoryLoader::new());
+
+        assert_eq!(
+            template.render_with(&context, &env),
+            Err(RenderError::MissingVariable("city".to_string()))
+        );
+
+        env.missing_variable = MissingVariable::Lenient;
+        assert_eq!(
+            render("<p>{{name}} {{ city | upper }}</p>", &context, &env),
+            "<p>Bob </p>"
+        );
+        assert_eq!(
+            render("{% if city = Boston %} yes {% endif %}", &context, &env),
+            ""
+        );
+
+        env.missing_variable = MissingVariable::Keep;
+        assert_eq!(
+            render("<p>{{name}} {{ city | upper }}</p>", &context, &env),
+            "<p>Bob {{ city | upper }}</p>"
+        );
+    }
+
+    #[test]
+    fn check_generate_expression_condition() {
+        let mut context = Context::new();
+        context.insert("items".to_string(), vec!["a", "b", "c", "d"].into());
+        context.insert("active".to_string(), Value::Bool(true));
+        let mut env = Environment::new(MemoryLoader::new());
+
+        assert_eq!(
+            render(
+                "{% if items.len > 3 and active %} many {% endif %}",
+                &context,
+                &env
+            ),
+            "many"
+        );
+        assert_eq!(
+            render(
+                "{% if not (active or items.len * 2 > 10) %} none {% endif %}",
+                &context,
+                &env
+            ),
+            ""
+        );
+
+        let template = Template::parse("{% if missing or active %} yes {% endif %}").unwrap();
+        assert_eq!(
+            template.render_with(&context, &env),
+            Err(RenderError::MissingVariable("missing".to_string()))
+        );
+
+        env.missing_variable = MissingVariable::Lenient;
+        assert_eq!(template.render_with(&context, &env), Ok(String::new()));
+    }
 }
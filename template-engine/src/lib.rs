@@ -0,0 +1,28 @@
+//! Small HTML template engine supporting variables, filters, if/for tags and includes.
+//!
+//! ```
+//! use template_engine::{Context, Template, Value};
+//!
+//! let mut context = Context::new();
+//! context.insert("name".to_string(), Value::from("Bob"));
+//!
+//! let template = Template::parse("<h1>Hello {{ name | upper }}</h1>").unwrap();
+//! assert_eq!(template.render(&context).unwrap(), "<h1>Hello BOB</h1>");
+//! ```
+
+pub mod environment;
+pub mod error;
+pub mod expression;
+pub mod filters;
+pub mod generator;
+pub mod loader;
+pub mod parser;
+pub mod template;
+pub mod value;
+
+pub use environment::{Environment, MissingVariable};
+pub use error::{ParseError, ParseErrorKind, RenderError};
+pub use filters::FilterRegistry;
+pub use loader::{FileLoader, MemoryLoader, TemplateLoader};
+pub use template::Template;
+pub use value::{Context, Value};
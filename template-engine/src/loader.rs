@@ -0,0 +1,61 @@
+use std::{collections::HashMap, fs, path::PathBuf, time::SystemTime};
+
+/// Source of the templates referenced by include tags
+pub trait TemplateLoader {
+    /// Returns the content of the template called `name`
+    fn load(&self, name: &str) -> Result<String, String>;
+
+    /// Last modification time of the template, compiled templates are reused while it is unchanged
+    fn modified(&self, _name: &str) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// Loads templates from files relative to a template root directory
+pub struct FileLoader {
+    root: PathBuf,
+}
+
+impl FileLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FileLoader { root: root.into() }
+    }
+}
+
+impl TemplateLoader for FileLoader {
+    fn load(&self, name: &str) -> Result<String, String> {
+        let path = self.root.join(name);
+
+        fs::read_to_string(&path).map_err(|e| format!("Cannot load {}: {}", path.display(), e))
+    }
+
+    fn modified(&self, name: &str) -> Option<SystemTime> {
+        fs::metadata(self.root.join(name)).ok()?.modified().ok()
+    }
+}
+
+/// Keeps templates in memory, useful when templates are not stored on disk
+#[derive(Default)]
+pub struct MemoryLoader {
+    templates: HashMap<String, String>,
+}
+
+impl MemoryLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the template called `name`
+    pub fn insert(&mut self, name: &str, source: &str) {
+        self.templates.insert(name.to_string(), source.to_string());
+    }
+}
+
+impl TemplateLoader for MemoryLoader {
+    fn load(&self, name: &str) -> Result<String, String> {
+        self.templates
+            .get(name)
+            .cloned()
+            .ok_or(format!("Template not found: {}", name))
+    }
+}
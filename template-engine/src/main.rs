@@ -1,35 +1,337 @@
-use std::{collections::HashMap, io, io::BufRead};
+use clap::{Parser, Subcommand};
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, SystemTime},
+};
 
-mod generator;
-use crate::generator::{generate_html_tag, generate_html_template_var};
+use template_engine::{
+    Context, Environment, FileLoader, MissingVariable, Template, Value,
+    value::{context_from_json, context_from_yaml, insert_path},
+};
 
-mod parser;
-use crate::parser::{get_content_type, ContentType, TagType};
+#[derive(Debug, Parser)]
+#[command(version, about)]
+/// Renders the HTML template read from stdin line by line, a whole template file with render or
+/// a whole directory of templates with build
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-fn main() -> () {
-    let mut context: HashMap<String, Vec<String>> = HashMap::new();
+    /// What to do with variables missing from the context: strict, lenient or keep
+    #[arg(long("missing"), default_value = "strict", global = true)]
+    missing_variable: MissingVariable,
 
-    context.insert("name".to_string(), vec!["Bob".to_string()]);
-    context.insert("city".to_string(), vec!["Boston".to_string()]);
+    /// Adds a variable to the context, dotted names create nested maps (e.g. user.name=Bob)
+    #[arg(long("var"), value_name = "KEY=VALUE", value_parser = parse_var, global = true)]
+    vars: Vec<(String, String)>,
 
-    for line in io::stdin().lock().lines() {
-        match get_content_type(&line.unwrap().clone()) {
-            ContentType::TemplateVariable(mut content) => {
-                let html = generate_html_template_var(&mut content, &context)
-                    .gen_html
-                    .clone();
-                println!("{}", html);
+    /// Exposes the environment variables under the env namespace (e.g. {{env.HOME}})
+    #[arg(long("env"), global = true)]
+    env: bool,
+
+    /// JSON or YAML (.yaml, .yml) file with the variables available to the templates
+    #[arg(long("context"), value_name = "FILE", global = true)]
+    context: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Renders a template file, if and for tags may span several lines and nest
+    Render(RenderArgs),
+    /// Renders every template of a directory tree into a mirrored output tree
+    Build(BuildArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct RenderArgs {
+    /// Template to render, included and extended templates are looked up next to it
+    file: PathBuf,
+}
+
+#[derive(Debug, clap::Args)]
+struct BuildArgs {
+    /// Directory holding the templates, files starting with _ are partials and are not rendered
+    #[arg(long("src"), default_value = "templates")]
+    src: PathBuf,
+
+    /// Directory where the rendered files are written
+    #[arg(long("out"), default_value = "site")]
+    out: PathBuf,
+
+    /// Rebuilds the site every time a file of the source directory changes
+    #[arg(long("watch"))]
+    watch: bool,
+}
+
+/// Extensions of the files rendered as templates, any other file is copied as is
+const TEMPLATE_EXTENSIONS: [&str; 2] = ["html", "htm"];
+
+/// Time between two checks of the source directory in watch mode
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Splits a `key=value` pair given with --var
+fn parse_var(input: &str) -> Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("Invalid variable, expected KEY=VALUE: {}", input)),
+    }
+}
+
+/// Reads a --context file, YAML when its extension says so and JSON otherwise
+fn context_from_file(path: &Path) -> Result<Context, String> {
+    let is_yaml = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ["yaml", "yml"].contains(&ext.to_lowercase().as_str()));
+
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|input| {
+            if is_yaml {
+                context_from_yaml(&input)
+            } else {
+                context_from_json(&input)
             }
-            ContentType::Literal(text) => println!("{}", text),
-            ContentType::Tag(TagType::ForTag(ref mut content)) => {
-                let html = generate_html_tag(&mut *content, &context);
-                println!("{}", html);
+        })
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Reads the --context file, or starts from `default` without one, and adds the --env and --var
+/// variables on top
+fn load_context(args: &Args, default: impl FnOnce() -> Context) -> Result<Context, String> {
+    let mut context = match &args.context {
+        Some(path) => context_from_file(path)?,
+        None => default(),
+    };
+    extend_context(&mut context, args);
+
+    Ok(context)
+}
+
+/// Adds the --env and --var variables on top of `context`
+fn extend_context(context: &mut Context, args: &Args) {
+    // Environment variables override the defaults and --var flags override everything
+    if args.env {
+        let vars = env::vars().map(|(key, value)| (key, Value::from(value)));
+        context.insert("env".to_string(), Value::Map(vars.collect()));
+    }
+
+    for (key, value) in &args.vars {
+        insert_path(context, key, Value::from(value.as_str()));
+    }
+}
+
+/// Context used when rendering from stdin without a --context file
+fn demo_context() -> Context {
+    let mut context = Context::new();
+
+    context.insert("name".to_string(), Value::from("Bob"));
+    context.insert("city".to_string(), Value::from("Boston"));
+
+    let users = [("Alice", "Paris"), ("Carol", "Madrid")]
+        .iter()
+        .map(|(name, city)| {
+            let mut user = HashMap::new();
+            user.insert("name".to_string(), Value::from(*name));
+            user.insert("city".to_string(), Value::from(*city));
+            Value::Map(user)
+        })
+        .collect();
+    context.insert("users".to_string(), Value::List(users));
+
+    context
+}
+
+/// Renders every line read from stdin as a template of its own, a line that fails is reported
+/// and the next ones are still rendered
+fn run_stdin(context: &Context, env: &Environment) -> Result<(), String> {
+    let mut failed = 0;
+
+    for line in io::stdin().lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+
+        match Template::parse(&line)
+            .map_err(|e| e.to_string())
+            .and_then(|template| {
+                template
+                    .render_with(context, env)
+                    .map_err(|e| e.to_string())
+            }) {
+            Ok(html) => println!("{}", html),
+            Err(e) => {
+                eprintln!("{}", e);
+                failed += 1;
             }
-            ContentType::Tag(TagType::IfTag(ref mut content)) => {
-                let html = generate_html_tag(&mut *content, &context);
-                println!("{}", html);
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} line(s) failed to render", n)),
+    }
+}
+
+/// Renders a whole template file
+fn run_render(render_args: &RenderArgs, args: &Args) -> Result<(), String> {
+    let context = load_context(args, Context::new)?;
+
+    let file = &render_args.file;
+    let (Some(dir), Some(name)) = (file.parent(), file.file_name()) else {
+        return Err(format!("{}: not a template file", file.display()));
+    };
+
+    let mut env = Environment::new(FileLoader::new(dir));
+    env.missing_variable = args.missing_variable;
+
+    let template = env.get_template(&name.to_string_lossy())?;
+    let html = template
+        .render_with(&context, &env)
+        .map_err(|e| format!("{}: {}", file.display(), e))?;
+
+    println!("{}", html);
+
+    Ok(())
+}
+
+/// Paths of every file below `dir`, relative to `root`
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    Ok(())
+}
+
+/// Partials are only meant to be included or extended by other templates
+fn is_partial(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('_'))
+}
+
+fn is_template(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| TEMPLATE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Renders or copies one file of the source tree into the output tree
+fn build_file(
+    relative: &Path,
+    args: &BuildArgs,
+    context: &Context,
+    env: &Environment,
+) -> Result<(), String> {
+    let target = args.out.join(relative);
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    if is_template(relative) {
+        let name = relative.to_string_lossy();
+        let template = env.get_template(&name)?;
+        let html = template
+            .render_with(context, env)
+            .map_err(|e| e.to_string())?;
+
+        fs::write(&target, html + "\n").map_err(|e| e.to_string())
+    } else {
+        fs::copy(args.src.join(relative), &target)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Builds the whole site and returns the number of files that failed
+fn build(args: &BuildArgs, context: &Context, env: &Environment) -> Result<usize, String> {
+    let mut files = vec![];
+    collect_files(&args.src, &args.src, &mut files)
+        .map_err(|e| format!("{}: {}", args.src.display(), e))?;
+    files.sort();
+
+    let mut failed = 0;
+    for relative in files.iter().filter(|path| !is_partial(path)) {
+        if let Err(e) = build_file(relative, args, context, env) {
+            eprintln!("{}: {}", args.src.join(relative).display(), e);
+            failed += 1;
+        }
+    }
+
+    Ok(failed)
+}
+
+/// Latest modification time among the files of the source tree
+fn last_modified(dir: &Path) -> Option<SystemTime> {
+    let mut files = vec![];
+    collect_files(dir, dir, &mut files).ok()?;
+
+    files
+        .iter()
+        .filter_map(|path| fs::metadata(dir.join(path)).ok()?.modified().ok())
+        .max()
+}
+
+fn run_build(build_args: &BuildArgs, args: &Args) -> Result<(), String> {
+    let context = load_context(args, Context::new)?;
+
+    let mut env = Environment::new(FileLoader::new(&build_args.src));
+    env.missing_variable = args.missing_variable;
+
+    let failed = build(build_args, &context, &env)?;
+
+    if build_args.watch {
+        let mut modified = last_modified(&build_args.src);
+
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+
+            let current = last_modified(&build_args.src);
+            if current != modified {
+                modified = current;
+                // A failed rebuild is reported and the next change is still picked up
+                match build(build_args, &context, &env) {
+                    Ok(_) => eprintln!("Rebuilt {}", build_args.out.display()),
+                    Err(e) => eprintln!("{}", e),
+                }
             }
-            ContentType::Unrecognized => println!("Unrecognized input"),
         }
     }
+
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} file(s) failed to render", n)),
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let result = match &args.command {
+        Some(Command::Render(render_args)) => run_render(render_args, &args),
+        Some(Command::Build(build_args)) => run_build(build_args, &args),
+        None => load_context(&args, demo_context).and_then(|context| {
+            let mut env = Environment::new(FileLoader::new("."));
+            env.missing_variable = args.missing_variable;
+
+            run_stdin(&context, &env)
+        }),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
@@ -1,31 +1,61 @@
+use crate::error::{ParseError, ParseErrorKind};
+use crate::expression::{Expr, parse_expression};
+use crate::value::Value;
+
 /// Each line in input can be one of following types
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum ContentType {
     Literal(String),
     TemplateVariable(ExpressionData),
     Tag(TagType),
-    Unrecognized,
 }
 
 /// Stores the result of the tokenization of the template string
 /// 1. Allow for the parsing of more than one template variable per statement
 /// 2. Allow for the parsing of more than two string literals in the input statement
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct ExpressionData {
     pub expression: String,
     pub var_map: Vec<String>,
-    pub gen_html: String,
+}
+
+/// A template variable split into the value lookup and the filters piped after it
+#[derive(Debug, PartialEq, Clone)]
+pub struct VariableExpression {
+    pub path: String,
+    pub filters: Vec<FilterCall>,
+}
+
+/// A single filter invocation such as `upper` or `default("x")`
+#[derive(Debug, PartialEq, Clone)]
+pub struct FilterCall {
+    pub name: String,
+    pub args: Vec<Value>,
 }
 
 /// Stores data from valid if tag expressions
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Conditional {
     pub condition: ConditionData,
     pub expression: Box<ContentType>,
+    pub whitespace: WhitespaceControl,
+}
+
+/// Trim markers (`{%-` and `-%}`) found on the delimiters of a tag line
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub struct WhitespaceControl {
+    /// `{%-` on the opening tag strips the whitespace before the tag
+    pub trim_before: bool,
+    /// `-%}` on the opening tag strips the whitespace at the start of the body
+    pub trim_body_start: bool,
+    /// `{%-` on the closing tag strips the whitespace at the end of the body
+    pub trim_body_end: bool,
+    /// `-%}` on the closing tag strips the whitespace after the tag
+    pub trim_after: bool,
 }
 
 /// Structurates data for evaluation purpuses
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct ConditionData {
     pub left_operand: String,
     pub operation: OperationType,
@@ -33,50 +63,249 @@ pub struct ConditionData {
 }
 
 /// Valid operation for for and if tags
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum OperationType {
     Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
     In,
+    /// Condition combining `and`, `or`, `not`, parenthesis or arithmetic
+    Expression(Expr),
     Nosoported(String),
 }
 
 /// Each Tag content corresponds to a for-tag or if-tag
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum TagType {
     ForTag(Box<Conditional>),
     IfTag(Box<Conditional>),
+    IncludeTag(String),
 }
 
 /// Accepts an input statement and tokenizes it into one of an if tag, a for tag, or a template varaible.
 /// Entry point for parser
-pub fn get_content_type(input: &str) -> ContentType {
-    let is_tag_expression = check_matching_pair(&input, "{%", "%}");
+pub fn get_content_type(input: &str) -> Result<ContentType, ParseError> {
+    let without_comments = strip_comments(input);
+    let raw_input = without_comments.as_str();
+    let normalized = strip_whitespace_markers(raw_input);
+    let input = normalized.as_str();
+
+    let is_tag_expression = check_matching_pair(input, "{%", "%}");
 
-    let is_for_tag = (check_symbol_string(&input, "for")) && check_symbol_string(&input, "in")
-        || check_symbol_string(&input, "endfor");
+    let is_for_tag = (check_symbol_string(input, "for")) && check_symbol_string(input, "in")
+        || check_symbol_string(input, "endfor");
 
-    let is_if_tag = check_symbol_string(&input, "if") || check_symbol_string(&input, "endif");
+    let is_if_tag = check_symbol_string(input, "if") || check_symbol_string(input, "endif");
 
-    let is_template_variable = check_matching_pair(&input, "{{", "}}");
+    let is_template_variable = check_matching_pair(input, "{{", "}}");
 
-    if is_tag_expression && is_for_tag {
-        let content = get_conditional_data(&input);
-        return ContentType::Tag(TagType::ForTag(Box::new(
-            content.expect("Should panic if it is not right"),
-        )));
+    let content = if let Some(name) = get_include_name(input) {
+        ContentType::Tag(TagType::IncludeTag(name))
+    } else if is_tag_expression && is_for_tag {
+        let content = get_conditional_data(raw_input)?;
+        ContentType::Tag(TagType::ForTag(Box::new(content)))
     } else if is_tag_expression && is_if_tag {
-        let content = get_conditional_data(&input);
-        return ContentType::Tag(TagType::IfTag(Box::new(
-            content.expect("Should panic if it is not right"),
-        )));
+        let content = get_conditional_data(raw_input)?;
+        ContentType::Tag(TagType::IfTag(Box::new(content)))
     } else if is_template_variable {
-        let content = get_expression_data(&input);
-        return ContentType::TemplateVariable(content);
+        let content = get_expression_data(input);
+        ContentType::TemplateVariable(content)
     } else if !is_tag_expression && !is_template_variable {
-        return ContentType::Literal(input.to_string());
+        ContentType::Literal(input.to_string())
     } else {
-        ContentType::Unrecognized
+        return Err(ParseError::new(
+            ParseErrorKind::UnrecognizedStatement,
+            input.trim(),
+        ));
+    };
+
+    Ok(content)
+}
+
+/// Removes `{# ... #}` comments, dropping the lines that only hold a comment
+pub fn strip_comments(input: &str) -> String {
+    strip_comment_lines(input)
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Removes `{# ... #}` comments and splits the result in lines, each one paired with the
+/// number of the source line where it starts so errors can point to the original template
+pub fn strip_comment_lines(input: &str) -> Vec<(usize, String)> {
+    let mut lines = vec![];
+    let mut current = String::new();
+    let mut had_comment = false;
+    let mut line = 1;
+    let mut start_line = 1;
+    let mut rest = input;
+
+    loop {
+        // Unclosed comments are kept as literal text
+        let next_comment = rest.find("{#").filter(|&i| rest[i..].contains("#}"));
+        let next_newline = rest.find('\n');
+
+        match (next_comment, next_newline) {
+            (Some(start), newline) if newline.is_none_or(|newline| start < newline) => {
+                let end = start + rest[start..].find("#}").unwrap() + 2;
+
+                current.push_str(&rest[..start]);
+                line += rest[start..end].matches('\n').count();
+                had_comment = true;
+                rest = &rest[end..];
+                continue;
+            }
+            (_, Some(newline)) => {
+                current.push_str(&rest[..newline]);
+                rest = &rest[newline + 1..];
+            }
+            (_, None) => {
+                if rest.is_empty() && current.is_empty() && !had_comment {
+                    break;
+                }
+                current.push_str(rest);
+                rest = "";
+            }
+        }
+
+        let text = current.strip_suffix('\r').unwrap_or(&current).to_string();
+        if !(had_comment && text.trim().is_empty()) {
+            lines.push((start_line, text));
+        }
+
+        current.clear();
+        had_comment = false;
+        line += 1;
+        start_line = line;
+
+        if rest.is_empty() && next_newline.is_none() {
+            break;
+        }
+    }
+
+    lines
+}
+
+/// Removes the trim markers so `{%- if x -%}` is parsed like `{% if x %}`
+fn strip_whitespace_markers(input: &str) -> String {
+    input.replace("{%-", "{%").replace("-%}", "%}")
+}
+
+/// Reads the trim markers of the first (opening) and last (closing) tag of a line
+pub fn get_whitespace_control(input: &str) -> WhitespaceControl {
+    let input = input.trim();
+
+    let (Some(first_open), Some(first_close), Some(last_open), Some(last_close)) = (
+        input.find("{%"),
+        input.find("%}"),
+        input.rfind("{%"),
+        input.rfind("%}"),
+    ) else {
+        return WhitespaceControl::default();
+    };
+
+    WhitespaceControl {
+        trim_before: input[first_open..].starts_with("{%-"),
+        trim_body_start: input[..first_close].ends_with('-'),
+        trim_body_end: input[last_open..].starts_with("{%-"),
+        trim_after: input[..last_close].ends_with('-'),
+    }
+}
+
+/// Returns the content between the delimiters of a statement made of a single tag
+fn get_tag_content(input: &str) -> Option<&str> {
+    let inner = input.trim().strip_prefix("{%")?.strip_suffix("%}")?;
+
+    if inner.contains("%}") || inner.contains("{%") {
+        return None;
+    }
+
+    Some(inner.trim())
+}
+
+/// Returns the argument of a single tag statement such as `{% include "header.html" %}`
+fn get_tag_argument(input: &str, keyword: &str) -> Option<String> {
+    let inner = get_tag_content(input)?
+        .strip_prefix(keyword)?
+        .strip_prefix(' ')?
+        .trim();
+
+    let argument = inner
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(inner);
+
+    Some(argument.to_string())
+}
+
+/// Returns the template name of an include tag (`{% include "header.html" %}`)
+fn get_include_name(input: &str) -> Option<String> {
+    get_tag_argument(input, "include")
+}
+
+/// Returns the parent template name of an extends tag (`{% extends "base.html" %}`)
+pub fn get_extends_name(input: &str) -> Option<String> {
+    get_tag_argument(&strip_whitespace_markers(input), "extends")
+}
+
+/// Returns the block name of a statement opening a block (`{% block content %}`)
+pub fn get_block_start(input: &str) -> Option<String> {
+    get_tag_argument(&strip_whitespace_markers(input), "block")
+}
+
+/// Checks if the statement closes a block (`{% endblock %}` or `{% endblock content %}`)
+pub fn is_block_end(input: &str) -> bool {
+    is_tag_end(input, "endblock")
+}
+
+/// Checks if the statement is a single closing tag such as `{% endfor %}`, a name may follow
+/// the keyword
+pub fn is_tag_end(input: &str, keyword: &str) -> bool {
+    let input = strip_whitespace_markers(input);
+
+    match get_tag_content(&input) {
+        Some(inner) => {
+            inner == keyword
+                || inner
+                    .strip_prefix(keyword)
+                    .is_some_and(|rest| rest.starts_with(' '))
+        }
+        None => false,
+    }
+}
+
+/// Returns the condition of a statement made only of the opening tag of an if or for tag
+/// (`{% for user in users %}`), whose body and closing tag come in the following lines
+pub fn get_tag_start(input: &str, keyword: &str) -> Option<String> {
+    let input = strip_whitespace_markers(input);
+    let condition = get_tag_content(&input)?.strip_prefix(keyword)?;
+
+    if !condition.starts_with(' ') || condition.trim().is_empty() {
+        return None;
+    }
+
+    Some(condition.trim().to_string())
+}
+
+/// Splits a block written in a single line (`{% block title %} Home {% endblock %}`)
+/// into its name and body
+pub fn get_inline_block(input: &str) -> Option<(String, String)> {
+    let input = input.trim();
+    let open_end = input.find("%}")? + 2;
+    let close_start = input.rfind("{%")?;
+
+    if close_start < open_end || !is_block_end(&input[close_start..]) {
+        return None;
     }
+
+    let name = get_block_start(&input[..open_end])?;
+
+    Some((name, input[open_end..close_start].trim().to_string()))
 }
 
 /// Checks if a symbol is present within another string.
@@ -99,27 +328,95 @@ pub fn get_index_for_symbol(input: &str, symbol: char) -> Option<usize> {
 }
 
 /// Parses a template string into its constituent parts for a token of type TemplateString
-fn get_expression_data(input: &str) -> ExpressionData {
-    let expression_iter = input.split_whitespace();
+pub fn get_expression_data(input: &str) -> ExpressionData {
     let mut template_var_map: Vec<String> = vec![];
-    for word in expression_iter {
-        if check_symbol_string(word, "{{") && check_symbol_string(word, "}}") {
-            template_var_map.push(word.to_string());
-        }
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        template_var_map.push(rest[start..start + end + 2].to_string());
+        rest = &rest[start + end + 2..];
     }
 
     ExpressionData {
         expression: input.into(),
         var_map: template_var_map,
-        gen_html: "".into(),
     }
 }
 
+/// Splits `input` on `separator` ignoring separators inside double quotes
+fn split_unquoted(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut in_quotes = false;
+    let mut start = 0;
+
+    for (i, c) in input.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == separator && !in_quotes {
+            parts.push(&input[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&input[start..]);
+
+    parts
+}
+
+/// Parses a literal filter argument: a quoted string or a number
+fn get_filter_argument(input: &str) -> Value {
+    let input = input.trim();
+
+    if let Some(text) = input.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return Value::from(text);
+    }
+
+    match input.parse::<f64>() {
+        Ok(n) => Value::Number(n),
+        Err(_) => Value::from(input),
+    }
+}
+
+/// Parses the content of a template variable (`user.name | default("x") | upper`)
+pub fn get_variable_expression(input: &str) -> VariableExpression {
+    let mut parts = split_unquoted(input, '|').into_iter();
+    let path = parts.next().unwrap_or_default().trim().to_string();
+
+    let filters = parts
+        .map(|filter| {
+            let filter = filter.trim();
+            match (filter.find('('), filter.ends_with(')')) {
+                (Some(i), true) => FilterCall {
+                    name: filter[..i].trim().to_string(),
+                    args: split_unquoted(&filter[i + 1..filter.len() - 1], ',')
+                        .into_iter()
+                        .filter(|arg| !arg.trim().is_empty())
+                        .map(get_filter_argument)
+                        .collect(),
+                },
+                _ => FilterCall {
+                    name: filter.to_string(),
+                    args: vec![],
+                },
+            }
+        })
+        .collect();
+
+    VariableExpression { path, filters }
+}
+
 #[allow(dead_code)]
 /// Gets the type of evaluation that should be validated in if or for tags
 fn get_operation_type(input: &str) -> OperationType {
     match input {
-        "=" => OperationType::Equal,
+        "=" | "==" => OperationType::Equal,
+        "!=" => OperationType::NotEqual,
+        ">" => OperationType::Greater,
+        ">=" => OperationType::GreaterEqual,
+        "<" => OperationType::Less,
+        "<=" => OperationType::LessEqual,
         "in" => OperationType::In,
         _ => OperationType::Nosoported("Unrecognized operator".to_string()),
     }
@@ -127,62 +424,83 @@ fn get_operation_type(input: &str) -> OperationType {
 
 #[allow(dead_code)]
 /// Structurate expression to be evaluated
-pub fn get_conditional_expression(input: &str) -> Result<ConditionData, String> {
-    // Valid operators to compare
-    let operators = [">", ">=", "=", "<=", "<", "in"];
-
+pub fn get_conditional_expression(input: &str) -> Result<ConditionData, ParseError> {
     let input = input.trim();
 
-    for operator in operators {
-        if input.contains(operator) {
-            let operants: Vec<&str> = input.split(operator).collect();
+    let expr = match parse_expression(input) {
+        Ok(expr) => expr,
+        // `for` loops are the only place where `in` is valid
+        Err(_) => {
+            return match input.split_once(" in ") {
+                Some((left, right)) if !left.trim().is_empty() && !right.trim().is_empty() => {
+                    Ok(ConditionData {
+                        left_operand: left.trim().to_string(),
+                        operation: OperationType::In,
+                        right_operand: right.trim().to_string(),
+                    })
+                }
+                _ => Err(ParseError::new(ParseErrorKind::InvalidCondition, input)),
+            };
+        }
+    };
 
-            if operants.len() != 2 {
-                break;
+    // Single comparisons between two operands keep their plain form
+    if let Expr::Binary(left, _, right) = &expr {
+        let is_operand = |e: &Expr| matches!(e, Expr::Variable(_) | Expr::Literal(_));
+
+        if is_operand(left) && is_operand(right) {
+            // Valid operators to compare, two-character operators first so `>=` is not read as `>`
+            for operator in ["==", "!=", ">=", "<=", "=", ">", "<"] {
+                if let Some((left, right)) = input.split_once(operator) {
+                    return Ok(ConditionData {
+                        left_operand: left.trim().to_string(),
+                        operation: get_operation_type(operator),
+                        right_operand: right.trim().to_string(),
+                    });
+                }
             }
-
-            return Ok(ConditionData {
-                left_operand: operants[0].trim().to_string(),
-                operation: get_operation_type(operator),
-                right_operand: operants[1].trim().to_string(),
-            });
         }
     }
 
-    Err("Invalid format".to_string())
+    Ok(ConditionData {
+        left_operand: input.to_string(),
+        operation: OperationType::Expression(expr),
+        right_operand: String::new(),
+    })
 }
 
 /// Structurate for and if tag expressions
-pub fn get_conditional_data(input: &str) -> Result<Conditional, String> {
-    // Checks input format
-    if !input.ends_with("{% endif %}") & !input.ends_with("{% endfor %}") {
-        return Err("Invalid input format".to_string());
-    }
-
-    let start_condition = match input.find("{% if ") {
-        Some(i) => i + 6,
-        None => {
-            input
-                .find("{% for ")
-                .expect("If not a if expression is a for expression")
-                + 7
-        }
-    };
-    let end_condition = input.find(" %}").unwrap();
-    let end_expr = match input.find("{% endif %}") {
-        Some(i) => i,
-        None => input
-            .find("{% endfor %}")
-            .expect("If not a if expression is a for expression"),
+pub fn get_conditional_data(input: &str) -> Result<Conditional, ParseError> {
+    let whitespace = get_whitespace_control(input);
+    let normalized = strip_whitespace_markers(input);
+    let input = normalized.trim();
+
+    // Checks input format, the closing tag must match the opening one so nested tags are kept
+    let (start_condition, end_tag) = if input.starts_with("{% if ") {
+        (6, "{% endif %}")
+    } else if input.starts_with("{% for ") {
+        (7, "{% endfor %}")
+    } else {
+        return Err(ParseError::new(ParseErrorKind::InvalidTag, input));
     };
 
+    if !input.ends_with(end_tag) {
+        return Err(ParseError::new(ParseErrorKind::InvalidTag, input));
+    }
+
+    let end_condition = input
+        .find(" %}")
+        .ok_or_else(|| ParseError::new(ParseErrorKind::InvalidTag, input))?;
+    let end_expr = input.len() - end_tag.len();
+
     if start_condition >= end_condition {
-        return Err("Invalid input format".to_string());
+        return Err(ParseError::new(ParseErrorKind::InvalidTag, input));
     }
 
     Ok(Conditional {
         condition: get_conditional_expression(&input[start_condition..end_condition])?,
-        expression: Box::new(get_content_type(&input[end_condition + 3..end_expr].trim())),
+        expression: Box::new(get_content_type(input[end_condition + 3..end_expr].trim())?),
+        whitespace,
     })
 }
 
@@ -193,7 +511,10 @@ mod tests {
     #[test]
     fn check_literal_test() {
         let s = "<h1>Hello world</h1>";
-        assert_eq!(ContentType::Literal(s.to_string()), get_content_type(s));
+        assert_eq!(
+            ContentType::Literal(s.to_string()),
+            get_content_type(s).unwrap()
+        );
     }
 
     #[test]
@@ -201,12 +522,11 @@ mod tests {
         let content = ExpressionData {
             expression: "Hi {{name}} ,welcome".to_string(),
             var_map: vec!["{{name}}".to_string()],
-            gen_html: "".into(),
         };
 
         assert_eq!(
             ContentType::TemplateVariable(content),
-            get_content_type("Hi {{name}} ,welcome")
+            get_content_type("Hi {{name}} ,welcome").unwrap()
         );
     }
 
@@ -222,10 +542,11 @@ mod tests {
                 expression: Box::new(ContentType::TemplateVariable(ExpressionData {
                     expression: "<p> Welcome {{name}} !! </p>".to_string(),
                     var_map: vec!["{{name}}".to_string()],
-                    gen_html: "".into(),
                 })),
+                whitespace: WhitespaceControl::default(),
             }))),
             get_content_type("{% for name in names %} <p> Welcome {{name}} !! </p> {% endfor %}")
+                .unwrap()
         )
     }
 
@@ -241,21 +562,122 @@ mod tests {
                 expression: Box::new(ContentType::TemplateVariable(ExpressionData {
                     expression: "<p> Welcome {{name}} </p>".to_string(),
                     var_map: vec!["{{name}}".to_string()],
-                    gen_html: "".into(),
                 })),
+                whitespace: WhitespaceControl::default(),
             }))),
-            get_content_type("{% if name = Bob %} <p> Welcome {{name}} </p> {% endif %}")
+            get_content_type("{% if name = Bob %} <p> Welcome {{name}} </p> {% endif %}").unwrap()
+        )
+    }
+
+    #[test]
+    fn check_include_tag_test() {
+        assert_eq!(
+            ContentType::Tag(TagType::IncludeTag("partials/footer.html".to_string())),
+            get_content_type("{% include \"partials/footer.html\" %}").unwrap()
         )
     }
 
+    #[test]
+    fn check_whitespace_control_test() {
+        assert_eq!(
+            get_whitespace_control("{%- for x in xs %} {{x}} {%- endfor -%}"),
+            WhitespaceControl {
+                trim_before: true,
+                trim_body_start: false,
+                trim_body_end: true,
+                trim_after: true,
+            }
+        );
+        assert_eq!(
+            get_conditional_data("{% if a = 1 -%} <p> hola </p> {% endif %}")
+                .unwrap()
+                .whitespace,
+            WhitespaceControl {
+                trim_body_start: true,
+                ..WhitespaceControl::default()
+            }
+        );
+        assert_eq!(
+            get_content_type("{%- include \"nav.html\" -%}").unwrap(),
+            ContentType::Tag(TagType::IncludeTag("nav.html".to_string()))
+        );
+    }
+
+    #[test]
+    fn check_strip_comments_test() {
+        assert_eq!(
+            strip_comments("<p>{# greeting #}Hi {{name}}</p>"),
+            "<p>Hi {{name}}</p>"
+        );
+        assert_eq!(
+            strip_comments("<ul>\n  {# list of\n users #}\n</ul>"),
+            "<ul>\n</ul>"
+        );
+        assert_eq!(strip_comments("{# unclosed"), "{# unclosed");
+        assert_eq!(
+            strip_comment_lines("a\n{# one\ntwo #}\nb {# c #}\n"),
+            vec![(1, "a".to_string()), (4, "b ".to_string())]
+        );
+        assert_eq!(
+            get_content_type("{# {% if name = Bob %} #}").unwrap(),
+            ContentType::Literal("".to_string())
+        );
+    }
+
+    #[test]
+    fn check_unrecognized_statement_test() {
+        assert_eq!(
+            get_content_type("  {% else %}"),
+            Err(ParseError::new(
+                ParseErrorKind::UnrecognizedStatement,
+                "{% else %}"
+            ))
+        );
+    }
+
+    #[test]
+    fn check_block_tags_test() {
+        assert_eq!(
+            get_extends_name("{% extends \"base.html\" %}"),
+            Some("base.html".to_string())
+        );
+        assert_eq!(
+            get_block_start("{%- block content -%}"),
+            Some("content".to_string())
+        );
+        assert!(is_block_end("{% endblock content %}"));
+        assert!(!is_block_end("{% endblocks %}"));
+        assert_eq!(
+            get_inline_block("{% block title %} Home {% endblock %}"),
+            Some(("title".to_string(), "Home".to_string()))
+        );
+        assert_eq!(get_inline_block("{% block title %}"), None);
+    }
+
+    #[test]
+    fn check_multiline_tags_test() {
+        assert_eq!(
+            get_tag_start("{%- for user in users -%}", "for"),
+            Some("user in users".to_string())
+        );
+        assert_eq!(
+            get_tag_start("{% if a == \"b\" %}", "if"),
+            Some("a == \"b\"".to_string())
+        );
+        assert_eq!(get_tag_start("{% if a %} yes {% endif %}", "if"), None);
+        assert_eq!(get_tag_start("{% iffy %}", "if"), None);
+        assert!(is_tag_end("{%- endfor %}", "endfor"));
+        assert!(!is_tag_end("{% endfor %}", "endif"));
+    }
+
     #[test]
     fn check_symbol_string_test() {
-        assert_eq!(true, check_symbol_string("{{Hello}}", "{{"))
+        assert!(check_symbol_string("{{Hello}}", "{{"))
     }
 
     #[test]
     fn check_symbol_pair_test() {
-        assert_eq!(true, check_matching_pair("{{Hello}}", "{{", "}}"))
+        assert!(check_matching_pair("{{Hello}}", "{{", "}}"))
     }
 
     #[test]
@@ -263,12 +685,43 @@ mod tests {
         let expression_data = ExpressionData {
             expression: "Hi {{name}} ,welcome".to_string(),
             var_map: vec!["{{name}}".to_string()],
-            gen_html: "".into(),
         };
 
         assert_eq!(expression_data, get_expression_data("Hi {{name}} ,welcome"));
     }
 
+    #[test]
+    fn check_get_expression_data_dotted_test() {
+        assert_eq!(
+            get_expression_data("<li> {{ user.name }} from {{user.city}} </li>").var_map,
+            vec!["{{ user.name }}".to_string(), "{{user.city}}".to_string()]
+        );
+    }
+
+    #[test]
+    fn check_get_variable_expression_test() {
+        assert_eq!(
+            get_variable_expression(" name | default(\"a|b\") | date(\"%Y\", 2) | upper "),
+            VariableExpression {
+                path: "name".to_string(),
+                filters: vec![
+                    FilterCall {
+                        name: "default".to_string(),
+                        args: vec![Value::from("a|b")],
+                    },
+                    FilterCall {
+                        name: "date".to_string(),
+                        args: vec![Value::from("%Y"), Value::Number(2.0)],
+                    },
+                    FilterCall {
+                        name: "upper".to_string(),
+                        args: vec![],
+                    },
+                ],
+            }
+        );
+    }
+
     #[test]
     fn check_get_index_for_symbol_test() {
         assert_eq!(
@@ -302,6 +755,38 @@ mod tests {
         )
     }
 
+    #[test]
+    fn check_get_conditional_expression_operators() {
+        let operations = [
+            ("a == 1", OperationType::Equal),
+            ("a != 1", OperationType::NotEqual),
+            ("a > 1", OperationType::Greater),
+            ("a >= 1", OperationType::GreaterEqual),
+            ("a < 1", OperationType::Less),
+            ("a <= 1", OperationType::LessEqual),
+        ];
+
+        for (input, operation) in operations {
+            assert_eq!(
+                get_conditional_expression(input).unwrap(),
+                ConditionData {
+                    left_operand: "a".to_string(),
+                    operation,
+                    right_operand: "1".to_string()
+                }
+            )
+        }
+
+        assert_eq!(
+            get_conditional_expression("string in strings").unwrap(),
+            ConditionData {
+                left_operand: "string".to_string(),
+                operation: OperationType::In,
+                right_operand: "strings".to_string()
+            }
+        )
+    }
+
     #[test]
     fn check_get_conditional_data() {
         assert_eq!(
@@ -313,6 +798,7 @@ mod tests {
                     right_operand: "2000".to_string(),
                 },
                 expression: Box::new(ContentType::Literal("<p> hola </p>".to_string())),
+                whitespace: WhitespaceControl::default(),
             }
         )
     }
@@ -0,0 +1,451 @@
+use crate::environment::Environment;
+use crate::error::{ParseError, ParseErrorKind, RenderError};
+use crate::generator::{generate_html_condition, generate_html_content, trim_body};
+use crate::parser::{
+    ConditionData, ContentType, OperationType, WhitespaceControl, get_block_start,
+    get_conditional_expression, get_content_type, get_extends_name, get_inline_block,
+    get_tag_start, get_whitespace_control, is_block_end, is_tag_end, strip_comment_lines,
+};
+use crate::value::Context;
+use std::{collections::HashMap, rc::Rc};
+
+/// A template parsed once that can be rendered against any number of contexts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+    /// Name of the parent template given by `{% extends %}`
+    extends: Option<String>,
+}
+
+/// Each element of a parsed template: a single line, a named block of lines or a for or if tag
+/// whose body spans several lines
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Line(Line),
+    Block(Block),
+    For(Tag),
+    If(Tag),
+}
+
+/// A parsed line of the template together with its trim markers
+#[derive(Debug, Clone, PartialEq)]
+struct Line {
+    content: ContentType,
+    whitespace: WhitespaceControl,
+}
+
+/// A `{% block name %} ... {% endblock %}` section that child templates can override
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    name: String,
+    nodes: Vec<Node>,
+}
+
+/// A for or if tag whose opening and closing tags stand on their own lines, with the nodes
+/// between them as its body
+#[derive(Debug, Clone, PartialEq)]
+struct Tag {
+    condition: ConditionData,
+    nodes: Vec<Node>,
+    /// Trim markers of the opening (before, body start) and closing (body end, after) lines
+    whitespace: WhitespaceControl,
+}
+
+/// Blocks available while rendering, the most derived definition of each name
+type Blocks<'a> = HashMap<&'a str, &'a [Node]>;
+
+impl Template {
+    /// Parses the source of a template, comments are dropped at this point
+    pub fn parse(source: &str) -> Result<Template, ParseError> {
+        let mut nodes = vec![];
+        let mut extends = None;
+        // Blocks and tags still open, with the line where they started
+        let mut open_nodes: Vec<(Node, usize, String)> = vec![];
+
+        for (number, line) in strip_comment_lines(source) {
+            let node = if let Some(name) = get_extends_name(&line) {
+                extends = Some(name);
+                continue;
+            } else if let Some((name, body)) = get_inline_block(&line) {
+                Node::Block(Block {
+                    name,
+                    nodes: vec![Template::parse_line(&body, number, &line)?],
+                })
+            } else if let Some(name) = get_block_start(&line) {
+                let block = Block {
+                    name,
+                    nodes: vec![],
+                };
+                open_nodes.push((Node::Block(block), number, line));
+                continue;
+            } else if let Some(node) = Template::parse_tag_start(&line, number)? {
+                open_nodes.push((node, number, line));
+                continue;
+            } else if is_block_end(&line)
+                || is_tag_end(&line, "endfor")
+                || is_tag_end(&line, "endif")
+            {
+                let closing = get_whitespace_control(&line);
+
+                match open_nodes.pop() {
+                    Some((node @ Node::Block(_), _, _)) if is_block_end(&line) => node,
+                    Some((Node::For(tag), _, _)) if is_tag_end(&line, "endfor") => {
+                        Node::For(tag.closed_by(closing))
+                    }
+                    Some((Node::If(tag), _, _)) if is_tag_end(&line, "endif") => {
+                        Node::If(tag.closed_by(closing))
+                    }
+                    _ => {
+                        return Err(
+                            ParseError::new(ParseErrorKind::InvalidTag, &line).at(number, &line)
+                        );
+                    }
+                }
+            } else {
+                Template::parse_line(&line, number, &line)?
+            };
+
+            match open_nodes
+                .last_mut()
+                .and_then(|(open, _, _)| open.body_mut())
+            {
+                Some(body) => body.push(node),
+                None => nodes.push(node),
+            }
+        }
+
+        if let Some((_, number, line)) = open_nodes.pop() {
+            return Err(ParseError::new(ParseErrorKind::InvalidTag, &line).at(number, &line));
+        }
+
+        Ok(Template { nodes, extends })
+    }
+
+    /// Parses a line made only of the opening tag of a for or if tag, `None` for any other line
+    fn parse_tag_start(line: &str, number: usize) -> Result<Option<Node>, ParseError> {
+        let (condition, is_for) = match (get_tag_start(line, "for"), get_tag_start(line, "if")) {
+            (Some(condition), _) => (condition, true),
+            (None, Some(condition)) => (condition, false),
+            (None, None) => return Ok(None),
+        };
+
+        let condition = get_conditional_expression(&condition).map_err(|e| e.at(number, line))?;
+
+        // Loops only iterate over lists, `x in xs` is not a valid condition for if tags
+        if is_for != (condition.operation == OperationType::In) {
+            return Err(ParseError::new(ParseErrorKind::InvalidTag, line).at(number, line));
+        }
+
+        let opening = get_whitespace_control(line);
+        let tag = Tag {
+            condition,
+            nodes: vec![],
+            whitespace: WhitespaceControl {
+                trim_before: opening.trim_before,
+                trim_body_start: opening.trim_body_start,
+                ..WhitespaceControl::default()
+            },
+        };
+
+        Ok(Some(if is_for {
+            Node::For(tag)
+        } else {
+            Node::If(tag)
+        }))
+    }
+
+    /// Parses a statement, errors point to `source_line`, line number `number` of the template
+    fn parse_line(input: &str, number: usize, source_line: &str) -> Result<Node, ParseError> {
+        Ok(Node::Line(Line {
+            content: get_content_type(input).map_err(|e| e.at(number, source_line))?,
+            whitespace: get_whitespace_control(input),
+        }))
+    }
+
+    /// Renders the template with the default environment
+    pub fn render(&self, context: &Context) -> Result<String, RenderError> {
+        self.render_with(context, &Environment::default())
+    }
+
+    /// Renders the template using the filters, loader and options of `env`
+    pub fn render_with(&self, context: &Context, env: &Environment) -> Result<String, RenderError> {
+        // First phase: load the chain of parent templates up to the root one
+        let mut parents: Vec<Rc<Template>> = vec![];
+        let mut parent_names: Vec<String> = vec![];
+        let mut extends = self.extends.clone();
+
+        while let Some(name) = extends {
+            if parent_names.contains(&name) {
+                return Err(RenderError::ExtendsError(format!(
+                    "Inheritance cycle detected: {}",
+                    name
+                )));
+            }
+            if parent_names.len() >= env.max_include_depth {
+                return Err(RenderError::ExtendsError(format!(
+                    "Inheritance depth limit of {} reached",
+                    env.max_include_depth
+                )));
+            }
+
+            let parent = env.get_template(&name).map_err(RenderError::ExtendsError)?;
+
+            extends = parent.extends.clone();
+            parent_names.push(name);
+            parents.push(parent);
+        }
+
+        // Second phase: merge the blocks, children override the blocks of their parents
+        let mut blocks = Blocks::new();
+        for template in std::iter::once(self).chain(parents.iter().map(|parent| &**parent)) {
+            collect_blocks(&template.nodes, &mut blocks);
+        }
+
+        let root = parents.last().map_or(self, |parent| &**parent);
+        render_nodes(&root.nodes, context, env, &blocks)
+    }
+}
+
+impl Node {
+    /// Nodes nested inside a block or a tag, `None` for single lines
+    fn body_mut(&mut self) -> Option<&mut Vec<Node>> {
+        match self {
+            Node::Block(Block { nodes, .. })
+            | Node::For(Tag { nodes, .. })
+            | Node::If(Tag { nodes, .. }) => Some(nodes),
+            Node::Line(_) => None,
+        }
+    }
+}
+
+impl Tag {
+    /// Adds the trim markers of the closing line, `{%-` trims the end of the body
+    fn closed_by(mut self, closing: WhitespaceControl) -> Tag {
+        self.whitespace.trim_body_end = closing.trim_before;
+        self.whitespace.trim_after = closing.trim_after;
+        self
+    }
+}
+
+/// Registers every block found in `nodes` unless a more derived template already defined it
+fn collect_blocks<'a>(nodes: &'a [Node], blocks: &mut Blocks<'a>) {
+    for node in nodes {
+        match node {
+            Node::Block(block) => {
+                blocks.entry(&block.name).or_insert(&block.nodes);
+                collect_blocks(&block.nodes, blocks);
+            }
+            Node::For(tag) | Node::If(tag) => collect_blocks(&tag.nodes, blocks),
+            Node::Line(_) => {}
+        }
+    }
+}
+
+/// Renders a list of nodes joining them with newlines
+fn render_nodes(
+    nodes: &[Node],
+    context: &Context,
+    env: &Environment,
+    blocks: &Blocks,
+) -> Result<String, RenderError> {
+    let mut html = String::new();
+    let mut trim_next = false;
+
+    for node in nodes {
+        let (node_html, whitespace) = match node {
+            Node::Line(line) => (
+                generate_html_content(&line.content, context, env)?,
+                line.whitespace,
+            ),
+            Node::Block(block) => {
+                let nodes = blocks
+                    .get(block.name.as_str())
+                    .copied()
+                    .unwrap_or(&block.nodes);
+                let block_html = render_nodes(nodes, context, env, blocks)?;
+
+                // Empty blocks leave no blank line behind
+                if block_html.is_empty() {
+                    continue;
+                }
+                (block_html, WhitespaceControl::default())
+            }
+            Node::For(tag) | Node::If(tag) => {
+                let tag_html =
+                    generate_html_condition(&tag.condition, context, env, "\n", |scope| {
+                        let body = render_nodes(&tag.nodes, scope, env, blocks)?;
+                        Ok(trim_body(&body, tag.whitespace).to_string())
+                    })?;
+
+                // Iterations are joined with newlines but the last one does not add a blank line
+                let tag_html = tag_html.strip_suffix('\n').unwrap_or(&tag_html);
+                if tag_html.is_empty() {
+                    continue;
+                }
+                (tag_html.to_string(), tag.whitespace)
+            }
+        };
+
+        // Outer trim markers swallow the newline that joins this line with its neighbours
+        if whitespace.trim_before {
+            html.truncate(html.trim_end().len());
+        } else if !html.is_empty() && !trim_next {
+            html.push('\n');
+        }
+
+        if trim_next {
+            html.push_str(node_html.trim_start());
+        } else {
+            html.push_str(&node_html);
+        }
+
+        trim_next = whitespace.trim_after;
+    }
+
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::MemoryLoader;
+    use crate::value::Value;
+
+    #[test]
+    fn check_parse_once_render_many() {
+        let template = Template::parse("<p>{{ name }}</p>").unwrap();
+
+        for name in ["Bob", "Lisa"] {
+            let mut context = Context::new();
+            context.insert("name".to_string(), Value::from(name));
+
+            assert_eq!(
+                template.render(&context).unwrap(),
+                format!("<p>{}</p>", name)
+            );
+        }
+    }
+
+    #[test]
+    fn check_parse_error() {
+        let source = "{# page\n title #}\n<h1>Title</h1>\n  {% if name == %} <p>hi</p> {% endif %}";
+        let error = Template::parse(source).unwrap_err();
+
+        assert_eq!(error.kind, ParseErrorKind::InvalidCondition);
+        assert_eq!(error.text, "name ==");
+        assert_eq!((error.line, error.column), (4, 9));
+        assert_eq!(error.snippet, "  {% if name == %} <p>hi</p> {% endif %}");
+    }
+
+    #[test]
+    fn check_parse_error_unclosed_tag() {
+        let error = Template::parse("{% for user in users %} <li>{{ user }}</li>").unwrap_err();
+
+        assert_eq!(error.kind, ParseErrorKind::InvalidTag);
+        assert_eq!((error.line, error.column), (1, 1));
+    }
+
+    #[test]
+    fn check_multiline_tags() {
+        let template = Template::parse(
+            "<ul>\n\
+             {% for user in users %}\n\
+             <li>{{ user }}</li>\n\
+             {% if loop.last %}\n\
+             {% for i in numbers %}<hr>{% endfor %}\n\
+             {% endif %}\n\
+             {% endfor %}\n\
+             </ul>\n\
+             {% if total > 5 -%}\n\
+             <p>many</p>\n\
+             {% endif %}",
+        )
+        .unwrap();
+
+        let mut context = Context::new();
+        context.insert("users".to_string(), Value::from(vec!["Bob", "Lisa"]));
+        context.insert("numbers".to_string(), Value::from(vec!["1", "2"]));
+        context.insert("total".to_string(), Value::Number(2.0));
+
+        assert_eq!(
+            template.render(&context).unwrap(),
+            "<ul>\n<li>Bob</li>\n<li>Lisa</li>\n<hr>\n<hr>\n</ul>"
+        );
+
+        let trimmed =
+            Template::parse("<p>\n{%- for user in users -%}\n{{ user }},\n{%- endfor -%}\n</p>")
+                .unwrap();
+        assert_eq!(trimmed.render(&context).unwrap(), "<p>Bob,\nLisa,</p>");
+    }
+
+    #[test]
+    fn check_multiline_tag_errors() {
+        let error = Template::parse("{% for user in users %}\n<li></li>\n{% endif %}").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidTag);
+        assert_eq!(error.line, 3);
+
+        let error =
+            Template::parse("<ul>\n{% if a %}\n{% for x in xs %}\n{% endfor %}").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidTag);
+        assert_eq!(error.line, 2);
+
+        let error = Template::parse("{% for user %}\n{% endfor %}").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidTag);
+        assert_eq!(error.line, 1);
+    }
+
+    #[test]
+    fn check_template_inheritance() {
+        let mut loader = MemoryLoader::new();
+        loader.insert(
+            "base.html",
+            "{% block title %}<title>Site</title>{% endblock %}\n\
+             {% block content %}\n\
+             <p>Default</p>\n\
+             {% block footer %}<footer>base</footer>{% endblock %}\n\
+             {% endblock %}",
+        );
+        loader.insert(
+            "page.html",
+            "{% extends \"base.html\" %}\n\
+             {% block content %}\n\
+             <p>{{ name }}</p>\n\
+             {% block footer %}<footer>page</footer>{% endblock %}\n\
+             {% endblock content %}",
+        );
+
+        let mut context = Context::new();
+        context.insert("name".to_string(), Value::from("Bob"));
+
+        let child = Template::parse(
+            "{% extends \"page.html\" %}\n{% block title %}<title>{{ name }}</title>{% endblock %}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            child
+                .render_with(&context, &Environment::new(loader))
+                .unwrap(),
+            "<title>Bob</title>\n<p>Bob</p>\n<footer>page</footer>"
+        );
+    }
+
+    #[test]
+    fn check_inheritance_errors() {
+        let mut loader = MemoryLoader::new();
+        loader.insert("a.html", "{% extends \"b.html\" %}");
+        loader.insert("b.html", "{% extends \"a.html\" %}");
+
+        let child = Template::parse("{% extends \"a.html\" %}").unwrap();
+        assert_eq!(
+            child.render_with(&Context::new(), &Environment::new(loader)),
+            Err(RenderError::ExtendsError(
+                "Inheritance cycle detected: a.html".to_string()
+            ))
+        );
+
+        let error = Template::parse("<div>\n{% block content %}\n<p></p>").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidTag);
+        assert_eq!(error.line, 2);
+    }
+}
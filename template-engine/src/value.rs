@@ -0,0 +1,287 @@
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// Variables available to a template while it is being rendered
+pub type Context = HashMap<String, Value>;
+
+/// Any value that can be stored in the rendering context
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    List(Vec<Value>),
+    Map(HashMap<String, Value>),
+}
+
+impl Value {
+    /// Returns the field stored under `key` when the value is a map
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Numeric view of the value, strings are parsed when they hold a number
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Truth value used by conditions: false, zero and empty values are false
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::String(s) => !s.is_empty(),
+            Value::Number(n) => *n != 0.0,
+            Value::Bool(b) => *b,
+            Value::List(items) => !items.is_empty(),
+            Value::Map(map) => !map.is_empty(),
+        }
+    }
+
+    /// Compares two values: numerically when both sides are numbers, otherwise as text
+    pub fn compare(&self, other: &Value) -> Ordering {
+        match (self.as_number(), other.as_number()) {
+            (Some(left), Some(right)) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+            _ => self.to_string().cmp(&other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{}", s),
+            Value::Number(n) if n.fract() == 0.0 && n.is_finite() => write!(f, "{}", *n as i64),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "{}", items.join(" "))
+            }
+            Value::Map(_) => write!(f, "[object]"),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(value: Vec<T>) -> Self {
+        Value::List(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::from(""),
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => Value::Number(n.as_f64().unwrap_or_default()),
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(items) => {
+                Value::List(items.into_iter().map(Into::into).collect())
+            }
+            serde_json::Value::Object(map) => {
+                Value::Map(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+        }
+    }
+}
+
+impl From<Yaml> for Value {
+    fn from(value: Yaml) -> Self {
+        match value {
+            Yaml::Real(_) => Value::Number(value.as_f64().unwrap_or_default()),
+            Yaml::Integer(i) => Value::Number(i as f64),
+            Yaml::String(s) => Value::String(s),
+            Yaml::Boolean(b) => Value::Bool(b),
+            Yaml::Array(items) => Value::List(items.into_iter().map(Into::into).collect()),
+            Yaml::Hash(map) => Value::Map(
+                map.into_iter()
+                    .map(|(k, v)| (yaml_key(k), v.into()))
+                    .collect(),
+            ),
+            Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => Value::from(""),
+        }
+    }
+}
+
+/// YAML keys can be any scalar, they are looked up by their text
+fn yaml_key(key: Yaml) -> String {
+    match key {
+        Yaml::String(s) | Yaml::Real(s) => s,
+        key => Value::from(key).to_string(),
+    }
+}
+
+/// Parses a JSON document whose top level is an object into a context
+pub fn context_from_json(input: &str) -> Result<Context, String> {
+    match serde_json::from_str(input).map_err(|e| e.to_string())? {
+        serde_json::Value::Object(map) => Ok(map.into_iter().map(|(k, v)| (k, v.into())).collect()),
+        _ => Err("The context must be a JSON object".to_string()),
+    }
+}
+
+/// Parses the first document of a YAML stream, a mapping, into a context
+pub fn context_from_yaml(input: &str) -> Result<Context, String> {
+    let documents = YamlLoader::load_from_str(input).map_err(|e| e.to_string())?;
+
+    match documents.into_iter().next() {
+        None => Ok(Context::new()),
+        Some(Yaml::Hash(map)) => Ok(map
+            .into_iter()
+            .map(|(k, v)| (yaml_key(k), v.into()))
+            .collect()),
+        Some(_) => Err("The context must be a YAML mapping".to_string()),
+    }
+}
+
+/// Resolves a dotted path such as `user.name` against the context
+pub fn lookup<'a>(context: &'a Context, path: &str) -> Option<&'a Value> {
+    let mut parts = path.trim().split('.');
+    let mut value = context.get(parts.next()?)?;
+
+    for part in parts {
+        value = value.get(part)?;
+    }
+
+    Some(value)
+}
+
+/// Stores `value` under a dotted path, creating the intermediate maps when needed
+pub fn insert_path(context: &mut Context, path: &str, value: Value) {
+    let mut parts = path.trim().split('.').collect::<Vec<&str>>();
+    let last = parts.pop().unwrap_or_default();
+    let mut map = context;
+
+    for part in parts {
+        let entry = map
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Map(HashMap::new()));
+        if !matches!(entry, Value::Map(_)) {
+            *entry = Value::Map(HashMap::new());
+        }
+        map = match entry {
+            Value::Map(inner) => inner,
+            _ => unreachable!(),
+        };
+    }
+
+    map.insert(last.to_string(), value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_lookup_dotted_path() {
+        let mut user = HashMap::new();
+        user.insert("name".to_string(), Value::from("Bob"));
+
+        let mut context = Context::new();
+        context.insert("user".to_string(), Value::Map(user));
+
+        assert_eq!(lookup(&context, "user.name"), Some(&Value::from("Bob")));
+        assert_eq!(lookup(&context, "user.age"), None);
+    }
+
+    #[test]
+    fn check_insert_dotted_path() {
+        let mut context = Context::new();
+        context.insert("user".to_string(), Value::from("Bob"));
+
+        insert_path(&mut context, "user.name", Value::from("Alice"));
+        insert_path(&mut context, "city", Value::from("Paris"));
+
+        assert_eq!(lookup(&context, "user.name"), Some(&Value::from("Alice")));
+        assert_eq!(lookup(&context, "city"), Some(&Value::from("Paris")));
+    }
+
+    #[test]
+    fn check_context_from_json() {
+        let context = context_from_json(
+            r#"{"name": "Bob", "age": 30, "tags": ["a", "b"], "user": {"admin": true}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(lookup(&context, "name"), Some(&Value::from("Bob")));
+        assert_eq!(lookup(&context, "age"), Some(&Value::Number(30.0)));
+        assert_eq!(lookup(&context, "tags"), Some(&vec!["a", "b"].into()));
+        assert_eq!(lookup(&context, "user.admin"), Some(&Value::Bool(true)));
+        assert!(context_from_json("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn check_context_from_yaml() {
+        let context = context_from_yaml(
+            "name: Bob\nage: 30\nratio: 0.5\ntags: [a, b]\nuser:\n  admin: true\n  1: one\n",
+        )
+        .unwrap();
+
+        assert_eq!(lookup(&context, "name"), Some(&Value::from("Bob")));
+        assert_eq!(lookup(&context, "age"), Some(&Value::Number(30.0)));
+        assert_eq!(lookup(&context, "ratio"), Some(&Value::Number(0.5)));
+        assert_eq!(lookup(&context, "tags"), Some(&vec!["a", "b"].into()));
+        assert_eq!(lookup(&context, "user.admin"), Some(&Value::Bool(true)));
+        assert_eq!(lookup(&context, "user.1"), Some(&Value::from("one")));
+
+        assert_eq!(context_from_yaml(""), Ok(Context::new()));
+        assert!(context_from_yaml("- 1\n- 2\n").is_err());
+        assert!(context_from_yaml("name: [Bob").is_err());
+    }
+
+    #[test]
+    fn check_compare_coercion() {
+        assert_eq!(
+            Value::from("10").compare(&Value::Number(9.0)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Value::from("10").compare(&Value::from("9")),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Value::from("abc").compare(&Value::from("abd")),
+            Ordering::Less
+        );
+        assert_eq!(
+            Value::Bool(true).compare(&Value::from("true")),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn check_display_number() {
+        assert_eq!(Value::Number(3.0).to_string(), "3");
+        assert_eq!(Value::Number(2.5).to_string(), "2.5");
+    }
+}
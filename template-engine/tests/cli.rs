@@ -0,0 +1,52 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use pretty_assertions::assert_eq;
+use std::fs;
+
+const PRG: &str = "template-engine";
+const LIST: &str = "tests/inputs/list.html";
+const USERS: &str = "tests/inputs/users.json";
+
+// --------------------------------------------------
+#[test]
+fn stdin_renders_line_by_line() {
+    let output = Command::cargo_bin(PRG)
+        .unwrap()
+        .write_stdin("<h1>{{ name }}</h1>\n{% for user in users %} <li>{{ user.name }}</li> {% endfor %}\n<p>{{ city }}</p>\n")
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
+    assert_eq!(
+        stdout,
+        "<h1>Bob</h1>\n<li>Alice</li>\n<li>Carol</li>\n\n<p>Boston</p>\n"
+    );
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_keeps_rendering_after_a_bad_line() {
+    Command::cargo_bin(PRG)
+        .unwrap()
+        .write_stdin("{% for user in users %}\n<p>{{ name }}</p>\n")
+        .assert()
+        .failure()
+        .stdout("<p>Bob</p>\n")
+        .stderr(predicate::str::contains("1 line(s) failed to render"));
+}
+
+// --------------------------------------------------
+#[test]
+fn render_spans_several_lines() {
+    let expected = fs::read_to_string("tests/expected/list.html.out").unwrap();
+    let output = Command::cargo_bin(PRG)
+        .unwrap()
+        .args(["--context", USERS, "render", LIST])
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
+    assert_eq!(stdout, expected);
+}
@@ -0,0 +1,523 @@
+//! Rust version of `uniq`, the `run` function is called by the `uniqr` binary and by `ruty`
+
+use clap::{Parser, ValueEnum};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+#[command(author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `runiq`
+pub struct Args {
+    /// Input file
+    #[arg(default_value_t = String::from("-"))]
+    in_file: String,
+
+    /// Output file
+    //#[arg(short('o'), long("output"))]
+    #[arg()]
+    out_file: Option<String>,
+
+    /// Show counts
+    #[arg(short('c'), long)]
+    count: bool,
+
+    /// Only print duplicated lines, one for each group
+    #[arg(short('d'), long)]
+    repeated: bool,
+
+    /// Print all the duplicated lines, the groups delimited by METHOD: none, prepend or separate
+    #[arg(
+        short('D'),
+        long,
+        value_name = "METHOD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "none",
+        conflicts_with = "count"
+    )]
+    all_repeated: Option<helpers::Delimit>,
+
+    /// Print all the lines, the groups separated by an empty line by METHOD: separate, prepend,
+    /// append or both
+    #[arg(
+        long,
+        value_name = "METHOD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "separate",
+        conflicts_with_all = ["count", "repeated", "all_repeated", "unique"]
+    )]
+    group: Option<helpers::Grouping>,
+
+    /// Only print unique lines
+    #[arg(short('u'), long)]
+    unique: bool,
+
+    /// Ignore differences in case when comparing lines
+    #[arg(short('i'), long)]
+    ignore_case: bool,
+
+    /// Avoid comparing the first N fields, separated by blanks
+    #[arg(short('f'), long, value_name = "N", default_value_t = 0)]
+    skip_fields: usize,
+
+    /// Avoid comparing the first N characters, after the skipped fields
+    #[arg(short('s'), long, value_name = "N", default_value_t = 0)]
+    skip_chars: usize,
+
+    /// Compare no more than N characters
+    #[arg(short('w'), long, value_name = "N")]
+    check_chars: Option<usize>,
+}
+
+mod helpers {
+    use ruty_core::{open_input, open_output};
+    use std::io::{self, BufRead, Write};
+
+    /// Empty lines around the groups of --all-repeated
+    #[derive(Debug, Clone, Copy, PartialEq, super::ValueEnum)]
+    pub enum Delimit {
+        None,
+        Prepend,
+        Separate,
+    }
+
+    /// Empty lines around the groups of --group
+    #[derive(Debug, Clone, Copy, PartialEq, super::ValueEnum)]
+    pub enum Grouping {
+        Separate,
+        Prepend,
+        Append,
+        Both,
+    }
+
+    /// Which groups of adjacent equal lines are printed, and how
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct Config {
+        pub count: bool,
+        pub repeated: bool,
+        pub all_repeated: Option<Delimit>,
+        pub group: Option<Grouping>,
+        pub unique: bool,
+        pub ignore_case: bool,
+        pub skip_fields: usize,
+        pub skip_chars: usize,
+        pub check_chars: Option<usize>,
+    }
+
+    impl Config {
+        fn from_args(args: &crate::Args) -> Self {
+            Config {
+                count: args.count,
+                repeated: args.repeated,
+                all_repeated: args.all_repeated,
+                group: args.group,
+                unique: args.unique,
+                ignore_case: args.ignore_case,
+                skip_fields: args.skip_fields,
+                skip_chars: args.skip_chars,
+                check_chars: args.check_chars,
+            }
+        }
+
+        /// Part of a line that is compared, without the skipped fields and characters like GNU uniq:
+        /// a field is a run of blanks followed by non blanks
+        pub fn key<'a>(&self, line: &'a str) -> &'a str {
+            let is_blank = |c: char| c == ' ' || c == '\t';
+            let mut rest = line;
+
+            for _ in 0..self.skip_fields {
+                rest = rest.trim_start_matches(is_blank);
+                rest = rest.trim_start_matches(|c| !is_blank(c));
+            }
+
+            let start = rest
+                .char_indices()
+                .nth(self.skip_chars)
+                .map_or(rest.len(), |(i, _)| i);
+            let rest = &rest[start..];
+
+            match self.check_chars {
+                None => rest,
+                Some(n) => {
+                    let end = rest.char_indices().nth(n).map_or(rest.len(), |(i, _)| i);
+                    &rest[..end]
+                }
+            }
+        }
+
+        /// Lines are compared without their line end and trailing whitespace
+        fn same(&self, a: &str, b: &str) -> bool {
+            let (a, b) = (self.key(a.trim_end()), self.key(b.trim_end()));
+
+            if self.ignore_case {
+                a.to_lowercase() == b.to_lowercase()
+            } else {
+                a == b
+            }
+        }
+
+        fn keeps(&self, count: u64) -> bool {
+            match self.all_repeated {
+                Some(_) => count > 1,
+                None => (!self.repeated || count > 1) && (!self.unique || count == 1),
+            }
+        }
+
+        /// Whether all the lines of the groups are printed, not only the first one
+        fn prints_members(&self) -> bool {
+            self.all_repeated.is_some() || self.group.is_some()
+        }
+    }
+
+    /// Group of adjacent equal lines: its first line, the number of lines and all of them when
+    /// they are printed
+    #[derive(Debug, PartialEq)]
+    pub struct Group {
+        pub line: String,
+        pub count: u64,
+        pub members: Vec<String>,
+    }
+
+    /// Iterator over the groups of adjacent equal lines of the input
+    pub struct Groups<'a, R> {
+        input: R,
+        config: &'a Config,
+        /// First line of the next group, read to find the end of the previous one
+        next_line: Option<String>,
+    }
+
+    impl<'a, R: BufRead> Groups<'a, R> {
+        pub fn new(input: R, config: &'a Config) -> Self {
+            Groups {
+                input,
+                config,
+                next_line: None,
+            }
+        }
+
+        fn read_line(&mut self) -> io::Result<Option<String>> {
+            let mut line = String::new();
+
+            match self.input.read_line(&mut line)? {
+                0 => Ok(None),
+                _ => Ok(Some(line)),
+            }
+        }
+    }
+
+    impl<R: BufRead> Iterator for Groups<'_, R> {
+        type Item = io::Result<Group>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let line = match self.next_line.take() {
+                Some(line) => line,
+                None => match self.read_line() {
+                    Ok(line) => line?,
+                    Err(e) => return Some(Err(e)),
+                },
+            };
+
+            let mut members = Vec::new();
+            if self.config.prints_members() {
+                members.push(line.clone());
+            }
+            let mut group = Group {
+                line,
+                count: 1,
+                members,
+            };
+
+            loop {
+                match self.read_line() {
+                    Err(e) => return Some(Err(e)),
+                    Ok(None) => break,
+                    Ok(Some(line)) if self.config.same(&group.line, &line) => {
+                        group.count += 1;
+                        if self.config.prints_members() {
+                            group.members.push(line);
+                        }
+                    }
+                    Ok(Some(line)) => {
+                        self.next_line = Some(line);
+                        break;
+                    }
+                }
+            }
+
+            Some(Ok(group))
+        }
+    }
+
+    pub fn run(args: crate::Args) -> anyhow::Result<()> {
+        let mut file =
+            open_input(&args.in_file).map_err(|e| anyhow::anyhow!("{}: {}", args.in_file, e))?;
+
+        let mut out_file = open_output(args.out_file.as_deref()).map_err(|e| {
+            anyhow::anyhow!(
+                "{}: {}",
+                args.out_file.clone().unwrap_or("stdout".to_string()),
+                e
+            )
+        })?;
+
+        uniq_lines(&mut file, &mut out_file, &Config::from_args(&args))?;
+        out_file.flush()?;
+
+        Ok(())
+    }
+
+    /// Writes the first line of each group of adjacent equal lines the config keeps, or all its
+    /// lines with --all-repeated and --group, with the empty lines around the groups like GNU uniq
+    pub fn uniq_lines<R: BufRead, W: Write>(
+        input: R,
+        mut output: W,
+        config: &Config,
+    ) -> anyhow::Result<()> {
+        let mut printed = false;
+
+        for group in Groups::new(input, config) {
+            let group = group?;
+
+            let separated = match (config.group, config.all_repeated) {
+                (Some(grouping), _) => {
+                    printed || matches!(grouping, Grouping::Prepend | Grouping::Both)
+                }
+                (None, _) if !config.keeps(group.count) => continue,
+                (None, Some(delimit)) => {
+                    delimit == Delimit::Prepend || (printed && delimit == Delimit::Separate)
+                }
+                (None, None) => false,
+            };
+            if separated {
+                writeln!(output)?;
+            }
+
+            if config.prints_members() {
+                // Like GNU uniq, -u with --all-repeated leaves out the first line of the groups
+                let skip = usize::from(config.unique && config.all_repeated.is_some());
+                group
+                    .members
+                    .iter()
+                    .skip(skip)
+                    .try_for_each(|line| write!(output, "{}", line))?;
+            } else if config.count {
+                write!(output, "{:>4} {}", group.count, group.line)?;
+            } else {
+                write!(output, "{}", group.line)?;
+            }
+
+            printed = true;
+        }
+
+        if printed && matches!(config.group, Some(Grouping::Append | Grouping::Both)) {
+            writeln!(output)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub use helpers::run;
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{Config, Delimit, Grouping, uniq_lines};
+    use pretty_assertions::assert_eq;
+    use std::io::Cursor;
+
+    fn uniq(input: &str, config: Config) -> String {
+        let mut output: Vec<u8> = Vec::new();
+        uniq_lines(Cursor::new(input), &mut output, &config).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    const INPUT: &str = "a\na\nb\nA\na\nc\nc\nc\n";
+
+    #[test]
+    fn test_uniq_lines() {
+        assert_eq!(uniq(INPUT, Config::default()), "a\nb\nA\na\nc\n");
+        assert_eq!(uniq("", Config::default()), "");
+
+        let count = Config {
+            count: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            uniq(INPUT, count),
+            "   2 a\n   1 b\n   1 A\n   1 a\n   3 c\n"
+        );
+    }
+
+    #[test]
+    fn test_uniq_lines_repeated_unique() {
+        let repeated = Config {
+            repeated: true,
+            ..Default::default()
+        };
+        assert_eq!(uniq(INPUT, repeated), "a\nc\n");
+
+        let unique = Config {
+            unique: true,
+            ..Default::default()
+        };
+        assert_eq!(uniq(INPUT, unique), "b\nA\na\n");
+
+        // No line is both repeated and unique
+        let both = Config {
+            repeated: true,
+            unique: true,
+            ..Default::default()
+        };
+        assert_eq!(uniq(INPUT, both), "");
+    }
+
+    #[test]
+    fn test_key() {
+        let line = "2024-05-01 10:00 \tERROR disk full";
+        assert_eq!(Config::default().key(line), line);
+
+        let fields = Config {
+            skip_fields: 2,
+            ..Default::default()
+        };
+        assert_eq!(fields.key(line), " \tERROR disk full");
+        let fields = Config {
+            skip_fields: 9,
+            ..Default::default()
+        };
+        assert_eq!(fields.key(line), "");
+
+        // The characters are skipped after the fields, blanks included
+        let chars = Config {
+            skip_fields: 2,
+            skip_chars: 3,
+            ..Default::default()
+        };
+        assert_eq!(chars.key(line), "RROR disk full");
+        let chars = Config {
+            skip_chars: 100,
+            ..Default::default()
+        };
+        assert_eq!(chars.key(line), "");
+
+        let check = Config {
+            skip_fields: 2,
+            skip_chars: 2,
+            check_chars: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(check.key(line), "ERROR");
+        let check = Config {
+            check_chars: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(check.key(line), "");
+
+        // Characters, not bytes
+        let chars = Config {
+            skip_chars: 1,
+            check_chars: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(chars.key("ñandú"), "an");
+    }
+
+    #[test]
+    fn test_uniq_lines_skip() {
+        let input = "1 a x\n2 a y\n3 b y\n";
+
+        let fields = Config {
+            skip_fields: 1,
+            count: true,
+            ..Default::default()
+        };
+        assert_eq!(uniq(input, fields), "   1 1 a x\n   1 2 a y\n   1 3 b y\n");
+
+        let check = Config {
+            skip_fields: 1,
+            check_chars: Some(2),
+            count: true,
+            ..Default::default()
+        };
+        assert_eq!(uniq(input, check), "   2 1 a x\n   1 3 b y\n");
+
+        let chars = Config {
+            skip_chars: 4,
+            ..Default::default()
+        };
+        assert_eq!(uniq(input, chars), "1 a x\n2 a y\n");
+    }
+
+    #[test]
+    fn test_uniq_lines_ignore_case() {
+        let ignore_case = Config {
+            ignore_case: true,
+            count: true,
+            ..Default::default()
+        };
+        assert_eq!(uniq(INPUT, ignore_case), "   2 a\n   1 b\n   2 A\n   3 c\n");
+
+        let repeated = Config {
+            ignore_case: true,
+            repeated: true,
+            ..Default::default()
+        };
+        assert_eq!(uniq(INPUT, repeated), "a\nA\nc\n");
+    }
+
+    #[test]
+    fn test_uniq_lines_all_repeated() {
+        let all = |delimit| Config {
+            all_repeated: Some(delimit),
+            ..Default::default()
+        };
+        assert_eq!(uniq(INPUT, all(Delimit::None)), "a\na\nc\nc\nc\n");
+        assert_eq!(uniq(INPUT, all(Delimit::Prepend)), "\na\na\n\nc\nc\nc\n");
+        assert_eq!(uniq(INPUT, all(Delimit::Separate)), "a\na\n\nc\nc\nc\n");
+        assert_eq!(uniq("a\nb\n", all(Delimit::Separate)), "");
+
+        // -u leaves out the first line of each group
+        let unique = Config {
+            unique: true,
+            ..all(Delimit::Separate)
+        };
+        assert_eq!(uniq("a\na\na\nb\nc\nc\n", unique), "a\na\n\nc\n");
+    }
+
+    #[test]
+    fn test_uniq_lines_group() {
+        let group = |grouping| Config {
+            group: Some(grouping),
+            ..Default::default()
+        };
+        let input = "a\na\nb\nc\nc\n";
+        assert_eq!(
+            uniq(input, group(Grouping::Separate)),
+            "a\na\n\nb\n\nc\nc\n"
+        );
+        assert_eq!(
+            uniq(input, group(Grouping::Prepend)),
+            "\na\na\n\nb\n\nc\nc\n"
+        );
+        assert_eq!(
+            uniq(input, group(Grouping::Append)),
+            "a\na\n\nb\n\nc\nc\n\n"
+        );
+        assert_eq!(
+            uniq(input, group(Grouping::Both)),
+            "\na\na\n\nb\n\nc\nc\n\n"
+        );
+        assert_eq!(uniq("", group(Grouping::Both)), "");
+    }
+
+    #[test]
+    fn test_uniq_lines_blank_lines() {
+        assert_eq!(uniq("\n\na\n\n", Config::default()), "\na\n\n");
+
+        let count = Config {
+            count: true,
+            ..Default::default()
+        };
+        assert_eq!(uniq("\n\na\n\n", count), "   2 \n   1 a\n   1 \n");
+    }
+}
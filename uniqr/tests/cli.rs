@@ -2,7 +2,7 @@ use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
-use rand::{distr::Alphanumeric, Rng};
+use rand::{Rng, distr::Alphanumeric};
 use std::fs;
 use tempfile::NamedTempFile;
 
@@ -617,3 +617,169 @@ fn t6_outfile_count() -> Result<()> {
 fn t6_stdin_outfile_count() -> Result<()> {
     run_stdin_outfile_count(&T6)
 }
+
+// --------------------------------------------------
+fn run_args(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read_to_string(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().expect("fail");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
+    assert_eq!(stdout, expected);
+    Ok(())
+}
+
+#[test]
+fn three_repeated() -> Result<()> {
+    run_args(&[THREE.input, "-d"], "tests/expected/three.txt.d.out")
+}
+
+#[test]
+fn three_repeated_count() -> Result<()> {
+    run_args(
+        &[THREE.input, "-c", "--repeated"],
+        "tests/expected/three.txt.c.d.out",
+    )
+}
+
+#[test]
+fn three_unique() -> Result<()> {
+    run_args(&[THREE.input, "-u"], "tests/expected/three.txt.u.out")
+}
+
+#[test]
+fn three_repeated_unique() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "-d", "-u"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+#[test]
+fn case_ignore_case() -> Result<()> {
+    run_args(
+        &["tests/inputs/case.txt", "-i"],
+        "tests/expected/case.txt.i.out",
+    )
+}
+
+#[test]
+fn case_ignore_case_count() -> Result<()> {
+    run_args(
+        &["tests/inputs/case.txt", "--ignore-case", "-c"],
+        "tests/expected/case.txt.i.c.out",
+    )
+}
+
+#[test]
+fn case_ignore_case_unique() -> Result<()> {
+    run_args(
+        &["tests/inputs/case.txt", "-i", "-u"],
+        "tests/expected/case.txt.i.u.out",
+    )
+}
+
+#[test]
+fn log_skip_fields() -> Result<()> {
+    run_args(
+        &["tests/inputs/log.txt", "-f", "2"],
+        "tests/expected/log.txt.f2.out",
+    )
+}
+
+#[test]
+fn log_skip_fields_check_chars() -> Result<()> {
+    run_args(
+        &[
+            "tests/inputs/log.txt",
+            "--skip-fields",
+            "2",
+            "--check-chars",
+            "9",
+        ],
+        "tests/expected/log.txt.f2.w9.out",
+    )
+}
+
+#[test]
+fn log_skip_chars_check_chars() -> Result<()> {
+    run_args(
+        &["tests/inputs/log.txt", "-s", "11", "-w", "5"],
+        "tests/expected/log.txt.s11.w5.out",
+    )
+}
+
+#[test]
+fn log_check_no_chars() -> Result<()> {
+    run_args(
+        &["tests/inputs/log.txt", "-w", "0"],
+        "tests/expected/log.txt.w0.out",
+    )
+}
+
+#[test]
+fn log_count_skip_all_chars() -> Result<()> {
+    run_args(
+        &["tests/inputs/log.txt", "-c", "-s", "99"],
+        "tests/expected/log.txt.c.s99.out",
+    )
+}
+
+#[test]
+fn log_skip_fields_ignore_case_repeated() -> Result<()> {
+    run_args(
+        &["tests/inputs/log.txt", "-f", "2", "-i", "-d"],
+        "tests/expected/log.txt.f2.i.d.out",
+    )
+}
+
+#[test]
+fn log_count_skip_fields_check_chars() -> Result<()> {
+    run_args(
+        &["tests/inputs/log.txt", "-c", "-f", "2", "-w", "4"],
+        "tests/expected/log.txt.c.f2.w4.out",
+    )
+}
+
+#[test]
+fn three_all_repeated() -> Result<()> {
+    run_args(&[THREE.input, "-D"], "tests/expected/three.txt.D.out")
+}
+
+#[test]
+fn three_all_repeated_separate() -> Result<()> {
+    run_args(
+        &[THREE.input, "--all-repeated=separate"],
+        "tests/expected/three.txt.D-separate.out",
+    )
+}
+
+#[test]
+fn three_group_both() -> Result<()> {
+    run_args(
+        &[THREE.input, "--group=both"],
+        "tests/expected/three.txt.group-both.out",
+    )
+}
+
+#[test]
+fn case_ignore_case_group() -> Result<()> {
+    run_args(
+        &["tests/inputs/case.txt", "-i", "--group"],
+        "tests/expected/case.txt.i.group.out",
+    )
+}
+
+#[test]
+fn dies_group_count() -> Result<()> {
+    for args in [["--group", "-c"], ["-D", "-c"], ["--group", "-d"]] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+    Ok(())
+}
@@ -0,0 +1,348 @@
+//! Rust version of `wc`, the `run` function is called by the `wcr` binary and by `ruty`
+
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+#[command(about, version = "0.1.0", author = "Eduardo Palou de Comasema Jaume")]
+/// Rust version of `wc`
+pub struct Args {
+    /// Input file(s)
+    #[arg(default_value = "-")]
+    files: Vec<String>,
+
+    /// Read the input files from the NUL-terminated names of FILE, or of stdin when FILE is -
+    #[arg(long("files0-from"), value_name = "FILE", conflicts_with = "files")]
+    files0_from: Option<String>,
+
+    /// Show line count
+    #[arg(short('l'), long)]
+    lines: bool,
+
+    /// Show word count
+    #[arg(short('w'), long)]
+    words: bool,
+
+    /// Show byte count
+    #[arg(short('c'), long)]
+    bytes: bool,
+
+    /// Show character count
+    #[arg(short('m'), long, conflicts_with = "bytes")]
+    chars: bool,
+
+    /// Show the display width of the longest line
+    #[arg(short('L'), long)]
+    max_line_length: bool,
+
+    /// Count the words by Unicode word segmentation (UAX #29) instead of by whitespace
+    #[arg(long)]
+    unicode_words: bool,
+
+    /// Show the count of grapheme clusters instead of characters
+    #[arg(long, conflicts_with = "bytes")]
+    graphemes: bool,
+}
+
+mod counter_logic {
+    use unicode_segmentation::UnicodeSegmentation;
+    use unicode_width::UnicodeWidthChar;
+
+    /// Counts that are only done when asked for, as they are slower to get
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct Options {
+        pub unicode_words: bool,
+        pub graphemes: bool,
+    }
+
+    /// Counts of a file, `num_unicode_words` and `num_graphemes` are 0 unless asked for
+    #[derive(Debug, Default, PartialEq, Clone)]
+    pub struct FileInfo {
+        pub num_lines: usize,
+        pub num_words: usize,
+        pub num_unicode_words: usize,
+        pub num_bytes: usize,
+        pub num_chars: usize,
+        pub num_graphemes: usize,
+        pub max_line_length: usize,
+    }
+
+    impl std::ops::Add<FileInfo> for FileInfo {
+        type Output = Self;
+
+        fn add(self, rhs: Self) -> Self::Output {
+            FileInfo {
+                num_lines: self.num_lines + rhs.num_lines,
+                num_words: self.num_words + rhs.num_words,
+                num_unicode_words: self.num_unicode_words + rhs.num_unicode_words,
+                num_bytes: self.num_bytes + rhs.num_bytes,
+                num_chars: self.num_chars + rhs.num_chars,
+                num_graphemes: self.num_graphemes + rhs.num_graphemes,
+                max_line_length: self.max_line_length.max(rhs.max_line_length),
+            }
+        }
+    }
+
+    pub fn count<B>(mut file: B, options: Options) -> anyhow::Result<FileInfo>
+    where
+        B: std::io::BufRead,
+    {
+        let mut num_lines = 0;
+        let mut num_words = 0;
+        let mut num_unicode_words = 0;
+        let mut num_bytes = 0;
+        let mut num_chars = 0;
+        let mut num_graphemes = 0;
+        let mut max_line_length = 0;
+        let mut line = String::new();
+
+        loop {
+            if file.read_line(&mut line)? == 0 {
+                break;
+            }
+
+            num_lines += 1;
+            num_words += line.split_whitespace().count();
+            num_bytes += line.len();
+            num_chars += line.chars().count();
+            max_line_length = max_line_length.max(line_width(&line));
+
+            if options.unicode_words {
+                num_unicode_words += line.unicode_words().count();
+            }
+
+            if options.graphemes {
+                num_graphemes += line.graphemes(true).count();
+            }
+
+            line.clear();
+        }
+
+        Ok(FileInfo {
+            num_lines,
+            num_words,
+            num_unicode_words,
+            num_bytes,
+            num_chars,
+            num_graphemes,
+            max_line_length,
+        })
+    }
+
+    /// Display width of a line like `wc -L`: tabs stop every 8 columns, carriage returns and
+    /// form feeds go back to the first column, East Asian wide characters take two columns and
+    /// combining marks and control characters take no room
+    pub fn line_width(line: &str) -> usize {
+        let mut width = 0;
+        let mut max = 0;
+
+        for c in line.chars() {
+            match c {
+                '\n' | '\r' | '\x0c' => {
+                    max = max.max(width);
+                    width = 0;
+                }
+                '\t' => width += 8 - width % 8,
+                c => width += c.width().unwrap_or(0),
+            }
+        }
+
+        max.max(width)
+    }
+}
+
+mod helper {
+    use rayon::prelude::*;
+
+    /// Helper function that encapsulte the main logic of the command line tool.
+    pub fn run(args: &crate::Args) -> anyhow::Result<()> {
+        let args = logic_arg(args);
+        let mut files_info = crate::counter_logic::FileInfo::default();
+        let options = crate::counter_logic::Options {
+            unicode_words: args.unicode_words,
+            graphemes: args.graphemes,
+        };
+
+        let files = match &args.files0_from {
+            Some(from) => read_files0(from)?,
+            None => args.files.clone(),
+        };
+
+        // The files are counted on the thread pool, the results keep the order of the files
+        let counts: Vec<_> = files
+            .par_iter()
+            .map(|filename| {
+                ruty_core::open_maybe_compressed(filename)
+                    .map(|file| crate::counter_logic::count(file, options))
+            })
+            .collect();
+
+        for (filename, count) in files.iter().zip(counts) {
+            match count {
+                Err(e) => eprintln!("{}: {}", filename, e),
+                Ok(file_info) => {
+                    let file_info = file_info?;
+
+                    files_info = files_info + file_info.clone();
+
+                    print_result(&file_info, filename, &args)?;
+                }
+            }
+        }
+
+        if files.len() > 1 {
+            print_result(&files_info, "total", &args)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the NUL-terminated file names given to --files0-from, the invalid ones are reported and skipped
+    fn read_files0(from: &str) -> anyhow::Result<Vec<String>> {
+        let reader = ruty_core::open_input(from).map_err(|e| anyhow::anyhow!("{}: {}", from, e))?;
+        let mut files = Vec::new();
+
+        for (i, name) in ruty_core::nul_records(reader).enumerate() {
+            let name = String::from_utf8_lossy(&name?).into_owned();
+
+            if name.is_empty() {
+                eprintln!("{}:{}: invalid zero-length file name", from, i + 1);
+            } else if name == "-" && from == "-" {
+                eprintln!("when reading file names from stdin, no file name of '-' allowed");
+            } else {
+                files.push(name);
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Helper function that expands the logic of the crate clap to the needs of
+    /// the command line tool.
+    fn logic_arg(args: &crate::Args) -> crate::Args {
+        let chars = args.chars || args.graphemes;
+
+        if !(args.lines || args.words || chars || args.bytes || args.max_line_length) {
+            crate::Args {
+                files: args.files.clone(),
+                files0_from: args.files0_from.clone(),
+                lines: true,
+                words: true,
+                bytes: true,
+                chars: false,
+                max_line_length: false,
+                unicode_words: args.unicode_words,
+                graphemes: false,
+            }
+        } else {
+            crate::Args {
+                files: args.files.clone(),
+                files0_from: args.files0_from.clone(),
+                lines: args.lines,
+                words: args.words,
+                bytes: args.bytes,
+                chars,
+                max_line_length: args.max_line_length,
+                unicode_words: args.unicode_words,
+                graphemes: args.graphemes,
+            }
+        }
+    }
+
+    /// Helper function to print results in the format that we want.
+    fn print_result(
+        input: &crate::counter_logic::FileInfo,
+        filename: &str,
+        args: &crate::Args,
+    ) -> anyhow::Result<()> {
+        let mut result = String::new();
+
+        if args.lines {
+            result.push_str(&format!("{:>8}", input.num_lines));
+        }
+
+        if args.words {
+            let words = match args.unicode_words {
+                true => input.num_unicode_words,
+                false => input.num_words,
+            };
+            result.push_str(&format!("{:>8}", words));
+        }
+
+        if args.chars {
+            let chars = match args.graphemes {
+                true => input.num_graphemes,
+                false => input.num_chars,
+            };
+            result.push_str(&format!("{:>8}", chars));
+        }
+
+        if args.bytes {
+            result.push_str(&format!("{:>8}", input.num_bytes));
+        }
+
+        if args.max_line_length {
+            result.push_str(&format!("{:>8}", input.max_line_length));
+        }
+
+        if filename != "-" {
+            result.push_str(&format!(" {}", filename));
+        }
+
+        println!("{}", result);
+
+        Ok(())
+    }
+}
+
+pub use helper::run;
+
+#[cfg(test)]
+mod test {
+    use crate::counter_logic;
+    use std::io;
+
+    #[test]
+    fn test_count() {
+        let text = "I don't want the world.\nI just want your half.\r\n";
+        let info = counter_logic::count(io::Cursor::new(text), Default::default());
+        assert!(info.is_ok());
+        let expected = counter_logic::FileInfo {
+            num_lines: 2,
+            num_words: 10,
+            num_unicode_words: 0,
+            num_chars: 48,
+            num_bytes: 48,
+            num_graphemes: 0,
+            max_line_length: 23,
+        };
+        assert_eq!(info.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_count_unicode() {
+        // Japanese has no spaces between words, a combining accent and a flag are one grapheme
+        let text = "日本語のテキスト\nnai\u{308}ve caf\u{e9} — 🇪🇸!\r\n";
+        let options = counter_logic::Options {
+            unicode_words: true,
+            graphemes: true,
+        };
+        let info = counter_logic::count(io::Cursor::new(text), options).unwrap();
+        assert_eq!(info.num_words, 5);
+        assert_eq!(info.num_unicode_words, 7);
+        assert_eq!(info.num_chars, 28);
+        assert_eq!(info.num_graphemes, 25);
+    }
+
+    #[test]
+    fn test_line_width() {
+        assert_eq!(counter_logic::line_width(""), 0);
+        assert_eq!(counter_logic::line_width("abc\n"), 3);
+        assert_eq!(counter_logic::line_width("ab\tc\n"), 9);
+        assert_eq!(counter_logic::line_width("\t\t"), 16);
+        assert_eq!(counter_logic::line_width("xyz\r12\n"), 3);
+        assert_eq!(counter_logic::line_width("h\u{e9}llo\u{7}"), 5);
+        // Wide characters take two columns, combining marks none
+        assert_eq!(counter_logic::line_width("日本語\n"), 6);
+        assert_eq!(counter_logic::line_width("e\u{301}t\u{e9}"), 3);
+    }
+}
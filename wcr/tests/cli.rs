@@ -2,7 +2,7 @@ use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
-use rand::{distr::Alphanumeric, Rng};
+use rand::{Rng, distr::Alphanumeric};
 use std::fs;
 
 const PRG: &str = "wcr";
@@ -217,3 +217,120 @@ fn test_all_words_lines() -> Result<()> {
 fn test_all_bytes_lines() -> Result<()> {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+#[cfg(feature = "compressed")]
+fn atlamal_xz() -> Result<()> {
+    run(
+        &["tests/inputs/atlamal.txt.xz"],
+        "tests/expected/atlamal.txt.xz.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn many_files_keep_their_order() -> Result<()> {
+    let files: Vec<&str> = [EMPTY, FOX, ATLAMAL].repeat(50);
+    let output = Command::cargo_bin(PRG)?.args(&files).output()?;
+    assert!(output.status.success());
+
+    let mut expected = String::new();
+    for file in [EMPTY, FOX, ATLAMAL] {
+        expected += &fs::read_to_string(format!(
+            "tests/expected/{}.out",
+            file.trim_start_matches("tests/inputs/")
+        ))?;
+    }
+    let expected = expected.repeat(50) + "     250    1900   11250 total\n";
+
+    assert_eq!(String::from_utf8(output.stdout)?, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn atlamal_max_line_length() -> Result<()> {
+    run(&["-L", ATLAMAL], "tests/expected/atlamal.txt.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_lines_max_line_length() -> Result<()> {
+    run(
+        &["--lines", "--max-line-length", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.lL.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_file() -> Result<()> {
+    run(
+        &["--files0-from", "tests/inputs/files0.txt"],
+        "tests/expected/all.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> Result<()> {
+    // Like the output of find -print0
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "-"])
+        .write_stdin(format!("{}\0{}\0\0-\0{}\0", EMPTY, FOX, ATLAMAL))
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr(predicate::str::contains(
+            "-:3: invalid zero-length file name",
+        ))
+        .stderr(predicate::str::contains("no file name of '-' allowed"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_with_files() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "tests/inputs/files0.txt", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_words() -> Result<()> {
+    run(
+        &["--unicode-words", "tests/inputs/unicode.txt"],
+        "tests/expected/unicode.txt.unicode-words.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_words_graphemes() -> Result<()> {
+    run(
+        &[
+            "--unicode-words",
+            "--graphemes",
+            "-w",
+            "tests/inputs/unicode.txt",
+        ],
+        "tests/expected/unicode.txt.unicode-words.graphemes.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_graphemes_and_bytes() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--graphemes", "-c"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}